@@ -0,0 +1,127 @@
+//! Benchmarks of what runs for every line and every frame: ingestion through
+//! `App`, rendering the Messages pane and updating the satellites. The lines
+//! come from `mixed.nmea`, a minute of a GPS, GLONASS, Galileo and BeiDou
+//! receiver with ten satellites in view of each.
+//!
+//! ```sh
+//! cargo bench --bench hot_paths
+//! ```
+
+use chrono::{DateTime, Duration, Local, TimeZone};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use nmeacli::{sentence::Sentence, sky::Sky, ui, App};
+use tui::{backend::TestBackend, Terminal};
+
+const MIXED: &str = include_str!("mixed.nmea");
+
+/// Lines fed to `App` in one run
+const INGEST_LINES: usize = 100_000;
+
+fn start() -> DateTime<Local> {
+    Local.timestamp(1_591_509_873, 0)
+}
+
+/// `count` lines of the log, over and over, each with its arrival time: an
+/// epoch a second, starting at RMC
+fn timed_lines(count: usize) -> Vec<(DateTime<Local>, &'static str)> {
+    let mut local = start();
+    MIXED
+        .lines()
+        .cycle()
+        .take(count)
+        .enumerate()
+        .map(|(i, line)| {
+            if line.starts_with("$GNRMC") && i > 0 {
+                local = local + Duration::seconds(1);
+            }
+            (local, line)
+        })
+        .collect()
+}
+
+/// An app which has seen a minute of the log, so the Messages pane is full
+fn fed_app(panels: &str) -> App {
+    let mut app = App::with_defaults();
+    app.prefs.panels = panels.parse().unwrap();
+    let lines = timed_lines(MIXED.lines().count());
+    for &(local, line) in &lines {
+        app.handle_line_at(local, line);
+    }
+    app.tick_at(lines.last().unwrap().0);
+    app
+}
+
+fn ingest(c: &mut Criterion) {
+    let lines = timed_lines(INGEST_LINES);
+    let mut group = c.benchmark_group("ingest");
+    group.throughput(Throughput::Elements(INGEST_LINES as u64));
+    group.sample_size(10);
+    group.bench_function("handle_line_100k", |b| {
+        b.iter_batched(
+            App::with_defaults,
+            |mut app| {
+                for &(local, line) in &lines {
+                    app.handle_line_at(local, line);
+                }
+                app
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+fn messages(c: &mut Criterion) {
+    let mut app = fed_app("messages");
+    assert_eq!(app.messages.lines.len(), app.options.messages);
+    let mut terminal = Terminal::new(TestBackend::new(200, 60)).unwrap();
+
+    let mut group = c.benchmark_group("messages");
+    group.bench_function("draw_200x60", |b| {
+        b.iter(|| terminal.draw(|f| ui::draw(f, &app)).unwrap())
+    });
+    app.hex_view = true;
+    group.bench_function("draw_200x60_hex", |b| {
+        b.iter(|| terminal.draw(|f| ui::draw(f, &app)).unwrap())
+    });
+    group.finish();
+}
+
+fn sky(c: &mut Criterion) {
+    // GSA and GSV of each epoch: 40 satellites in 4 constellations
+    let epochs: Vec<(DateTime<Local>, Vec<&str>)> = timed_lines(MIXED.lines().count())
+        .chunk_by(|a, b| a.0 == b.0)
+        .map(|epoch| {
+            let sky_lines = epoch
+                .iter()
+                .map(|&(_, line)| line)
+                .filter(|line| {
+                    line.get(3..6)
+                        .is_some_and(|kind| kind == "GSV" || kind == "GSA")
+                })
+                .collect();
+            (epoch[0].0, sky_lines)
+        })
+        .collect();
+    assert_eq!(epochs[0].1.len(), 16);
+
+    let mut group = c.benchmark_group("sky");
+    group.bench_function("epoch_40_satellites", |b| {
+        let mut sky = Sky::new();
+        let mut n = 0;
+        b.iter(|| {
+            let (local, lines) = &epochs[n % epochs.len()];
+            // on from one pass over the log to the next, like a receiver
+            let local = *local + Duration::seconds(60 * (n / epochs.len()) as i64);
+            for line in lines {
+                sky.handle(local, &Sentence::parse(line).unwrap());
+            }
+            sky.expire(local);
+            n += 1;
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, ingest, messages, sky);
+criterion_main!(benches);
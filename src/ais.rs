@@ -0,0 +1,513 @@
+//! AIS position reports and static data from `!AIVDM` sentences, and the
+//! table of vessels heard
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Local};
+
+use crate::{app::distance_bearing, sentence::Sentence};
+
+const KNOTS_TO_MPS: f64 = 1852.0 / 3600.0;
+
+/// Payload bits, six per armored character
+struct Bits(Vec<bool>);
+
+impl Bits {
+    fn dearmor(payload: &str, fill_bits: usize) -> Result<Bits, String> {
+        let mut bits = Vec::with_capacity(payload.len() * 6);
+        for c in payload.bytes() {
+            let value = match c {
+                b'0'..=b'W' => c - 48,
+                b'`'..=b'w' => c - 56,
+                _ => return Err(format!("invalid AIS payload character '{}'", c as char)),
+            };
+            bits.extend((0..6).rev().map(|i| (value >> i) & 1 == 1));
+        }
+        bits.truncate(bits.len().saturating_sub(fill_bits));
+        Ok(Bits(bits))
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn uint(&self, start: usize, len: usize) -> u32 {
+        self.0[start..start + len]
+            .iter()
+            .fold(0, |acc, &bit| (acc << 1) | bit as u32)
+    }
+
+    fn int(&self, start: usize, len: usize) -> i32 {
+        let value = self.uint(start, len) as i32;
+        // sign-extend from `len` bits
+        value << (32 - len) >> (32 - len)
+    }
+
+    /// Six-bit ASCII, without the `@` and space padding
+    fn text(&self, start: usize, len: usize) -> Option<String> {
+        let text: String = (start..start + len)
+            .step_by(6)
+            .map(|i| {
+                let c = self.uint(i, 6) as u8;
+                if c < 32 {
+                    (c + 64) as char
+                } else {
+                    c as char
+                }
+            })
+            .collect();
+        let text = text.trim_end_matches(|c| c == '@' || c == ' ');
+        Some(text.to_owned()).filter(|text| !text.is_empty())
+    }
+
+    /// Degrees from 1/10000 minutes; `None` for the "not available" values
+    /// 181 and 91
+    fn coord(&self, start: usize, len: usize, limit: f64) -> Option<f64> {
+        let degrees = self.int(start, len) as f64 / 600_000.0;
+        Some(degrees).filter(|d| d.abs() <= limit)
+    }
+
+    /// Knots from 1/10 knot, 1023 meaning not available
+    fn speed(&self, start: usize) -> Option<f32> {
+        let value = self.uint(start, 10);
+        Some(value as f32 / 10.0).filter(|_| value != 1023)
+    }
+
+    /// Degrees from 1/10 degree, 3600 meaning not available
+    fn course(&self, start: usize) -> Option<f32> {
+        let value = self.uint(start, 12);
+        Some(value as f32 / 10.0).filter(|_| value < 3600)
+    }
+
+    /// Degrees, 511 meaning not available
+    fn heading(&self, start: usize) -> Option<u16> {
+        let value = self.uint(start, 9) as u16;
+        Some(value).filter(|&h| h < 360)
+    }
+}
+
+/// What one AIS message tells about a vessel
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Message {
+    pub msg_type: u8,
+    pub mmsi: u32,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    /// knots
+    pub sog: Option<f32>,
+    /// degrees
+    pub cog: Option<f32>,
+    /// degrees
+    pub heading: Option<u16>,
+    pub name: Option<String>,
+    pub callsign: Option<String>,
+    /// sent by a class B transponder
+    pub class_b: bool,
+}
+
+/// Decodes a complete payload. Message types other than position reports
+/// (1, 2, 3, 18, 19) and static data (5) give `None`.
+pub fn decode(payload: &str, fill_bits: usize) -> Result<Option<Message>, String> {
+    let bits = Bits::dearmor(payload, fill_bits)?;
+    if bits.len() < 38 {
+        return Err(format!("AIS payload too short ({} bits)", bits.len()));
+    }
+    let msg_type = bits.uint(0, 6) as u8;
+    let needed = match msg_type {
+        1..=3 => 137,
+        5 => 232,
+        18 => 133,
+        19 => 263,
+        _ => return Ok(None),
+    };
+    if bits.len() < needed {
+        return Err(format!(
+            "AIS message {} too short ({} of {} bits)",
+            msg_type,
+            bits.len(),
+            needed
+        ));
+    }
+    let mut msg = Message {
+        msg_type,
+        mmsi: bits.uint(8, 30),
+        ..Message::default()
+    };
+    match msg_type {
+        1..=3 => {
+            msg.sog = bits.speed(50);
+            msg.lon = bits.coord(61, 28, 180.0);
+            msg.lat = bits.coord(89, 27, 90.0);
+            msg.cog = bits.course(116);
+            msg.heading = bits.heading(128);
+        }
+        5 => {
+            msg.callsign = bits.text(70, 42);
+            msg.name = bits.text(112, 120);
+        }
+        _ => {
+            // 18 and 19 share the layout up to the heading
+            msg.class_b = true;
+            msg.sog = bits.speed(46);
+            msg.lon = bits.coord(57, 28, 180.0);
+            msg.lat = bits.coord(85, 27, 90.0);
+            msg.cog = bits.course(112);
+            msg.heading = bits.heading(124);
+            if msg_type == 19 {
+                msg.name = bits.text(143, 120);
+            }
+        }
+    }
+    Ok(Some(msg))
+}
+
+/// A message split over several sentences, waiting for the rest
+#[derive(Debug)]
+struct Partial {
+    count: usize,
+    received: usize,
+    payload: String,
+}
+
+/// Joins fragments of multi-sentence messages by their sequential ID
+#[derive(Debug, Default)]
+struct Fragments {
+    pending: HashMap<String, Partial>,
+}
+
+impl Fragments {
+    /// The whole payload and its fill bits once the last fragment is in
+    fn push(&mut self, sentence: &Sentence) -> Result<Option<(String, usize)>, String> {
+        let count: usize = sentence.parse_field(0).ok_or("no fragment count")?;
+        let number: usize = sentence.parse_field(1).ok_or("no fragment number")?;
+        let payload = sentence.field(4).unwrap_or("");
+        let fill_bits: usize = sentence.parse_field(5).unwrap_or(0);
+        if count == 1 {
+            return Ok(Some((payload.to_owned(), fill_bits)));
+        }
+        let key = format!(
+            "{}{},{}",
+            sentence.talker,
+            sentence.kind,
+            sentence.field(2).unwrap_or("")
+        );
+        if number == 1 {
+            self.pending.insert(
+                key,
+                Partial {
+                    count,
+                    received: 1,
+                    payload: payload.to_owned(),
+                },
+            );
+            return Ok(None);
+        }
+        let partial = match self.pending.get_mut(&key) {
+            Some(partial) if partial.count == count && partial.received + 1 == number => partial,
+            _ => {
+                self.pending.remove(&key);
+                return Err(format!("AIS fragment {} of {} out of order", number, count));
+            }
+        };
+        partial.received = number;
+        partial.payload.push_str(payload);
+        if number < count {
+            return Ok(None);
+        }
+        let partial = self.pending.remove(&key).map(|p| p.payload);
+        Ok(partial.map(|payload| (payload, fill_bits)))
+    }
+}
+
+/// A vessel from the messages received with its MMSI
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub mmsi: u32,
+    pub name: Option<String>,
+    pub callsign: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub sog: Option<f32>,
+    pub cog: Option<f32>,
+    pub heading: Option<u16>,
+    pub class_b: bool,
+    pub last_seen: DateTime<Local>,
+}
+
+impl Target {
+    fn new(mmsi: u32, local: DateTime<Local>) -> Target {
+        Target {
+            mmsi,
+            name: None,
+            callsign: None,
+            lat: None,
+            lon: None,
+            sog: None,
+            cog: None,
+            heading: None,
+            class_b: false,
+            last_seen: local,
+        }
+    }
+
+    /// Distance in meters and bearing in degrees from `here`
+    pub fn distance_bearing(&self, here: (f64, f64)) -> Option<(f64, f64)> {
+        Some(distance_bearing(here, (self.lat?, self.lon?)))
+    }
+}
+
+/// Closest point of approach
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cpa {
+    /// meters
+    pub distance: f64,
+    /// until the closest point, negative once it is behind
+    pub time: chrono::Duration,
+}
+
+/// Where own ship and a target are and where they go
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Motion {
+    pub lat: f64,
+    pub lon: f64,
+    /// knots
+    pub sog: f32,
+    /// degrees
+    pub cog: f32,
+}
+
+impl Motion {
+    /// Meters per second east and north
+    fn velocity(&self) -> (f64, f64) {
+        let speed = self.sog as f64 * KNOTS_TO_MPS;
+        let cog = (self.cog as f64).to_radians();
+        (speed * cog.sin(), speed * cog.cos())
+    }
+}
+
+/// Closest approach of two vessels keeping course and speed, on a flat
+/// earth around `own`, which is close enough at AIS ranges. `None` while
+/// they keep their distance.
+pub fn cpa(own: Motion, target: Motion) -> Option<Cpa> {
+    let (distance, bearing) = distance_bearing((own.lat, own.lon), (target.lat, target.lon));
+    let bearing = bearing.to_radians();
+    let (x, y) = (distance * bearing.sin(), distance * bearing.cos());
+    let (own_vx, own_vy) = own.velocity();
+    let (target_vx, target_vy) = target.velocity();
+    let (vx, vy) = (target_vx - own_vx, target_vy - own_vy);
+    let speed2 = vx * vx + vy * vy;
+    if speed2 < 1e-6 {
+        return None;
+    }
+    let t = -(x * vx + y * vy) / speed2;
+    Some(Cpa {
+        distance: (x + vx * t).hypot(y + vy * t),
+        time: chrono::Duration::seconds(t.round() as i64),
+    })
+}
+
+/// Targets by MMSI, forgotten when not heard for a while
+#[derive(Debug)]
+pub struct Ais {
+    targets: BTreeMap<u32, Target>,
+    fragments: Fragments,
+    timeout: chrono::Duration,
+}
+
+impl Ais {
+    pub const DEFAULT_TIMEOUT_SECS: u64 = 600;
+
+    pub fn new(timeout_secs: u64) -> Ais {
+        Ais {
+            targets: BTreeMap::new(),
+            fragments: Fragments::default(),
+            timeout: chrono::Duration::seconds(timeout_secs as i64),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    pub fn get(&self, mmsi: u32) -> Option<&Target> {
+        self.targets.get(&mmsi)
+    }
+
+    /// Takes a VDM or VDO sentence; errors tell why it was of no use
+    pub fn handle(&mut self, local: DateTime<Local>, sentence: &Sentence) -> Result<(), String> {
+        let (payload, fill_bits) = match self.fragments.push(sentence)? {
+            Some(complete) => complete,
+            None => return Ok(()),
+        };
+        let msg = match decode(&payload, fill_bits)? {
+            Some(msg) => msg,
+            None => return Ok(()),
+        };
+        let target = self
+            .targets
+            .entry(msg.mmsi)
+            .or_insert_with(|| Target::new(msg.mmsi, local));
+        target.last_seen = local;
+        target.class_b |= msg.class_b;
+        if let Some(name) = msg.name {
+            target.name = Some(name);
+        }
+        if let Some(callsign) = msg.callsign {
+            target.callsign = Some(callsign);
+        }
+        if matches!(msg.msg_type, 1..=3 | 18 | 19) {
+            target.lat = msg.lat;
+            target.lon = msg.lon;
+            target.sog = msg.sog;
+            target.cog = msg.cog;
+            target.heading = msg.heading;
+        }
+        Ok(())
+    }
+
+    /// Drops targets not heard within the timeout
+    pub fn expire(&mut self, now: DateTime<Local>) {
+        let timeout = self.timeout;
+        self.targets
+            .retain(|_, target| now - target.last_seen <= timeout);
+    }
+
+    /// Nearest first, then targets without a position by MMSI
+    pub fn by_distance(&self, here: Option<(f64, f64)>) -> Vec<&Target> {
+        let mut targets: Vec<&Target> = self.targets.values().collect();
+        if let Some(here) = here {
+            let distance = |t: &Target| t.distance_bearing(here).map_or(f64::INFINITY, |d| d.0);
+            targets.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+        }
+        targets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(ais: &mut Ais, local: DateTime<Local>, line: &str) -> Result<(), String> {
+        ais.handle(local, &Sentence::parse(line).expect("valid sentence"))
+    }
+
+    #[test]
+    fn position_reports_are_decoded() {
+        let mut ais = Ais::new(Ais::DEFAULT_TIMEOUT_SECS);
+        let now = Local::now();
+        feed(
+            &mut ais,
+            now,
+            "!AIVDM,1,1,,B,15M67FC000G?ufbE`FepT@3n00Sa,0*5C",
+        )
+        .unwrap();
+        let target = ais.get(366053209).unwrap();
+        assert!((target.lat.unwrap() - 37.802118).abs() < 1e-6);
+        assert!((target.lon.unwrap() + 122.341618).abs() < 1e-6);
+        assert_eq!(target.sog, Some(0.0));
+        assert_eq!(target.cog, Some(219.3));
+        assert_eq!(target.heading, Some(1));
+        assert!(!target.class_b);
+
+        // class B, without a heading
+        feed(
+            &mut ais,
+            now,
+            "!AIVDM,1,1,,B,B5NJ;PP005l4ot5Isbl03wsUkP06,0*75",
+        )
+        .unwrap();
+        let target = ais.get(367430530).unwrap();
+        assert!((target.lat.unwrap() - 37.785035).abs() < 1e-6);
+        assert_eq!(target.heading, None);
+        assert!(target.class_b);
+
+        feed(
+            &mut ais,
+            now,
+            "!AIVDM,1,1,,B,C5N3SRgPEnJGEBT>NhWAwwo862PaLELTBJ:V00000000S0D:R220,0*0B",
+        )
+        .unwrap();
+        let target = ais.get(367059850).unwrap();
+        assert_eq!(target.name.as_deref(), Some("CAPT.J.RIMES"));
+        assert_eq!(target.sog, Some(8.7));
+        assert_eq!(target.cog, Some(335.9));
+    }
+
+    #[test]
+    fn static_data_is_reassembled_from_fragments() {
+        let mut ais = Ais::new(Ais::DEFAULT_TIMEOUT_SECS);
+        let now = Local::now();
+        let first =
+            "!AIVDM,2,1,1,A,55?MbV02;H;s<HtKR20EHE:0@T4@Dn2222222216L961O5Gf0NSQEp6ClRp8,0*1C";
+        let second = "!AIVDM,2,2,1,A,88888888880,2*25";
+
+        feed(&mut ais, now, first).unwrap();
+        assert!(ais.is_empty());
+        feed(&mut ais, now, second).unwrap();
+        let target = ais.get(351759000).unwrap();
+        assert_eq!(target.name.as_deref(), Some("EVER DIADEM"));
+        assert_eq!(target.callsign.as_deref(), Some("3FOF8"));
+        assert_eq!(target.lat, None);
+
+        // a second fragment without its first is dropped
+        let mut ais = Ais::new(Ais::DEFAULT_TIMEOUT_SECS);
+        assert!(feed(&mut ais, now, second).is_err());
+        assert!(ais.is_empty());
+    }
+
+    #[test]
+    fn targets_expire_and_sort_by_distance() {
+        let mut ais = Ais::new(60);
+        let now = Local::now();
+        feed(
+            &mut ais,
+            now,
+            "!AIVDM,1,1,,B,15M67FC000G?ufbE`FepT@3n00Sa,0*5C",
+        )
+        .unwrap();
+        let later = now + chrono::Duration::seconds(30);
+        feed(
+            &mut ais,
+            later,
+            "!AIVDM,1,1,,B,15NG6V0P01G?cFhE`R2IU?wn28R>,0*05",
+        )
+        .unwrap();
+
+        // near the second one, off Fisherman's Wharf
+        let here = Some((37.8069, -122.4043));
+        let order: Vec<u32> = ais.by_distance(here).iter().map(|t| t.mmsi).collect();
+        assert_eq!(order, vec![367380120, 366053209]);
+
+        ais.expire(now + chrono::Duration::seconds(80));
+        assert_eq!(ais.len(), 1);
+        assert!(ais.get(367380120).is_some());
+    }
+
+    #[test]
+    fn crossing_vessels_meet_at_the_cpa() {
+        // one mile south of own ship, heading north at 10 knots
+        let own = Motion {
+            lat: 0.0,
+            lon: 0.0,
+            sog: 0.0,
+            cog: 0.0,
+        };
+        let target = Motion {
+            lat: -1852.0 / 111_195.0,
+            lon: 0.0,
+            sog: 10.0,
+            cog: 0.0,
+        };
+        let cpa = cpa(own, target).unwrap();
+        assert!(cpa.distance < 1.0, "{}", cpa.distance);
+        assert_eq!(cpa.time, chrono::Duration::seconds(360));
+
+        // both still: no approach at all
+        let target = Motion { sog: 0.0, ..target };
+        assert!(super::cpa(own, target).is_none());
+    }
+}
@@ -0,0 +1,3670 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    path::PathBuf,
+    sync::{mpsc, Arc, Mutex},
+};
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat};
+use nmea::{FixType, Nmea};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ais::Ais,
+    autopilot,
+    clipboard::{self, Copied},
+    datum::{self, Datum},
+    epoch::EpochTracker,
+    export::{self, Altitude, CsvWriter},
+    filter::SentenceFilter,
+    geofence::{Geofence, Zone, ZoneEvent},
+    inspect::{self, Inspection},
+    keys::{key_name, Action, Key, KeyMap, MouseKind},
+    latency::LineGaps,
+    magnetic::{self, Variation},
+    ntrip,
+    pmtk::{self, AckWatch},
+    prefs::{Panel, Prefs},
+    route::{Route, RoutePoint},
+    search::Query,
+    sentence::{with_checksum, Sentence},
+    sky::Sky,
+    source::{DeviceWriter, End, Line},
+    theme::{Theme, ThemeName},
+    ttff::{self, Outcome, Ttff},
+    ubx::{self, Frame, NavDop, NavPvt},
+    vessel::Vessel,
+};
+
+/// Settings which stay fixed for the whole session
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// append one row per satellite per GSV cycle to this CSV file
+    pub snr_log: Option<PathBuf>,
+    /// start averaging at launch and stop after this many seconds
+    pub average: Option<u64>,
+    /// only average fixes with an HDOP below this
+    pub average_max_hdop: f32,
+    /// also average fixes from a 2D solution
+    pub average_allow_2d: bool,
+    /// append the result of each averaging run to this file
+    pub average_out: Option<PathBuf>,
+    /// waypoint export file, as GPX when the extension is .gpx
+    pub waypoints: Option<PathBuf>,
+    /// weight of the newest sample in the smoothed speed
+    pub speed_alpha: f32,
+    /// lines kept in the Messages pane
+    pub messages: usize,
+    /// fixes kept for the track map
+    pub track: usize,
+    /// drop fixes implying a faster move than this
+    pub max_speed: Option<MaxSpeed>,
+    /// user equivalent range error of the receiver in meters, which turns
+    /// HDOP into an accuracy estimate
+    pub uere: f32,
+    /// zones whose entry and exit are reported
+    pub zones: Vec<Zone>,
+    /// route to follow, empty for none
+    pub route: Vec<RoutePoint>,
+    /// meters from a route point at which it counts as reached
+    pub arrival_radius: f64,
+    /// seconds after which an AIS target not heard from is dropped
+    pub ais_timeout: u64,
+    /// only parse sentences from these talkers, all when empty
+    pub accept_talkers: Vec<String>,
+    /// never parse sentences of these types
+    pub ignore_sentences: Vec<String>,
+    /// move receiver dates which look like a GPS week number rollover
+    /// forward by 1024 weeks
+    pub fix_week_rollover: bool,
+    /// which height waypoint exports carry
+    pub altitude: Altitude,
+    /// time the first fix after startup, reconnects and restart commands
+    pub ttff: bool,
+    /// append a CSV row for each time to first fix to this file
+    pub ttff_out: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            snr_log: None,
+            average: None,
+            average_max_hdop: 2.0,
+            average_allow_2d: false,
+            average_out: None,
+            waypoints: None,
+            speed_alpha: SpeedFilter::DEFAULT_ALPHA,
+            messages: 100,
+            track: 300,
+            max_speed: None,
+            uere: DEFAULT_UERE,
+            zones: Vec::new(),
+            route: Vec::new(),
+            arrival_radius: Route::DEFAULT_ARRIVAL_RADIUS,
+            ais_timeout: Ais::DEFAULT_TIMEOUT_SECS,
+            accept_talkers: Vec::new(),
+            ignore_sentences: Vec::new(),
+            fix_week_rollover: false,
+            altitude: Altitude::Msl,
+            ttff: false,
+            ttff_out: None,
+        }
+    }
+}
+
+/// Typical user equivalent range error of a consumer receiver, in meters
+pub const DEFAULT_UERE: f32 = 5.0;
+
+/// Rough horizontal accuracy in meters: HDOP scaled by the range error
+pub fn estimated_accuracy_m(hdop: f32, uere: f32) -> f32 {
+    hdop * uere
+}
+
+/// UTC date and time of the last fix
+pub fn gps_datetime(nmea: &Nmea) -> Option<NaiveDateTime> {
+    let fix_date = nmea.fix_date?;
+    // RMC dates come with a two digit year, ZDA ones with all four
+    let year = match fix_date.year() {
+        year if year < 100 => year + 2000,
+        year => year,
+    };
+    let date = NaiveDate::from_ymd_opt(year, fix_date.month(), fix_date.day())?;
+    Some(date.and_time(nmea.fix_time?))
+}
+
+/// GPS counts weeks in 10 bits; receivers which miss a rollover report dates
+/// this far in the past
+pub const WEEK_ROLLOVER_DAYS: i64 = 1024 * 7;
+
+/// A receiver date further behind the system clock than this is suspect
+const DATE_MAX_BEHIND_DAYS: i64 = 365;
+/// and so is one further ahead than this
+const DATE_MAX_AHEAD_DAYS: i64 = 1;
+
+/// A receiver date which doesn't fit with the system clock
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateWarning {
+    /// over a year behind, as after a missed week number rollover
+    Behind,
+    /// over a day ahead
+    Ahead,
+}
+
+/// Whether the receiver's `gps` date and time fits with `now`, both UTC
+pub fn check_date(gps: NaiveDateTime, now: NaiveDateTime) -> Option<DateWarning> {
+    if now - gps > chrono::Duration::days(DATE_MAX_BEHIND_DAYS) {
+        Some(DateWarning::Behind)
+    } else if gps - now > chrono::Duration::days(DATE_MAX_AHEAD_DAYS) {
+        Some(DateWarning::Ahead)
+    } else {
+        None
+    }
+}
+
+/// `gps` moved forward 1024 weeks at a time while it is over a year behind
+/// `now`, without going past it
+pub fn fix_week_rollover(gps: NaiveDateTime, now: NaiveDateTime) -> NaiveDateTime {
+    let rollover = chrono::Duration::days(WEEK_ROLLOVER_DAYS);
+    let mut fixed = gps;
+    while check_date(fixed, now) == Some(DateWarning::Behind)
+        && check_date(fixed + rollover, now) != Some(DateWarning::Ahead)
+    {
+        fixed += rollover;
+    }
+    fixed
+}
+
+/// What RMC says of the position: its status, and the mode indicator of
+/// NMEA 2.3 and later
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NavStatus {
+    /// status A, where V is void
+    pub active: bool,
+    /// like `A` for autonomous or `E` for dead reckoning
+    pub mode: Option<char>,
+}
+
+impl NavStatus {
+    fn from_rmc(rmc: &Sentence) -> NavStatus {
+        NavStatus {
+            active: rmc.field(1) == Some("A"),
+            mode: rmc.field(11).and_then(|mode| mode.chars().next()),
+        }
+    }
+
+    /// V and N are no fix, whatever else the receiver still sends
+    pub fn valid(self) -> bool {
+        self.active && self.mode != Some('N')
+    }
+
+    /// A position carried on from the last fix by the speed and course, or
+    /// the wheel ticks of automotive units, which drifts the longer it lasts
+    pub fn dead_reckoning(self) -> bool {
+        self.mode == Some('E')
+    }
+
+    /// `Autonomous (A/A)`
+    pub fn describe(self) -> String {
+        let name = match self.mode {
+            _ if !self.valid() => "Not valid",
+            Some('A') => "Autonomous",
+            Some('D') => "Differential",
+            Some('E') => "Estimated (dead reckoning)",
+            Some('F') => "RTK float",
+            Some('R') => "RTK fixed",
+            Some('P') => "Precise",
+            Some('M') => "Manual input",
+            Some('S') => "Simulator",
+            Some(_) => "unknown mode",
+            None => "Valid",
+        };
+        let status = if self.active { 'A' } else { 'V' };
+        match self.mode {
+            Some(mode) => format!("{} ({}/{})", name, status, mode),
+            None => format!("{} ({})", name, status),
+        }
+    }
+}
+
+/// System clock minus the receiver's UTC `time`, for sentences without a
+/// date: the date is taken from the system clock, on whichever side of
+/// midnight is closer
+fn clock_offset_at(local: DateTime<Local>, time: NaiveTime) -> chrono::Duration {
+    let now = local.naive_utc();
+    let offset = now - now.date().and_time(time);
+    let day = chrono::Duration::days(1);
+    if offset > day / 2 {
+        offset - day
+    } else if offset < -day / 2 {
+        offset + day
+    } else {
+        offset
+    }
+}
+
+/// Clock offset beyond which the system clock or the serial path is suspect,
+/// and for how long it has to last before it is flagged
+const CLOCK_SKEW_LIMIT_MS: i64 = 1000;
+const CLOCK_SKEW_SUSTAIN_SECS: i64 = 10;
+
+/// TXT messages kept for the text panel
+const TEXT_CAPACITY: usize = 100;
+const TEXT_SCROLL_STEP: usize = 5;
+/// Lines scrolled by a turn of the mouse wheel
+const WHEEL_STEP: usize = 3;
+
+const KNOTS_TO_MPS: f64 = 1852.0 / 3600.0;
+
+/// Seconds after which the last fix is too old to steer by
+const AUTOPILOT_MAX_AGE_SECS: i64 = 3;
+
+/// Commands kept for recalling at the prompt
+const COMMAND_HISTORY: usize = 20;
+
+/// Sentences handled here even when the nmea crate doesn't parse them
+pub(crate) const LOCAL_SENTENCES: &[&str] = &[
+    "GSV", "GSA", "GST", "ZDA", "GLL", "TXT", "PUBX00", "PUBX03", "PUBX04", "VDM", "VDO", "DPT",
+    "DBT", "MWV", "MTW", "HDT", "HDM", "ROT", "MTK001", "DTM",
+];
+
+/// ZDA is all some timing receivers send to tell the date
+fn zda_datetime(nmea: &mut Nmea, zda: &Sentence) {
+    let date = match (zda.parse_field(1), zda.parse_field(2), zda.parse_field(3)) {
+        (Some(day), Some(month), Some(year)) => NaiveDate::from_ymd_opt(year, month, day),
+        _ => None,
+    };
+    if let (Some(date), Some(time)) = (date, zda.time_field(0)) {
+        nmea.fix_date = Some(date);
+        nmea.fix_time = Some(time);
+    }
+}
+
+/// u-blox position, covering what GGA, RMC and GSA say together; returns
+/// whether the fix is 3D
+fn pubx_position(nmea: &mut Nmea, pubx: &Sentence) -> bool {
+    let nav_stat = pubx.field(7);
+    nmea.fix_type = Some(match nav_stat {
+        Some("G2" | "G3" | "RK") => FixType::Gps,
+        Some("D2" | "D3") => FixType::DGps,
+        Some("DR") => FixType::Estimated,
+        _ => FixType::Invalid,
+    });
+    if let Some(time) = pubx.time_field(1) {
+        nmea.fix_time = Some(time);
+    }
+    nmea.latitude = pubx.coord_field(2);
+    nmea.longitude = pubx.coord_field(4);
+    // the height is above the ellipsoid, kept apart from the MSL altitude
+    // GGA gives
+    nmea.speed_over_ground = pubx.parse_field::<f32>(10).map(|kmh| kmh / 1.852);
+    nmea.true_course = pubx.parse_field(11);
+    nmea.hdop = pubx.parse_field(14);
+    nmea.vdop = pubx.parse_field(15);
+    nmea.num_of_fix_satellites = pubx.parse_field(17);
+    matches!(nav_stat, Some("G3" | "D3"))
+}
+
+/// u-blox time of day and date
+fn pubx_datetime(nmea: &mut Nmea, pubx: &Sentence) {
+    if let (Some(time), Some(date)) = (pubx.time_field(1), pubx.date_field(2)) {
+        nmea.fix_time = Some(time);
+        nmea.fix_date = Some(date);
+    }
+}
+
+/// GLL is the only position sentence of some chartplotters
+fn gll_position(nmea: &mut Nmea, gll: &Sentence) {
+    if gll.field(5) != Some("A") {
+        return;
+    }
+    if let (Some(lat), Some(lon)) = (gll.coord_field(0), gll.coord_field(2)) {
+        nmea.latitude = Some(lat);
+        nmea.longitude = Some(lon);
+        if let Some(time) = gll.time_field(4) {
+            nmea.fix_time = Some(time);
+        }
+        // without GGA nothing else says the position is valid
+        if nmea.fix_type.is_none() {
+            nmea.fix_type = Some(FixType::Gps);
+        }
+    }
+}
+
+pub fn has_fix(nmea: &Nmea) -> bool {
+    nmea.latitude.is_some()
+        && nmea.longitude.is_some()
+        && !matches!(nmea.fix_type, None | Some(FixType::Invalid))
+}
+
+/// A fix as kept in the position ring buffer
+#[derive(Debug, Clone)]
+pub struct Position {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+fn push_position(positions: &mut VecDeque<Position>, nmea: &Nmea, capacity: usize) {
+    if let (Some(latitude), Some(longitude)) = (nmea.latitude, nmea.longitude) {
+        positions.push_back(Position {
+            latitude,
+            longitude,
+        });
+        while positions.len() > capacity {
+            positions.pop_front();
+        }
+    }
+}
+
+/// Exponential moving average of speed over ground, in knots
+#[derive(Debug, Clone)]
+pub struct SpeedFilter {
+    alpha: f32,
+    pub value: Option<f32>,
+}
+
+impl SpeedFilter {
+    pub const DEFAULT_ALPHA: f32 = 0.3;
+
+    pub fn new(alpha: f32) -> SpeedFilter {
+        SpeedFilter { alpha, value: None }
+    }
+
+    /// `s = alpha * x + (1 - alpha) * s`, seeded with the first sample
+    pub fn update(&mut self, sample: f32) -> f32 {
+        let value = match self.value {
+            Some(prev) => self.alpha * sample + (1.0 - self.alpha) * prev,
+            None => sample,
+        };
+        self.value = Some(value);
+        value
+    }
+}
+
+/// Differential corrections from the tail of a GGA sentence, which the nmea
+/// crate doesn't keep
+#[derive(Debug, Clone)]
+pub struct Corrections {
+    /// seconds since the last correction was applied
+    pub age: Option<f32>,
+    pub station: Option<String>,
+}
+
+fn gga_corrections(line: &str) -> Option<Corrections> {
+    let body = line.trim().split('*').next()?;
+    let fields: Vec<&str> = body.split(',').collect();
+    let age = fields.get(13).and_then(|s| s.parse().ok());
+    let station = fields
+        .get(14)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string());
+    if age.is_none() && station.is_none() {
+        return None;
+    }
+    Some(Corrections { age, station })
+}
+
+/// Error estimates from GST, in meters
+#[derive(Debug, Clone)]
+pub struct ErrorStats {
+    /// RMS of the pseudorange residuals
+    pub rms: Option<f32>,
+    pub semi_major: Option<f32>,
+    pub semi_minor: Option<f32>,
+    /// of the semi-major axis, degrees from true north
+    pub orientation: Option<f32>,
+    pub latitude: Option<f32>,
+    pub longitude: Option<f32>,
+    pub altitude: Option<f32>,
+}
+
+fn gst_errors(gst: &Sentence) -> ErrorStats {
+    ErrorStats {
+        rms: gst.parse_field(1),
+        semi_major: gst.parse_field(2),
+        semi_minor: gst.parse_field(3),
+        orientation: gst.parse_field(4),
+        latitude: gst.parse_field(5),
+        longitude: gst.parse_field(6),
+        altitude: gst.parse_field(7),
+    }
+}
+
+fn gsa_fix_3d(line: &str) -> bool {
+    line.split(',').nth(2) == Some("3")
+}
+
+/// A line which was rejected, and why
+#[derive(Debug, Clone)]
+pub struct Rejection {
+    pub local: DateTime<Local>,
+    pub line: String,
+    pub reason: String,
+}
+
+/// Rejected lines, newest first, and how many there were of each type
+#[derive(Debug, Default)]
+pub struct RejectLog {
+    pub lines: VecDeque<Rejection>,
+    /// by sentence type, `?` for lines which aren't sentences: count and
+    /// latest reason
+    counts: BTreeMap<String, (u64, String)>,
+}
+
+impl RejectLog {
+    /// Rejected lines kept for the Messages pane
+    pub const CAPACITY: usize = 100;
+
+    fn push(&mut self, local: DateTime<Local>, kind: &str, line: &str, reason: String) {
+        let entry = self
+            .counts
+            .entry(kind.to_owned())
+            .or_insert_with(|| (0, String::new()));
+        entry.0 += 1;
+        entry.1.clone_from(&reason);
+
+        self.lines.push_front(Rejection {
+            local,
+            line: line.trim().to_owned(),
+            reason,
+        });
+        self.lines.truncate(RejectLog::CAPACITY);
+    }
+
+    /// Type, count and latest reason, most rejected first
+    pub fn breakdown(&self) -> Vec<(&str, u64, &str)> {
+        let mut breakdown: Vec<(&str, u64, &str)> = self
+            .counts
+            .iter()
+            .map(|(kind, (count, reason))| (kind.as_str(), *count, reason.as_str()))
+            .collect();
+        breakdown.sort_by(|a, b| b.1.cmp(&a.1));
+        breakdown
+    }
+}
+
+/// Sentences counted per type over a sliding window
+pub struct SentenceRates {
+    window: chrono::Duration,
+    arrivals: VecDeque<(DateTime<Local>, String)>,
+    first: Option<DateTime<Local>>,
+}
+
+impl SentenceRates {
+    pub const WINDOW_SECS: i64 = 10;
+
+    fn new() -> SentenceRates {
+        SentenceRates {
+            window: chrono::Duration::seconds(SentenceRates::WINDOW_SECS),
+            arrivals: VecDeque::new(),
+            first: None,
+        }
+    }
+
+    fn push(&mut self, local: DateTime<Local>, kind: &str) {
+        self.first.get_or_insert(local);
+        self.arrivals.push_back((local, kind.to_owned()));
+        self.expire(local);
+    }
+
+    fn expire(&mut self, now: DateTime<Local>) {
+        while let Some((t, _)) = self.arrivals.front() {
+            if now - *t <= self.window {
+                break;
+            }
+            self.arrivals.pop_front();
+        }
+    }
+
+    /// Sentences per second by type, highest rate first. Until a full window
+    /// has passed, rates are taken over the time since the first sentence.
+    pub fn rates(&self, now: DateTime<Local>) -> Vec<(String, f64)> {
+        let elapsed = match self.first {
+            Some(first) => (now - first).min(self.window),
+            None => return Vec::new(),
+        };
+        let secs = (elapsed.num_milliseconds() as f64 / 1000.0).max(1.0);
+
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        for (_, name) in self
+            .arrivals
+            .iter()
+            .filter(|(t, _)| now - *t <= self.window)
+        {
+            match counts.iter_mut().find(|(n, _)| n == name) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((name.clone(), 1)),
+            }
+        }
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+            .into_iter()
+            .map(|(name, count)| (name, count as f64 / secs))
+            .collect()
+    }
+}
+
+/// Meters per degree of latitude, precise enough for the small offsets we average
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Running mean and variance of fixes for surveying a fixed point.
+///
+/// Samples are accumulated as north/east/up offsets in meters from the first
+/// fix (Welford's algorithm), which keeps the sums small instead of adding up
+/// thousands of nearly identical absolute coordinates.
+pub struct Averager {
+    started: DateTime<Local>,
+    duration: Option<chrono::Duration>,
+    /// latitude, longitude, altitude of the first sample
+    origin: Option<(f64, f64, f64)>,
+    samples: u64,
+    mean: [f64; 3],
+    m2: [f64; 3],
+}
+
+impl Averager {
+    pub fn new(started: DateTime<Local>, duration: Option<chrono::Duration>) -> Averager {
+        Averager {
+            started,
+            duration,
+            origin: None,
+            samples: 0,
+            mean: [0.0; 3],
+            m2: [0.0; 3],
+        }
+    }
+
+    fn meters_per_degree_lon(lat: f64) -> f64 {
+        METERS_PER_DEGREE * lat.to_radians().cos()
+    }
+
+    pub fn add(&mut self, lat: f64, lon: f64, alt: f64) {
+        let (lat0, lon0, alt0) = *self.origin.get_or_insert((lat, lon, alt));
+        let offset = [
+            (lat - lat0) * METERS_PER_DEGREE,
+            (lon - lon0) * Self::meters_per_degree_lon(lat0),
+            alt - alt0,
+        ];
+
+        self.samples += 1;
+        let n = self.samples as f64;
+        for ((mean, m2), x) in self.mean.iter_mut().zip(self.m2.iter_mut()).zip(&offset) {
+            let delta = x - *mean;
+            *mean += delta / n;
+            *m2 += delta * (x - *mean);
+        }
+    }
+
+    pub fn mean(&self) -> Option<(f64, f64, f64)> {
+        let (lat0, lon0, alt0) = self.origin?;
+        Some((
+            lat0 + self.mean[0] / METERS_PER_DEGREE,
+            lon0 + self.mean[1] / Self::meters_per_degree_lon(lat0),
+            alt0 + self.mean[2],
+        ))
+    }
+
+    /// Sample standard deviation (north, east, up) in meters
+    pub fn std_dev(&self) -> [f64; 3] {
+        if self.samples < 2 {
+            return [0.0; 3];
+        }
+        let n = (self.samples - 1) as f64;
+        [
+            (self.m2[0] / n).sqrt(),
+            (self.m2[1] / n).sqrt(),
+            (self.m2[2] / n).sqrt(),
+        ]
+    }
+
+    pub fn finished(&self, now: DateTime<Local>) -> bool {
+        self.duration.is_some_and(|d| now - self.started >= d)
+    }
+
+    pub fn summary(&self) -> Option<String> {
+        let (lat, lon, alt) = self.mean()?;
+        let [n, e, u] = self.std_dev();
+        Some(format!(
+            "{:.8} / {:.8} / {:.3} (n={}, sd n/e/u={:.3}/{:.3}/{:.3}m)",
+            lat, lon, alt, self.samples, n, e, u
+        ))
+    }
+}
+
+/// Great-circle distance in meters and initial bearing in degrees from
+/// `from` to `to`, both (latitude, longitude)
+pub fn distance_bearing(from: (f64, f64), to: (f64, f64)) -> (f64, f64) {
+    const EARTH_RADIUS: f64 = 6_371_000.0;
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+    let (dlat, dlon) = (lat2 - lat1, lon2 - lon1);
+
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    let distance = 2.0 * EARTH_RADIUS * a.sqrt().asin();
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    let bearing = (y.atan2(x).to_degrees() + 360.0) % 360.0;
+    (distance, bearing)
+}
+
+/// Speed limit of the outlier filter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaxSpeed {
+    /// meters per second
+    Fixed(f64),
+    /// a few times the smoothed speed, and never below `OutlierFilter::AUTO_FLOOR`
+    Auto,
+}
+
+impl std::str::FromStr for MaxSpeed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<MaxSpeed, String> {
+        match s.trim() {
+            "auto" => Ok(MaxSpeed::Auto),
+            s => match s.parse::<f64>() {
+                Ok(speed) if speed > 0.0 => Ok(MaxSpeed::Fixed(speed)),
+                _ => Err(format!(
+                    "expected 'auto' or a speed in m/s above 0, got '{}'",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FixPoint {
+    time: NaiveTime,
+    latitude: f64,
+    longitude: f64,
+}
+
+impl FixPoint {
+    /// Speed in m/s needed to get from `self` to `to`; fixes of the same
+    /// epoch are taken as a second apart
+    fn speed_to(&self, to: &FixPoint) -> f64 {
+        let (distance, _) =
+            distance_bearing((self.latitude, self.longitude), (to.latitude, to.longitude));
+        let mut secs = (to.time - self.time).num_milliseconds() as f64 / 1000.0;
+        if secs < 0.0 {
+            // past midnight
+            secs += 86_400.0;
+        }
+        distance / secs.max(1.0)
+    }
+}
+
+/// Rejects fixes which imply an impossible speed from the last accepted one.
+/// Enough rejected fixes in a row which agree with each other are taken as a
+/// real jump, like after an outage, and the latest of them is accepted.
+#[derive(Debug, Default)]
+pub struct OutlierFilter {
+    last: Option<FixPoint>,
+    suspects: Vec<FixPoint>,
+    /// time of the last fix decided on, and whether it was accepted; GGA,
+    /// RMC and the other position sentences of an epoch share it
+    decided: Option<(NaiveTime, bool)>,
+    /// fixes rejected since launch or the last reset
+    pub rejected: u64,
+}
+
+impl OutlierFilter {
+    /// Fixes which have to agree before a jump is accepted
+    const REJOIN_FIXES: usize = 5;
+    /// Lowest limit in m/s under `MaxSpeed::Auto`, so a receiver at rest
+    /// can still start moving
+    pub const AUTO_FLOOR: f64 = 50.0;
+    const AUTO_FACTOR: f64 = 3.0;
+
+    fn accept(&mut self, point: FixPoint, limit: f64) -> bool {
+        match self.decided {
+            Some((time, accepted)) if time == point.time => accepted,
+            _ => {
+                let accepted = self.decide(point, limit);
+                self.decided = Some((point.time, accepted));
+                accepted
+            }
+        }
+    }
+
+    fn decide(&mut self, point: FixPoint, limit: f64) -> bool {
+        let last = match &self.last {
+            Some(last) => last,
+            None => {
+                self.last = Some(point);
+                return true;
+            }
+        };
+        if last.speed_to(&point) <= limit {
+            self.last = Some(point);
+            self.suspects.clear();
+            return true;
+        }
+
+        if let Some(suspect) = self.suspects.last() {
+            if suspect.speed_to(&point) > limit {
+                self.suspects.clear();
+            }
+        }
+        self.suspects.push(point);
+        if self.suspects.len() >= OutlierFilter::REJOIN_FIXES {
+            self.last = Some(point);
+            self.suspects.clear();
+            return true;
+        }
+        self.rejected += 1;
+        false
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Waypoint {
+    pub seq: usize,
+    pub local: DateTime<Local>,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// above mean sea level
+    pub altitude: Option<f32>,
+    #[serde(default)]
+    pub geoid_separation: Option<f32>,
+    /// above the WGS84 ellipsoid, as u-blox PUBX,00 gives it
+    #[serde(default)]
+    pub ellipsoidal_height: Option<f32>,
+    /// DTM code of the datum the position is in, if the receiver told
+    #[serde(default)]
+    pub datum: Option<String>,
+    pub name: String,
+}
+
+impl Waypoint {
+    /// Current position as a waypoint, refused while there is no valid fix
+    fn mark(
+        seq: usize,
+        local: DateTime<Local>,
+        nmea: &Nmea,
+        geoid_separation: Option<f32>,
+        ellipsoidal_height: Option<f32>,
+        datum: Option<&Datum>,
+    ) -> Option<Waypoint> {
+        if !has_fix(nmea) {
+            return None;
+        }
+        Some(Waypoint {
+            seq,
+            local,
+            latitude: nmea.latitude?,
+            longitude: nmea.longitude?,
+            altitude: nmea.altitude,
+            geoid_separation,
+            ellipsoidal_height,
+            datum: datum.map(|d| d.code.clone()),
+            name: String::new(),
+        })
+    }
+
+    /// Altitude above `reference`; none when one height is known and the
+    /// geoid separation to convert it isn't
+    pub fn altitude_as(&self, reference: Altitude) -> Option<f32> {
+        match reference {
+            Altitude::Msl => self
+                .altitude
+                .or_else(|| Some(self.ellipsoidal_height? - self.geoid_separation?)),
+            Altitude::Ellipsoidal => {
+                export::ellipsoidal_height(self.altitude, self.geoid_separation)
+                    .or(self.ellipsoidal_height)
+            }
+        }
+    }
+}
+
+/// Message type field of TXT
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextLevel {
+    Error,
+    Warning,
+    Notice,
+    User,
+}
+
+impl TextLevel {
+    fn from_field(field: Option<&str>) -> TextLevel {
+        match field {
+            Some("00") => TextLevel::Error,
+            Some("01") => TextLevel::Warning,
+            Some("07") => TextLevel::User,
+            _ => TextLevel::Notice,
+        }
+    }
+}
+
+/// Free-form text from the receiver, like firmware versions and antenna status
+#[derive(Debug, Clone)]
+pub struct TextMessage {
+    pub local: DateTime<Local>,
+    pub level: TextLevel,
+    pub text: String,
+}
+
+/// A TXT message still missing parts
+struct PendingText {
+    total: u32,
+    last: u32,
+    message: TextMessage,
+}
+
+/// "ANTENNA OPEN" and "ANTSTATUS=SHORT" give the problem, "ANTENNA OK" and
+/// "ANTSTATUS=OK" clear it; `None` for text about something else
+fn antenna_status(text: &str) -> Option<Option<String>> {
+    let text = text.trim().to_ascii_uppercase();
+    let status = text
+        .strip_prefix("ANTSTATUS=")
+        .or_else(|| text.strip_prefix("ANTENNA "))?
+        .trim();
+    if status == "OK" {
+        Some(None)
+    } else {
+        Some(Some(format!("antenna {}", status.to_ascii_lowercase())))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageKind {
+    /// a sentence as received
+    Sentence,
+    /// a line which isn't a sentence, only kept in hex view
+    Rejected,
+    /// a sentence held back from the parser by the sentence filter
+    Filtered,
+    /// a command sent to the receiver
+    Sent,
+    Notice,
+    Alert,
+}
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub local: DateTime<Local>,
+    pub kind: MessageKind,
+    pub text: String,
+    /// bytes as received, when `text` doesn't tell them
+    pub raw: Option<Vec<u8>>,
+    /// came from the second receiver
+    pub peer: bool,
+}
+
+impl Message {
+    pub fn bytes(&self) -> &[u8] {
+        self.raw.as_deref().unwrap_or_else(|| self.text.as_bytes())
+    }
+
+    /// What a search looks through: the text after its timestamp
+    pub fn search_text(&self) -> String {
+        format!(
+            "{} {}",
+            self.local.to_rfc3339_opts(SecondsFormat::Secs, true),
+            self.text
+        )
+    }
+}
+
+/// Lines of the Messages pane, newest first
+pub struct MessageLog {
+    pub lines: Vec<Message>,
+    /// index of the line selected for inspection, which stays on that line
+    /// as newer ones arrive
+    pub selected: Option<usize>,
+    /// selection from before the search, which cancelling it goes back to
+    before_search: Option<usize>,
+    /// line scrolled to the top with the mouse wheel, or `None` to follow
+    /// the selection or else the newest line
+    pub top: Option<usize>,
+    capacity: usize,
+}
+
+impl MessageLog {
+    fn new(capacity: usize) -> MessageLog {
+        MessageLog {
+            lines: Vec::with_capacity(capacity),
+            selected: None,
+            before_search: None,
+            top: None,
+            capacity,
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = MessageLog::new(self.capacity);
+    }
+
+    fn push(&mut self, local: DateTime<Local>, kind: MessageKind, text: String) {
+        self.push_message(Message {
+            local,
+            kind,
+            text,
+            raw: None,
+            peer: false,
+        });
+    }
+
+    fn push_message(&mut self, message: Message) {
+        self.lines.insert(0, message);
+        self.lines.truncate(self.capacity);
+        let len = self.lines.len();
+        let shift = |i: Option<usize>| i.map(|i| i + 1).filter(|&i| i < len);
+        self.selected = shift(self.selected);
+        self.before_search = shift(self.before_search);
+        self.top = shift(self.top);
+    }
+
+    /// Selects a line, scrolling back to it
+    fn select(&mut self, selected: Option<usize>) {
+        self.selected = selected;
+        self.top = None;
+    }
+
+    /// Keeps `raw` along with the trimmed text where they differ
+    fn push_raw(&mut self, local: DateTime<Local>, kind: MessageKind, line: &str, raw: &[u8]) {
+        let text = line.trim();
+        self.push(local, kind, text.to_owned());
+        if raw != text.as_bytes() {
+            self.lines[0].raw = Some(raw.to_vec());
+        }
+    }
+}
+
+/// Everything nmeacli knows, fed with sentences by `handle_line`, with key
+/// presses by `handle_key` and with the passing of time by `tick`
+pub struct App {
+    pub nmea: Nmea,
+    pub prefs: Prefs,
+    pub theme: Theme,
+    pub keymap: KeyMap,
+    pub options: Options,
+    pub messages: MessageLog,
+    /// show the bytes of each message in hex, rejected lines included
+    pub hex_view: bool,
+    /// show rejected lines instead of messages
+    pub show_rejected: bool,
+    pub rejected: RejectLog,
+    /// TXT messages, newest first
+    pub texts: Vec<TextMessage>,
+    /// lines the text panel is scrolled down by
+    pub text_scroll: usize,
+    /// antenna problem reported in TXT, until the receiver says it's fine
+    pub antenna: Option<String>,
+    pub rates: SentenceRates,
+    /// bursts of sentences per solution, for the fix rate and its jitter
+    pub epochs: EpochTracker,
+    /// gaps between the lines of the first receiver as they were read
+    pub gaps: LineGaps,
+    pub filter: SentenceFilter,
+    /// sentence type selected in the Rates panel, for toggling its filter
+    pub selected_kind: Option<String>,
+    /// lines accepted as sentences since launch or the last reset
+    pub sentences_parsed: u64,
+    /// lines rejected since launch or the last reset
+    pub sentences_failed: u64,
+    /// bytes the source dropped to find the start of a sentence, once
+    /// opened and after each reconnection
+    pub bytes_skipped: u64,
+    /// lines the readers dropped because the UI was too slow to take them
+    pub lines_dropped: u64,
+    /// how long the latest line waited between its arrival and being
+    /// handled, which grows when the UI falls behind
+    pub queue_lag: Option<std::time::Duration>,
+    /// satellites in view, merged across talkers
+    pub sky: Sky,
+    /// system clock minus GPS time, taken when the last time-bearing
+    /// sentence arrived, transmission delay included
+    pub clock_offset: Option<chrono::Duration>,
+    /// arrival time of the last sentence which carried a valid position
+    pub last_fix: Option<DateTime<Local>>,
+    pub positions: VecDeque<Position>,
+    /// fix time of the last track point; GGA, RMC and the other position
+    /// sentences of an epoch add one between them
+    track_epoch: Option<NaiveTime>,
+    pub averager: Option<Averager>,
+    pub corrections: Option<Corrections>,
+    /// geoid height above the WGS84 ellipsoid, from the last GGA; receivers
+    /// may leave it out
+    pub geoid_separation: Option<f32>,
+    /// height above the WGS84 ellipsoid from the last PUBX,00
+    pub ellipsoidal_height: Option<f32>,
+    /// from the last RMC, or modeled when it had none
+    pub variation: Option<Variation>,
+    /// from the last DTM; receivers which don't send it give WGS84 as a rule
+    pub datum: Option<Datum>,
+    /// from the last RMC
+    pub nav_status: Option<NavStatus>,
+    /// from the last GST
+    pub errors: Option<ErrorStats>,
+    /// times to first fix, when measured
+    pub ttff: Option<Ttff>,
+    /// latest UBX NAV-PVT and its arrival time, for the accuracy estimates
+    /// and carrier solution NMEA doesn't tell
+    pub nav_pvt: Option<(DateTime<Local>, NavPvt)>,
+    /// latest UBX NAV-DOP
+    pub nav_dop: Option<NavDop>,
+    pub speed_filter: SpeedFilter,
+    pub outliers: OutlierFilter,
+    pub geofence: Geofence,
+    pub route: Option<Route>,
+    /// vessels heard over AIS
+    pub ais: Ais,
+    /// depth, wind and the other instruments on a boat
+    pub vessel: Vessel,
+    pub waypoints: Vec<Waypoint>,
+    /// second receiver, fed from a source of its own and shown side by side
+    /// with this one
+    pub peer: Option<Box<App>>,
+    /// where lines come from, to tell receivers apart
+    pub source: String,
+    /// why the source stopped, once it did
+    pub ended: Option<End>,
+    /// set by the reopen key, for the main loop to open the ended sources
+    /// again
+    pub reopen: bool,
+    /// index of the waypoint distance and bearing are shown to
+    pub target: Option<usize>,
+    /// waypoint being named in the input prompt
+    pub naming: Option<Waypoint>,
+    /// sends commands to the receiver, when the source can be written to
+    pub writer: Option<DeviceWriter>,
+    /// client feeding the receiver RTK corrections, which is given its GGA
+    pub ntrip: Option<Arc<Mutex<ntrip::Status>>>,
+    /// command being typed at the prompt
+    pub command: Option<String>,
+    /// search being typed at the prompt
+    pub search_input: Option<String>,
+    /// search of the Messages pane whose matches `n` and `N` go through
+    pub search: Option<Query>,
+    /// commands sent, oldest first
+    pub command_history: Vec<String>,
+    /// entry of the history shown at the prompt
+    history_pos: Option<usize>,
+    /// PMTK commands waiting for their reply
+    pub acks: AckWatch,
+    /// entry selected in the PMTK menu, while it is open
+    pub pmtk_menu: Option<usize>,
+    pub show_help: bool,
+    /// panel clicked last, drawn highlighted
+    pub focus: Option<Panel>,
+    /// panels hidden by key, the last hidden last
+    pub hidden_panels: Vec<Panel>,
+    /// only speed, course and fix in big digits, instead of the panels
+    pub zen: bool,
+    /// set while asking whether to reset the receiver state
+    pub confirm_reset: bool,
+    /// the selected message broken into fields, while the popup is open
+    pub inspector: Option<Inspection>,
+    /// confirmation shown in the Status title for a few seconds
+    pub toast: Option<(DateTime<Local>, String)>,
+    /// set while the receiver's date doesn't fit with the system clock
+    pub date_warning: Option<DateWarning>,
+    /// set once a quit key was pressed
+    pub quit: bool,
+    /// time of the latest line or tick, which rendering takes as the present
+    pub now: DateTime<Local>,
+    /// since when `clock_offset` has been beyond the limit
+    skewed_since: Option<DateTime<Local>>,
+    /// from GSA or PUBX,00
+    pub fix_3d: bool,
+    pending_text: Option<PendingText>,
+    /// arrival time of the first GSV sentence of the cycle in progress
+    gsv_cycle: Option<DateTime<Local>>,
+    csv: CsvWriter,
+    notices: mpsc::Receiver<Result<String, String>>,
+}
+
+impl App {
+    pub fn new(options: Options, mut prefs: Prefs, theme: Theme, keymap: KeyMap) -> App {
+        let now = Local::now();
+        let (notice_tx, notices) = mpsc::channel();
+        let csv = CsvWriter::spawn(options.snr_log.clone(), notice_tx);
+        let averager = options
+            .average
+            .map(|secs| Averager::new(now, Some(chrono::Duration::seconds(secs as i64))));
+
+        App {
+            nmea: Nmea::new(),
+            prefs,
+            theme,
+            keymap,
+            messages: MessageLog::new(options.messages),
+            hex_view: false,
+            show_rejected: false,
+            rejected: RejectLog::default(),
+            texts: Vec::new(),
+            text_scroll: 0,
+            antenna: None,
+            rates: SentenceRates::new(),
+            epochs: EpochTracker::default(),
+            gaps: LineGaps::default(),
+            filter: SentenceFilter::new(&options.accept_talkers, &options.ignore_sentences),
+            selected_kind: None,
+            sentences_parsed: 0,
+            sentences_failed: 0,
+            bytes_skipped: 0,
+            lines_dropped: 0,
+            queue_lag: None,
+            sky: Sky::new(),
+            clock_offset: None,
+            last_fix: None,
+            positions: VecDeque::with_capacity(options.track),
+            track_epoch: None,
+            averager,
+            corrections: None,
+            geoid_separation: None,
+            ellipsoidal_height: None,
+            variation: None,
+            datum: None,
+            nav_status: None,
+            errors: None,
+            ttff: if options.ttff {
+                Some(Ttff::default())
+            } else {
+                None
+            },
+            nav_pvt: None,
+            nav_dop: None,
+            speed_filter: SpeedFilter::new(options.speed_alpha),
+            outliers: OutlierFilter::default(),
+            geofence: Geofence::new(options.zones.clone()),
+            route: if options.route.is_empty() {
+                None
+            } else {
+                Some(Route::new(options.route.clone(), options.arrival_radius))
+            },
+            ais: Ais::new(options.ais_timeout),
+            vessel: Vessel::default(),
+            waypoints: std::mem::take(&mut prefs.waypoints),
+            peer: None,
+            source: String::new(),
+            ended: None,
+            reopen: false,
+            target: None,
+            naming: None,
+            writer: None,
+            ntrip: None,
+            command: None,
+            search_input: None,
+            search: None,
+            command_history: Vec::new(),
+            history_pos: None,
+            acks: AckWatch::default(),
+            pmtk_menu: None,
+            show_help: false,
+            focus: None,
+            hidden_panels: Vec::new(),
+            zen: false,
+            confirm_reset: false,
+            inspector: None,
+            toast: None,
+            date_warning: None,
+            quit: false,
+            now,
+            skewed_since: None,
+            fix_3d: false,
+            pending_text: None,
+            gsv_cycle: None,
+            csv,
+            notices,
+            options,
+        }
+    }
+
+    /// An app with the default options, preferences and keys and no colors
+    pub fn with_defaults() -> App {
+        App::new(
+            Options::default(),
+            Prefs::default(),
+            Theme::new(ThemeName::Mono),
+            KeyMap::default(),
+        )
+    }
+
+    pub fn notice(&mut self, msg: String) {
+        log::info!("{}", msg);
+        self.messages.push(self.now, MessageKind::Notice, msg);
+    }
+
+    pub fn alert(&mut self, msg: String) {
+        log::warn!("{}", msg);
+        self.messages.push(self.now, MessageKind::Alert, msg);
+    }
+
+    /// Handles a line which just arrived
+    pub fn handle_line(&mut self, line: &str) {
+        self.handle_line_at(Local::now(), line);
+    }
+
+    pub fn handle_line_at(&mut self, local: DateTime<Local>, line: &str) {
+        self.ingest(local, line, line.as_bytes());
+    }
+
+    /// Handles a line as read from a `Source`, which may not be UTF-8, or a
+    /// UBX packet
+    pub fn handle_raw_at(&mut self, local: DateTime<Local>, raw: &[u8]) {
+        match Frame::parse(raw) {
+            Some(frame) => self.ingest_ubx(local, &frame, raw),
+            None => self.ingest(local, &String::from_utf8_lossy(raw), raw),
+        }
+    }
+
+    fn ingest_ubx(&mut self, local: DateTime<Local>, frame: &Frame, raw: &[u8]) {
+        self.now = local;
+        let name = ubx::frame_name(frame.class, frame.id);
+        let text = match frame.summary() {
+            Some(summary) => format!("UBX {}: {}", name, summary),
+            None => format!("UBX {}, {} bytes", name, frame.payload.len()),
+        };
+        self.messages
+            .push_raw(local, MessageKind::Sentence, &text, raw);
+        self.rates.push(local, &name);
+        match (frame.class, frame.id) {
+            (ubx::CLASS_NAV, ubx::ID_NAV_PVT) => {
+                self.nav_pvt = frame.nav_pvt().map(|pvt| (local, pvt));
+            }
+            (ubx::CLASS_NAV, ubx::ID_NAV_DOP) => self.nav_dop = frame.nav_dop(),
+            _ => {}
+        }
+    }
+
+    /// Starts comparing with a second receiver, which reads `source`. It
+    /// writes no files of its own, and zones and the route only apply to the
+    /// first receiver.
+    pub fn add_peer(&mut self, source: String) {
+        let options = Options {
+            snr_log: None,
+            average: None,
+            average_out: None,
+            ttff_out: None,
+            waypoints: None,
+            zones: Vec::new(),
+            route: Vec::new(),
+            ..self.options.clone()
+        };
+        let mut peer = App::new(options, Prefs::default(), self.theme, KeyMap::default());
+        peer.source = source;
+        self.peer = Some(Box::new(peer));
+    }
+
+    /// A line from a reader thread, whose arrival counts towards the gaps
+    /// between lines
+    pub fn handle_read(&mut self, line: &Line) {
+        if line.id == 0 {
+            self.gaps.push(line.arrival);
+        }
+        self.queue_lag = Some(line.arrival.elapsed());
+        match (line.id, &mut self.peer) {
+            (1, Some(peer)) => peer.bytes_skipped += line.skipped,
+            _ => self.bytes_skipped += line.skipped,
+        }
+        self.handle_raw_from(line.id, line.local, &line.raw);
+    }
+
+    /// Source `id` stopped: 0 is this receiver, 1 the peer
+    pub fn source_ended(&mut self, id: usize, end: End) {
+        let name = match (id, &mut self.peer) {
+            (1, Some(peer)) => {
+                peer.ended = Some(end.clone());
+                peer.source.clone()
+            }
+            _ => {
+                self.ended = Some(end.clone());
+                self.source.clone()
+            }
+        };
+        self.alert(format!("{} {}", name, end));
+    }
+
+    pub fn has_ended(&self, id: usize) -> bool {
+        match (id, &self.peer) {
+            (1, Some(peer)) => peer.ended.is_some(),
+            _ => self.ended.is_some(),
+        }
+    }
+
+    /// Source `id` was opened again and is being read; the first fix is
+    /// timed from its first sentence again
+    pub fn source_reopened(&mut self, id: usize) {
+        let (name, ttff) = match (id, &mut self.peer) {
+            (1, Some(peer)) => {
+                peer.ended = None;
+                (peer.source.clone(), &mut peer.ttff)
+            }
+            _ => {
+                self.ended = None;
+                (self.source.clone(), &mut self.ttff)
+            }
+        };
+        if let Some(ttff) = ttff {
+            ttff.opened();
+        }
+        self.notice(format!("reopened {}", name));
+    }
+
+    /// Shown across the screen while a source has ended, with the keys which
+    /// quit and reopen
+    pub fn end_notice(&self) -> Option<String> {
+        let peer_end = self.peer.as_ref().and_then(|peer| peer.ended.as_ref());
+        let (what, end) = match (&self.ended, peer_end) {
+            (Some(end), _) => ("source", end),
+            (None, Some(end)) => ("second source", end),
+            (None, None) => return None,
+        };
+        Some(format!(
+            "{} {} — press {} to quit or {} to reopen",
+            what,
+            end,
+            self.key_hint(Action::Quit),
+            self.key_hint(Action::Reopen)
+        ))
+    }
+
+    /// The first key bound to `action`, or its name when none is
+    pub fn key_hint(&self, action: Action) -> String {
+        self.keymap
+            .keys(action)
+            .first()
+            .map_or_else(|| action.name().to_owned(), |&key| key_name(key))
+    }
+
+    /// Handles a line from source `id`: 0 is this receiver, 1 the peer
+    pub fn handle_raw_from(&mut self, id: usize, local: DateTime<Local>, raw: &[u8]) {
+        match (id, &mut self.peer) {
+            (1, Some(peer)) => {
+                peer.handle_raw_at(local, raw);
+                // both receivers share one Messages pane
+                for mut message in peer.messages.lines.drain(..).rev() {
+                    message.peer = true;
+                    self.messages.push_message(message);
+                }
+                self.now = local;
+            }
+            _ => self.handle_raw_at(local, raw),
+        }
+    }
+
+    /// Flags a receiver date which doesn't fit with the system clock, and
+    /// corrects a missed week number rollover if asked to, in place so the
+    /// display, `--format` and the freeze frame all see the corrected date
+    fn check_date(&mut self, local: DateTime<Local>) {
+        let gps = match gps_datetime(&self.nmea) {
+            Some(gps) => gps,
+            None => return,
+        };
+        let now = local.naive_utc();
+        let gps = if self.options.fix_week_rollover {
+            let fixed = fix_week_rollover(gps, now);
+            if fixed != gps {
+                self.nmea.fix_date = Some(fixed.date());
+            }
+            fixed
+        } else {
+            gps
+        };
+        let warning = check_date(gps, now);
+        if warning != self.date_warning {
+            // not an alert: a replayed log is as old as it is
+            match warning {
+                Some(DateWarning::Behind) => {
+                    log::warn!("receiver date {} is over a year old", gps.date())
+                }
+                Some(DateWarning::Ahead) => {
+                    log::warn!("receiver date {} is in the future", gps.date())
+                }
+                None => {}
+            }
+        }
+        self.date_warning = warning;
+    }
+
+    fn update_variation(&mut self, local: DateTime<Local>, rmc: &Sentence) {
+        self.variation = match magnetic::rmc_variation(rmc) {
+            Some(degrees) => Some(Variation {
+                degrees,
+                modeled: false,
+            }),
+            None => self.modeled_variation(local),
+        };
+    }
+
+    /// Takes V and N as a lost fix, which GGA of the next epoch may bring
+    /// back, and tells when RMC's validity changes
+    fn update_nav_status(&mut self, rmc: &Sentence) {
+        let status = NavStatus::from_rmc(rmc);
+        let was_valid = self.nav_status.is_some_and(NavStatus::valid);
+        if !status.valid() {
+            self.nmea.fix_type = Some(FixType::Invalid);
+        } else if !was_valid && matches!(self.nmea.fix_type, Some(FixType::Invalid)) {
+            // undone for receivers which send RMC alone
+            self.nmea.fix_type = Some(FixType::Gps);
+        }
+        if was_valid && !status.valid() {
+            self.alert(format!("fix lost: {}", status.describe()));
+        } else if self.nav_status.is_some() && !was_valid && status.valid() {
+            self.notice(format!("fix regained: {}", status.describe()));
+        }
+        if status.dead_reckoning() && !self.nav_status.is_some_and(NavStatus::dead_reckoning) {
+            self.alert("dead reckoning: the position is estimated".to_owned());
+        }
+        self.nav_status = Some(status);
+    }
+
+    /// Times the first fix after the start, when asked to
+    fn update_ttff(&mut self, local: DateTime<Local>, sentence: &Sentence) {
+        let ttff = match &mut self.ttff {
+            Some(ttff) => ttff,
+            None => return,
+        };
+        if ttff::boot_message(sentence) {
+            ttff.booted(local);
+        }
+        let measurement = match ttff.push(local, sentence, self.nmea.num_of_fix_satellites) {
+            Some(Outcome::Fix(measurement)) => measurement,
+            Some(Outcome::AlreadyFixed) => {
+                self.notice("TTFF: the receiver had a fix already".to_owned());
+                return;
+            }
+            None => return,
+        };
+        if let Some(path) = self.options.ttff_out.clone() {
+            if let Err(e) = export::append_ttff(&path, &measurement) {
+                self.alert(format!("failed to write {}: {}", path.display(), e));
+            }
+        }
+        self.notice(format!("TTFF {}", measurement.describe()));
+    }
+
+    /// Warns when positions turn out not to be WGS84, once per change
+    fn update_datum(&mut self, dtm: &Sentence) {
+        let datum = match datum::dtm_datum(dtm) {
+            Some(datum) => datum,
+            None => return,
+        };
+        let was_wgs84 = self.datum.as_ref().is_none_or(Datum::is_wgs84);
+        let changed = self.datum.as_ref().map(|d| &d.code) != Some(&datum.code);
+        if changed && !datum.is_wgs84() {
+            self.alert(format!(
+                "positions are in the {} datum, not WGS84",
+                datum.name()
+            ));
+        } else if changed && !was_wgs84 {
+            self.notice("positions are in WGS84 again".to_owned());
+        }
+        self.datum = Some(datum);
+    }
+
+    #[cfg(feature = "magnetic-model")]
+    fn modeled_variation(&self, local: DateTime<Local>) -> Option<Variation> {
+        let year = local.year() as f64 + local.ordinal0() as f64 / 365.25;
+        magnetic::model_variation(self.nmea.latitude?, self.nmea.longitude?, year)
+    }
+
+    #[cfg(not(feature = "magnetic-model"))]
+    fn modeled_variation(&self, _local: DateTime<Local>) -> Option<Variation> {
+        None
+    }
+
+    /// Distance in meters and bearing in degrees from this receiver's fix to
+    /// the peer's
+    pub fn baseline(&self) -> Option<(f64, f64)> {
+        let peer = self.peer.as_ref()?;
+        if !has_fix(&self.nmea) || !has_fix(&peer.nmea) {
+            return None;
+        }
+        Some(distance_bearing(
+            (self.nmea.latitude?, self.nmea.longitude?),
+            (peer.nmea.latitude?, peer.nmea.longitude?),
+        ))
+    }
+
+    fn reject(
+        &mut self,
+        local: DateTime<Local>,
+        line: &str,
+        raw: &[u8],
+        kind: &str,
+        reason: String,
+    ) {
+        self.sentences_failed += 1;
+        log::debug!("rejected {:?}: {}", line, reason);
+        self.rejected.push(local, kind, line, reason);
+        if self.hex_view {
+            self.messages
+                .push_raw(local, MessageKind::Rejected, line, raw);
+        }
+    }
+
+    fn ingest(&mut self, local: DateTime<Local>, line: &str, raw: &[u8]) {
+        self.now = local;
+        let sentence = match Sentence::parse(line) {
+            Some(sentence) => sentence,
+            None => {
+                let reason = if line.trim().starts_with(|c| c == '$' || c == '!') {
+                    "bad checksum"
+                } else {
+                    "not a sentence"
+                };
+                self.reject(local, line, raw, "?", reason.to_owned());
+                return;
+            }
+        };
+        if !self.filter.check(&sentence) {
+            // shown and counted, but kept away from the state
+            self.rates.push(local, sentence.name());
+            self.messages
+                .push_raw(local, MessageKind::Filtered, line, raw);
+            return;
+        }
+        let previous = (self.nmea.latitude, self.nmea.longitude, self.nmea.altitude);
+        // sentences the nmea crate rejects still count when parsed here
+        let parsed = self.nmea.parse(line);
+        if let Err(e) = &parsed {
+            if !LOCAL_SENTENCES.contains(&sentence.kind) {
+                let reason = e.to_string();
+                self.reject(local, line, raw, sentence.name(), reason);
+                return;
+            }
+        }
+        // VDO is about own ship, which the receiver already tells
+        if sentence.kind == "VDM" {
+            if let Err(reason) = self.ais.handle(local, &sentence) {
+                self.reject(local, line, raw, sentence.kind, reason);
+                return;
+            }
+        }
+        self.sentences_parsed += 1;
+        let kind = sentence.name();
+
+        self.messages
+            .push_raw(local, MessageKind::Sentence, line, raw);
+
+        self.rates.push(local, kind);
+        self.epochs.push(local, &sentence);
+
+        self.sky.handle(local, &sentence);
+        if kind == "GSV" {
+            self.gsv_cycle.get_or_insert(local);
+        } else if let Some(cycle) = self.gsv_cycle.take() {
+            self.csv.log_cycle(cycle, export::sat_csv_rows(&self.sky));
+        }
+
+        match kind {
+            "GST" => self.errors = Some(gst_errors(&sentence)),
+            "RMC" => {
+                self.update_variation(local, &sentence);
+                self.update_nav_status(&sentence);
+            }
+            "DTM" => self.update_datum(&sentence),
+            "ZDA" => zda_datetime(&mut self.nmea, &sentence),
+            "GLL" => gll_position(&mut self.nmea, &sentence),
+            "TXT" => self.handle_txt(local, &sentence),
+            "MTK001" => {
+                if let Some(ack) = self.acks.reply(&sentence) {
+                    if ack.succeeded() {
+                        self.notice(ack.describe());
+                    } else {
+                        self.alert(ack.describe());
+                    }
+                }
+            }
+            "PUBX00" => {
+                self.fix_3d = pubx_position(&mut self.nmea, &sentence);
+                self.ellipsoidal_height = sentence.parse_field(6);
+            }
+            "PUBX04" => pubx_datetime(&mut self.nmea, &sentence),
+            "DPT" | "DBT" | "MWV" | "MTW" | "HDT" | "HDM" | "ROT" => {
+                self.vessel.handle(local, &sentence)
+            }
+            _ => {}
+        }
+        self.update_ttff(local, &sentence);
+
+        if matches!(kind, "RMC" | "ZDA" | "PUBX04") {
+            self.check_date(local);
+        }
+
+        // these carry date and time of the same epoch; both sides are UTC,
+        // whatever the local time zone is
+        let offset = match kind {
+            "RMC" | "ZDA" | "PUBX04" => gps_datetime(&self.nmea).map(|gps| local.naive_utc() - gps),
+            "GGA" | "GST" => sentence.time_field(0).map(|t| clock_offset_at(local, t)),
+            "PUBX00" => sentence.time_field(1).map(|t| clock_offset_at(local, t)),
+            "GLL" => sentence.time_field(4).map(|t| clock_offset_at(local, t)),
+            _ => None,
+        };
+        if let Some(offset) = offset {
+            self.update_clock_offset(local, offset);
+        }
+
+        // a void GLL or RMC leaves the last position in place
+        let void = match kind {
+            "GLL" => sentence.field(5) != Some("A"),
+            "RMC" => !self.nav_status.is_some_and(NavStatus::valid),
+            _ => false,
+        };
+        let position = matches!(kind, "GGA" | "RMC" | "GLL" | "PUBX00");
+        let outlier = position && !void && has_fix(&self.nmea) && self.is_outlier(local);
+        if outlier {
+            // the sentence stays in Messages but the last position is kept
+            let (latitude, longitude, altitude) = previous;
+            self.nmea.latitude = latitude;
+            self.nmea.longitude = longitude;
+            self.nmea.altitude = altitude;
+        } else if position && !void && has_fix(&self.nmea) {
+            self.last_fix = Some(local);
+            let epoch = self.nmea.fix_time;
+            if epoch.is_none() || epoch != self.track_epoch {
+                self.track_epoch = epoch;
+                push_position(&mut self.positions, &self.nmea, self.options.track);
+            }
+            if let (Some(lat), Some(lon)) = (self.nmea.latitude, self.nmea.longitude) {
+                for event in self.geofence.update(local, lat, lon) {
+                    let time = local.format("%H:%M:%S");
+                    self.notice(match event {
+                        ZoneEvent::Entered(name) => format!("entered {} ({})", name, time),
+                        ZoneEvent::Left(name) => format!("left {} ({})", name, time),
+                    });
+                }
+                if let Some(name) = self.route.as_mut().and_then(|r| r.update(lat, lon)) {
+                    self.notice(format!("arrived at {}", name));
+                }
+            }
+        }
+
+        if matches!(kind, "RMC" | "VTG" | "PUBX00") {
+            if let Some(speed) = self.nmea.speed_over_ground {
+                self.speed_filter.update(speed);
+            }
+        }
+
+        if kind == "GSA" {
+            self.fix_3d = gsa_fix_3d(line);
+        }
+
+        if kind == "GGA" {
+            self.corrections = gga_corrections(line);
+            self.geoid_separation = sentence.parse_field(10);
+            // a VRS caster computes corrections for this position
+            if let (Some(ntrip), true) = (&self.ntrip, has_fix(&self.nmea)) {
+                ntrip.lock().unwrap().gga = Some(line.trim().to_owned());
+            }
+        }
+
+        // GGA and PUBX,00 are the only position sentences with altitude, and
+        // a receiver sends one of them, so this is one sample per epoch
+        if let (false, "GGA" | "PUBX00", Some(avg)) = (outlier, kind, &mut self.averager) {
+            let nmea = &self.nmea;
+            let max_hdop = self.options.average_max_hdop;
+            let quality = has_fix(nmea)
+                && (self.fix_3d || self.options.average_allow_2d)
+                && nmea.hdop.is_some_and(|hdop| hdop < max_hdop);
+            // PUBX,00 only gives the height above the ellipsoid
+            let alt = match kind {
+                "PUBX00" => self.ellipsoidal_height,
+                _ => nmea.altitude,
+            };
+            if let (true, Some(lat), Some(lon), Some(alt)) =
+                (quality, nmea.latitude, nmea.longitude, alt)
+            {
+                avg.add(lat, lon, alt as f64);
+            }
+        }
+    }
+
+    fn update_clock_offset(&mut self, local: DateTime<Local>, offset: chrono::Duration) {
+        self.clock_offset = Some(offset);
+        if offset.num_milliseconds().abs() > CLOCK_SKEW_LIMIT_MS {
+            self.skewed_since.get_or_insert(local);
+        } else {
+            self.skewed_since = None;
+        }
+    }
+
+    /// True once the clock offset has stayed beyond the limit for a while,
+    /// which usually means NTP is off or the serial path buffers too much
+    pub fn clock_skewed(&self) -> bool {
+        self.skewed_since
+            .is_some_and(|since| (self.now - since).num_seconds() >= CLOCK_SKEW_SUSTAIN_SECS)
+    }
+
+    /// Runs the current position through the outlier filter, when enabled
+    fn is_outlier(&mut self, local: DateTime<Local>) -> bool {
+        let limit = match self.options.max_speed {
+            Some(MaxSpeed::Fixed(speed)) => speed,
+            Some(MaxSpeed::Auto) => {
+                let speed = self.speed_filter.value.unwrap_or(0.0) as f64 * KNOTS_TO_MPS;
+                (speed * OutlierFilter::AUTO_FACTOR).max(OutlierFilter::AUTO_FLOOR)
+            }
+            None => return false,
+        };
+        let (latitude, longitude) = match (self.nmea.latitude, self.nmea.longitude) {
+            (Some(latitude), Some(longitude)) => (latitude, longitude),
+            _ => return false,
+        };
+        let point = FixPoint {
+            time: self
+                .nmea
+                .fix_time
+                .unwrap_or_else(|| local.naive_utc().time()),
+            latitude,
+            longitude,
+        };
+        !self.outliers.accept(point, limit)
+    }
+
+    /// Reassembles multi-part TXT messages
+    fn handle_txt(&mut self, local: DateTime<Local>, txt: &Sentence) {
+        let (total, num) = match (txt.parse_field::<u32>(0), txt.parse_field::<u32>(1)) {
+            (Some(total), Some(num)) => (total, num),
+            _ => return,
+        };
+        let text = txt.fields.get(3..).unwrap_or(&[]).join(",");
+
+        if num == 1 {
+            self.pending_text = Some(PendingText {
+                total,
+                last: 0,
+                message: TextMessage {
+                    local,
+                    level: TextLevel::from_field(txt.field(2)),
+                    text: String::new(),
+                },
+            });
+        }
+        let pending = match self.pending_text.as_mut() {
+            Some(pending) if pending.total == total && pending.last + 1 == num => pending,
+            _ => {
+                self.pending_text = None;
+                return;
+            }
+        };
+        pending.last = num;
+        pending.message.text.push_str(&text);
+        if num < total {
+            return;
+        }
+
+        let message = match self.pending_text.take() {
+            Some(pending) => pending.message,
+            None => return,
+        };
+        if let Some(antenna) = antenna_status(&message.text) {
+            self.antenna = antenna;
+        }
+        self.texts.insert(0, message);
+        self.texts.truncate(TEXT_CAPACITY);
+        // keep the lines on screen put while new ones arrive at the top
+        if self.text_scroll > 0 {
+            self.text_scroll = (self.text_scroll + 1).min(self.texts.len() - 1);
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.tick_at(Local::now());
+    }
+
+    /// Collects outcomes of background writes, drops satellites gone out of
+    /// view and AIS targets gone quiet, gives up on unanswered PMTK commands
+    /// and ends a timed averaging run
+    pub fn tick_at(&mut self, now: DateTime<Local>) {
+        self.now = now;
+        self.sky.expire(now);
+        self.ais.expire(now);
+        if let Some(peer) = &mut self.peer {
+            peer.tick_at(now);
+        }
+
+        for command in self.acks.expire(now) {
+            self.alert(format!("PMTK{:03}: no reply", command));
+        }
+
+        while let Ok(msg) = self.notices.try_recv() {
+            match msg {
+                Ok(msg) => self.notice(msg),
+                Err(msg) => self.alert(msg),
+            }
+        }
+
+        if self.averager.as_ref().is_some_and(|avg| avg.finished(now)) {
+            self.stop_average();
+        }
+    }
+
+    fn stop_average(&mut self) {
+        let avg = match self.averager.take() {
+            Some(avg) => avg,
+            None => return,
+        };
+        let msg = match avg.summary() {
+            Some(summary) => {
+                if let Some(path) = self.options.average_out.clone() {
+                    if let Err(e) = export::append_average(&path, self.now, &summary) {
+                        let msg = format!("failed to write {}: {}", path.display(), e);
+                        self.alert(msg);
+                    }
+                }
+                format!("average: {}", summary)
+            }
+            None => "averaging stopped without any usable fix".to_owned(),
+        };
+        self.notice(msg);
+    }
+
+    fn skip_route_point(&mut self, forward: bool) {
+        let msg = match &mut self.route {
+            Some(route) => {
+                if forward {
+                    route.next();
+                } else {
+                    route.prev();
+                }
+                match route.target() {
+                    Some(point) => format!("heading for {}", point.name),
+                    None => "route finished".to_owned(),
+                }
+            }
+            None => "no route loaded".to_owned(),
+        };
+        self.notice(msg);
+    }
+
+    /// Numbers waypoints left without a name
+    fn save_waypoint(&mut self, mut waypoint: Waypoint) {
+        if waypoint.name.trim().is_empty() {
+            waypoint.name = format!("WP{:03}", waypoint.seq);
+        }
+        self.notice(format!("marked waypoint {}", waypoint.name));
+        self.waypoints.push(waypoint);
+    }
+
+    pub fn target_waypoint(&self) -> Option<&Waypoint> {
+        self.waypoints.get(self.target?)
+    }
+
+    /// Distance in meters and bearing in degrees from the current position to `waypoint`
+    pub fn distance_to(&self, waypoint: &Waypoint) -> Option<(f64, f64)> {
+        let here = (self.nmea.latitude?, self.nmea.longitude?);
+        Some(distance_bearing(
+            here,
+            (waypoint.latitude, waypoint.longitude),
+        ))
+    }
+
+    /// APB and XTE for the active route leg; none without a recent valid
+    /// fix, so an autopilot doesn't follow a stale position
+    pub fn autopilot_sentences(&self) -> Option<Vec<String>> {
+        let last_fix = self.last_fix?;
+        if !has_fix(&self.nmea)
+            || self.now - last_fix > chrono::Duration::seconds(AUTOPILOT_MAX_AGE_SECS)
+        {
+            return None;
+        }
+        let nav = self.route.as_ref()?.navigation(
+            self.nmea.latitude?,
+            self.nmea.longitude?,
+            self.nmea.speed_over_ground,
+        )?;
+        Some(vec![autopilot::apb(&nav), autopilot::xte(&nav)])
+    }
+
+    /// Sends a sentence to the receiver, with `$` and the checksum added;
+    /// any typed in are replaced
+    pub fn send_command(&mut self, input: &str) {
+        let body = input.trim().trim_start_matches('$');
+        let body = body.split('*').next().unwrap_or_default().trim();
+        if body.is_empty() {
+            return;
+        }
+        if self.command_history.last().map(String::as_str) != Some(body) {
+            self.command_history.push(body.to_owned());
+            if self.command_history.len() > COMMAND_HISTORY {
+                self.command_history.remove(0);
+            }
+        }
+
+        let line = with_checksum(body);
+        let result = match &mut self.writer {
+            Some(writer) => writer.write_line(&line),
+            None => {
+                self.alert(format!("can't send {}: the source is read-only", line));
+                return;
+            }
+        };
+        match result {
+            Ok(()) => {
+                self.messages.push(self.now, MessageKind::Sent, line);
+                if let Some(command) = pmtk::acked_command(body) {
+                    self.acks.expect(command, self.now);
+                }
+                if let Some(start_type) = ttff::restart_command(body) {
+                    self.restart_sent(start_type);
+                }
+            }
+            Err(e) => self.alert(format!("failed to send {}: {}", line, e)),
+        }
+    }
+
+    /// The receiver drops its fix to restart; the reacquisition is timed
+    /// when measuring TTFF
+    fn restart_sent(&mut self, start_type: ttff::StartType) {
+        if has_fix(&self.nmea) {
+            self.nmea.fix_type = Some(FixType::Invalid);
+        }
+        match &mut self.ttff {
+            Some(ttff) => {
+                ttff.command_sent(self.now, start_type);
+                self.alert(format!(
+                    "fix lost: {} start, timing the first fix",
+                    start_type
+                ));
+            }
+            None => self.alert(format!("fix lost: {} start", start_type)),
+        }
+    }
+
+    /// Keys for the PMTK menu: Up and Down select, Enter sends, anything
+    /// else closes it
+    fn pmtk_menu_key(&mut self, selected: usize, key: Key) {
+        match key {
+            Key::Up => self.pmtk_menu = Some(selected.saturating_sub(1)),
+            Key::Down => self.pmtk_menu = Some((selected + 1).min(pmtk::PRESETS.len() - 1)),
+            Key::Char('\n') => {
+                self.pmtk_menu = None;
+                let preset = pmtk::PRESETS[selected];
+                self.notice(preset.label.to_owned());
+                self.send_command(preset.body);
+            }
+            _ => {
+                self.pmtk_menu = None;
+                if self.keymap.action(key) == Some(Action::Quit) {
+                    self.quit = true;
+                }
+            }
+        }
+    }
+
+    /// Moves the selection in the Messages pane to the next line shown,
+    /// older or newer, starting from the newest
+    fn select_message(&mut self, older: bool) {
+        let lines = &self.messages.lines;
+        let next = match (self.messages.selected, older) {
+            (None, _) => lines.iter().position(|msg| self.shown(msg)),
+            (Some(i), true) => (i + 1..lines.len()).find(|&j| self.shown(&lines[j])),
+            (Some(i), false) => (0..i).rev().find(|&j| self.shown(&lines[j])),
+        };
+        if next.is_some() {
+            self.messages.select(next);
+        }
+    }
+
+    /// Whether the Messages pane shows the line: rejected ones only show in
+    /// hex view
+    fn shown(&self, msg: &Message) -> bool {
+        self.hex_view || msg.kind != MessageKind::Rejected
+    }
+
+    /// Lines of the Messages pane the search matches, newest first
+    pub fn search_matches(&self) -> Vec<usize> {
+        let query = match &self.search {
+            Some(query) => query,
+            None => return Vec::new(),
+        };
+        self.messages
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, msg)| self.shown(msg) && query.is_match(&msg.search_text()))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Searches the Messages pane, selecting the most recent match
+    fn start_search(&mut self, input: &str) {
+        let query = match Query::parse(input) {
+            Ok(query) => query,
+            Err(e) => {
+                self.alert(e);
+                return;
+            }
+        };
+        if self.search.is_none() {
+            self.messages.before_search = self.messages.selected;
+        }
+        self.search = Some(query);
+        match self.search_matches().first() {
+            Some(&newest) => self.messages.select(Some(newest)),
+            None => self.toast = Some((self.now, format!("no match for {}", input))),
+        }
+    }
+
+    /// Moves the selection to the next match, older or newer, going round
+    /// at either end
+    fn next_match(&mut self, older: bool) {
+        if self.search.is_none() {
+            self.notice("search the messages with / first".to_owned());
+            return;
+        }
+        let matches = self.search_matches();
+        let next = match (self.messages.selected, older) {
+            (Some(i), true) => matches.iter().find(|&&j| j > i).or_else(|| matches.first()),
+            (Some(i), false) => matches
+                .iter()
+                .rev()
+                .find(|&&j| j < i)
+                .or_else(|| matches.last()),
+            (None, _) => matches.first(),
+        };
+        if let Some(&next) = next {
+            self.messages.select(Some(next));
+        }
+    }
+
+    fn inspect_selected(&mut self) {
+        let selected = self
+            .messages
+            .selected
+            .and_then(|i| self.messages.lines.get(i));
+        match selected.map(|msg| inspect::inspect(&msg.text)) {
+            Some(Some(inspection)) => self.inspector = Some(inspection),
+            Some(None) => self.notice("only sentences can be inspected".to_owned()),
+            None => self.notice("select a message with up and down first".to_owned()),
+        }
+    }
+
+    /// What copying takes: the selected message, or else the position in
+    /// the coordinate format shown
+    pub fn copy_text(&self) -> Option<String> {
+        if let Some(msg) = self
+            .messages
+            .selected
+            .and_then(|i| self.messages.lines.get(i))
+        {
+            return Some(msg.text.clone());
+        }
+        let format = self.prefs.coord_format;
+        Some(format!(
+            "{},{}",
+            format.format(self.nmea.latitude?, 'N', 'S'),
+            format.format(self.nmea.longitude?, 'E', 'W')
+        ))
+    }
+
+    fn copy(&mut self) {
+        let text = match self.copy_text() {
+            Some(text) => text,
+            None => {
+                self.notice("no position or selected message to copy".to_owned());
+                return;
+            }
+        };
+        let msg = match clipboard::copy(&text) {
+            Ok(Copied::Clipboard) => format!("copied {}", text),
+            Ok(Copied::Terminal) => format!("copied {} through the terminal", text),
+            Err(e) => {
+                self.alert(format!("failed to copy: {}", e));
+                return;
+            }
+        };
+        self.toast = Some((self.now, msg));
+    }
+
+    /// Rows of panels to show: those of the layout with something to show,
+    /// and a second receiver, a route, AIS targets or instruments right
+    /// below the first row even when the layout leaves them out
+    pub fn panel_rows(&self) -> Vec<Vec<Panel>> {
+        let mut rows = self.prefs.panels.visible(|panel| match panel {
+            _ if self.hidden_panels.contains(&panel) => false,
+            Panel::Track => self.prefs.track_map,
+            Panel::Waypoints => !self.waypoints.is_empty(),
+            Panel::Compare => self.peer.is_some(),
+            Panel::Navigation => self.route.is_some(),
+            Panel::Ais => !self.ais.is_empty(),
+            Panel::Vessel => !self.vessel.is_empty(),
+            _ => true,
+        });
+        let wanted = [
+            (Panel::Compare, self.peer.is_some()),
+            (Panel::Navigation, self.route.is_some()),
+            (Panel::Ais, !self.ais.is_empty()),
+            (Panel::Vessel, !self.vessel.is_empty()),
+        ];
+        for &(panel, wanted) in wanted.iter().rev() {
+            let wanted = wanted && !self.hidden_panels.contains(&panel);
+            if wanted && !rows.iter().any(|row| row.contains(&panel)) {
+                rows.insert(rows.len().min(1), vec![panel]);
+            }
+        }
+        rows
+    }
+
+    /// Focuses the panel after the focused one, left to right and top to
+    /// bottom; in a small terminal it is the one shown
+    fn focus_next_panel(&mut self) {
+        let panels: Vec<Panel> = self.panel_rows().into_iter().flatten().collect();
+        let next = self
+            .focus
+            .and_then(|focus| panels.iter().position(|&p| p == focus))
+            .and_then(|i| panels.get(i + 1));
+        self.focus = next.or_else(|| panels.first()).copied();
+    }
+
+    /// Hides the focused panel until `Action::ShowPanel`, keeping at least
+    /// one panel
+    fn hide_panel(&mut self) {
+        let panel = match self.focus {
+            Some(panel) => panel,
+            None => {
+                let msg = format!(
+                    "focus a panel to hide with {} first",
+                    self.key_hint(Action::NextPanel)
+                );
+                self.notice(msg);
+                return;
+            }
+        };
+        if self.panel_rows().iter().flatten().count() <= 1 {
+            self.notice("the last panel can't be hidden".to_owned());
+            return;
+        }
+        self.hidden_panels.push(panel);
+        self.focus = None;
+        let msg = format!(
+            "{} hidden, {} shows it",
+            panel,
+            self.key_hint(Action::ShowPanel)
+        );
+        self.toast = Some((self.now, msg));
+    }
+
+    /// Shows the panel hidden last again, focused
+    fn show_panel(&mut self) {
+        match self.hidden_panels.pop() {
+            Some(panel) => {
+                self.focus = Some(panel);
+                self.toast = Some((self.now, format!("{} shown", panel)));
+            }
+            None => self.notice("no panel is hidden".to_owned()),
+        }
+    }
+
+    /// Panel scrolled by the scroll keys: Messages once clicked, the
+    /// receiver text otherwise
+    fn scroll_target(&self) -> Panel {
+        match self.focus {
+            Some(Panel::Messages) => Panel::Messages,
+            _ => Panel::Text,
+        }
+    }
+
+    /// Scrolls a panel by `step` lines, down to older ones or back up. The
+    /// Messages pane follows the newest line again once back at the top.
+    fn scroll(&mut self, panel: Panel, down: bool, step: usize) {
+        match panel {
+            Panel::Messages => {
+                let messages = &mut self.messages;
+                let top = messages.top.or(messages.selected).unwrap_or(0);
+                let top = if down {
+                    (top + step).min(messages.lines.len().saturating_sub(1))
+                } else {
+                    top.saturating_sub(step)
+                };
+                messages.top = Some(top).filter(|&top| top > 0);
+            }
+            Panel::Text => {
+                self.text_scroll = if down {
+                    (self.text_scroll + step).min(self.texts.len().saturating_sub(1))
+                } else {
+                    self.text_scroll.saturating_sub(step)
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Mouse events over `panel`, with the message under the pointer when
+    /// it is the Messages pane: the wheel scrolls the panel, a click focuses
+    /// it and selects the message, and clicking the selection inspects it
+    pub fn handle_mouse(&mut self, kind: MouseKind, panel: Panel, message: Option<usize>) {
+        if self.prompting() || self.pmtk_menu.is_some() {
+            return;
+        }
+        if self.show_help || self.inspector.is_some() {
+            // a click closes the overlay like any key
+            if kind == MouseKind::Click {
+                self.show_help = false;
+                self.inspector = None;
+            }
+            return;
+        }
+        match kind {
+            MouseKind::Click => {
+                self.focus = Some(panel);
+                match message {
+                    Some(i) if self.messages.selected == Some(i) => self.inspect_selected(),
+                    Some(i) => self.messages.selected = Some(i),
+                    None => {}
+                }
+            }
+            MouseKind::WheelUp => self.scroll(panel, false, WHEEL_STEP),
+            MouseKind::WheelDown => self.scroll(panel, true, WHEEL_STEP),
+        }
+    }
+
+    /// Keys for the search prompt
+    fn search_key(&mut self, key: Key) {
+        let input = match self.search_input.as_mut() {
+            Some(input) => input,
+            None => return,
+        };
+        match key {
+            Key::Char('\n') => {
+                if let Some(input) = self.search_input.take() {
+                    self.start_search(&input);
+                }
+            }
+            Key::Esc => self.search_input = None,
+            Key::Backspace => {
+                input.pop();
+            }
+            Key::Char(c) => input.push(c),
+            _ => {}
+        }
+    }
+
+    /// Keys for the command prompt: editing, history and sending
+    fn command_key(&mut self, key: Key) {
+        let command = match self.command.as_mut() {
+            Some(command) => command,
+            None => return,
+        };
+        match key {
+            Key::Char('\n') => {
+                self.history_pos = None;
+                if let Some(command) = self.command.take() {
+                    self.send_command(&command);
+                }
+            }
+            Key::Esc => {
+                self.history_pos = None;
+                self.command = None;
+            }
+            Key::Backspace => {
+                command.pop();
+            }
+            Key::Up if !self.command_history.is_empty() => {
+                let pos = match self.history_pos {
+                    Some(pos) => pos.saturating_sub(1),
+                    None => self.command_history.len() - 1,
+                };
+                self.history_pos = Some(pos);
+                *command = self.command_history[pos].clone();
+            }
+            Key::Down => {
+                let next = self.history_pos.map(|pos| pos + 1);
+                match next.and_then(|pos| self.command_history.get(pos)) {
+                    Some(entry) => *command = entry.clone(),
+                    None => command.clear(),
+                }
+                self.history_pos = next.filter(|&pos| pos < self.command_history.len());
+            }
+            Key::Char(c) => command.push(c),
+            _ => {}
+        }
+    }
+
+    /// True while a prompt takes all keys, quit keys included
+    pub fn prompting(&self) -> bool {
+        self.naming.is_some()
+            || self.command.is_some()
+            || self.search_input.is_some()
+            || self.confirm_reset
+    }
+
+    /// Forgets everything the receiver told and all counters, as after
+    /// switching antennas or receivers. The sources and the files written
+    /// stay as they are, and so does what the user set up: waypoints, the
+    /// route, zones and filters.
+    fn reset_receiver(&mut self) {
+        self.nmea = Nmea::new();
+        self.sky = Sky::new();
+        self.rates = SentenceRates::new();
+        self.epochs = EpochTracker::default();
+        self.gaps = LineGaps::default();
+        self.rejected = RejectLog::default();
+        self.filter.reset_counts();
+        self.texts.clear();
+        self.text_scroll = 0;
+        self.pending_text = None;
+        self.antenna = None;
+        self.sentences_parsed = 0;
+        self.sentences_failed = 0;
+        self.bytes_skipped = 0;
+        self.lines_dropped = 0;
+        self.queue_lag = None;
+        self.clock_offset = None;
+        self.skewed_since = None;
+        self.date_warning = None;
+        self.last_fix = None;
+        self.positions.clear();
+        self.track_epoch = None;
+        self.corrections = None;
+        self.geoid_separation = None;
+        self.ellipsoidal_height = None;
+        self.variation = None;
+        self.datum = None;
+        self.nav_status = None;
+        self.errors = None;
+        self.nav_pvt = None;
+        self.nav_dop = None;
+        self.fix_3d = false;
+        self.gsv_cycle = None;
+        self.speed_filter = SpeedFilter::new(self.options.speed_alpha);
+        self.outliers = OutlierFilter::default();
+        self.ais = Ais::new(self.options.ais_timeout);
+        self.vessel = Vessel::default();
+        // an average across two antennas means nothing, start over
+        if let Some(duration) = self.averager.as_ref().map(|avg| avg.duration) {
+            self.averager = Some(Averager::new(self.now, duration));
+        }
+    }
+
+    /// Resets both receivers and starts the Messages pane afresh with a
+    /// marker, which goes to the log files too
+    fn reset_state(&mut self) {
+        self.reset_receiver();
+        if let Some(peer) = self.peer.as_mut() {
+            peer.reset_receiver();
+        }
+        self.clear_messages();
+        let marker = "state reset";
+        self.csv.log_marker(self.now, marker);
+        self.notice(marker.to_owned());
+    }
+
+    fn clear_messages(&mut self) {
+        self.messages.clear();
+        self.search = None;
+        self.inspector = None;
+    }
+
+    pub fn handle_key(&mut self, key: Key) {
+        if let Some(selected) = self.pmtk_menu {
+            self.pmtk_menu_key(selected, key);
+            return;
+        }
+        if self.command.is_some() {
+            self.command_key(key);
+            return;
+        }
+        if self.search_input.is_some() {
+            self.search_key(key);
+            return;
+        }
+        if self.confirm_reset {
+            self.confirm_reset = false;
+            if key == Key::Char('y') {
+                self.reset_state();
+            } else {
+                self.toast = Some((self.now, "reset cancelled".to_owned()));
+            }
+            return;
+        }
+
+        // the name prompt owns the keyboard until Enter or Esc
+        if let Some(waypoint) = self.naming.as_mut() {
+            match key {
+                Key::Char('\n') => {
+                    if let Some(waypoint) = self.naming.take() {
+                        self.save_waypoint(waypoint);
+                    }
+                }
+                Key::Esc => self.naming = None,
+                Key::Backspace => {
+                    waypoint.name.pop();
+                }
+                Key::Char(c) => waypoint.name.push(c),
+                _ => {}
+            }
+            return;
+        }
+
+        let action = self.keymap.action(key);
+        if self.show_help || self.inspector.is_some() {
+            // any key closes the overlay, quitting still quits
+            self.show_help = false;
+            self.inspector = None;
+            if action != Some(Action::Quit) {
+                return;
+            }
+        }
+
+        match action {
+            // cancelling a search goes back to where it started
+            None if key == Key::Esc && self.search.is_some() => {
+                self.search = None;
+                let before = self.messages.before_search.take();
+                self.messages.select(before);
+            }
+            None if key == Key::Esc => self.messages.select(None),
+            None => {}
+            Some(Action::Quit) => self.quit = true,
+            Some(Action::Help) => self.show_help = true,
+            Some(Action::ToggleAverage) => {
+                if self.averager.is_some() {
+                    self.stop_average();
+                } else {
+                    let duration = self
+                        .options
+                        .average
+                        .map(|secs| chrono::Duration::seconds(secs as i64));
+                    self.averager = Some(Averager::new(self.now, duration));
+                    self.notice("averaging started".to_owned());
+                }
+            }
+            Some(Action::ScrollTextUp) => {
+                self.scroll(self.scroll_target(), false, TEXT_SCROLL_STEP)
+            }
+            Some(Action::ScrollTextDown) => {
+                self.scroll(self.scroll_target(), true, TEXT_SCROLL_STEP)
+            }
+            Some(Action::ToggleHex) => self.hex_view = !self.hex_view,
+            Some(Action::ToggleRejected) => self.show_rejected = !self.show_rejected,
+            Some(Action::CycleUnits) => self.prefs.units = self.prefs.units.next(),
+            Some(Action::CycleCoordFormat) => {
+                self.prefs.coord_format = self.prefs.coord_format.next()
+            }
+            Some(Action::ToggleTrack) => self.prefs.track_map = !self.prefs.track_map,
+            Some(Action::MarkWaypoint) => {
+                let (separation, datum) = (self.geoid_separation, self.datum.as_ref());
+                let height = self.ellipsoidal_height;
+                let seq = self.waypoints.len() + 1;
+                match Waypoint::mark(seq, self.now, &self.nmea, separation, height, datum) {
+                    Some(waypoint) => self.naming = Some(waypoint),
+                    None => self.notice("no valid fix, waypoint not marked".to_owned()),
+                }
+            }
+            Some(Action::QuickWaypoint) => {
+                let (separation, datum) = (self.geoid_separation, self.datum.as_ref());
+                let height = self.ellipsoidal_height;
+                let seq = self.waypoints.len() + 1;
+                match Waypoint::mark(seq, self.now, &self.nmea, separation, height, datum) {
+                    Some(waypoint) => self.save_waypoint(waypoint),
+                    None => self.notice("no valid fix, waypoint not marked".to_owned()),
+                }
+            }
+            Some(Action::NextRoutePoint) => self.skip_route_point(true),
+            Some(Action::PrevRoutePoint) => self.skip_route_point(false),
+            Some(Action::SelectSentence) => {
+                let kinds: Vec<String> = self
+                    .rates
+                    .rates(self.now)
+                    .into_iter()
+                    .map(|(kind, _)| kind)
+                    .collect();
+                let next = match &self.selected_kind {
+                    Some(selected) => kinds
+                        .iter()
+                        .position(|k| k == selected)
+                        .map_or(0, |i| i + 1),
+                    None => 0,
+                };
+                self.selected_kind = kinds.get(next).cloned();
+            }
+            Some(Action::Command) => self.command = Some(String::new()),
+            Some(Action::PmtkMenu) => self.pmtk_menu = Some(0),
+            Some(Action::SelectNewerMessage) => self.select_message(false),
+            Some(Action::SelectOlderMessage) => self.select_message(true),
+            Some(Action::Search) => self.search_input = Some(String::new()),
+            Some(Action::NextMatch) => self.next_match(true),
+            Some(Action::PrevMatch) => self.next_match(false),
+            Some(Action::InspectMessage) => self.inspect_selected(),
+            Some(Action::Copy) => self.copy(),
+            Some(Action::NextPanel) => self.focus_next_panel(),
+            Some(Action::HidePanel) => self.hide_panel(),
+            Some(Action::ShowPanel) => self.show_panel(),
+            // the layout is left alone, so leaving restores it as it was
+            Some(Action::Zen) => self.zen = !self.zen,
+            Some(Action::ClearMessages) => {
+                self.clear_messages();
+                self.toast = Some((self.now, "messages cleared".to_owned()));
+            }
+            Some(Action::ResetState) => self.confirm_reset = true,
+            Some(Action::Reopen) => {
+                if self.has_ended(0) || self.has_ended(1) {
+                    self.reopen = true;
+                } else {
+                    self.notice("the source is still being read".to_owned());
+                }
+            }
+            Some(Action::ToggleSentenceFilter) => {
+                if let Some(kind) = self.selected_kind.clone() {
+                    let msg = if self.filter.toggle(&kind) {
+                        format!("no longer parsing {}", kind)
+                    } else {
+                        format!("parsing {} again", kind)
+                    };
+                    self.notice(msg);
+                }
+            }
+            Some(Action::CycleTarget) => {
+                self.target = match self.target {
+                    None if !self.waypoints.is_empty() => Some(0),
+                    Some(i) if i + 1 < self.waypoints.len() => Some(i + 1),
+                    _ => None,
+                };
+                let msg = match self.target_waypoint() {
+                    Some(waypoint) => format!("navigating to {}", waypoint.name),
+                    None => "navigation target cleared".to_owned(),
+                };
+                self.notice(msg);
+            }
+            Some(Action::ExportWaypoints) => {
+                let path = self.options.waypoints.clone().unwrap_or_else(|| {
+                    let name = format!("waypoints-{}.gpx", self.now.format("%Y%m%d-%H%M%S"));
+                    PathBuf::from(name)
+                });
+                match export::write_waypoints(&path, &self.waypoints, self.options.altitude) {
+                    Ok(()) => self.notice(format!("exported waypoints to {}", path.display())),
+                    Err(e) => self.alert(format!("failed to export {}: {}", path.display(), e)),
+                }
+            }
+            Some(Action::FreezeFrame) => {
+                let path = format!("freeze-{}.txt", self.now.format("%Y%m%d-%H%M%S"));
+                let (nmea, sky) = (&self.nmea, &self.sky);
+                let (corrections, errors) = (self.corrections.as_ref(), self.errors.as_ref());
+                match export::write_freeze_frame(
+                    path.as_ref(),
+                    self.now,
+                    nmea,
+                    sky,
+                    self.geoid_separation,
+                    corrections,
+                    errors,
+                ) {
+                    Ok(()) => self.toast = Some((self.now, format!("wrote {}", path))),
+                    Err(e) => self.alert(format!("failed to write {}: {}", path, e)),
+                }
+            }
+            Some(Action::ExportSatellites) => {
+                let path = format!("satellites-{}.csv", self.now.format("%Y%m%d-%H%M%S"));
+                self.csv
+                    .snapshot(PathBuf::from(path), export::sat_csv_rows(&self.sky));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../example.txt");
+
+    fn feed(app: &mut App, log: &str) {
+        for line in log.lines() {
+            app.handle_line(line);
+        }
+    }
+
+    #[test]
+    fn example_log_yields_a_fix() {
+        let mut app = App::with_defaults();
+        feed(&mut app, EXAMPLE);
+
+        assert!(has_fix(&app.nmea));
+        assert!(app.last_fix.is_some());
+        assert!(!app.positions.is_empty());
+        assert_eq!(app.nmea.satellites.len(), 12);
+        assert!(app.corrections.is_none());
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Sentence);
+    }
+
+    #[test]
+    fn garbage_is_not_logged() {
+        let mut app = App::with_defaults();
+        app.handle_line("not a sentence");
+        assert!(app.messages.lines.is_empty());
+        assert!(app.rates.rates(app.now).is_empty());
+    }
+
+    #[test]
+    fn hex_view_keeps_rejected_bytes() {
+        let mut app = App::with_defaults();
+        app.handle_key(Key::Char('x'));
+        assert!(app.hex_view);
+        app.handle_raw_at(Local::now(), b"\xb5\x62\x01\x07$GPGGA");
+        let msg = &app.messages.lines[0];
+        assert_eq!(msg.kind, MessageKind::Rejected);
+        assert_eq!(msg.bytes(), b"\xb5\x62\x01\x07$GPGGA");
+        assert_eq!(app.sentences_failed, 1);
+    }
+
+    #[test]
+    fn ellipsoidal_height_adds_the_geoid_separation() {
+        let mut app = App::with_defaults();
+        app.handle_line("$GPGGA,060437.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,*6F");
+        assert_eq!(app.geoid_separation, Some(18.3));
+        let waypoint =
+            Waypoint::mark(1, app.now, &app.nmea, app.geoid_separation, None, None).unwrap();
+        assert_eq!(waypoint.altitude_as(Altitude::Msl), Some(46.3));
+        let height = waypoint.altitude_as(Altitude::Ellipsoidal).unwrap();
+        assert!((height - 64.6).abs() < 1e-4);
+
+        // left out, so only MSL is known
+        app.handle_line("$GPGGA,060437.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,,M,,*7B");
+        assert_eq!(app.geoid_separation, None);
+        let waypoint =
+            Waypoint::mark(2, app.now, &app.nmea, app.geoid_separation, None, None).unwrap();
+        assert_eq!(waypoint.altitude_as(Altitude::Msl), Some(46.3));
+        assert_eq!(waypoint.altitude_as(Altitude::Ellipsoidal), None);
+    }
+
+    #[test]
+    fn state_is_reset_after_confirming() {
+        let mut app = App::with_defaults();
+        feed(&mut app, EXAMPLE);
+        app.handle_key(Key::Char('s'));
+        assert_eq!(app.waypoints.len(), 1);
+
+        app.handle_key(Key::Char('C'));
+        assert!(app.messages.lines.is_empty());
+        assert!(has_fix(&app.nmea));
+
+        // anything but y cancels
+        app.handle_key(Key::Ctrl('x'));
+        assert!(app.prompting());
+        app.handle_key(Key::Char('q'));
+        assert!(!app.prompting() && !app.quit);
+        assert!(has_fix(&app.nmea));
+
+        app.handle_key(Key::Ctrl('x'));
+        app.handle_key(Key::Char('y'));
+        assert!(!has_fix(&app.nmea));
+        assert!(app.sky.is_empty() && app.positions.is_empty());
+        assert_eq!((app.sentences_parsed, app.clock_offset), (0, None));
+        assert_eq!(app.messages.lines.len(), 1);
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Notice);
+        assert_eq!(app.messages.lines[0].text, "state reset");
+        // what the user made stays
+        assert_eq!(app.waypoints.len(), 1);
+
+        feed(&mut app, EXAMPLE);
+        assert!(has_fix(&app.nmea));
+    }
+
+    #[test]
+    fn rmc_variation_is_kept() {
+        let mut app = App::with_defaults();
+        app.handle_line("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A");
+        let variation = app.variation.unwrap();
+        assert_eq!(variation.degrees, -3.1);
+        assert!(!variation.modeled);
+        assert!((variation.magnetic(84.4) - 87.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rmc_modes_set_the_nav_status() {
+        use chrono::TimeZone;
+
+        // 2020-06-07 06:04:33 UTC
+        let start = Local.timestamp(1_591_509_873, 0);
+        let rmc = |secs: i64, status: &str, lat: &str, mode: &str| {
+            let body = format!(
+                "GPRMC,0604{:02}.00,{},{},N,12654.85121,E,0.212,,070620,,,{}",
+                33 + secs,
+                status,
+                lat,
+                mode
+            );
+            (
+                start + chrono::Duration::seconds(secs),
+                with_checksum(&body),
+            )
+        };
+        let mut app = App::with_defaults();
+        app.handle_line_at(
+            start,
+            "$GPGGA,060433.00,3732.93104,N,12654.85121,E,1,04,1.22,46.3,M,18.3,M,,*61",
+        );
+        let (local, line) = rmc(0, "A", "3732.93104", "A");
+        app.handle_line_at(local, &line);
+        assert!(has_fix(&app.nmea));
+        assert_eq!(app.nav_status.unwrap().describe(), "Autonomous (A/A)");
+
+        // dead reckoning still moves the position, but says so
+        let (local, line) = rmc(1, "A", "3732.93204", "E");
+        app.handle_line_at(local, &line);
+        assert!(app.nav_status.unwrap().dead_reckoning());
+        assert_eq!(app.last_fix, Some(local));
+        assert_eq!(
+            app.messages.lines[0].text,
+            "dead reckoning: the position is estimated"
+        );
+
+        // N, or V from receivers before NMEA 2.3, leave the position alone
+        let (local, line) = rmc(2, "A", "3732.99999", "N");
+        app.handle_line_at(local, &line);
+        assert!(!has_fix(&app.nmea));
+        assert_eq!(app.last_fix, Some(start + chrono::Duration::seconds(1)));
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Alert);
+        assert_eq!(app.messages.lines[0].text, "fix lost: Not valid (A/N)");
+        let (local, line) = rmc(3, "V", "3732.99999", "");
+        app.handle_line_at(local, &line);
+        assert_eq!(app.nav_status.unwrap().describe(), "Not valid (V)");
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Sentence);
+        assert_eq!(app.last_fix, Some(start + chrono::Duration::seconds(1)));
+
+        let (local, line) = rmc(4, "A", "3732.93304", "D");
+        app.handle_line_at(local, &line);
+        assert!(has_fix(&app.nmea));
+        assert_eq!(app.last_fix, Some(local));
+        assert_eq!(
+            app.messages.lines[0].text,
+            "fix regained: Differential (A/D)"
+        );
+    }
+
+    #[test]
+    fn local_datums_are_warned_about_once() {
+        let mut app = App::with_defaults();
+        app.handle_line("$GPDTM,W84,,0.0,N,0.0,E,0.0,W84*6F");
+        assert!(app.datum.as_ref().unwrap().is_wgs84());
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Sentence);
+
+        let tokyo = "$GPDTM,TOKYO,,0.1930,S,0.1310,W,,W84*5B";
+        app.handle_line(tokyo);
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Alert);
+        assert_eq!(
+            app.messages.lines[0].text,
+            "positions are in the TOKYO datum, not WGS84"
+        );
+        app.handle_line(tokyo);
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Sentence);
+
+        // waypoints keep the datum they were marked in
+        app.handle_line("$GPGGA,060437.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,*6F");
+        let waypoint =
+            Waypoint::mark(1, app.now, &app.nmea, None, None, app.datum.as_ref()).unwrap();
+        assert_eq!(waypoint.datum.as_deref(), Some("TOKYO"));
+
+        app.handle_line("$GPDTM,W84,,0.0,N,0.0,E,0.0,W84*6F");
+        assert_eq!(app.messages.lines[0].text, "positions are in WGS84 again");
+    }
+
+    #[test]
+    fn ubx_packets_are_decoded_alongside_sentences() {
+        let mut app = App::with_defaults();
+        app.handle_line("$GPGGA,060437.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,*6F");
+        assert!(app.nav_pvt.is_none());
+
+        // a 3D fix with a fixed carrier solution, 14 mm horizontally and 21
+        // mm vertically
+        let mut payload = [0; 92];
+        payload[20] = 3;
+        payload[21] = 0x81;
+        payload[40] = 14;
+        payload[44] = 21;
+        let pvt = ubx::packet(ubx::CLASS_NAV, ubx::ID_NAV_PVT, &payload);
+        app.handle_raw_at(Local::now(), &pvt);
+        let (_, pvt) = app.nav_pvt.as_ref().unwrap();
+        assert_eq!(pvt.carr_soln_name(), "fixed");
+        assert!((pvt.h_acc - 0.014).abs() < 1e-9);
+        assert!((pvt.v_acc - 0.021).abs() < 1e-9);
+        let msg = &app.messages.lines[0];
+        assert_eq!(msg.kind, MessageKind::Sentence);
+        assert!(msg.text.starts_with("UBX NAV-PVT: no time yet 3D fix"));
+        assert_eq!(msg.bytes().len(), 100);
+        assert_eq!(app.sentences_failed, 0);
+        assert!(app
+            .rates
+            .rates(app.now)
+            .iter()
+            .any(|(kind, _)| kind == "NAV-PVT"));
+    }
+
+    #[test]
+    fn selected_message_is_inspected() {
+        let mut app = App::with_defaults();
+        app.handle_line("$GPGGA,060437.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,*6F");
+        app.handle_line("$GPGSA,A,3,06,03,17,22,28,,,,,,,,2.63,1.17,2.36*0C");
+        app.handle_key(Key::Down);
+        assert_eq!(app.messages.selected, Some(0));
+        app.handle_key(Key::Down);
+        app.handle_key(Key::Down);
+        assert_eq!(app.messages.selected, Some(1));
+        app.handle_key(Key::Up);
+        assert_eq!(app.messages.selected, Some(0));
+        app.handle_key(Key::Down);
+
+        // the selection stays on the GGA as lines arrive
+        app.handle_line("$GPGSA,A,3,06,03,17,22,28,,,,,,,,2.63,1.17,2.36*0C");
+        assert_eq!(app.messages.selected, Some(2));
+        app.handle_key(Key::Char('\n'));
+        let inspection = app.inspector.as_ref().unwrap();
+        assert_eq!(inspection.kind, "GGA");
+        assert_eq!(inspection.fields[5].value.as_deref(), Some("GPS fix"));
+        assert!(inspection.checksum.is_valid());
+
+        // any key closes the popup, Esc then clears the selection
+        app.handle_key(Key::Down);
+        assert!(app.inspector.is_none());
+        assert_eq!(app.messages.selected, Some(2));
+        app.handle_key(Key::Esc);
+        assert_eq!(app.messages.selected, None);
+    }
+
+    #[test]
+    fn search_cycles_through_matches() {
+        let mut app = App::with_defaults();
+        let typed =
+            |app: &mut App, text: &str| text.chars().for_each(|c| app.handle_key(Key::Char(c)));
+        for _ in 0..2 {
+            app.handle_line(
+                "$GPGGA,060437.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,*6F",
+            );
+            app.handle_line("$GPGSA,A,3,06,03,17,22,28,,,,,,,,2.63,1.17,2.36*0C");
+        }
+        app.handle_key(Key::Down);
+        assert_eq!(app.messages.selected, Some(0));
+
+        // the prompt takes every key, quit included
+        typed(&mut app, "/GqGA");
+        assert!(!app.quit);
+        app.handle_key(Key::Backspace);
+        app.handle_key(Key::Backspace);
+        app.handle_key(Key::Backspace);
+        typed(&mut app, "GA\n");
+        assert!(app.search_input.is_none());
+        assert_eq!(app.search_matches(), vec![1, 3]);
+        assert_eq!(app.messages.selected, Some(1));
+        typed(&mut app, "n");
+        assert_eq!(app.messages.selected, Some(3));
+        typed(&mut app, "n");
+        assert_eq!(app.messages.selected, Some(1));
+        typed(&mut app, "N");
+        assert_eq!(app.messages.selected, Some(3));
+
+        // Esc goes back to the line selected before the search
+        app.handle_line("$GPGSA,A,3,06,03,17,22,28,,,,,,,,2.63,1.17,2.36*0C");
+        app.handle_key(Key::Esc);
+        assert!(app.search.is_none());
+        assert_eq!(app.messages.selected, Some(1));
+
+        typed(&mut app, "/re:^\\S+ \\$GPGSA,A,3\n");
+        assert_eq!(app.search_matches(), vec![0, 1, 3]);
+        assert_eq!(app.messages.selected, Some(0));
+
+        typed(&mut app, "/re:(GSA\n");
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Alert);
+    }
+
+    #[test]
+    fn position_or_selection_is_copied() {
+        let mut app = App::with_defaults();
+        assert_eq!(app.copy_text(), None);
+        app.handle_line("$GPGGA,060437.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,*6F");
+        assert_eq!(app.copy_text().as_deref(), Some("37.565510,126.914181"));
+        app.prefs.coord_format = crate::prefs::CoordFormat::Dm;
+        assert_eq!(
+            app.copy_text().as_deref(),
+            Some("37°33.93059'N,126°54.85088'E")
+        );
+
+        app.handle_key(Key::Down);
+        assert_eq!(
+            app.copy_text().as_deref(),
+            Some("$GPGGA,060437.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,*6F")
+        );
+    }
+
+    #[test]
+    fn mouse_scrolls_focuses_and_selects() {
+        let mut app = App::with_defaults();
+        for _ in 0..10 {
+            app.handle_line("$GPGSA,A,3,06,03,17,22,28,,,,,,,,2.63,1.17,2.36*0C");
+        }
+
+        // the view stays put as lines arrive, and follows them again once
+        // scrolled back to the top
+        app.handle_mouse(MouseKind::WheelDown, Panel::Messages, None);
+        assert_eq!(app.messages.top, Some(3));
+        app.handle_line("$GPGSA,A,3,06,03,17,22,28,,,,,,,,2.63,1.17,2.36*0C");
+        assert_eq!(app.messages.top, Some(4));
+        app.handle_mouse(MouseKind::WheelUp, Panel::Messages, None);
+        assert_eq!(app.messages.top, Some(1));
+        app.handle_mouse(MouseKind::WheelUp, Panel::Messages, None);
+        assert_eq!(app.messages.top, None);
+
+        // page keys scroll the receiver text until Messages is clicked
+        app.handle_key(Key::PageDown);
+        assert_eq!(app.messages.top, None);
+        app.handle_mouse(MouseKind::Click, Panel::Messages, None);
+        assert_eq!(app.focus, Some(Panel::Messages));
+        app.handle_key(Key::PageDown);
+        assert_eq!(app.messages.top, Some(5));
+
+        // a click selects a line, a second one inspects it
+        app.handle_mouse(MouseKind::Click, Panel::Messages, Some(6));
+        assert_eq!(app.messages.selected, Some(6));
+        assert!(app.inspector.is_none());
+        app.handle_mouse(MouseKind::Click, Panel::Messages, Some(6));
+        assert_eq!(app.inspector.as_ref().unwrap().kind, "GSA");
+        // and the next click only closes the popup
+        app.handle_mouse(MouseKind::Click, Panel::Status, None);
+        assert!(app.inspector.is_none());
+        assert_eq!(app.focus, Some(Panel::Messages));
+
+        // prompts ignore the mouse
+        app.handle_key(Key::Char(':'));
+        app.handle_mouse(MouseKind::Click, Panel::Status, None);
+        assert_eq!(app.focus, Some(Panel::Messages));
+    }
+
+    #[test]
+    fn rejections_are_counted_by_type() {
+        let mut app = App::with_defaults();
+        for _ in 0..3 {
+            app.handle_line("$GPXYZ,1,2*4F");
+        }
+        app.handle_line("$GPGGA,bad*00");
+        app.handle_line("not a sentence");
+
+        assert!(app.messages.lines.is_empty());
+        assert_eq!(app.sentences_failed, 5);
+        assert_eq!(app.rejected.lines.len(), 5);
+        assert_eq!(app.rejected.lines[0].reason, "not a sentence");
+        assert_eq!(app.rejected.lines[1].reason, "bad checksum");
+        let breakdown: Vec<(&str, u64)> = app
+            .rejected
+            .breakdown()
+            .into_iter()
+            .map(|(kind, count, _)| (kind, count))
+            .collect();
+        assert_eq!(breakdown, vec![("XYZ", 3), ("?", 2)]);
+    }
+
+    #[test]
+    fn outliers_are_dropped_until_they_agree() {
+        let mut app = App::new(
+            Options {
+                max_speed: Some(MaxSpeed::Fixed(60.0)),
+                ..Options::default()
+            },
+            Prefs::default(),
+            Theme::new(ThemeName::Mono),
+            KeyMap::default(),
+        );
+        feed(&mut app, EXAMPLE);
+        let latitude = app.nmea.latitude;
+        let positions = app.positions.len();
+        assert_eq!(app.outliers.rejected, 0);
+
+        // a minute of latitude north, about 1.85km in a second, told by GGA
+        // and RMC alike, which must count once per epoch
+        let epoch = |secs: u32| {
+            let gga = with_checksum(&format!(
+                "GPGGA,0604{}.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,",
+                secs
+            ));
+            let rmc = with_checksum(&format!(
+                "GPRMC,0604{}.00,A,3733.93059,N,12654.85088,E,0.212,,070620,,,A",
+                secs
+            ));
+            format!("{}\n{}", gga, rmc)
+        };
+        feed(&mut app, &epoch(37));
+        assert_eq!(app.nmea.latitude, latitude);
+        assert_eq!(app.positions.len(), positions);
+        assert_eq!(app.outliers.rejected, 1);
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Sentence);
+
+        for secs in 38..41 {
+            feed(&mut app, &epoch(secs));
+            assert_eq!(app.nmea.latitude, latitude);
+        }
+        assert_eq!(app.outliers.rejected, 4);
+        feed(&mut app, &epoch(41));
+        assert!(app.nmea.latitude.unwrap() > latitude.unwrap() + 0.01);
+        assert_eq!(app.positions.len(), positions + 1);
+        assert_eq!(app.outliers.rejected, 4);
+    }
+
+    #[test]
+    fn speed_filter_smooths_from_the_first_sample() {
+        let mut filter = SpeedFilter::new(SpeedFilter::DEFAULT_ALPHA);
+        assert_eq!(filter.value, None);
+        assert_eq!(filter.update(10.0), 10.0);
+        // 0.3 * 20 + 0.7 * 10
+        assert!((filter.update(20.0) - 13.0).abs() < 1e-5);
+        assert!((filter.value.unwrap() - 13.0).abs() < 1e-5);
+
+        let mut filter = SpeedFilter::new(1.0);
+        for speed in [3.0, 12.5, 0.0] {
+            assert_eq!(filter.update(speed), speed);
+        }
+    }
+
+    #[test]
+    fn peer_keeps_its_own_state_and_shares_messages() {
+        let mut app = App::with_defaults();
+        app.add_peer("udp://0.0.0.0:10110".to_owned());
+        for line in EXAMPLE.lines() {
+            app.handle_raw_from(0, Local::now(), line.as_bytes());
+        }
+        assert!(app.baseline().is_none());
+
+        // a minute of latitude north of the first receiver's last fix
+        app.handle_raw_from(
+            1,
+            Local::now(),
+            b"$GPGGA,060437.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,*6F",
+        );
+        let peer = app.peer.as_ref().unwrap();
+        assert!(peer.messages.lines.is_empty());
+        assert!(peer.nmea.latitude.unwrap() > app.nmea.latitude.unwrap());
+        assert!(app.messages.lines[0].peer);
+        assert!(!app.messages.lines[1].peer);
+
+        let (distance, bearing) = app.baseline().unwrap();
+        assert!((distance - 1853.0).abs() < 5.0);
+        assert!(bearing < 1.0 || bearing > 359.0);
+    }
+
+    #[test]
+    fn zone_entry_is_reported() {
+        let mut app = App::new(
+            Options {
+                zones: vec![Zone {
+                    name: "home".to_owned(),
+                    lat: 37.5488,
+                    lon: 126.9142,
+                    radius: 50.0,
+                }],
+                ..Options::default()
+            },
+            Prefs::default(),
+            Theme::new(ThemeName::Mono),
+            KeyMap::default(),
+        );
+        feed(&mut app, EXAMPLE);
+        let entered: Vec<&str> = app
+            .messages
+            .lines
+            .iter()
+            .filter(|msg| msg.kind == MessageKind::Notice)
+            .map(|msg| msg.text.as_str())
+            .collect();
+        assert_eq!(entered.len(), 1);
+        assert!(entered[0].starts_with("entered home ("));
+        assert!(app.geofence.summary(app.now)[0].1);
+    }
+
+    #[test]
+    fn route_advances_past_reached_points() {
+        let point = |name: &str, lat, lon| RoutePoint {
+            name: name.to_owned(),
+            lat,
+            lon,
+        };
+        let mut app = App::new(
+            Options {
+                route: vec![
+                    point("here", 37.54884, 126.91418),
+                    point("north", 37.6, 126.91418),
+                ],
+                ..Options::default()
+            },
+            Prefs::default(),
+            Theme::new(ThemeName::Mono),
+            KeyMap::default(),
+        );
+        feed(&mut app, EXAMPLE);
+        assert!(app
+            .messages
+            .lines
+            .iter()
+            .any(|msg| msg.text == "arrived at here"));
+        assert_eq!(app.route.as_ref().unwrap().target().unwrap().name, "north");
+        let sentences = app.autopilot_sentences().unwrap();
+        assert!(sentences[0].starts_with("$GPAPB,A,A,0.00,"));
+        assert!(sentences[0].contains(",T,north,"));
+
+        app.handle_key(Key::Char(']'));
+        assert!(app.route.as_ref().unwrap().finished());
+        assert!(app.autopilot_sentences().is_none());
+        assert_eq!(app.messages.lines[0].text, "route finished");
+        app.handle_key(Key::Char('['));
+        assert_eq!(app.messages.lines[0].text, "heading for north");
+        assert!(app.autopilot_sentences().is_some());
+
+        // an autopilot mustn't steer by a position gone stale
+        let last_fix = app.last_fix.unwrap();
+        app.tick_at(last_fix + chrono::Duration::seconds(AUTOPILOT_MAX_AGE_SECS));
+        assert!(app.autopilot_sentences().is_some());
+        app.tick_at(last_fix + chrono::Duration::seconds(AUTOPILOT_MAX_AGE_SECS + 1));
+        assert!(app.autopilot_sentences().is_none());
+    }
+
+    /// A writer whose output stays readable after it is handed over
+    #[derive(Clone, Default)]
+    struct SharedBuf(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn commands_are_checksummed_sent_and_recalled() {
+        let mut app = App::with_defaults();
+        app.handle_key(Key::Char(':'));
+        for c in "PMTK220,100\n".chars() {
+            app.handle_key(Key::Char(c));
+        }
+        // no writer: the command is kept but not sent
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Alert);
+
+        let out = SharedBuf::default();
+        app.writer = Some(DeviceWriter::new(out.clone()));
+        app.send_command("$PUBX,40,GLL,0,0,0,0*00");
+        assert_eq!(
+            String::from_utf8(out.0.lock().unwrap().clone()).unwrap(),
+            "$PUBX,40,GLL,0,0,0,0*5C\r\n"
+        );
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Sent);
+
+        app.handle_key(Key::Char(':'));
+        app.handle_key(Key::Up);
+        app.handle_key(Key::Up);
+        assert_eq!(app.command.as_deref(), Some("PMTK220,100"));
+        app.handle_key(Key::Down);
+        assert_eq!(app.command.as_deref(), Some("PUBX,40,GLL,0,0,0,0"));
+        app.handle_key(Key::Char('\n'));
+        assert_eq!(
+            String::from_utf8(out.0.lock().unwrap().clone()).unwrap(),
+            "$PUBX,40,GLL,0,0,0,0*5C\r\n$PUBX,40,GLL,0,0,0,0*5C\r\n"
+        );
+        assert_eq!(app.command_history.len(), 2);
+        assert!(!app.prompting());
+    }
+
+    #[test]
+    fn pmtk_menu_sends_and_reports_the_reply() {
+        let mut app = App::with_defaults();
+        let out = SharedBuf::default();
+        app.writer = Some(DeviceWriter::new(out.clone()));
+
+        app.handle_key(Key::Char('p'));
+        app.handle_key(Key::Down);
+        app.handle_key(Key::Down);
+        app.handle_key(Key::Char('\n'));
+        assert!(app.pmtk_menu.is_none());
+        assert_eq!(
+            String::from_utf8(out.0.lock().unwrap().clone()).unwrap(),
+            "$PMTK220,100*2F\r\n"
+        );
+
+        app.handle_line("$PMTK001,220,3*30");
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Notice);
+        assert_eq!(app.messages.lines[0].text, "PMTK220: succeeded (flag 3)");
+
+        app.send_command("PMTK314,-1");
+        app.tick_at(Local::now() + chrono::Duration::seconds(10));
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Alert);
+        assert_eq!(app.messages.lines[0].text, "PMTK314: no reply");
+    }
+
+    #[test]
+    fn first_fix_is_timed_after_a_cold_restart() {
+        use chrono::TimeZone;
+
+        let no_fix = "$GPGGA,060436.00,,,,,0,00,99.99,,,,,,*61";
+        let fix = "$GPGGA,060437.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,*6F";
+        let mut app = App::new(
+            Options {
+                ttff: true,
+                ..Options::default()
+            },
+            Prefs::default(),
+            Theme::new(ThemeName::Mono),
+            KeyMap::default(),
+        );
+        let start = Local.timestamp(1_591_509_873, 0);
+        let at = |secs| start + chrono::Duration::seconds(secs);
+        app.handle_line_at(start, no_fix);
+        app.handle_line_at(at(20), fix);
+        assert_eq!(
+            app.messages.lines[0].text,
+            "TTFF 20.0 s after the first sentence, 4 satellites"
+        );
+
+        app.writer = Some(DeviceWriter::new(SharedBuf::default()));
+        app.tick_at(at(30));
+        app.send_command("PMTK103");
+        assert!(!has_fix(&app.nmea));
+        assert_eq!(
+            app.messages.lines[0].text,
+            "fix lost: cold start, timing the first fix"
+        );
+        // the receiver restarts, and has no fix until it does
+        app.handle_line_at(at(30), fix);
+        assert!(app.ttff.as_ref().unwrap().waiting().is_some());
+        app.handle_line_at(at(31), no_fix);
+        app.handle_line_at(at(70), fix);
+        let ttff = app.ttff.as_ref().unwrap();
+        assert_eq!(ttff.history.len(), 2);
+        assert_eq!(ttff.history[0].start_type, Some(ttff::StartType::Cold));
+        assert_eq!(
+            app.messages.lines[0].text,
+            "TTFF 40.0 s after a cold start, 4 satellites"
+        );
+
+        // after a reconnect to a receiver with a fix there is nothing to time
+        app.source_reopened(0);
+        app.handle_line_at(at(80), fix);
+        assert_eq!(
+            app.messages.lines[0].text,
+            "TTFF: the receiver had a fix already"
+        );
+    }
+
+    #[test]
+    fn filtered_talkers_leave_the_position_alone() {
+        let mut app = App::new(
+            Options {
+                accept_talkers: vec!["GP".to_owned()],
+                ..Options::default()
+            },
+            Prefs::default(),
+            Theme::new(ThemeName::Mono),
+            KeyMap::default(),
+        );
+        app.handle_line("$HCRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*74");
+        assert_eq!(app.nmea.latitude, None);
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Filtered);
+        assert_eq!(app.filter.counts().collect::<Vec<_>>(), vec![("RMC", 1)]);
+
+        app.handle_line("$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68");
+        assert!(app.nmea.latitude.is_some());
+
+        // the only type seen so far is selected and ignored
+        app.handle_key(Key::Char('r'));
+        assert_eq!(app.selected_kind.as_deref(), Some("RMC"));
+        app.handle_key(Key::Char('i'));
+        assert!(app.filter.is_ignored("RMC"));
+        assert_eq!(app.messages.lines[0].text, "no longer parsing RMC");
+    }
+
+    #[test]
+    fn instrument_sentences_are_accepted() {
+        let mut app = App::with_defaults();
+        for line in &[
+            "$SDDBT,42.0,f,,M,,F*30",
+            "$WIMWV,045.0,R,12.4,N,A*15",
+            "$HEHDT,274.1,T*2F",
+        ] {
+            app.handle_line(line);
+        }
+        assert_eq!(app.sentences_failed, 0);
+        assert_eq!(app.vessel.heading_true.unwrap().value, 274.1);
+        assert!(app.vessel.apparent_wind.is_some());
+    }
+
+    #[test]
+    fn ais_sentences_make_targets() {
+        let mut app = App::with_defaults();
+        app.handle_line("!AIVDM,1,1,,B,15M67FC000G?ufbE`FepT@3n00Sa,0*5C");
+        app.handle_line("!AIVDM,2,2,1,A,88888888880,2*25");
+        assert_eq!(app.ais.len(), 1);
+        assert_eq!(app.sentences_parsed, 1);
+        assert_eq!(app.sentences_failed, 1);
+        assert_eq!(
+            app.rejected.lines[0].reason,
+            "AIS fragment 2 of 2 out of order"
+        );
+    }
+
+    #[test]
+    fn rtk_corrections_are_read_from_gga() {
+        let mut app = App::with_defaults();
+        app.handle_line(
+            "$GPGGA,172814.0,3723.46587704,N,12202.26957864,W,2,6,1.2,18.893,M,-25.669,M,2.0,0031*4F",
+        );
+        let corrections = app.corrections.expect("corrections");
+        assert_eq!(corrections.age, Some(2.0));
+        assert_eq!(corrections.station.as_deref(), Some("0031"));
+    }
+
+    #[test]
+    fn gst_errors_are_read() {
+        let mut app = App::with_defaults();
+        app.handle_line("$GPGST,172814.0,0.006,0.023,0.020,273.6,0.023,0.020,0.031*6A");
+        let errors = app.errors.expect("errors");
+        assert_eq!(errors.semi_major, Some(0.023));
+        assert_eq!(errors.orientation, Some(273.6));
+        assert_eq!(errors.altitude, Some(0.031));
+    }
+
+    #[test]
+    fn clock_offset_compares_utc_with_utc() {
+        use chrono::TimeZone;
+
+        let mut app = App::with_defaults();
+        // 2020-06-07 06:04:33.5 UTC, half a second after the fix
+        let local = Local.timestamp_millis(1_591_509_873_500);
+        app.handle_line_at(
+            local,
+            "$GPRMC,060433.00,A,3732.93104,N,12654.85121,E,0.212,,070620,,,A*71",
+        );
+        assert_eq!(app.clock_offset, Some(chrono::Duration::milliseconds(500)));
+    }
+
+    #[test]
+    fn week_rollover_is_flagged_and_fixed() {
+        use chrono::TimeZone;
+
+        // 1024 weeks before 2020-06-07, as a receiver which missed the
+        // rollover would say
+        let rmc = "$GPRMC,060433.00,A,3732.93104,N,12654.85121,E,0.212,,221000,,,A*73";
+        // 2020-06-07 06:04:33.5 UTC
+        let local = Local.timestamp_millis(1_591_509_873_500);
+
+        let mut app = App::with_defaults();
+        app.handle_line_at(local, rmc);
+        assert_eq!(app.date_warning, Some(DateWarning::Behind));
+        assert_eq!(app.nmea.fix_date, Some(NaiveDate::from_ymd(2000, 10, 22)));
+
+        let mut app = App::new(
+            Options {
+                fix_week_rollover: true,
+                ..Options::default()
+            },
+            Prefs::default(),
+            Theme::new(ThemeName::Mono),
+            KeyMap::default(),
+        );
+        app.handle_line_at(local, rmc);
+        assert_eq!(app.date_warning, None);
+        assert_eq!(app.nmea.fix_date, Some(NaiveDate::from_ymd(2020, 6, 7)));
+        assert_eq!(app.clock_offset, Some(chrono::Duration::milliseconds(500)));
+
+        // two days before the receiver's date
+        let mut app = App::with_defaults();
+        app.handle_line_at(
+            Local.timestamp_millis(1_591_337_073_500),
+            "$GPRMC,060433.00,A,3732.93104,N,12654.85121,E,0.212,,070620,,,A*71",
+        );
+        assert_eq!(app.date_warning, Some(DateWarning::Ahead));
+    }
+
+    #[test]
+    fn sustained_skew_is_flagged_from_time_only_sentences() {
+        use chrono::TimeZone;
+
+        let mut app = App::with_defaults();
+        // 2020-06-07 06:04:37 UTC, two seconds after each GGA
+        let start = Local.timestamp_millis(1_591_509_877_000);
+        for secs in 0..12 {
+            let t = 35 + secs;
+            let body = format!(
+                "GPGGA,0604{:02}.00,3732.93058,N,12654.85087,E,1,05,1.17,46.6,M,18.3,M,,",
+                t
+            );
+            let checksum = body.bytes().fold(0, |acc, b| acc ^ b);
+            app.handle_line_at(
+                start + chrono::Duration::seconds(secs),
+                &format!("${}*{:02X}", body, checksum),
+            );
+            assert_eq!(app.clock_offset, Some(chrono::Duration::seconds(2)));
+            assert_eq!(app.clock_skewed(), secs >= CLOCK_SKEW_SUSTAIN_SECS);
+        }
+    }
+
+    #[test]
+    fn accuracy_scales_hdop_by_uere() {
+        assert_eq!(estimated_accuracy_m(1.2, DEFAULT_UERE), 6.0);
+        assert_eq!(estimated_accuracy_m(0.8, 2.5), 2.0);
+    }
+
+    #[test]
+    fn clock_offset_wraps_around_midnight() {
+        use chrono::TimeZone;
+
+        // 2020-06-08 00:00:00.5 UTC, for a fix at 23:59:59.5 the day before
+        let local = Local.timestamp_millis(1_591_574_400_500);
+        let time = NaiveTime::from_hms_milli(23, 59, 59, 500);
+        assert_eq!(clock_offset_at(local, time), chrono::Duration::seconds(1));
+    }
+
+    #[test]
+    fn zda_alone_gives_date_and_time() {
+        // a timing receiver which sends nothing else
+        let mut app = App::with_defaults();
+        feed(
+            &mut app,
+            "$GPZDA,060433.00,07,06,2020,00,00*65\n$GPZDA,060434.00,07,06,2020,00,00*62\n",
+        );
+        let expected = NaiveDate::from_ymd(2020, 6, 7).and_hms(6, 4, 34);
+        assert_eq!(gps_datetime(&app.nmea), Some(expected));
+        assert!(app.clock_offset.is_some());
+        assert!(!has_fix(&app.nmea));
+    }
+
+    #[test]
+    fn gll_alone_gives_position() {
+        // a chartplotter repeating its position, then losing it
+        let mut app = App::with_defaults();
+        feed(
+            &mut app,
+            "$IIGLL,3732.93104,N,12654.85121,E,060433.00,A,A*7D\n\
+             $IIGLL,3732.93110,N,12654.85130,E,060434.00,A,A*7F\n\
+             $IIGLL,,,,,060435.00,V,N*59\n",
+        );
+        assert!(has_fix(&app.nmea));
+        assert!((app.nmea.latitude.unwrap() - 37.548852).abs() < 1e-6);
+        assert!((app.nmea.longitude.unwrap() - 126.914188).abs() < 1e-6);
+        assert_eq!(app.positions.len(), 2);
+        assert_eq!(app.rates.rates(app.now)[0].0, "GLL");
+    }
+
+    #[test]
+    fn txt_parts_are_joined_and_antenna_reported() {
+        let mut app = App::with_defaults();
+        feed(
+            &mut app,
+            "$GPTXT,02,01,02,MOD=NEO-M8N-0 *5A\n\
+             $GPTXT,02,02,02,FWVER=SPG 3.01*58\n\
+             $GPTXT,01,01,01,ANTENNA OPEN*25\n",
+        );
+        assert_eq!(app.texts.len(), 2);
+        assert_eq!(app.texts[0].level, TextLevel::Warning);
+        assert_eq!(app.texts[1].text, "MOD=NEO-M8N-0 FWVER=SPG 3.01");
+        assert_eq!(app.antenna.as_deref(), Some("antenna open"));
+
+        app.handle_line("$GPTXT,01,01,02,ANTSTATUS=OK*3B");
+        assert!(app.antenna.is_none());
+    }
+
+    #[test]
+    fn pubx_alone_fills_status_and_sky() {
+        // a u-blox module with the standard sentences turned off
+        let mut app = App::with_defaults();
+        feed(
+            &mut app,
+            "$PUBX,00,081350.00,4717.113210,N,00833.915187,E,546.589,G3,2.1,2.0,0.007,77.52,0.007,,0.92,1.19,0.77,9,0,0*5F\n\
+             $PUBX,03,11,23,-,,,45,010,29,-,,,46,013,07,-,,,42,015,08,U,067,31,42,025,10,U,195,33,46,026,18,U,326,08,39,026,17,-,,,32,015,26,U,306,66,48,025,27,U,073,10,36,026,28,U,089,61,46,024,15,-,,,39,014*0D\n\
+             $PUBX,04,073731.00,091202,113851.00,1196,15D,1930035,-2660.664,43,*5D\n",
+        );
+
+        assert!(has_fix(&app.nmea));
+        assert!(app.fix_3d);
+        assert!((app.nmea.latitude.unwrap() - 47.285220).abs() < 1e-6);
+        assert_eq!(app.nmea.altitude, None);
+        assert_eq!(app.ellipsoidal_height, Some(546.589));
+        assert_eq!(app.nmea.num_of_fix_satellites, Some(9));
+        assert_eq!(app.positions.len(), 1);
+
+        assert_eq!(app.sky.len(), 11);
+        assert_eq!(app.sky.used_count(), 6);
+        let sat = app.sky.satellites().find(|sat| sat.prn == 8).unwrap();
+        assert_eq!(
+            (sat.azimuth, sat.elevation, sat.snr),
+            (Some(67.0), Some(31.0), Some(42.0))
+        );
+
+        let expected = NaiveDate::from_ymd(2002, 12, 9).and_hms(7, 37, 31);
+        assert_eq!(gps_datetime(&app.nmea), Some(expected));
+    }
+
+    #[test]
+    fn pubx_waypoints_keep_the_height_above_the_ellipsoid() {
+        let mut app = App::with_defaults();
+        app.handle_line("$PUBX,00,081350.00,4717.113210,N,00833.915187,E,546.589,G3,2.1,2.0,0.007,77.52,0.007,,0.92,1.19,0.77,9,0,0*5F");
+        app.handle_key(Key::Char('s'));
+        let waypoint = &app.waypoints[0];
+        assert_eq!(waypoint.altitude, None);
+        assert_eq!(waypoint.altitude_as(Altitude::Ellipsoidal), Some(546.589));
+        // no GGA gave the geoid separation
+        assert_eq!(waypoint.altitude_as(Altitude::Msl), None);
+
+        let waypoint = Waypoint {
+            geoid_separation: Some(46.5),
+            ..waypoint.clone()
+        };
+        let msl = waypoint.altitude_as(Altitude::Msl).unwrap();
+        assert!((msl - 500.089).abs() < 1e-3);
+    }
+
+    #[test]
+    fn waypoint_prompt_takes_quit_key() {
+        let mut app = App::with_defaults();
+        feed(&mut app, EXAMPLE);
+
+        app.handle_key(Key::Char('w'));
+        assert!(app.prompting());
+        for c in "quay".chars() {
+            app.handle_key(Key::Char(c));
+        }
+        app.handle_key(Key::Char('\n'));
+
+        assert!(!app.quit);
+        assert_eq!(app.waypoints.len(), 1);
+        assert_eq!(app.waypoints[0].name, "quay");
+
+        app.handle_key(Key::Char('q'));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn quick_waypoints_become_targets_in_turn() {
+        let mut app = App::with_defaults();
+        feed(&mut app, EXAMPLE);
+
+        app.handle_key(Key::Char('s'));
+        app.handle_key(Key::Char('s'));
+        assert!(!app.prompting());
+        let names: Vec<&str> = app.waypoints.iter().map(|wp| wp.name.as_str()).collect();
+        assert_eq!(names, vec!["WP001", "WP002"]);
+
+        let mut targets = Vec::new();
+        for _ in 0..3 {
+            app.handle_key(Key::Char('t'));
+            targets.push(app.target);
+        }
+        assert_eq!(targets, vec![Some(0), Some(1), None]);
+
+        app.target = Some(0);
+        let (distance, _) = app.distance_to(app.target_waypoint().unwrap()).unwrap();
+        assert!(distance < 1.0);
+    }
+
+    #[test]
+    fn distance_and_bearing_along_the_equator() {
+        let (distance, bearing) = distance_bearing((0.0, 0.0), (0.0, 1.0));
+        assert!((distance - 111_195.0).abs() < 1.0);
+        assert!((bearing - 90.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn help_overlay_swallows_one_key() {
+        let mut app = App::with_defaults();
+        app.handle_key(Key::Char('?'));
+        assert!(app.show_help);
+        app.handle_key(Key::Char('u'));
+        assert!(!app.show_help);
+        assert_eq!(app.prefs.units, crate::prefs::Units::Metric);
+    }
+
+    #[test]
+    fn gaps_are_taken_from_the_first_source() {
+        let mut app = App::with_defaults();
+        let start = std::time::Instant::now();
+        for (i, raw) in EXAMPLE.lines().enumerate() {
+            let line = Line {
+                id: 0,
+                local: Local::now(),
+                arrival: start + std::time::Duration::from_millis(i as u64 * 10),
+                raw: raw.as_bytes().to_vec(),
+                skipped: 0,
+            };
+            app.handle_read(&line);
+        }
+        assert_eq!(app.gaps.len(), EXAMPLE.lines().count() - 1);
+        assert_eq!(app.gaps.max(), Some(std::time::Duration::from_millis(10)));
+        assert!(app.nmea.latitude.is_some());
+
+        // a second receiver's lines come in between, from another thread
+        let line = Line {
+            id: 1,
+            local: Local::now(),
+            arrival: start + std::time::Duration::from_secs(10),
+            raw: EXAMPLE.lines().next().unwrap().as_bytes().to_vec(),
+            skipped: 0,
+        };
+        app.handle_read(&line);
+        assert_eq!(app.gaps.len(), EXAMPLE.lines().count() - 1);
+    }
+
+    #[test]
+    fn ended_source_is_told_and_reopened() {
+        let mut app = App::with_defaults();
+        app.source = "log.nmea".to_owned();
+        app.add_peer("/dev/ttyUSB1".to_owned());
+        assert_eq!(app.end_notice(), None);
+        app.handle_key(Key::Char('R'));
+        assert!(!app.reopen);
+
+        app.source_ended(1, End::Error("device gone".to_owned()));
+        assert_eq!(app.messages.lines[0].kind, MessageKind::Alert);
+        assert_eq!(
+            app.messages.lines[0].text,
+            "/dev/ttyUSB1 failed (device gone)"
+        );
+        assert_eq!(
+            app.end_notice().unwrap(),
+            "second source failed (device gone) — press q to quit or R to reopen"
+        );
+
+        app.source_ended(0, End::Eof);
+        assert_eq!(
+            app.end_notice().unwrap(),
+            "source ended (EOF) — press q to quit or R to reopen"
+        );
+
+        app.handle_key(Key::Char('R'));
+        assert!(app.reopen);
+        assert!(app.has_ended(0) && app.has_ended(1));
+        app.source_reopened(0);
+        app.source_reopened(1);
+        assert_eq!(app.end_notice(), None);
+    }
+}
@@ -0,0 +1,89 @@
+//! APB and XTE sentences for an autopilot or chartplotter following the
+//! active route leg
+
+use crate::{route::Navigation, sentence::with_checksum};
+
+const METERS_PER_NM: f64 = 1852.0;
+
+fn flag(set: bool) -> &'static str {
+    if set {
+        "A"
+    } else {
+        "V"
+    }
+}
+
+/// Cross track error in nautical miles and the side to steer to get back on
+/// the leg. On the first leg there is no leg line, so no error either.
+fn steer(nav: &Navigation) -> (f64, &'static str) {
+    let xte = nav.xte.unwrap_or(0.0);
+    let side = if xte > 0.0 { "L" } else { "R" };
+    (xte.abs() / METERS_PER_NM, side)
+}
+
+/// `$GPAPB`, autopilot sentence B, with bearings relative to true north
+pub fn apb(nav: &Navigation) -> String {
+    let (xte, side) = steer(nav);
+    with_checksum(&format!(
+        "GPAPB,A,A,{:.2},{},N,{},{},{:05.1},T,{},{:05.1},T,{:05.1},T",
+        xte,
+        side,
+        flag(nav.arrived),
+        flag(nav.passed),
+        nav.track,
+        nav.to,
+        nav.bearing,
+        nav.bearing,
+    ))
+}
+
+/// `$GPXTE`, cross track error
+pub fn xte(nav: &Navigation) -> String {
+    let (xte, side) = steer(nav);
+    with_checksum(&format!("GPXTE,A,A,{:.2},{},N", xte, side))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sentence::checksum;
+
+    #[test]
+    fn checksums_match_reference_sentences() {
+        assert_eq!(
+            checksum("GPAPB,A,A,0.10,R,N,V,V,011,M,DEST,011,M,011,M"),
+            0x3C
+        );
+        assert_eq!(checksum("GPXTE,A,A,0.67,L,N"), 0x6F);
+    }
+
+    #[test]
+    fn sentences_steer_back_to_the_leg() {
+        let mut nav = Navigation {
+            to: "east".to_owned(),
+            distance: 500.0,
+            bearing: 90.3,
+            track: 90.0,
+            arrived: false,
+            passed: false,
+            xte: Some(111.2),
+            eta: None,
+        };
+        assert_eq!(
+            apb(&nav),
+            "$GPAPB,A,A,0.06,L,N,V,V,090.0,T,east,090.3,T,090.3,T*2E"
+        );
+        assert_eq!(xte(&nav), "$GPXTE,A,A,0.06,L,N*68");
+
+        nav.to = "RP001".to_owned();
+        nav.bearing = 45.0;
+        nav.track = 45.0;
+        nav.arrived = true;
+        nav.xte = None;
+        assert_eq!(
+            apb(&nav),
+            "$GPAPB,A,A,0.00,R,N,A,V,045.0,T,RP001,045.0,T,045.0,T*19"
+        );
+        assert_eq!(xte(&nav), "$GPXTE,A,A,0.00,R,N*70");
+    }
+}
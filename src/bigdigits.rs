@@ -0,0 +1,175 @@
+//! Numbers in seven-segment digits several rows high, to be read from across
+//! a car. A digit `height` rows high is `(height + 1) / 2` columns wide, and
+//! digits are one column apart.
+
+/// Below this a 6 can't be told from an 8
+pub const MIN_HEIGHT: u16 = 5;
+
+const SEGMENT: char = '█';
+
+/// Segments lit per digit, bits 0 to 6 being a (top), b (upper right),
+/// c (lower right), d (bottom), e (lower left), f (upper left) and g (middle)
+const DIGITS: [u8; 10] = [
+    0b011_1111, // 0: abcdef
+    0b000_0110, // 1: bc
+    0b101_1011, // 2: abdeg
+    0b100_1111, // 3: abcdg
+    0b110_0110, // 4: bcfg
+    0b110_1101, // 5: acdfg
+    0b111_1101, // 6: acdefg
+    0b000_0111, // 7: abc
+    0b111_1111, // 8: abcdefg
+    0b110_1111, // 9: abcdfg
+];
+/// What '-' lights: the middle segment
+const MINUS: u8 = 0b100_0000;
+
+fn digit_width(height: u16) -> u16 {
+    height.div_ceil(2)
+}
+
+/// Columns `c` takes, none for characters which can't be shown
+fn char_width(c: char, height: u16) -> u16 {
+    match c {
+        '0'..='9' | '-' | ' ' => digit_width(height),
+        '.' => 1,
+        _ => 0,
+    }
+}
+
+/// Columns `text` takes at `height`
+pub fn width(text: &str, height: u16) -> u16 {
+    let widths: Vec<u16> = text
+        .chars()
+        .map(|c| char_width(c, height))
+        .filter(|&w| w > 0)
+        .collect();
+    widths.iter().sum::<u16>() + (widths.len() as u16).saturating_sub(1)
+}
+
+/// The tallest odd height, from `MIN_HEIGHT` up, at which `text` fits in
+/// `max_width` columns and `max_height` rows
+pub fn fit_height(text: &str, max_width: u16, max_height: u16) -> Option<u16> {
+    let mut height = if max_height.is_multiple_of(2) {
+        max_height.saturating_sub(1)
+    } else {
+        max_height
+    };
+    while height >= MIN_HEIGHT {
+        if width(text, height) <= max_width {
+            return Some(height);
+        }
+        height -= 2;
+    }
+    None
+}
+
+/// Whether cell (`row`, `column`) of a digit `height` rows high is lit by
+/// `segments`
+fn lit(segments: u8, row: u16, column: u16, height: u16) -> bool {
+    let (last_row, last_column, middle) = (height - 1, digit_width(height) - 1, height / 2);
+    let on = |bit: u8| segments & (1 << bit) != 0;
+    (on(0) && row == 0)
+        || (on(1) && column == last_column && row <= middle)
+        || (on(2) && column == last_column && row >= middle)
+        || (on(3) && row == last_row)
+        || (on(4) && column == 0 && row >= middle)
+        || (on(5) && column == 0 && row <= middle)
+        || (on(6) && row == middle)
+}
+
+/// `text` as `height` rows of seven-segment digits, `height` being odd and
+/// at least `MIN_HEIGHT`. Digits, '.', '-' and spaces are shown, anything
+/// else is left out.
+pub fn render(text: &str, height: u16) -> Vec<String> {
+    let mut rows = vec![String::new(); height as usize];
+    let shown = text.chars().filter(|&c| char_width(c, height) > 0);
+    for (i, c) in shown.enumerate() {
+        for (row, line) in (0..height).zip(rows.iter_mut()) {
+            if i > 0 {
+                line.push(' ');
+            }
+            match c {
+                '.' => line.push(if row == height - 1 { SEGMENT } else { ' ' }),
+                c => {
+                    let segments = match c {
+                        '-' => MINUS,
+                        ' ' => 0,
+                        c => DIGITS[c.to_digit(10).unwrap_or(8) as usize],
+                    };
+                    for column in 0..digit_width(height) {
+                        let lit = lit(segments, row, column, height);
+                        line.push(if lit { SEGMENT } else { ' ' });
+                    }
+                }
+            }
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A glyph drawn with '#' for the segments, rows split by '/'
+    fn glyph(text: &str, height: u16) -> String {
+        render(text, height).join("/").replace(SEGMENT, "#")
+    }
+
+    #[test]
+    fn every_digit_at_the_smallest_height() {
+        let expected = [
+            "###/# #/# #/# #/###",
+            "  #/  #/  #/  #/  #",
+            "###/  #/###/#  /###",
+            "###/  #/###/  #/###",
+            "# #/# #/###/  #/  #",
+            "###/#  /###/  #/###",
+            "###/#  /###/# #/###",
+            "###/  #/  #/  #/  #",
+            "###/# #/###/# #/###",
+            "###/# #/###/  #/###",
+        ];
+        for (digit, expected) in expected.iter().enumerate() {
+            assert_eq!(glyph(&digit.to_string(), 5), *expected, "{}", digit);
+        }
+    }
+
+    #[test]
+    fn digits_scale_with_the_height() {
+        assert_eq!(
+            glyph("4", 7),
+            ["#  #", "#  #", "#  #", "####", "   #", "   #", "   #"].join("/")
+        );
+        assert_eq!(width("4", 7), 4);
+    }
+
+    #[test]
+    fn decimal_point_minus_and_spacing() {
+        assert_eq!(
+            glyph("1.5", 5),
+            [
+                "  #   ###",
+                "  #   #  ",
+                "  #   ###",
+                "  #     #",
+                "  # # ###"
+            ]
+            .join("/")
+        );
+        assert_eq!(width("1.5", 5), 9);
+        assert_eq!(glyph("-", 5), "   /   /###/   /   ");
+        // what can't be shown takes no room
+        assert_eq!(glyph("7°", 5), glyph("7", 5));
+    }
+
+    #[test]
+    fn tallest_height_that_fits() {
+        // 12.3 is 3 digits, a point and 3 gaps: 3 * 5 + 1 + 3 at height 9
+        assert_eq!(fit_height("12.3", 19, 20), Some(9));
+        assert_eq!(fit_height("12.3", 80, 10), Some(9));
+        assert_eq!(fit_height("12.3", 80, 4), None);
+        assert_eq!(fit_height("12.3", 12, 20), None);
+    }
+}
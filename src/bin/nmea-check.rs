@@ -0,0 +1,114 @@
+//! Validation of recorded NMEA logs: parses every line as nmeacli would and
+//! prints what the logs hold and what is wrong with them, failing when too
+//! many lines are bad so it can gate recorded fixtures in CI.
+
+use std::{
+    fs,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::{Context, Error};
+use clap::Parser;
+use flate2::read::GzDecoder;
+use nmeacli::{check::Report, source::Gzip};
+
+/// Checks recorded NMEA logs
+#[derive(Parser, Debug)]
+#[clap(version)]
+struct Args {
+    /// Logs to check, one after the other; stdin when none or `-`
+    #[clap(value_name = "FILE")]
+    files: Vec<PathBuf>,
+
+    /// Exit with status 1 when more than this percentage of the lines which
+    /// aren't blank fail
+    #[clap(long, value_name = "PERCENT")]
+    max_errors: Option<f64>,
+
+    /// Print each failing line with where it is and why
+    #[clap(long, short)]
+    verbose: bool,
+
+    /// Decompress logs with gzip: auto does so for names ending in .gz
+    #[clap(long, value_enum, value_name = "WHEN", default_value = "auto")]
+    gzip: Gzip,
+}
+
+fn open(path: &Path, gzip: Gzip) -> io::Result<Box<dyn BufRead>> {
+    if path == Path::new("-") {
+        return Ok(Box::new(io::BufReader::new(io::stdin())));
+    }
+    let file = fs::File::open(path)?;
+    Ok(if gzip.applies_to(path) {
+        Box::new(io::BufReader::new(GzDecoder::new(file)))
+    } else {
+        Box::new(io::BufReader::new(file))
+    })
+}
+
+/// Feeds `reader` to `report` a line at a time; lines aren't always UTF-8,
+/// so they are read as bytes
+fn check(
+    report: &mut Report,
+    name: &str,
+    mut reader: impl BufRead,
+    verbose: bool,
+) -> io::Result<()> {
+    let mut buf = Vec::new();
+    let mut number = 0;
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            return Ok(());
+        }
+        number += 1;
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim_end_matches(|c| c == '\r' || c == '\n');
+        if let Err(reason) = report.check_line(line) {
+            if verbose {
+                println!("{}:{}: {}: {}", name, number, reason, line);
+            }
+        }
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let args = Args::parse();
+    let files = if args.files.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        args.files
+    };
+
+    let mut report = Report::new();
+    for path in &files {
+        let name = if path == Path::new("-") {
+            "stdin".to_owned()
+        } else {
+            path.display().to_string()
+        };
+        let reader = open(path, args.gzip).with_context(|| format!("opening {}", name))?;
+        check(&mut report, &name, reader, args.verbose)
+            .with_context(|| format!("reading {}", name))?;
+    }
+    if args.verbose && report.error_count() > 0 {
+        println!();
+    }
+    for line in report.summary() {
+        println!("{}", line);
+    }
+
+    if let Some(max) = args.max_errors {
+        if report.error_rate() > max {
+            eprintln!(
+                "{:.1}% of the lines failed, more than the {}% allowed",
+                report.error_rate(),
+                max
+            );
+            process::exit(1);
+        }
+    }
+    Ok(())
+}
@@ -1,310 +1,1237 @@
-use std::{io, net::TcpStream, sync::mpsc, thread};
-
-use anyhow::Error;
-use io::BufRead;
-use nmea::Nmea;
-use termion::{event::Key, input::MouseTerminal, raw::IntoRawMode, screen::AlternateScreen};
-use tui::{
-    backend::TermionBackend,
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Style},
-    text::{Span, Spans},
-    widgets::{Block, Borders, Paragraph, Wrap},
-    Terminal,
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
 };
 
+use anyhow::{anyhow, Context, Error};
+use clap::Parser;
+use nmeacli::{
+    ais::Ais,
+    app::{MaxSpeed, SpeedFilter, DEFAULT_UERE},
+    export::{self, Altitude},
+    format::StatusFormat,
+    geofence::Zone,
+    keys::KeyMap,
+    logfile,
+    metrics::{self, MetricValues},
+    ntrip::{self, Mountpoint},
+    output::Output,
+    prefs::{CoordFormat, PanelLayout, Prefs, Units},
+    route,
+    simulate::{self, Circle, SimSource, Simulator},
+    source::{self, End, Gzip, Source, SourceEvent, SourceSpec},
+    theme::{Theme, ThemeName},
+    ui, App, Options,
+};
+use serde::{Deserialize, Serialize};
+use tui::Terminal;
+
 #[allow(dead_code)]
 mod util {
     use std::io;
     use std::sync::mpsc;
-    use std::sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    };
     use std::thread;
     use std::time::Duration;
 
-    use termion::event::Key;
-    use termion::input::TermRead;
+    use nmeacli::{
+        keys::{Mouse, MouseKind},
+        Key,
+    };
+
+    #[cfg(not(any(feature = "termion-backend", feature = "crossterm-backend")))]
+    compile_error!("enable either the termion-backend or the crossterm-backend feature");
 
     pub enum Event<I> {
         Input(I),
-        Tick,
+        Mouse(Mouse),
+        /// the terminal changed size
+        Resize,
+    }
+
+    /// Blocking source of key presses and mouse events, read from the input
+    /// thread
+    pub trait KeyReader: Send + 'static {
+        /// Next key or mouse event, or `None` once the input is closed
+        fn read_event(&mut self) -> Option<Event<Key>>;
+    }
+
+    #[cfg(not(feature = "crossterm-backend"))]
+    mod imp {
+        use std::{
+            fs,
+            io::{self, Write},
+        };
+
+        use termion::event::{Event as TermEvent, Key as TermKey, MouseButton, MouseEvent};
+        use termion::input::{Events, MouseTerminal, TermRead};
+        use termion::raw::IntoRawMode;
+        use termion::screen::AlternateScreen;
+        use tui::backend::TermionBackend;
+
+        use super::{Event, Key, KeyReader, Mouse, MouseKind};
+
+        /// Stdout in raw mode, reporting the mouse or not
+        pub type Backend = TermionBackend<AlternateScreen<Box<dyn Write>>>;
+
+        /// Columns and rows of the terminal
+        pub fn size() -> io::Result<(u16, u16)> {
+            termion::terminal_size()
+        }
+
+        pub fn backend(mouse: bool) -> io::Result<Backend> {
+            let stdout = io::stdout().into_raw_mode()?;
+            // MouseTerminal turns reporting on, and off again when dropped
+            let stdout: Box<dyn Write> = if mouse {
+                Box::new(MouseTerminal::from(stdout))
+            } else {
+                Box::new(stdout)
+            };
+            let stdout = AlternateScreen::from(stdout);
+            Ok(TermionBackend::new(stdout))
+        }
+
+        /// Keys and mouse events from the controlling terminal rather than
+        /// stdin, which may carry NMEA
+        pub struct TtyKeys(Events<fs::File>);
+
+        pub fn key_reader() -> io::Result<TtyKeys> {
+            Ok(TtyKeys(termion::get_tty()?.events()))
+        }
+
+        impl KeyReader for TtyKeys {
+            fn read_event(&mut self) -> Option<Event<Key>> {
+                for event in self.0.by_ref().flatten() {
+                    let key = match event {
+                        TermEvent::Key(key) => key,
+                        TermEvent::Mouse(MouseEvent::Press(button, column, row)) => {
+                            let kind = match button {
+                                MouseButton::Left => MouseKind::Click,
+                                MouseButton::WheelUp => MouseKind::WheelUp,
+                                MouseButton::WheelDown => MouseKind::WheelDown,
+                                _ => continue,
+                            };
+                            // termion counts cells from 1
+                            return Some(Event::Mouse(Mouse {
+                                kind,
+                                column: column.saturating_sub(1),
+                                row: row.saturating_sub(1),
+                            }));
+                        }
+                        _ => continue,
+                    };
+                    let key = match key {
+                        TermKey::Backspace => Key::Backspace,
+                        TermKey::Left => Key::Left,
+                        TermKey::Right => Key::Right,
+                        TermKey::Up => Key::Up,
+                        TermKey::Down => Key::Down,
+                        TermKey::Home => Key::Home,
+                        TermKey::End => Key::End,
+                        TermKey::PageUp => Key::PageUp,
+                        TermKey::PageDown => Key::PageDown,
+                        TermKey::Delete => Key::Delete,
+                        TermKey::Insert => Key::Insert,
+                        TermKey::F(n) => Key::F(n),
+                        TermKey::Char(c) => Key::Char(c),
+                        TermKey::Alt(c) => Key::Alt(c),
+                        TermKey::Ctrl(c) => Key::Ctrl(c),
+                        TermKey::Esc => Key::Esc,
+                        _ => continue,
+                    };
+                    return Some(Event::Input(key));
+                }
+                None
+            }
+        }
+    }
+
+    #[cfg(feature = "crossterm-backend")]
+    mod imp {
+        use std::io::{self, Write};
+
+        use crossterm::event::{
+            self, DisableMouseCapture, EnableMouseCapture, Event as TermEvent, KeyCode, KeyEvent,
+            KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+        };
+        use crossterm::execute;
+        use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+        use tui::backend::CrosstermBackend;
+
+        use super::{Event, Key, KeyReader, Mouse, MouseKind};
+
+        /// Stdout in raw mode on the alternate screen, restored on drop
+        pub struct Screen(io::Stdout);
+
+        impl Write for Screen {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                self.0.flush()
+            }
+        }
+
+        impl Drop for Screen {
+            fn drop(&mut self) {
+                execute!(self.0, DisableMouseCapture, LeaveAlternateScreen).ok();
+                terminal::disable_raw_mode().ok();
+            }
+        }
+
+        pub type Backend = CrosstermBackend<Screen>;
+
+        fn error(e: crossterm::ErrorKind) -> io::Error {
+            io::Error::new(io::ErrorKind::Other, e.to_string())
+        }
+
+        /// Columns and rows of the terminal
+        pub fn size() -> io::Result<(u16, u16)> {
+            terminal::size().map_err(error)
+        }
+
+        pub fn backend(mouse: bool) -> io::Result<Backend> {
+            terminal::enable_raw_mode().map_err(error)?;
+            let mut stdout = Screen(io::stdout());
+            execute!(stdout.0, EnterAlternateScreen).map_err(error)?;
+            if mouse {
+                execute!(stdout.0, EnableMouseCapture).map_err(error)?;
+            }
+            Ok(CrosstermBackend::new(stdout))
+        }
+
+        /// crossterm reads the controlling terminal when stdin is redirected
+        pub struct TtyKeys;
+
+        pub fn key_reader() -> io::Result<TtyKeys> {
+            Ok(TtyKeys)
+        }
+
+        impl KeyReader for TtyKeys {
+            fn read_event(&mut self) -> Option<Event<Key>> {
+                loop {
+                    let KeyEvent { code, modifiers } = match event::read() {
+                        Ok(TermEvent::Key(key)) => key,
+                        Ok(TermEvent::Mouse(MouseEvent {
+                            kind, column, row, ..
+                        })) => {
+                            let kind = match kind {
+                                MouseEventKind::Down(MouseButton::Left) => MouseKind::Click,
+                                MouseEventKind::ScrollUp => MouseKind::WheelUp,
+                                MouseEventKind::ScrollDown => MouseKind::WheelDown,
+                                _ => continue,
+                            };
+                            return Some(Event::Mouse(Mouse { kind, column, row }));
+                        }
+                        Ok(TermEvent::Resize(..)) => return Some(Event::Resize),
+                        Ok(_) => continue,
+                        Err(_) => return None,
+                    };
+                    let key = match code {
+                        KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => {
+                            Key::Ctrl(c)
+                        }
+                        KeyCode::Char(c) if modifiers.contains(KeyModifiers::ALT) => Key::Alt(c),
+                        KeyCode::Char(c) => Key::Char(c),
+                        KeyCode::Enter => Key::Char('\n'),
+                        KeyCode::Tab => Key::Char('\t'),
+                        KeyCode::Backspace => Key::Backspace,
+                        KeyCode::Left => Key::Left,
+                        KeyCode::Right => Key::Right,
+                        KeyCode::Up => Key::Up,
+                        KeyCode::Down => Key::Down,
+                        KeyCode::Home => Key::Home,
+                        KeyCode::End => Key::End,
+                        KeyCode::PageUp => Key::PageUp,
+                        KeyCode::PageDown => Key::PageDown,
+                        KeyCode::Delete => Key::Delete,
+                        KeyCode::Insert => Key::Insert,
+                        KeyCode::F(n) => Key::F(n),
+                        KeyCode::Esc => Key::Esc,
+                        _ => continue,
+                    };
+                    return Some(Event::Input(key));
+                }
+            }
+        }
     }
 
-    /// A small event handler that wrap termion input and tick events. Each event
-    /// type is handled in its own thread and returned to a common `Receiver`
+    /// tui backend of the enabled terminal library; crossterm wins when both
+    /// features are enabled
+    pub use imp::{backend, key_reader, size, Backend};
+
+    /// A small event handler that wrap key input and resizes. Each event type
+    /// is handled in its own thread and returned to a common `Receiver`
     pub struct Events {
         rx: mpsc::Receiver<Event<Key>>,
+        /// taken off the channel by `wait`, for `next` to return
+        pending: Option<Event<Key>>,
         input_handle: thread::JoinHandle<()>,
-        ignore_exit_key: Arc<AtomicBool>,
-        tick_handle: thread::JoinHandle<()>,
+        resize_handle: thread::JoinHandle<()>,
     }
 
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Debug, Clone)]
     pub struct Config {
-        pub exit_key: Key,
-        pub tick_rate: Duration,
+        /// how often the terminal size is checked; nothing is sent unless it
+        /// changed
+        pub resize_poll: Duration,
     }
 
     impl Default for Config {
         fn default() -> Config {
             Config {
-                exit_key: Key::Char('q'),
-                tick_rate: Duration::from_millis(50),
+                resize_poll: Duration::from_millis(500),
             }
         }
     }
 
     impl Events {
-        pub fn new() -> Events {
+        pub fn new() -> io::Result<Events> {
             Events::with_config(Config::default())
         }
 
-        pub fn with_config(config: Config) -> Events {
+        pub fn with_config(config: Config) -> io::Result<Events> {
+            Ok(Events::with_reader(config, key_reader()?))
+        }
+
+        pub fn with_reader<R: KeyReader>(config: Config, mut reader: R) -> Events {
             let (tx, rx) = mpsc::channel();
-            let ignore_exit_key = Arc::new(AtomicBool::new(false));
+            let resize_poll = config.resize_poll;
             let input_handle = {
                 let tx = tx.clone();
-                let ignore_exit_key = ignore_exit_key.clone();
+                // every key goes to the main loop, which decides what quits
                 thread::spawn(move || {
-                    let stdin = io::stdin();
-                    for evt in stdin.keys() {
-                        if let Ok(key) = evt {
-                            if let Err(err) = tx.send(Event::Input(key)) {
-                                eprintln!("{}", err);
-                                return;
-                            }
-                            if !ignore_exit_key.load(Ordering::Relaxed) && key == config.exit_key {
-                                return;
-                            }
+                    while let Some(event) = reader.read_event() {
+                        if let Err(err) = tx.send(event) {
+                            log::warn!("stopped reading input: {}", err);
+                            return;
                         }
                     }
                 })
             };
 
-            let tick_handle = {
-                thread::spawn(move || loop {
-                    tx.send(Event::Tick).unwrap();
-                    thread::sleep(config.tick_rate);
+            let resize_handle = {
+                // termion tells nothing of resizes, so the size is watched
+                // here for both backends
+                thread::spawn(move || {
+                    let mut last_size = size().ok();
+                    loop {
+                        thread::sleep(resize_poll);
+                        let current = size().ok();
+                        if current != last_size {
+                            last_size = current;
+                            if tx.send(Event::Resize).is_err() {
+                                return;
+                            }
+                        }
+                    }
                 })
             };
             Events {
                 rx,
-                ignore_exit_key,
+                pending: None,
                 input_handle,
-                tick_handle,
+                resize_handle,
             }
         }
 
-        pub fn next(&self) -> Result<Event<Key>, mpsc::TryRecvError> {
-            self.rx.try_recv()
-        }
-
-        pub fn disable_exit_key(&mut self) {
-            self.ignore_exit_key.store(true, Ordering::Relaxed);
+        pub fn next(&mut self) -> Result<Event<Key>, mpsc::TryRecvError> {
+            match self.pending.take() {
+                Some(event) => Ok(event),
+                None => self.rx.try_recv(),
+            }
         }
 
-        pub fn enable_exit_key(&mut self) {
-            self.ignore_exit_key.store(false, Ordering::Relaxed);
+        /// Waits up to `timeout` for an event, which `next` then returns
+        /// first
+        pub fn wait(&mut self, timeout: Duration) {
+            if self.pending.is_none() {
+                self.pending = self.rx.recv_timeout(timeout).ok();
+            }
         }
     }
 }
 
-use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike};
-use chrono::{Local, SecondsFormat, Utc};
 use util::*;
 
-fn datetime_str(nmea: &Nmea, rmc_datetime: NaiveDateTime) -> Option<String> {
-    let fix_date = nmea.fix_date?;
-    let date = NaiveDate::from_ymd(fix_date.year() + 2000, fix_date.month(), fix_date.day());
-    let datetime = date.and_time(nmea.fix_time?);
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct SourceConfig {
+    /// TCP address to read from, like NMEACLI_ADDR
+    addr: Option<String>,
+    /// Unix domain socket to read from, like NMEACLI_UNIX
+    unix: Option<PathBuf>,
+    /// serial device or file to read from, like NMEACLI_DEV
+    dev: Option<PathBuf>,
+}
 
-    let diff = rmc_datetime - datetime;
+impl SourceConfig {
+    /// NMEACLI_ADDR, NMEACLI_UNIX and NMEACLI_DEV, as `var` finds them,
+    /// over the config file
+    fn with_env(self, var: impl Fn(&str) -> Option<OsString>) -> SourceConfig {
+        SourceConfig {
+            addr: var("NMEACLI_ADDR")
+                .and_then(|addr| addr.into_string().ok())
+                .or(self.addr),
+            unix: var("NMEACLI_UNIX").map(PathBuf::from).or(self.unix),
+            dev: var("NMEACLI_DEV").map(PathBuf::from).or(self.dev),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct BufferConfig {
+    /// lines kept in the Messages pane
+    messages: usize,
+    /// fixes kept for the track map
+    track: usize,
+}
 
-    Some(format!(
-        "{} / diff={} / {}",
-        datetime,
-        diff,
-        datetime.nanosecond()
-    ))
+impl Default for BufferConfig {
+    fn default() -> BufferConfig {
+        BufferConfig {
+            messages: 100,
+            track: 300,
+        }
+    }
 }
 
-fn latlonalt_str(nmea: &Nmea) -> Option<String> {
-    Some(format!(
-        "{:.6} / {:.6} / {:.6}",
-        nmea.latitude?, nmea.longitude?, nmea.altitude?
-    ))
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct LoggingConfig {
+    snr_log: Option<PathBuf>,
+    average_out: Option<PathBuf>,
+    ttff_out: Option<PathBuf>,
+    waypoints: Option<PathBuf>,
 }
 
-fn dop_str(nmea: &Nmea) -> Option<String> {
-    Some(format!(
-        "{:.2} / {:.2} / {:.2}",
-        nmea.hdop?, nmea.vdop?, nmea.pdop?
-    ))
+/// User settings from `config.toml`. They take precedence over the
+/// preferences remembered from the last run, command line flags and
+/// environment variables take precedence over both.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Config {
+    units: Option<Units>,
+    coord_format: Option<CoordFormat>,
+    theme: Option<ThemeName>,
+    panels: Option<PanelLayout>,
+    speed_alpha: Option<f32>,
+    /// range error of the receiver in meters
+    uere: Option<f32>,
+    /// seconds after which an AIS target not heard from is dropped
+    ais_timeout: Option<u64>,
+    /// talkers whose sentences are parsed, all when empty
+    accept_talkers: Vec<String>,
+    /// sentence types which are only shown, never parsed
+    ignore_sentences: Vec<String>,
+    /// move dates over a year old forward by 1024 GPS weeks
+    fix_week_rollover: Option<bool>,
+    /// which height waypoint exports carry
+    altitude: Option<Altitude>,
+    /// key names per action, replacing that action's default keys
+    keys: HashMap<String, Vec<String>>,
+    source: SourceConfig,
+    buffers: BufferConfig,
+    logging: LoggingConfig,
+    /// circular zones whose entry and exit are reported
+    zones: Vec<Zone>,
 }
 
-fn option_str(s: Option<String>) -> String {
-    match s {
-        Some(s) => s,
-        None => "<not available>".to_owned(),
+/// Lines read but not handled yet; past this many a log waits and a
+/// receiver's oldest lines are dropped
+const LINE_QUEUE: usize = 1024;
+
+/// Lines handled between two redraws
+const MAX_LINES_PER_TICK: usize = 1000;
+
+/// How often autopilot sentences are sent
+const OUTPUT_INTERVAL: Duration = Duration::from_secs(1);
+/// Longest wait for lines while they keep coming
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+/// Longest wait once no line came for `IDLE_AFTER`
+const IDLE_REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+const IDLE_AFTER: Duration = Duration::from_secs(5);
+/// The screen is redrawn at least this often, for the clock and the age of
+/// the data, even when nothing arrived
+const HEARTBEAT: Duration = Duration::from_secs(1);
+
+const DEFAULT_CONFIG: &str = r#"# nmeacli configuration, usually ~/.config/nmeacli/config.toml
+
+# metric, imperial or nautical
+units = "metric"
+# decimal, dm or dms
+coord_format = "decimal"
+# dark, light or mono
+theme = "dark"
+panels = "status,sky+track+waypoints,messages"
+# weight of the newest sample in the smoothed speed, 0 < alpha <= 1
+speed_alpha = 0.3
+# range error of the receiver in meters; HDOP times this estimates accuracy
+uere = 5.0
+# seconds after which an AIS target not heard from is dropped
+ais_timeout = 600
+# only parse sentences from these talkers, e.g. ["GN", "GP"]; all when empty
+accept_talkers = []
+# sentence types which are only shown, never parsed, e.g. ["GSV"]
+ignore_sentences = []
+# move receiver dates over a year old forward by 1024 weeks, for receivers
+# which missed a GPS week number rollover
+fix_week_rollover = false
+# height exported waypoints carry: msl, or ellipsoidal for MSL plus the
+# geoid separation from GGA
+altitude = "msl"
+
+[keys]
+# each entry replaces the default keys of that action
+# quit = ["q", "ctrl-c"]
+# help = ["?", "F1"]
+# export_satellites = ["E"]
+# toggle_average = ["a"]
+# cycle_units = ["u"]
+# cycle_coord_format = ["c"]
+# toggle_track = ["m"]
+# mark_waypoint = ["w"]
+# export_waypoints = ["W"]
+# freeze_frame = ["f"]
+# scroll_text_up = ["pageup"]
+# scroll_text_down = ["pagedown"]
+# toggle_hex = ["x"]
+# quick_waypoint = ["s"]
+# cycle_target = ["t"]
+# toggle_rejected = ["X"]
+# next_route_point = ["]"]
+# prev_route_point = ["["]
+# select_sentence = ["r"]
+# toggle_sentence_filter = ["i"]
+# command = [":"]
+# pmtk_menu = ["p"]
+# select_newer_message = ["up"]
+# select_older_message = ["down"]
+# inspect_message = ["enter"]
+# search = ["/"]
+# next_match = ["n"]
+# prev_match = ["N"]
+# copy = ["y"]
+# next_panel = ["tab"]
+# reopen = ["R"]
+# hide_panel = ["h"]
+# show_panel = ["H"]
+# zen = ["z"]
+# clear_messages = ["C"]
+# reset_state = ["ctrl-x"]
+
+[source]
+# NMEACLI_ADDR, NMEACLI_UNIX and NMEACLI_DEV take precedence
+# addr = "127.0.0.1:10021"
+# unix = "/run/gps.sock"
+# dev = "/dev/ttyACM0"
+
+[buffers]
+messages = 100
+track = 300
+
+[logging]
+# snr_log = "snr.csv"
+# average_out = "average.txt"
+# ttff_out = "ttff.csv"
+# waypoints = "waypoints.gpx"
+
+# entering and leaving these is reported in the Messages pane; radius in meters
+# [[zones]]
+# name = "home"
+# lat = 37.5665
+# lon = 126.9780
+# radius = 100
+"#;
+
+impl Config {
+    fn default_path() -> Option<PathBuf> {
+        let dirs = directories::ProjectDirs::from("", "", "nmeacli")?;
+        Some(dirs.config_dir().join("config.toml"))
+    }
+
+    /// Parses `s`, collecting the keys nmeacli doesn't know about instead of
+    /// rejecting them
+    fn parse(s: &str) -> Result<(Config, Vec<String>), Error> {
+        let mut unknown = Vec::new();
+        let config = serde_ignored::deserialize(&mut toml::Deserializer::new(s), |path| {
+            unknown.push(path.to_string())
+        })?;
+        Ok((config, unknown))
+    }
+
+    /// Loads `path`, or the default location when `None`. Only the default
+    /// file is allowed to be missing.
+    fn load(path: Option<&Path>) -> Result<(Config, Vec<String>), Error> {
+        let (path, required) = match (path, Config::default_path()) {
+            (Some(path), _) => (path.to_owned(), true),
+            (None, Some(path)) => (path, false),
+            (None, None) => return Ok((Config::default(), Vec::new())),
+        };
+
+        let s = match fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(e) if !required && e.kind() == io::ErrorKind::NotFound => {
+                return Ok((Config::default(), Vec::new()));
+            }
+            Err(e) => return Err(anyhow!("failed to read {}: {}", path.display(), e)),
+        };
+        Config::parse(&s).map_err(|e| anyhow!("invalid config {}: {}", path.display(), e))
+    }
+
+    /// The config `args` ask for: none with --no-config, unless --config
+    /// names one anyway
+    fn for_args(args: &Args) -> Result<(Config, Vec<String>), Error> {
+        if args.no_config && args.config.is_none() {
+            Ok((Config::default(), Vec::new()))
+        } else {
+            Config::load(args.config.as_deref())
+        }
+    }
+
+    /// Fills in whatever the command line left unset
+    fn merge_into(self, args: &mut Args, prefs: &mut Prefs) {
+        args.snr_log = args.snr_log.take().or(self.logging.snr_log);
+        args.average_out = args.average_out.take().or(self.logging.average_out);
+        args.ttff_out = args.ttff_out.take().or(self.logging.ttff_out);
+        args.waypoints = args.waypoints.take().or(self.logging.waypoints);
+        args.theme = args.theme.or(self.theme);
+        args.panels = args.panels.take().or(self.panels);
+        args.speed_alpha = args.speed_alpha.or(self.speed_alpha);
+        args.uere = args.uere.or(self.uere);
+        args.ais_timeout = args.ais_timeout.or(self.ais_timeout);
+        if args.accept_talkers.is_empty() {
+            args.accept_talkers = self.accept_talkers;
+        }
+        if args.ignore_sentences.is_empty() {
+            args.ignore_sentences = self.ignore_sentences;
+        }
+        args.fix_week_rollover |= self.fix_week_rollover.unwrap_or(false);
+        args.altitude = args.altitude.or(self.altitude);
+
+        prefs.units = self.units.unwrap_or(prefs.units);
+        prefs.coord_format = self.coord_format.unwrap_or(prefs.coord_format);
+        if let Some(panels) = args.panels.clone() {
+            prefs.panels = panels;
+        }
     }
 }
 
+/// TUI-based NMEA data stream visualizer
+#[derive(Parser, Debug)]
+struct Args {
+    /// Append one row per satellite per GSV cycle to this CSV file
+    #[clap(long, value_name = "FILE")]
+    snr_log: Option<PathBuf>,
+
+    /// Start averaging fixes at launch and stop after SECS seconds
+    #[clap(long, value_name = "SECS")]
+    average: Option<u64>,
+
+    /// Only average fixes with an HDOP below this
+    #[clap(long, value_name = "HDOP", default_value = "2.0")]
+    average_max_hdop: f32,
+
+    /// Also average fixes from a 2D solution
+    #[clap(long)]
+    average_allow_2d: bool,
+
+    /// Append the result of each averaging run to this file
+    #[clap(long, value_name = "FILE")]
+    average_out: Option<PathBuf>,
+
+    /// Time the first fix: from the first sentence, after a reconnect, or
+    /// from a hot, warm or cold restart command sent at the prompt
+    #[clap(long)]
+    ttff: bool,
+
+    /// Append a CSV row for each time to first fix to this file; implies
+    /// --ttff
+    #[clap(long, value_name = "FILE")]
+    ttff_out: Option<PathBuf>,
+
+    /// Export waypoints to this file on exit and on 'W', as GPX when the
+    /// extension is .gpx and CSV otherwise
+    #[clap(long, value_name = "FILE")]
+    waypoints: Option<PathBuf>,
+
+    /// Panels to show, top to bottom; panels joined with '+' share a row
+    #[clap(long, value_name = "LAYOUT")]
+    panels: Option<PanelLayout>,
+
+    /// Weight of the newest sample when smoothing speed, between 0 and 1
+    #[clap(long, value_name = "ALPHA")]
+    speed_alpha: Option<f32>,
+
+    /// Follow the route of this GPX file, or its waypoints when it has none
+    #[clap(long, value_name = "FILE")]
+    route: Option<PathBuf>,
+
+    /// Distance in meters at which a route point counts as reached
+    #[clap(long, value_name = "METERS", default_value = "20")]
+    arrival_radius: f64,
+
+    /// Serve APB and XTE sentences for the active route leg to TCP clients
+    /// connecting to ADDR
+    #[clap(long, value_name = "ADDR")]
+    serve: Option<String>,
+
+    /// Write APB and XTE sentences for the active route leg to this serial
+    /// port or file
+    #[clap(long, value_name = "PATH")]
+    out_device: Option<PathBuf>,
+
+    /// Range error of the receiver in meters, which HDOP is multiplied by
+    /// for the estimated accuracy
+    #[clap(long, value_name = "METERS")]
+    uere: Option<f32>,
+
+    /// Drop AIS targets not heard from for SECS seconds
+    #[clap(long, value_name = "SECS")]
+    ais_timeout: Option<u64>,
+
+    /// Only parse sentences from these talkers, e.g. GN,GP; the others are
+    /// shown in Messages marked filtered. Proprietary sentences always pass.
+    #[clap(long, value_name = "TALKERS", value_delimiter = ',')]
+    accept_talkers: Vec<String>,
+
+    /// Only show sentences of these types, e.g. GSV, without parsing them
+    #[clap(long, value_name = "TYPES", value_delimiter = ',')]
+    ignore_sentences: Vec<String>,
+
+    /// Move receiver dates over a year old forward by 1024 weeks, for
+    /// receivers which missed a GPS week number rollover
+    #[clap(long)]
+    fix_week_rollover: bool,
+
+    /// Height exported waypoints carry: above mean sea level, or above the
+    /// WGS84 ellipsoid using the geoid separation from GGA
+    #[clap(long, value_enum, value_name = "REF")]
+    altitude: Option<Altitude>,
+
+    /// Drop fixes implying a move faster than SPEED m/s from the last one, or
+    /// with 'auto' a few times faster than the recent speed
+    #[clap(long, value_name = "SPEED")]
+    max_speed: Option<MaxSpeed>,
+
+    /// Color theme, overriding the preferences file and NO_COLOR
+    #[clap(long, value_enum, value_name = "THEME")]
+    theme: Option<ThemeName>,
+
+    /// Read settings from this file instead of the default config.toml
+    #[clap(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Print a config file with the default settings and exit
+    #[clap(long)]
+    print_default_config: bool,
+
+    /// Ignore config.toml unless --config is given, and neither load nor
+    /// save UI preferences
+    #[clap(long)]
+    no_config: bool,
+
+    /// Serve Prometheus metrics on http://ADDR/metrics
+    #[clap(long, value_name = "ADDR")]
+    metrics_addr: Option<String>,
+
+    /// Append diagnostics to FILE: sources opened and reconnected, rejected
+    /// lines, dropped lines and, at trace, every line received. Nothing else
+    /// is written to stderr while the TUI is up, but --format.
+    #[clap(long, value_name = "FILE")]
+    log_file: Option<PathBuf>,
+
+    /// Least severe diagnostics written to --log-file: error, warn, info,
+    /// debug or trace
+    #[clap(long, value_name = "LEVEL", default_value = "info")]
+    log_level: log::LevelFilter,
+
+    /// Print a line to stderr on every fix, like "{lat},{lon},{alt},{hdop}";
+    /// unknown placeholders are printed as they are
+    #[clap(long, value_name = "TEMPLATE")]
+    format: Option<StatusFormat>,
+
+    /// Feed the receiver RTK corrections from an NTRIP caster, given as
+    /// user:pass@caster:2101/MOUNTPOINT, and send it the receiver's GGA.
+    /// The first SOURCE must be writable: a serial port or a socket.
+    #[clap(long, value_name = "MOUNTPOINT")]
+    ntrip: Option<Mountpoint>,
+
+    /// Leave the mouse to the terminal, for selecting text, instead of
+    /// scrolling and focusing panels and selecting messages with it
+    #[clap(long)]
+    no_mouse: bool,
+
+    /// Read from a simulated receiver instead of a SOURCE, following --route
+    /// when given and driving in a circle otherwise
+    #[clap(long, conflicts_with = "sources")]
+    simulate: bool,
+
+    /// Circle the simulated receiver drives along, with the radius in meters
+    #[clap(long, value_name = "LAT,LON,RADIUS", requires = "simulate")]
+    simulate_circle: Option<Circle>,
+
+    /// Epochs a second of the simulated receiver
+    #[clap(long, value_name = "HZ", default_value = "1")]
+    simulate_rate: f64,
+
+    /// Seed of the simulated receiver, for the same satellites and fix
+    /// changes on every run; random by default
+    #[clap(long, value_name = "SEED", requires = "simulate")]
+    simulate_seed: Option<u64>,
+
+    /// Decompress recorded logs with gzip: auto does so for names ending in .gz
+    #[clap(long, value_enum, value_name = "WHEN", default_value = "auto")]
+    gzip: Gzip,
+
+    /// Where to read from: host:port or tcp://host:port, udp://addr to listen
+    /// on, a serial port, a Unix socket, a log file or '-' for stdin.
+    /// Overrides the NMEACLI_* variables. A second receiver given as another
+    /// SOURCE is compared side by side with the first.
+    #[clap(value_name = "SOURCE", max_values = 2)]
+    sources: Vec<String>,
+}
+
 fn main() -> Result<(), Error> {
+    let mut args = Args::parse();
+    if args.print_default_config {
+        print!("{}", DEFAULT_CONFIG);
+        return Ok(());
+    }
+    if let Some(path) = &args.log_file {
+        logfile::init(path, args.log_level)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        log::info!("nmeacli {} started", env!("CARGO_PKG_VERSION"));
+    }
+
+    let mut prefs = if args.no_config {
+        Prefs::default()
+    } else {
+        Prefs::load()
+    };
+    let (config, unknown_keys) = Config::for_args(&args)?;
+    let keymap = KeyMap::new(&config.keys)?;
+    let buffers = config.buffers.clone();
+    let source = config.source.clone();
+    let zones = config.zones.clone();
+    config.merge_into(&mut args, &mut prefs);
+    let speed_alpha = args.speed_alpha.unwrap_or(SpeedFilter::DEFAULT_ALPHA);
+    if !(speed_alpha > 0.0 && speed_alpha <= 1.0) {
+        return Err(anyhow!(
+            "speed alpha must be in (0, 1], got {}",
+            speed_alpha
+        ));
+    }
+    let uere = args.uere.unwrap_or(DEFAULT_UERE);
+    if !(uere > 0.0 && uere.is_finite()) {
+        return Err(anyhow!("uere must be above 0, got {}", uere));
+    }
+
+    // see https://no-color.org: flags and config files override NO_COLOR
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let theme_name = args.theme.unwrap_or(if no_color {
+        ThemeName::Mono
+    } else {
+        ThemeName::Dark
+    });
+    let theme = Theme::new(theme_name);
+    let route = match &args.route {
+        Some(path) => route::load(path)?,
+        None => Vec::new(),
+    };
+    if !(args.simulate_rate > 0.0 && args.simulate_rate <= 100.0) {
+        return Err(anyhow!(
+            "simulate rate must be in (0, 100] Hz, got {}",
+            args.simulate_rate
+        ));
+    }
+    let simulation = if args.simulate {
+        let path = if route.is_empty() {
+            simulate::Path::Circle(args.simulate_circle.unwrap_or_default())
+        } else {
+            simulate::Path::Route(route.clone())
+        };
+        let seed = args.simulate_seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos() as u64)
+        });
+        let start = chrono::Utc::now().naive_utc();
+        Some((seed, Simulator::new(path, args.simulate_rate, seed, start)))
+    } else {
+        None
+    };
+
+    let options = Options {
+        snr_log: args.snr_log.clone(),
+        average: args.average,
+        average_max_hdop: args.average_max_hdop,
+        average_allow_2d: args.average_allow_2d,
+        average_out: args.average_out.clone(),
+        waypoints: args.waypoints.clone(),
+        speed_alpha,
+        messages: buffers.messages,
+        track: buffers.track,
+        max_speed: args.max_speed,
+        uere,
+        zones,
+        route,
+        arrival_radius: args.arrival_radius,
+        ais_timeout: args.ais_timeout.unwrap_or(Ais::DEFAULT_TIMEOUT_SECS),
+        accept_talkers: args.accept_talkers.clone(),
+        ignore_sentences: args.ignore_sentences.clone(),
+        fix_week_rollover: args.fix_week_rollover,
+        altitude: args.altitude.unwrap_or(Altitude::Msl),
+        ttff: args.ttff || args.ttff_out.is_some(),
+        ttff_out: args.ttff_out.clone(),
+    };
+
+    // connect before taking over the terminal so failures are readable
+    let SourceConfig { addr, unix, dev } = source.with_env(std::env::var_os);
+    // specs are kept to reopen sources which ended, a simulation has none
+    let (name, spec, source): (String, Option<SourceSpec>, Box<dyn Source>) = match simulation {
+        Some((seed, sim)) => (
+            format!("simulation, seed {}", seed),
+            None,
+            Box::new(SimSource::new(sim)),
+        ),
+        None => {
+            let spec = match (args.sources.first(), addr, unix, dev) {
+                (Some(spec), _, _, _) => SourceSpec::detect(spec)?,
+                (_, Some(addr), _, _) => SourceSpec::Tcp(addr),
+                (_, _, Some(path), _) => SourceSpec::Unix(path),
+                (_, _, _, Some(dev)) if dev == Path::new("-") => SourceSpec::Stdin,
+                (_, _, _, Some(dev)) => SourceSpec::Device(dev),
+                _ => {
+                    return Err(anyhow!(
+                        "no source given: pass SOURCE, --simulate or set NMEACLI_ADDR, NMEACLI_UNIX or NMEACLI_DEV"
+                    ));
+                }
+            };
+            let source = spec
+                .open(args.gzip)
+                .with_context(|| format!("failed to open {}", spec))?;
+            (spec.to_string(), Some(spec), source)
+        }
+    };
+    let peer = match args.sources.get(1) {
+        Some(spec) => {
+            let spec = SourceSpec::detect(spec)?;
+            let source = spec
+                .open(args.gzip)
+                .with_context(|| format!("failed to open {}", spec))?;
+            Some((spec, source))
+        }
+        None => None,
+    };
+    let ntrip = match &args.ntrip {
+        Some(mount) => {
+            let writer = source.writer().with_context(|| {
+                format!("--ntrip forwards corrections to the receiver, but {}", name)
+            })?;
+            Some(ntrip::spawn(mount.clone(), writer))
+        }
+        None => None,
+    };
+
+    let metrics = match &args.metrics_addr {
+        Some(addr) => {
+            let values = Arc::new(Mutex::new(MetricValues::default()));
+            metrics::serve(addr, values.clone())?;
+            Some(values)
+        }
+        None => None,
+    };
+
+    let mut output = Output::new();
+    if let Some(addr) = &args.serve {
+        output
+            .serve(addr)
+            .with_context(|| format!("failed to listen on {}", addr))?;
+    }
+    if let Some(path) = &args.out_device {
+        output
+            .open_device(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+    }
+
     // Terminal initialization
-    let stdout = io::stdout().into_raw_mode()?;
-    let stdout = MouseTerminal::from(stdout);
-    let stdout = AlternateScreen::from(stdout);
-    let backend = TermionBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    let mut terminal = Terminal::new(util::backend(!args.no_mouse)?)?;
     terminal.hide_cursor()?;
     terminal.clear()?;
 
-    let events = Events::new();
+    let mut events = Events::new()?;
 
-    let (tx, rx) = mpsc::channel();
+    let (tx, rx) = source::line_queue(LINE_QUEUE);
+    let mut app = App::new(options, prefs, theme, keymap);
+    log::info!("reading {}", name);
+    app.source = name;
+    // commands can only be sent to serial ports and sockets
+    app.writer = source.writer().ok();
+    app.ntrip = ntrip;
+    source::spawn_reader(0, source, tx.clone());
+    let mut specs = vec![spec];
+    if let Some((spec, source)) = peer {
+        log::info!("reading {} as the second receiver", spec);
+        app.add_peer(spec.to_string());
+        source::spawn_reader(1, source, tx.clone());
+        specs.push(Some(spec));
+    }
+    for key in unknown_keys {
+        app.notice(format!("ignoring unknown config key '{}'", key));
+    }
 
-    let bufread: io::BufReader<Box<dyn io::Read + Send>> =
-        match (std::env::var("NMEACLI_ADDR"), std::env::var("NMEACLI_DEV")) {
-            (Ok(addr), _) => {
-                let stream = TcpStream::connect(addr)?;
-                io::BufReader::new(Box::new(stream))
-            }
-            (_, Ok(dev)) => {
-                let file = std::fs::File::open(dev)?;
-                io::BufReader::new(Box::new(file))
-            }
-            _ => {
-                panic!("NMEACLI_ADDR or NMEACLI_DEV should be specified");
+    let mut last_output = Instant::now();
+    let mut last_line = Instant::now();
+    let mut last_draw: Option<Instant> = None;
+    // as counted by the line queue
+    let mut lines_dropped = 0;
+    loop {
+        // only lines, input and the heartbeat change the screen
+        let mut dirty = false;
+        // a burst is taken in slices so the screen keeps being redrawn
+        for event in rx.try_iter().take(MAX_LINES_PER_TICK) {
+            dirty = true;
+            let line = match event {
+                SourceEvent::Line(line) => line,
+                SourceEvent::End { id, end } => {
+                    app.source_ended(id, end);
+                    continue;
+                }
+            };
+            last_line = Instant::now();
+            let last_fix = app.last_fix;
+            app.handle_read(&line);
+            if let (Some(format), true) = (&args.format, app.last_fix != last_fix) {
+                eprintln!("{}", format.render(&app.nmea));
             }
-        };
+        }
+        let dropped = rx.dropped();
+        if dropped > lines_dropped {
+            log::warn!(
+                "dropped {} lines, the UI is too slow",
+                dropped - lines_dropped
+            );
+            // counted by the app, which may be reset
+            app.lines_dropped += dropped - lines_dropped;
+            lines_dropped = dropped;
+        }
 
-    let _thread = thread::spawn(move || {
-        let tx = tx.clone();
+        app.tick();
 
-        let mut lines = bufread.lines();
-        lines.next();
+        if !output.is_empty() && last_output.elapsed() >= OUTPUT_INTERVAL {
+            last_output = Instant::now();
+            for sentence in app.autopilot_sentences().unwrap_or_default() {
+                if let Err(e) = output.send(&sentence) {
+                    app.alert(format!("stopped writing to the output device: {}", e));
+                    break;
+                }
+            }
+        }
 
-        for line in lines {
-            let local: DateTime<Local> = Local::now();
-            let line = line.unwrap();
-            tx.send((local, line)).ok();
+        if let Some(metrics) = &metrics {
+            if let Ok(mut values) = metrics.lock() {
+                *values = MetricValues::from_app(&app);
+            }
         }
-    });
 
-    let mut nmea = Nmea::new();
-    let mut messages = Vec::new();
-    let mut rmc_datetime = Utc::now().naive_utc();
-
-    'outer: loop {
-        while let Ok((local, line)) = rx.try_recv() {
-            if let Ok(msg) = nmea.parse(&line) {
-                let time_str = local.to_rfc3339_opts(SecondsFormat::Secs, true);
-
-                messages.insert(
-                    0,
-                    Spans::from(vec![
-                        Span::styled(time_str, Style::default().fg(Color::DarkGray)),
-                        Span::raw(format!(" {}\n", line.trim())),
-                    ]),
-                );
-
-                while messages.len() > 100 {
-                    messages.pop();
+        while let Ok(event) = events.next() {
+            let input = match event {
+                Event::Input(input) => input,
+                Event::Mouse(mouse) => {
+                    dirty = true;
+                    let size = terminal.size()?;
+                    if let Some((panel, message)) =
+                        ui::panel_at(size, &app, mouse.column, mouse.row)
+                    {
+                        app.handle_mouse(mouse.kind, panel, message);
+                    }
+                    continue;
                 }
+                // redrawn below, at the new size
+                Event::Resize => {
+                    dirty = true;
+                    continue;
+                }
+            };
+            dirty = true;
+            app.handle_key(input);
+            if app.quit {
+                break;
+            }
+        }
+        if app.quit {
+            break;
+        }
 
-                match msg {
-                    nmea::SentenceType::RMC => {
-                        rmc_datetime = local.naive_utc();
+        if std::mem::take(&mut app.reopen) {
+            for (id, spec) in specs.iter().enumerate() {
+                let spec = match spec {
+                    Some(spec) if app.has_ended(id) => spec,
+                    _ => continue,
+                };
+                match spec.open(args.gzip) {
+                    Ok(source) => {
+                        if id == 0 {
+                            app.writer = source.writer().ok();
+                        }
+                        source::spawn_reader(id, source, tx.clone());
+                        app.source_reopened(id);
                     }
-                    _ => {}
+                    Err(e) => app.alert(format!("failed to reopen {}: {}", spec, e)),
                 }
             }
         }
 
-        while let Ok(Event::Input(input)) = events.next() {
-            if let Key::Char('q') = input {
-                break 'outer;
-            }
+        if dirty || last_draw.is_none_or(|t| t.elapsed() >= HEARTBEAT) {
+            terminal.draw(|f| ui::draw(f, &app))?;
+            last_draw = Some(Instant::now());
         }
+        // a key press or a resize is drawn right away, lines at the next
+        // pass
+        let wait = if last_line.elapsed() >= IDLE_AFTER {
+            IDLE_REDRAW_INTERVAL
+        } else {
+            REDRAW_INTERVAL
+        };
+        events.wait(wait);
+    }
 
-        terminal.draw(|f| {
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints(
-                    [
-                        Constraint::Length(5),
-                        Constraint::Min(15),
-                        Constraint::Length(20),
-                    ]
-                    .as_ref(),
-                )
-                .split(f.size());
-
-            {
-                let chunk = chunks[0];
-                let block = Block::default().title("Status").borders(Borders::TOP);
-
-                let mut msgs = Vec::new();
-
-                msgs.push(Spans::from(format!(
-                    "datetime   : {}\n",
-                    option_str(datetime_str(&nmea, rmc_datetime)),
-                )));
-                msgs.push(Spans::from(format!(
-                    "latlonalt  : {}\n",
-                    option_str(latlonalt_str(&nmea)),
-                )));
-                msgs.push(Spans::from(format!(
-                    "dop (h/v/p): {}\n",
-                    option_str(dop_str(&nmea)),
-                )));
-
-                let body_rect = block.inner(chunk);
-                let paragraph = Paragraph::new(msgs).wrap(Wrap { trim: false });
-
-                f.render_widget(block, chunk);
-                f.render_widget(paragraph, body_rect);
-            }
+    terminal.clear()?;
+    // leave the alternate screen first so a save error stays visible
+    drop(terminal);
+
+    if let Some(ttff) = &app.ttff {
+        for measurement in ttff.history.iter().rev() {
+            println!(
+                "TTFF {} {}",
+                measurement.started.format("%Y-%m-%d %H:%M:%S"),
+                measurement.describe()
+            );
+        }
+    }
 
-            {
-                let chunk = chunks[1];
-                let title = Span::raw(format!(
-                    "Satellites (fixed={}, total={})",
-                    option_str(nmea.num_of_fix_satellites.map(|v| v.to_string())),
-                    nmea.satellites.len(),
-                ));
-                let block = Block::default().title(title).borders(Borders::TOP);
+    if let (Some(path), false) = (&args.waypoints, app.waypoints.is_empty()) {
+        if let Err(e) = export::write_waypoints(path, &app.waypoints, app.options.altitude) {
+            log::error!("failed to export waypoints to {}: {}", path.display(), e);
+            eprintln!("failed to export waypoints to {}: {}", path.display(), e);
+        }
+    }
 
-                let mut msgs = Vec::new();
+    if !args.no_config {
+        app.prefs.waypoints = app.waypoints.clone();
+        if let Err(e) = app.prefs.save() {
+            log::error!("failed to save preferences: {}", e);
+            eprintln!("failed to save preferences: {}", e);
+        }
+    }
 
-                for sat in &nmea.satellites {
-                    msgs.push(Spans::from(format!("{}\n", sat)));
-                }
+    // quitting after the source failed is an error, after it ended is not
+    if let Some(End::Error(e)) = &app.ended {
+        log::error!("{} failed: {}", app.source, e);
+        return Err(anyhow!("{} failed: {}", app.source, e));
+    }
+    Ok(())
+}
 
-                let body_rect = block.inner(chunk);
-                let paragraph = Paragraph::new(msgs).wrap(Wrap { trim: false });
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                f.render_widget(block, chunk);
-                f.render_widget(paragraph, body_rect);
-            }
+    #[test]
+    fn default_config_parses_without_unknown_keys() {
+        let (config, unknown) = Config::parse(DEFAULT_CONFIG).unwrap();
+        assert!(unknown.is_empty(), "{:?}", unknown);
+        assert_eq!(config.units, Some(Units::Metric));
+        assert_eq!(config.theme, Some(ThemeName::Dark));
+        assert_eq!(config.buffers.messages, 100);
+    }
 
-            {
-                let chunk = chunks[2];
+    #[test]
+    fn unknown_keys_are_reported_not_rejected() {
+        let (config, unknown) =
+            Config::parse("colour = \"red\"\nuere = 3.0\n[buffers]\nmessages = 50\nlines = 3\n")
+                .unwrap();
+        assert_eq!(unknown, ["colour", "buffers.lines"]);
+        assert_eq!(config.uere, Some(3.0));
+        assert_eq!(config.buffers.messages, 50);
+        assert!(Config::parse("uere = \"five\"").is_err());
+    }
 
-                let block = Block::default().title("Messages").borders(Borders::TOP);
+    #[test]
+    fn flags_beat_the_config_which_beats_the_preferences() {
+        let (config, _) = Config::parse(
+            "units = \"nautical\"\ntheme = \"light\"\nuere = 3.0\n\
+             [logging]\nsnr_log = \"config.csv\"\n",
+        )
+        .unwrap();
+        let mut args = Args::parse_from(["nmeacli", "--theme", "mono", "--snr-log", "flag.csv"]);
+        let mut prefs = Prefs {
+            units: Units::Imperial,
+            coord_format: CoordFormat::Dms,
+            ..Prefs::default()
+        };
+        config.merge_into(&mut args, &mut prefs);
 
-                let body_rect = block.inner(chunk);
-                let paragraph = Paragraph::new(messages.clone()).wrap(Wrap { trim: false });
+        assert_eq!(args.theme, Some(ThemeName::Mono));
+        assert_eq!(args.snr_log, Some(PathBuf::from("flag.csv")));
+        assert_eq!(args.uere, Some(3.0));
+        assert_eq!(args.speed_alpha, None);
+        assert_eq!(prefs.units, Units::Nautical);
+        // not in the config, so the saved preference stays
+        assert_eq!(prefs.coord_format, CoordFormat::Dms);
+    }
 
-                f.render_widget(block, chunk);
-                f.render_widget(paragraph, body_rect);
-            }
-        })?;
-        thread::sleep(std::time::Duration::from_millis(200));
+    #[test]
+    fn the_environment_beats_the_config() {
+        let source = SourceConfig {
+            addr: Some("config:10110".to_owned()),
+            unix: None,
+            dev: Some(PathBuf::from("/dev/ttyConfig")),
+        };
+        let source = source.with_env(|name| match name {
+            "NMEACLI_ADDR" => Some("env:10110".into()),
+            "NMEACLI_UNIX" => Some("/run/env.sock".into()),
+            _ => None,
+        });
+        assert_eq!(source.addr.as_deref(), Some("env:10110"));
+        assert_eq!(source.unix, Some(PathBuf::from("/run/env.sock")));
+        assert_eq!(source.dev, Some(PathBuf::from("/dev/ttyConfig")));
     }
 
-    terminal.clear()?;
-    Ok(())
+    #[test]
+    fn no_config_skips_all_but_an_explicit_config() {
+        let (config, unknown) =
+            Config::for_args(&Args::parse_from(["nmeacli", "--no-config"])).unwrap();
+        assert_eq!(config.uere, None);
+        assert!(unknown.is_empty());
+
+        let path = std::env::temp_dir().join(format!("nmeacli-test-{}.toml", std::process::id()));
+        fs::write(&path, "uere = 2.5\nbogus = 1\n").unwrap();
+        let path_arg = path.to_str().unwrap();
+        let loaded = Config::for_args(&Args::parse_from([
+            "nmeacli",
+            "--no-config",
+            "--config",
+            path_arg,
+        ]));
+        fs::remove_file(&path).unwrap();
+        let (config, unknown) = loaded.unwrap();
+        assert_eq!(config.uere, Some(2.5));
+        assert_eq!(unknown, ["bogus"]);
+
+        // only the default file may be missing
+        assert!(Config::for_args(&Args::parse_from([
+            "nmeacli",
+            "--no-config",
+            "--config",
+            path_arg,
+        ]))
+        .is_err());
+    }
 }
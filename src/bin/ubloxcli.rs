@@ -1,15 +1,2575 @@
-use std::io::Write;
+//! Configuration of u-blox receivers over their serial port. Each packet is
+//! sent until the receiver acks or naks it, or restarts for a reset. Also
+//! prints what a receiver sends, live or from a capture file.
 
-pub fn main() -> std::io::Result<()> {
-    let rst = ublox::CfgRstBuilder {
-        nav_bbr_mask: ublox::NavBbrMask::all(),
-        reset_mode: ublox::ResetMode::HardwareResetImmediately,
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Error};
+use chrono::Utc;
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use nmeacli::{
+    provision::{self, Step},
+    sentence::Sentence,
+    ubx::{
+        self, CfgCfg, Frame, FrameReader, Gnss, HpPosLlh, Nav5, Nav5Change, NavPvt, NavSat,
+        NavSvin, Piece, PortConfig, PulseLen, PulseRate, RelPosNed, RfStatus, SatInfo, TimeMode,
+        TimePulse, Version,
+    },
+    ubxcfg::{self, Item, Key},
+};
+use serialport::SerialPort;
+use ublox::{
+    AlignmentToReferenceTime, CfgMsgAllPortsBuilder, CfgRateBuilder, CfgRstBuilder, NavBbrMask,
+    ResetMode,
+};
+
+/// How long a single read of the port may block
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Baud rates u-blox UARTs support
+const BAUD_RATES: &[u32] = &[
+    4800, 9600, 19200, 38400, 57600, 115200, 230400, 460800, 921600,
+];
+
+/// How long to wait for the MGA-ACK of an aiding message
+const MGA_ACK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Pause after each aiding message when the receiver doesn't ack them
+const MGA_PACING: Duration = Duration::from_millis(10);
+
+const ASSISTNOW_URL: &str = "https://online-live1.services.u-blox.com/GetOnlineData.ashx";
+
+/// Bytes of the default NMEA output per solution, roughly
+const NMEA_BYTES_PER_SOLUTION: u32 = 500;
+
+/// Configures a u-blox receiver
+#[derive(Parser, Debug)]
+#[clap(version)]
+struct Args {
+    /// Serial port of the receiver, like /dev/ttyUSB0 or COM3
+    #[clap(long, global = true, value_name = "PATH")]
+    device: Option<String>,
+
+    /// Baud rate of the serial port
+    #[clap(long, global = true, value_name = "BAUD", default_value = "9600")]
+    baud: u32,
+
+    /// Write the packets to FILE instead of sending them, for offline use;
+    /// for raw, the file the measurements go to
+    #[clap(long, global = true, value_name = "FILE")]
+    out: Option<PathBuf>,
+
+    /// Seconds to wait for the receiver to answer
+    #[clap(long, global = true, value_name = "SECS", default_value = "5")]
+    timeout: u64,
+
+    /// Send a packet again up to N times when the receiver doesn't answer
+    #[clap(long, global = true, value_name = "N", default_value = "0")]
+    retries: u32,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Restart the receiver and wait for its boot messages
+    #[clap(group(ArgGroup::new("start").args(&["hot", "warm", "cold"])))]
+    Reset {
+        /// Keep all navigation data
+        #[clap(long)]
+        hot: bool,
+        /// Clear the ephemeris; the default is a cold start
+        #[clap(long)]
+        warm: bool,
+        /// Clear all navigation data
+        #[clap(long)]
+        cold: bool,
+    },
+
+    /// Set how often the receiver measures and computes a solution, then read
+    /// the setting back
+    Rate {
+        /// Solutions per second, 0.1 to 40; short for --meas-ms with one
+        /// measurement per solution
+        #[clap(
+            long,
+            value_name = "HZ",
+            value_parser = parse_hz,
+            required_unless_present = "meas_ms",
+            conflicts_with_all = &["meas_ms", "nav_ratio"]
+        )]
+        hz: Option<f64>,
+        /// Milliseconds between measurements, 25 to 10000
+        #[clap(
+            long,
+            value_name = "MS",
+            value_parser = clap::value_parser!(u16).range(25..=10000)
+        )]
+        meas_ms: Option<u16>,
+        /// Measurements per navigation solution, 1 to 127
+        #[clap(
+            long,
+            alias = "nav-cycles",
+            value_name = "N",
+            default_value = "1",
+            value_parser = clap::value_parser!(u16).range(1..=127)
+        )]
+        nav_ratio: u16,
+        /// Time the measurements are aligned to
+        #[clap(long, value_enum, value_name = "REF", default_value = "gps")]
+        time_ref: TimeRef,
+    },
+
+    /// Show or set how often messages are output
+    #[clap(group(ArgGroup::new("action").required(true).args(&["list", "set"])))]
+    Msg {
+        /// Poll the rates of the standard NMEA messages
+        #[clap(long)]
+        list: bool,
+        /// Output MSG once every RATE solutions, 0 to turn it off, like GSV=5
+        /// or NAV-PVT=1; MSG may also be class and id in hex like F0-03. May
+        /// be repeated.
+        #[clap(long, value_name = "MSG=RATE", value_parser = parse_msg_rate)]
+        set: Vec<MsgRate>,
+        /// Ports to set the rates on, all of them by default: i2c, uart1,
+        /// uart2, usb or spi
+        #[clap(
+            long,
+            value_name = "PORTS",
+            value_delimiter = ',',
+            value_parser = parse_port
+        )]
+        port: Vec<usize>,
+    },
+
+    /// Set the dynamic platform model, minimum elevation and fix mode, or
+    /// show the navigation settings; settings not given are left alone
+    #[clap(group(
+        ArgGroup::new("nav")
+            .required(true)
+            .multiple(true)
+            .args(&["model", "min_elev", "fix_mode", "show"])
+    ))]
+    Nav5 {
+        /// Dynamic platform model: portable, stationary, pedestrian,
+        /// automotive, sea, airborne1g, airborne2g, airborne4g, wrist or bike
+        #[clap(long, value_name = "MODEL", value_parser = parse_dyn_model)]
+        model: Option<u8>,
+        /// Degrees above the horizon below which satellites are not used, -90
+        /// to 90
+        #[clap(
+            long,
+            value_name = "DEG",
+            allow_hyphen_values = true,
+            value_parser = clap::value_parser!(i8).range(-90..=90)
+        )]
+        min_elev: Option<i8>,
+        /// Fix mode: 2d, 3d or auto
+        #[clap(long, value_name = "MODE", value_parser = parse_fix_mode)]
+        fix_mode: Option<u8>,
+        /// Print the navigation settings and change nothing
+        #[clap(long, conflicts_with_all = &["model", "min_elev", "fix_mode"])]
+        show: bool,
+    },
+
+    /// Enable or disable satellite systems, or show which are configured
+    #[clap(group(
+        ArgGroup::new("systems")
+            .required(true)
+            .multiple(true)
+            .args(&["enable", "disable", "show"])
+    ))]
+    Gnss {
+        /// Systems to turn on: gps, sbas, galileo, beidou, imes, qzss or
+        /// glonass
+        #[clap(
+            long,
+            value_name = "GNSS",
+            value_delimiter = ',',
+            value_parser = parse_gnss
+        )]
+        enable: Vec<u8>,
+        /// Systems to turn off
+        #[clap(
+            long,
+            value_name = "GNSS",
+            value_delimiter = ',',
+            value_parser = parse_gnss
+        )]
+        disable: Vec<u8>,
+        /// Print the systems with their channels and change nothing
+        #[clap(long, conflicts_with_all = &["enable", "disable"])]
+        show: bool,
+    },
+
+    /// Save the configuration so it survives power cycles
+    Save {
+        /// Sections to save: ioport, msgconf, infmsg, navconf, rxmconf or
+        /// antconf; all of them by default
+        #[clap(
+            long,
+            value_name = "SECTIONS",
+            value_delimiter = ',',
+            value_parser = parse_section
+        )]
+        sections: Vec<u32>,
+        /// Where to save: bbr, flash, eeprom or spi-flash
+        #[clap(
+            long,
+            value_name = "DEVICES",
+            value_delimiter = ',',
+            default_values = &["bbr", "flash"],
+            value_parser = parse_device
+        )]
+        devices: Vec<u8>,
+    },
+
+    /// Revert to the factory settings, clearing the saved configuration
+    LoadDefaults {
+        /// Sections to revert, as for save; all of them by default
+        #[clap(
+            long,
+            value_name = "SECTIONS",
+            value_delimiter = ',',
+            value_parser = parse_section
+        )]
+        sections: Vec<u32>,
+        /// Where to clear the saved configuration
+        #[clap(
+            long,
+            value_name = "DEVICES",
+            value_delimiter = ',',
+            default_values = &["bbr", "flash"],
+            value_parser = parse_device
+        )]
+        devices: Vec<u8>,
+        /// Confirm the saved configuration is to be lost
+        #[clap(long)]
+        yes: bool,
+    },
+
+    /// Print the firmware and hardware versions and the extensions, like the
+    /// protocol version, GNSS and module type
+    Version {
+        /// Print JSON instead
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Print the unique ID of the chip with the module type and firmware, for
+    /// telling receivers apart
+    Id {
+        /// Print JSON instead
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Change the baud rate of a port and the protocols it takes and sends,
+    /// or show its settings
+    #[clap(group(
+        ArgGroup::new("change")
+            .required(true)
+            .multiple(true)
+            .args(&["set", "in_proto", "out_proto", "show"])
+    ))]
+    Baud {
+        /// New baud rate: 4800, 9600, 19200, 38400, 57600, 115200, 230400,
+        /// 460800 or 921600
+        #[clap(long, value_name = "BAUD", value_parser = parse_baud)]
+        set: Option<u32>,
+        /// Port to change: i2c, uart1, uart2, usb or spi
+        #[clap(
+            long,
+            value_name = "PORT",
+            default_value = "uart1",
+            value_parser = parse_port
+        )]
+        port: usize,
+        /// Protocols the port takes, like ubx,nmea: ubx, nmea, rtcm or rtcm3
+        #[clap(
+            long,
+            value_name = "PROTOS",
+            value_delimiter = ',',
+            value_parser = parse_protocol
+        )]
+        in_proto: Vec<u16>,
+        /// Protocols the port sends, like ubx for UBX only: ubx, nmea or rtcm3
+        #[clap(
+            long,
+            value_name = "PROTOS",
+            value_delimiter = ',',
+            value_parser = parse_protocol
+        )]
+        out_proto: Vec<u16>,
+        /// Print the settings of the port and change nothing
+        #[clap(long, conflicts_with_all = &["set", "in_proto", "out_proto"])]
+        show: bool,
+    },
+
+    /// Turn on NAV-PVT and print each solution the receiver computes
+    Monitor {
+        /// Print JSON instead, an object per line
+        #[clap(long)]
+        json: bool,
+        /// Stop after N solutions
+        #[clap(long, value_name = "N")]
+        count: Option<u64>,
+        /// Stop after SECS seconds
+        #[clap(long, value_name = "SECS")]
+        seconds: Option<u64>,
+    },
+
+    /// Turn on NAV-HPPOSLLH and NAV-RELPOSNED and print the high precision
+    /// position and the baseline to the base on a line updated each solution
+    Hp {
+        /// Print JSON instead, an object per message
+        #[clap(long)]
+        json: bool,
+        /// Stop after SECS seconds
+        #[clap(long, value_name = "SECS")]
+        seconds: Option<u64>,
+    },
+
+    /// Turn on NAV-SAT and print a table of the satellites of each epoch:
+    /// signal, position in the sky, pseudorange residual, quality and health
+    Sats {
+        /// Print a single epoch and exit
+        #[clap(long)]
+        once: bool,
+        /// Leave out satellites weaker than DBHZ
+        #[clap(long, value_name = "DBHZ", default_value = "0")]
+        min_cno: u8,
+        /// Stop after SECS seconds
+        #[clap(long, value_name = "SECS", conflicts_with = "once")]
+        seconds: Option<u64>,
+    },
+
+    /// Print the antenna status and power, noise level, AGC and jamming
+    /// state, from MON-HW and on generation 9 receivers MON-RF
+    Hw {
+        /// Print a reading every SECS seconds, until Ctrl-C
+        #[clap(
+            long,
+            value_name = "SECS",
+            value_parser = clap::value_parser!(u64).range(1..)
+        )]
+        watch: Option<u64>,
+        /// Print a single reading and exit non-zero on a warning, the default
+        /// without --watch
+        #[clap(long, conflicts_with = "watch")]
+        once: bool,
+    },
+
+    /// Turn the RTCM3 messages of a base station on or off, or show their
+    /// rates
+    #[clap(group(
+        ArgGroup::new("action")
+            .required(true)
+            .multiple(true)
+            .args(&["enable", "disable", "show"])
+    ))]
+    Rtcm {
+        /// Message types to send, like 1005,1074,1084,1094,1124,1230: 1005,
+        /// 1074, 1077, 1084, 1087, 1094, 1097, 1124, 1127, 1230, 4072.0 or
+        /// 4072.1
+        #[clap(
+            long,
+            value_name = "TYPES",
+            value_delimiter = ',',
+            value_parser = parse_rtcm
+        )]
+        enable: Vec<u8>,
+        /// Message types to stop sending
+        #[clap(
+            long,
+            value_name = "TYPES",
+            value_delimiter = ',',
+            value_parser = parse_rtcm
+        )]
+        disable: Vec<u8>,
+        /// Send the enabled types once every RATE solutions
+        #[clap(long, value_name = "RATE", default_value = "1")]
+        rate: u8,
+        /// Port to send them on: i2c, uart1, uart2, usb or spi
+        #[clap(
+            long,
+            value_name = "PORT",
+            default_value = "uart1",
+            value_parser = parse_port
+        )]
+        port: usize,
+        /// Print the rates of the RTCM3 messages on each port
+        #[clap(long)]
+        show: bool,
+    },
+
+    /// Upload AssistNow aiding data for a faster first fix, after the
+    /// current time
+    #[clap(group(ArgGroup::new("source").required(true).args(&["file", "online"])))]
+    Assist {
+        /// AssistNow Offline or Online file, like mgaoffline.ubx
+        #[clap(long, value_name = "FILE")]
+        file: Option<PathBuf>,
+        /// Fetch AssistNow Online data from u-blox
+        #[clap(long, requires = "token")]
+        online: bool,
+        /// AssistNow token for --online
+        #[clap(long, value_name = "TOKEN")]
+        token: Option<String>,
+    },
+
+    /// Turn on RXM-RAWX and RXM-SFRBX and write everything the receiver
+    /// sends to --out, for post-processing; the rates they had are put back
+    /// at the end
+    Raw {
+        /// Stop after SECS seconds, or at Ctrl-C
+        #[clap(long, value_name = "SECS")]
+        seconds: Option<u64>,
+    },
+
+    /// Set up an RTK base station: survey its position in, give it, or go
+    /// back to a rover
+    Base {
+        #[clap(subcommand)]
+        mode: BaseMode,
+    },
+
+    /// Set the time pulse: its frequency or period, its duty cycle or
+    /// length, and whether it waits for GNSS time; or show it
+    #[clap(group(ArgGroup::new("rate").args(&["freq", "period_us"])))]
+    #[clap(group(ArgGroup::new("length").args(&["duty", "pulse_us"])))]
+    Timepulse {
+        /// Pin to set: timepulse or timepulse2
+        #[clap(
+            long,
+            value_name = "PIN",
+            default_value = "timepulse",
+            value_parser = parse_pin
+        )]
+        pin: u8,
+        /// Pulses per second, 1 to 25000000
+        #[clap(long, value_name = "HZ", requires = "length")]
+        freq: Option<u32>,
+        /// Microseconds from a pulse to the next
+        #[clap(long, value_name = "US", requires = "length")]
+        period_us: Option<u32>,
+        /// Percent of the period the pulse lasts, above 0 and below 100
+        #[clap(long, value_name = "PERCENT", requires = "rate")]
+        duty: Option<f64>,
+        /// Microseconds the pulse lasts
+        #[clap(long, value_name = "US", requires = "rate")]
+        pulse_us: Option<u32>,
+        /// Align the pulses to the top of the second
+        #[clap(long)]
+        aligned_to_tow: bool,
+        /// No pulses until the receiver is locked to GNSS time
+        #[clap(long)]
+        only_when_locked: bool,
+        /// Print the settings of the pin and change nothing
+        #[clap(
+            long,
+            conflicts_with_all = &["rate", "length", "aligned_to_tow", "only_when_locked"]
+        )]
+        show: bool,
+    },
+
+    /// Read or change configuration items of generation 9 receivers, like
+    /// the ZED-F9P, with CFG-VALGET and CFG-VALSET
+    Cfg {
+        #[clap(subcommand)]
+        action: CfgAction,
+    },
+
+    /// Run the steps of a YAML file in order, like `- rate: {hz: 5}`, each a
+    /// subcommand with its options, then print which ones worked
+    Apply {
+        /// Steps to run
+        #[clap(value_name = "FILE")]
+        file: PathBuf,
+        /// Go on with the next steps after one fails
+        #[clap(long)]
+        keep_going: bool,
+        /// Print the packets each step would send, in hex, and send nothing
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// List the packets and sentences in a capture file, like a u-center log
+    /// or `cat /dev/ttyACM0 > dump.bin`, decoding the packets it knows
+    Decode {
+        /// Capture file
+        #[clap(value_name = "FILE")]
+        file: PathBuf,
+        /// Only list these packets, like NAV-PVT,ACK-ACK or 0D-01; NMEA for
+        /// the sentences
+        #[clap(
+            long,
+            value_name = "NAMES",
+            value_delimiter = ',',
+            value_parser = parse_filter
+        )]
+        filter: Vec<Filter>,
+        /// Print the packets per type, checksum errors and unknown bytes
+        /// instead
+        #[clap(long)]
+        stats: bool,
+    },
+}
+
+fn parse_baud(s: &str) -> Result<u32, String> {
+    let baud: u32 = s.parse().map_err(|_| format!("invalid baud rate {}", s))?;
+    if BAUD_RATES.contains(&baud) {
+        Ok(baud)
+    } else {
+        Err(format!("unsupported baud rate {}", baud))
+    }
+}
+
+fn parse_hz(s: &str) -> Result<f64, String> {
+    let hz: f64 = s.parse().map_err(|_| format!("invalid rate {}", s))?;
+    if (0.1..=40.0).contains(&hz) {
+        Ok(hz)
+    } else {
+        Err(format!("{} Hz is out of range, 0.1 to 40 Hz", hz))
+    }
+}
+
+fn parse_port(s: &str) -> Result<usize, String> {
+    ubx::PORTS
+        .iter()
+        .position(|port| port.eq_ignore_ascii_case(s.trim()))
+        .ok_or_else(|| format!("unknown port {}; ports are {}", s, ubx::PORTS.join(", ")))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum TimeRef {
+    Utc,
+    Gps,
+}
+
+/// Milliseconds between measurements and measurements per solution
+fn meas_rate(hz: Option<f64>, meas_ms: Option<u16>, nav_ratio: u16) -> (u16, u16) {
+    match hz {
+        Some(hz) => ((1000.0 / hz).round() as u16, 1),
+        None => (meas_ms.expect("clap requires --hz or --meas-ms"), nav_ratio),
+    }
+}
+
+fn parse_protocol(s: &str) -> Result<u16, String> {
+    ubx::protocol_bit(s).ok_or_else(|| {
+        let names: Vec<&str> = ubx::PROTOCOLS.iter().map(|&(name, _)| name).collect();
+        format!("unknown protocol {}; protocols are {}", s, names.join(", "))
+    })
+}
+
+fn parse_named(table: &[(&str, u8)], what: &str, s: &str) -> Result<u8, String> {
+    table
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(s.trim()))
+        .map(|&(_, value)| value)
+        .ok_or_else(|| {
+            let names: Vec<&str> = table.iter().map(|&(name, _)| name).collect();
+            format!("unknown {} {}; use {}", what, s, names.join(", "))
+        })
+}
+
+fn parse_dyn_model(s: &str) -> Result<u8, String> {
+    parse_named(ubx::DYN_MODELS, "model", s)
+}
+
+fn parse_fix_mode(s: &str) -> Result<u8, String> {
+    parse_named(ubx::FIX_MODES, "fix mode", s)
+}
+
+fn parse_gnss(s: &str) -> Result<u8, String> {
+    parse_named(ubx::GNSS_IDS, "system", s)
+}
+
+fn parse_section(s: &str) -> Result<u32, String> {
+    let name = s.replace(|c| c == '-' || c == '_', "");
+    ubx::CFG_SECTIONS
+        .iter()
+        .find(|(section, _)| section.eq_ignore_ascii_case(name.trim()))
+        .map(|&(_, bit)| bit)
+        .ok_or_else(|| {
+            let names: Vec<&str> = ubx::CFG_SECTIONS.iter().map(|&(name, _)| name).collect();
+            format!("unknown section {}; use {}", s, names.join(", "))
+        })
+}
+
+fn parse_device(s: &str) -> Result<u8, String> {
+    parse_named(ubx::CFG_DEVICES, "device", s)
+}
+
+/// CFG-CFG for `save` and `load-defaults`
+fn cfg_cfg(command: &Command) -> Option<CfgCfg> {
+    let mask = |sections: &[u32]| match sections {
+        [] => ubx::CFG_SECTIONS
+            .iter()
+            .fold(0, |mask, &(_, bit)| mask | bit),
+        _ => sections.iter().fold(0, |mask, bit| mask | bit),
+    };
+    let devices = |devices: &[u8]| devices.iter().fold(0, |mask, bit| mask | bit);
+    match command {
+        Command::Save {
+            sections,
+            devices: d,
+        } => Some(CfgCfg::save(mask(sections), devices(d))),
+        Command::LoadDefaults {
+            sections,
+            devices: d,
+            ..
+        } => Some(CfgCfg::load_defaults(mask(sections), devices(d))),
+        _ => None,
+    }
+}
+
+/// A rate to set for a message
+#[derive(Debug, Clone)]
+struct MsgRate {
+    name: String,
+    class: u8,
+    id: u8,
+    rate: u8,
+}
+
+fn parse_msg_rate(s: &str) -> Result<MsgRate, String> {
+    let (name, rate) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected MSG=RATE, like GSV=5, not {}", s))?;
+    let (class, id) = ubx::message_id(name).ok_or_else(|| {
+        let names: Vec<&str> = ubx::MESSAGES.iter().map(|&(name, _, _)| name).collect();
+        format!(
+            "unknown message {}; known ones are {}, or give class and id in hex like F0-03",
+            name,
+            names.join(", ")
+        )
+    })?;
+    let rate = rate
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid rate {}, expected 0 to 255", rate))?;
+    Ok(MsgRate {
+        name: ubx::message_name(class, id).map_or_else(|| name.to_owned(), str::to_owned),
+        class,
+        id,
+        rate,
+    })
+}
+
+#[derive(Subcommand, Debug)]
+enum CfgAction {
+    /// Print the values of items, like CFG-RATE-MEAS CFG-UART1-BAUDRATE
+    Get {
+        /// Items by name, or by id and type like 0x30210001:U2
+        #[clap(value_name = "KEY", required = true, value_parser = Key::parse)]
+        keys: Vec<Key>,
+        /// Layer to read: ram, bbr, flash or default
+        #[clap(
+            long,
+            value_name = "LAYER",
+            default_value = "ram",
+            value_parser = parse_get_layer
+        )]
+        layer: u8,
+    },
+    /// Set items, like CFG-RATE-MEAS=100 or 0x30210001:U2=100
+    Set {
+        #[clap(value_name = "KEY=VALUE", required = true, value_parser = parse_item)]
+        items: Vec<Item>,
+        /// Layers to write: ram, bbr and flash
+        #[clap(
+            long,
+            value_name = "LAYERS",
+            value_delimiter = ',',
+            default_value = "ram",
+            value_parser = parse_layer
+        )]
+        layer: Vec<u8>,
+    },
+}
+
+fn parse_item(s: &str) -> Result<Item, String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got {}", s))?;
+    let key = Key::parse(key)?;
+    let value = key.ty.encode(value)?;
+    Ok((key, value))
+}
+
+fn parse_layer_of(s: &str, layers: &[(&str, u8)]) -> Result<u8, String> {
+    layers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(s.trim()))
+        .map(|&(_, layer)| layer)
+        .ok_or_else(|| {
+            let names: Vec<&str> = layers.iter().map(|&(name, _)| name).collect();
+            format!("unknown layer {}; layers are {}", s, names.join(", "))
+        })
+}
+
+fn parse_layer(s: &str) -> Result<u8, String> {
+    parse_layer_of(s, ubxcfg::LAYERS)
+}
+
+fn parse_get_layer(s: &str) -> Result<u8, String> {
+    parse_layer_of(s, ubxcfg::GET_LAYERS)
+}
+
+#[derive(Subcommand, Debug)]
+enum BaseMode {
+    /// Average the position until it is accurate enough, printing the
+    /// progress of NAV-SVIN
+    Survey {
+        /// Survey for SECS seconds at least
+        #[clap(long, value_name = "SECS", default_value = "300")]
+        min_duration: u32,
+        /// Go on until the mean position is within METERS
+        #[clap(
+            long,
+            value_name = "METERS",
+            default_value = "2.0",
+            value_parser = parse_accuracy
+        )]
+        accuracy: u32,
+    },
+    /// Give the position of the antenna, as latitude, longitude and height,
+    /// or as ECEF coordinates
+    #[clap(group(ArgGroup::new("position").required(true).args(&["lat", "ecef_x"])))]
+    Fixed {
+        /// Latitude in degrees, negative to the south
+        #[clap(
+            long,
+            value_name = "DEG",
+            allow_hyphen_values = true,
+            requires_all = &["lon", "alt"],
+            value_parser = parse_lat
+        )]
+        lat: Option<i64>,
+        /// Longitude in degrees, negative to the west
+        #[clap(
+            long,
+            value_name = "DEG",
+            allow_hyphen_values = true,
+            requires = "lat",
+            value_parser = parse_lon
+        )]
+        lon: Option<i64>,
+        /// Height above the ellipsoid in meters, not above sea level
+        #[clap(
+            long,
+            value_name = "METERS",
+            allow_hyphen_values = true,
+            requires = "lat",
+            value_parser = parse_height
+        )]
+        alt: Option<i64>,
+        /// ECEF X in meters
+        #[clap(
+            long,
+            value_name = "METERS",
+            allow_hyphen_values = true,
+            requires_all = &["ecef_y", "ecef_z"],
+            conflicts_with = "lat",
+            value_parser = parse_ecef
+        )]
+        ecef_x: Option<i64>,
+        /// ECEF Y in meters
+        #[clap(
+            long,
+            value_name = "METERS",
+            allow_hyphen_values = true,
+            requires = "ecef_x",
+            value_parser = parse_ecef
+        )]
+        ecef_y: Option<i64>,
+        /// ECEF Z in meters
+        #[clap(
+            long,
+            value_name = "METERS",
+            allow_hyphen_values = true,
+            requires = "ecef_x",
+            value_parser = parse_ecef
+        )]
+        ecef_z: Option<i64>,
+        /// Accuracy of the position in meters
+        #[clap(
+            long,
+            value_name = "METERS",
+            default_value = "2.0",
+            value_parser = parse_accuracy
+        )]
+        accuracy: u32,
+    },
+    /// Turn the time mode off, making the receiver a rover again
+    Off,
+}
+
+/// A decimal with `decimals` places within `limit` of those units
+fn parse_scaled(s: &str, decimals: u32, limit: i64, what: &str) -> Result<i64, String> {
+    let value = ubx::parse_fixed(s, decimals).ok_or_else(|| format!("{} is not a number", s))?;
+    if value.abs() > limit {
+        return Err(format!(
+            "{} must be within ±{}",
+            what,
+            ubx::format_fixed(limit, decimals)
+        ));
+    }
+    Ok(value)
+}
+
+/// Degrees to 1e-9 degrees
+fn parse_lat(s: &str) -> Result<i64, String> {
+    parse_scaled(s, 9, 90_000_000_000, "latitude")
+}
+
+fn parse_lon(s: &str) -> Result<i64, String> {
+    parse_scaled(s, 9, 180_000_000_000, "longitude")
+}
+
+/// Meters to 0.1 mm
+fn parse_height(s: &str) -> Result<i64, String> {
+    parse_scaled(s, 4, 100_000 * 10_000, "height")
+}
+
+fn parse_ecef(s: &str) -> Result<i64, String> {
+    parse_scaled(s, 4, 10_000_000 * 10_000, "ECEF coordinates")
+}
+
+fn parse_accuracy(s: &str) -> Result<u32, String> {
+    let value = parse_scaled(s, 4, i64::from(u32::MAX), "accuracy")?;
+    if value <= 0 {
+        return Err("accuracy must be above 0".to_owned());
+    }
+    Ok(value as u32)
+}
+
+fn parse_pin(s: &str) -> Result<u8, String> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "timepulse" | "1" => Ok(0),
+        "timepulse2" | "2" => Ok(1),
+        _ => Err(format!(
+            "unknown pin {}; pins are timepulse and timepulse2",
+            s
+        )),
+    }
+}
+
+/// `tp` with the pulses asked for on the command line
+fn time_pulse(mut tp: TimePulse, command: &Command) -> Result<TimePulse, Error> {
+    let (freq, period_us, duty, pulse_us, aligned_to_tow, only_when_locked) = match *command {
+        Command::Timepulse {
+            freq,
+            period_us,
+            duty,
+            pulse_us,
+            aligned_to_tow,
+            only_when_locked,
+            ..
+        } => (
+            freq,
+            period_us,
+            duty,
+            pulse_us,
+            aligned_to_tow,
+            only_when_locked,
+        ),
+        _ => unreachable!("only timepulse sets time pulses"),
+    };
+    let rate = match (freq, period_us) {
+        (Some(hz), _) => PulseRate::Hz(hz),
+        (None, Some(us)) => PulseRate::PeriodUs(us),
+        (None, None) => {
+            return Err(anyhow!(
+                "give --freq or --period-us and --duty or --pulse-us, or --show"
+            ))
+        }
+    };
+    let len = match (duty, pulse_us) {
+        (Some(duty), _) => PulseLen::Duty(duty),
+        (None, Some(us)) => PulseLen::Us(us),
+        (None, None) => unreachable!("clap requires --duty or --pulse-us"),
+    };
+    tp.set(rate, len, aligned_to_tow, only_when_locked)
+        .map_err(|e| anyhow!(e))?;
+    Ok(tp)
+}
+
+fn time_mode(mode: &BaseMode) -> TimeMode {
+    match *mode {
+        BaseMode::Survey {
+            min_duration,
+            accuracy,
+        } => TimeMode::SurveyIn {
+            min_duration,
+            acc_limit: accuracy,
+        },
+        BaseMode::Fixed {
+            lat: Some(lat),
+            lon: Some(lon),
+            alt: Some(alt),
+            accuracy,
+            ..
+        } => TimeMode::FixedLla {
+            lat,
+            lon,
+            alt,
+            acc: accuracy,
+        },
+        BaseMode::Fixed {
+            ecef_x,
+            ecef_y,
+            ecef_z,
+            accuracy,
+            ..
+        } => TimeMode::FixedEcef {
+            x: ecef_x.expect("clap requires a position"),
+            y: ecef_y.expect("clap requires ecef-y along with ecef-x"),
+            z: ecef_z.expect("clap requires ecef-z along with ecef-x"),
+            acc: accuracy,
+        },
+        BaseMode::Off => TimeMode::Disabled,
+    }
+}
+
+fn parse_rtcm(s: &str) -> Result<u8, String> {
+    ubx::rtcm_id(s).ok_or_else(|| {
+        let known: Vec<&str> = ubx::MESSAGES
+            .iter()
+            .filter(|&&(_, class, _)| class == ubx::CLASS_RTCM3)
+            .map(|&(name, _, _)| name.trim_start_matches("RTCM3-"))
+            .collect();
+        format!(
+            "RTCM3 {} can't be sent by u-blox receivers; try {}",
+            s,
+            known.join(", ")
+        )
+    })
+}
+
+/// Each RTCM3 message to change with its new rate, `rate` for those enabled
+/// and 0 for those disabled
+fn rtcm_changes(enable: &[u8], disable: &[u8], rate: u8) -> Vec<(u8, u8)> {
+    let on = enable.iter().map(|&id| (id, rate));
+    on.chain(disable.iter().map(|&id| (id, 0))).collect()
+}
+
+/// What `decode --filter` lists
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Filter {
+    Nmea,
+    Frame(u8, u8),
+}
+
+fn parse_filter(s: &str) -> Result<Filter, String> {
+    if s.eq_ignore_ascii_case("nmea") {
+        return Ok(Filter::Nmea);
+    }
+    ubx::frame_id(s)
+        .map(|(class, id)| Filter::Frame(class, id))
+        .ok_or_else(|| {
+            format!(
+                "unknown packet {}; give NMEA, a name like NAV-PVT, or class and id like 01-07",
+                s
+            )
+        })
+}
+
+fn reset_packet(hot: bool, warm: bool) -> Vec<u8> {
+    let nav_bbr_mask = if hot {
+        NavBbrMask::empty()
+    } else if warm {
+        NavBbrMask::EPHEMERIS
+    } else {
+        NavBbrMask::all()
+    };
+    CfgRstBuilder {
+        nav_bbr_mask,
+        reset_mode: ResetMode::HardwareResetImmediately,
         reserved1: 0,
+    }
+    .into_packet_bytes()
+    .to_vec()
+}
+
+fn rate_packet(meas_ms: u16, nav_ratio: u16, time_ref: TimeRef) -> Vec<u8> {
+    CfgRateBuilder {
+        measure_rate_ms: meas_ms,
+        nav_rate: nav_ratio,
+        time_ref: match time_ref {
+            TimeRef::Utc => AlignmentToReferenceTime::Utc,
+            TimeRef::Gps => AlignmentToReferenceTime::Gps,
+        },
+    }
+    .into_packet_bytes()
+    .to_vec()
+}
+
+/// CFG-MSG with `rates` in the order of `ubx::PORTS`
+fn msg_packet(class: u8, id: u8, rates: [u8; 5]) -> Vec<u8> {
+    let [i2c, uart1, uart2, usb, spi] = rates;
+    CfgMsgAllPortsBuilder {
+        msg_class: class,
+        msg_id: id,
+        rates: [i2c, uart1, uart2, usb, spi, 0],
+    }
+    .into_packet_bytes()
+    .to_vec()
+}
+
+/// `config` with the changes asked for
+fn changed_port(
+    config: PortConfig,
+    baud: Option<u32>,
+    in_proto: &[u16],
+    out_proto: &[u16],
+) -> Result<PortConfig, Error> {
+    if baud.is_some() && !config.is_uart() {
+        return Err(anyhow!("{} has no baud rate", config.port_name()));
+    }
+    let mask = |protos: &[u16], current| match protos {
+        [] => current,
+        _ => protos.iter().fold(0, |mask, bit| mask | bit),
+    };
+    Ok(PortConfig {
+        baud: baud.unwrap_or(config.baud),
+        in_proto: mask(in_proto, config.in_proto),
+        out_proto: mask(out_proto, config.out_proto),
+        ..config
+    })
+}
+
+fn print_nav5(nav5: &Nav5) {
+    println!("model             {}", nav5.dyn_model_name());
+    println!("fix mode          {}", nav5.fix_mode_name());
+    println!("fixed altitude    {:.2} m", nav5.fixed_alt);
+    println!("min elevation     {} deg", nav5.min_elev);
+    println!("PDOP mask         {:.1}", nav5.pdop_mask);
+    println!("TDOP mask         {:.1}", nav5.tdop_mask);
+    println!("position acc.     {} m", nav5.pacc_mask);
+    println!("time acc.         {} m", nav5.tacc_mask);
+    println!("static hold       {} cm/s", nav5.static_hold);
+    println!("DGNSS timeout     {} s", nav5.dgnss_timeout);
+}
+
+fn print_gnss(gnss: &Gnss) {
+    println!("{} of {} channels in use", gnss.trk_ch_use, gnss.trk_ch_hw);
+    println!("{:<8} {:<4} {:>8} {:>4}", "system", "on", "reserved", "max");
+    for block in &gnss.blocks {
+        let on = if block.enabled() { "yes" } else { "no" };
+        println!(
+            "{:<8} {:<4} {:>8} {:>4}",
+            block.name(),
+            on,
+            block.res_trk_ch,
+            block.max_trk_ch
+        );
+    }
+}
+
+fn print_version(version: &Version) {
+    println!("software    {}", version.software);
+    println!("hardware    {}", version.hardware);
+    for (i, ext) in version.extensions.iter().enumerate() {
+        let label = if i == 0 { "extensions" } else { "" };
+        println!("{:<10}  {}", label, ext);
+    }
+}
+
+/// `text` in red on a terminal
+fn alarm(text: &str) -> String {
+    if io::stdout().is_terminal() {
+        format!("\x1b[1;31m{}\x1b[0m", text)
+    } else {
+        text.to_owned()
+    }
+}
+
+/// Prints a line per front end and returns the warnings
+fn print_hw(statuses: &[RfStatus]) -> Vec<String> {
+    let time = Utc::now().format("%H:%M:%S");
+    let mut all = Vec::new();
+    for rf in statuses {
+        let label = match rf.block {
+            Some(block) => format!("RF block {}", block),
+            None => "MON-HW".to_owned(),
+        };
+        let warnings = rf.warnings();
+        let line = if warnings.is_empty() {
+            rf.describe()
+        } else {
+            alarm(&rf.describe())
+        };
+        println!("{}  {:<10}  {}", time, label, line);
+        all.extend(warnings);
+    }
+    all.sort();
+    all.dedup();
+    all
+}
+
+fn hp_json(hp: &HpPosLlh) -> serde_json::Value {
+    let meters = |v: i64| v as f64 / 1e4;
+    serde_json::json!({
+        "msg": "NAV-HPPOSLLH",
+        "itow": hp.itow,
+        "valid": !hp.invalid,
+        "lat": hp.lat as f64 / 1e9,
+        "lon": hp.lon as f64 / 1e9,
+        "height": meters(hp.height),
+        "h_msl": meters(hp.h_msl),
+        "h_acc": meters(i64::from(hp.h_acc)),
+        "v_acc": meters(i64::from(hp.v_acc)),
+    })
+}
+
+fn rel_pos_json(rel: &RelPosNed) -> serde_json::Value {
+    let meters = |v: i64| v as f64 / 1e4;
+    serde_json::json!({
+        "msg": "NAV-RELPOSNED",
+        "itow": rel.itow,
+        "ref_station": rel.ref_station,
+        "valid": rel.rel_pos_valid(),
+        "carr_soln": rel.carr_soln_name(),
+        "gnss_fix_ok": rel.gnss_fix_ok(),
+        "diff_soln": rel.diff_soln(),
+        "n": meters(rel.ned[0]),
+        "e": meters(rel.ned[1]),
+        "d": meters(rel.ned[2]),
+        "length": rel.length.map(meters),
+        "heading": rel.heading.map(|h| f64::from(h) / 1e5),
+        "acc_n": meters(i64::from(rel.acc_ned[0])),
+        "acc_e": meters(i64::from(rel.acc_ned[1])),
+        "acc_d": meters(i64::from(rel.acc_ned[2])),
+    })
+}
+
+fn print_port(config: &PortConfig) {
+    println!("port  {}", config.port_name());
+    if let Some(mode) = config.mode_name() {
+        println!("baud  {}", config.baud);
+        println!("mode  {}", mode);
+    }
+    println!("in    {}", ubx::protocol_names(config.in_proto).join(", "));
+    println!("out   {}", ubx::protocol_names(config.out_proto).join(", "));
+}
+
+fn print_pvt(pvt: &NavPvt) {
+    println!(
+        "{}  {:<7} {:<5} {:>12.7} {:>12.7}  h {:.2} m  msl {:.2} m  acc {:.2}/{:.2} m  {:.2} m/s  {:5.1}°  {:>2} SV  PDOP {:.2}",
+        pvt.utc.as_deref().unwrap_or("no time yet"),
+        pvt.fix_type_name(),
+        pvt.carr_soln_name(),
+        pvt.lat,
+        pvt.lon,
+        pvt.height,
+        pvt.h_msl,
+        pvt.h_acc,
+        pvt.v_acc,
+        pvt.g_speed,
+        pvt.heading,
+        pvt.num_sv,
+        pvt.p_dop
+    );
+}
+
+fn print_svin(svin: &NavSvin) {
+    let [x, y, z] = svin.mean.map(|v| ubx::format_fixed(v, 4));
+    println!(
+        "{:>6} s {:>6} obs  mean ECEF {} {} {} m  accuracy {} m{}",
+        svin.dur,
+        svin.obs,
+        x,
+        y,
+        z,
+        ubx::format_fixed(i64::from(svin.mean_acc), 4),
+        if svin.valid { "  valid" } else { "" }
+    );
+}
+
+fn print_sats(sat: &NavSat, min_cno: u8) {
+    let svs: Vec<&SatInfo> = sat.svs.iter().filter(|sv| sv.cno >= min_cno).collect();
+    println!(
+        "time of week {:.3} s: {} satellites, {} used, {} shown",
+        f64::from(sat.itow) / 1000.0,
+        sat.svs.len(),
+        sat.svs.iter().filter(|sv| sv.used()).count(),
+        svs.len()
+    );
+    println!(
+        "{:<8} {:>3} {:>4} {:>5} {:>5} {:>7}  {:<15} {:<9} {:<4} {:<4} orbit",
+        "gnss", "sv", "cno", "elev", "azim", "res m", "quality", "health", "used", "diff"
+    );
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    for sv in svs {
+        println!(
+            "{:<8} {:>3} {:>4} {:>5} {:>5} {:>7.1}  {:<15} {:<9} {:<4} {:<4} {}",
+            sv.gnss_name(),
+            sv.sv_id,
+            sv.cno,
+            sv.elev,
+            sv.azim,
+            sv.pr_res,
+            sv.quality_name(),
+            sv.health_name(),
+            yes_no(sv.used()),
+            yes_no(sv.diff_corr()),
+            sv.orbit_source_name()
+        );
+    }
+    println!();
+}
+
+/// Next bytes from `port`, none when the read timed out
+fn read_some<'a>(port: &mut dyn Read, buf: &'a mut [u8]) -> io::Result<&'a [u8]> {
+    match port.read(buf) {
+        Ok(n) => Ok(&buf[..n]),
+        Err(e) if e.kind() == io::ErrorKind::TimedOut => Ok(&[]),
+        Err(e) if e.kind() == io::ErrorKind::Interrupted => Ok(&[]),
+        Err(e) => Err(e),
+    }
+}
+
+/// Reads `port` until a TXT sentence shows up, which u-blox receivers print
+/// while booting, and returns its text
+fn wait_for_boot(port: &mut dyn Read, timeout: Duration) -> io::Result<Option<String>> {
+    let deadline = Instant::now() + timeout;
+    let mut line = Vec::new();
+    let mut buf = [0u8; 256];
+    while Instant::now() < deadline {
+        for &b in read_some(port, &mut buf)? {
+            if b != b'\n' {
+                line.push(b);
+                continue;
+            }
+            let text = String::from_utf8_lossy(&line).into_owned();
+            line.clear();
+            if let Some(sentence) = Sentence::parse(&text) {
+                if sentence.kind == "TXT" {
+                    return Ok(Some(sentence.field(3).unwrap_or_default().to_owned()));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Reads frames from `port` until `pick` takes one
+fn wait_for_frame<T>(
+    port: &mut dyn Read,
+    timeout: Duration,
+    mut pick: impl FnMut(&Frame) -> Option<T>,
+) -> io::Result<Option<T>> {
+    let deadline = Instant::now() + timeout;
+    let mut reader = FrameReader::default();
+    let mut buf = [0u8; 256];
+    while Instant::now() < deadline {
+        let bytes = read_some(port, &mut buf)?;
+        if let Some(picked) = reader.push(bytes).iter().find_map(&mut pick) {
+            return Ok(Some(picked));
+        }
+    }
+    Ok(None)
+}
+
+/// True for an ACK-ACK of the packet of class and id `sent`, false for an
+/// ACK-NAK
+fn ack_of(frame: &Frame, sent: (u8, u8)) -> Option<bool> {
+    frame
+        .ack()
+        .filter(|ack| (ack.class, ack.id) == sent)
+        .map(|ack| ack.acked)
+}
+
+/// A receiver on a serial port
+struct Receiver {
+    port: Box<dyn SerialPort>,
+    device: String,
+    baud: u32,
+    timeout: Duration,
+    retries: u32,
+}
+
+impl Receiver {
+    fn open(args: &Args, device: &str) -> Result<Receiver, Error> {
+        let port = serialport::new(device, args.baud)
+            .timeout(READ_TIMEOUT)
+            .open()
+            .with_context(|| format!("failed to open {}", device))?;
+        Ok(Receiver {
+            port,
+            device: device.to_owned(),
+            baud: args.baud,
+            timeout: Duration::from_secs(args.timeout),
+            retries: args.retries,
+        })
+    }
+
+    /// Writes `packet` at `baud`, dropping whatever came before
+    fn send(&mut self, packet: &[u8]) -> Result<(), Error> {
+        let device = &self.device;
+        let port = &mut self.port;
+        port.set_baud_rate(self.baud)
+            .with_context(|| format!("failed to set {} to {} baud", device, self.baud))?;
+        // whatever the receiver said before is no answer to the packet
+        port.clear(serialport::ClearBuffer::Input)
+            .with_context(|| format!("failed to clear {}", device))?;
+        port.write_all(packet)
+            .and_then(|_| port.flush())
+            .with_context(|| format!("failed to write to {}", device))
+    }
+
+    /// Sends `packet` until `answer` makes something of what comes back, at
+    /// most `retries` times more
+    fn exchange<T>(
+        &mut self,
+        packet: &[u8],
+        what: &str,
+        mut answer: impl FnMut(&mut dyn Read, Duration) -> io::Result<Option<T>>,
+    ) -> Result<T, Error> {
+        for attempt in 0..=self.retries {
+            if attempt > 0 {
+                eprintln!(
+                    "no answer within {}s, sending again ({}/{})",
+                    self.timeout.as_secs(),
+                    attempt,
+                    self.retries
+                );
+            }
+            self.send(packet)?;
+            let port: &mut dyn Read = &mut self.port;
+            if let Some(answer) = answer(port, self.timeout)
+                .with_context(|| format!("failed to read from {}", self.device))?
+            {
+                return Ok(answer);
+            }
+        }
+        Err(anyhow!(
+            "no answer to {} from {} within {}s; check the baud rate",
+            what,
+            self.device,
+            self.timeout.as_secs()
+        ))
+    }
+
+    /// Sends a CFG packet and waits for it to be acked
+    fn configure(&mut self, packet: &[u8], what: &str) -> Result<(), Error> {
+        let sent = ubx::packet_id(packet).expect("packets start with the UBX sync chars");
+        let acked = self.exchange(packet, what, |port, timeout| {
+            wait_for_frame(port, timeout, |frame| ack_of(frame, sent))
+        })?;
+        if !acked {
+            return Err(anyhow!("the receiver rejected {}", what));
+        }
+        eprintln!("{}: acknowledged", what);
+        Ok(())
+    }
+
+    /// Sends a poll and returns the reply `pick` takes
+    fn poll<T>(
+        &mut self,
+        packet: &[u8],
+        what: &str,
+        mut pick: impl FnMut(&Frame) -> Option<T>,
+    ) -> Result<T, Error> {
+        let sent = ubx::packet_id(packet).expect("packets start with the UBX sync chars");
+        let reply = self.exchange(packet, what, |port, timeout| {
+            wait_for_frame(port, timeout, |frame| match ack_of(frame, sent) {
+                Some(false) => Some(None),
+                _ => pick(frame).map(Some),
+            })
+        })?;
+        reply.ok_or_else(|| anyhow!("the receiver rejected {}", what))
+    }
+
+    /// Sends a poll once and returns the reply `pick` takes, or None when the
+    /// receiver rejects it or stays silent, as older receivers do with
+    /// messages they don't know
+    fn try_poll<T>(
+        &mut self,
+        packet: &[u8],
+        mut pick: impl FnMut(&Frame) -> Option<T>,
+    ) -> Result<Option<T>, Error> {
+        let sent = ubx::packet_id(packet).expect("packets start with the UBX sync chars");
+        self.send(packet)?;
+        let port: &mut dyn Read = &mut self.port;
+        let reply = wait_for_frame(port, self.timeout, |frame| match ack_of(frame, sent) {
+            Some(false) => Some(None),
+            _ => pick(frame).map(Some),
+        })
+        .with_context(|| format!("failed to read from {}", self.device))?;
+        Ok(reply.flatten())
+    }
+
+    /// Rates of a message per port, in the order of `ubx::PORTS`
+    fn msg_rates(&mut self, class: u8, id: u8) -> Result<[u8; 5], Error> {
+        let what = format!(
+            "CFG-MSG poll of {}",
+            ubx::message_name(class, id).unwrap_or("the message")
+        );
+        self.poll(&ubx::poll_msg(class, id), &what, |frame| {
+            frame
+                .msg_rates()
+                .filter(|&(c, i, _)| (c, i) == (class, id))
+                .map(|(_, _, rates)| rates)
+        })
+    }
+}
+
+/// Rates of each port after setting `rate` on `ports`, or on all of them when
+/// none are given
+fn with_rate(current: [u8; 5], ports: &[usize], rate: u8) -> [u8; 5] {
+    let mut rates = current;
+    for (i, r) in rates.iter_mut().enumerate() {
+        if ports.is_empty() || ports.contains(&i) {
+            *r = rate;
+        }
+    }
+    rates
+}
+
+/// Warns when `baud` is too slow for the default NMEA output at `hz`
+fn warn_baud(hz: f64, baud: u32) {
+    // a UART byte takes 10 bits
+    let needed = hz * f64::from(NMEA_BYTES_PER_SOLUTION) * 10.0;
+    if needed <= f64::from(baud) {
+        return;
+    }
+    match BAUD_RATES.iter().find(|&&rate| f64::from(rate) >= needed) {
+        Some(rate) => eprintln!(
+            "warning: {:.0} Hz of NMEA may not fit through {} baud; try `ubloxcli baud --set {}`",
+            hz, baud, rate
+        ),
+        None => eprintln!(
+            "warning: {:.0} Hz of NMEA may not fit through any baud rate; turn messages off with `ubloxcli msg --set`",
+            hz
+        ),
+    }
+}
+
+/// Sends a CFG-PRT changing the baud rate, then checks the receiver answers
+/// at the new rate
+fn switch_baud(rx: &mut Receiver, config: &PortConfig) -> Result<(), Error> {
+    let old = rx.baud;
+    let sent = (ubx::CLASS_CFG, ubx::ID_CFG_PRT);
+    rx.send(&config.packet())?;
+    eprintln!("sent CFG-PRT to {} at {} baud", rx.device, old);
+    // the ack comes at the old rate, unless the switch cuts it off
+    let ack = wait_for_frame(&mut rx.port, rx.timeout, |frame| ack_of(frame, sent))
+        .with_context(|| format!("failed to read from {}", rx.device))?;
+    if ack == Some(false) {
+        return Err(anyhow!("the receiver rejected CFG-PRT"));
+    }
+
+    rx.baud = config.baud;
+    if rx
+        .poll(&ubx::poll_mon_ver(), "MON-VER poll", Frame::version)
+        .is_ok()
+    {
+        eprintln!(
+            "{} now runs at {} baud; use --baud {} from now on. `ubloxcli save` keeps it over power-off.",
+            config.port_name(),
+            config.baud,
+            config.baud
+        );
+        return Ok(());
+    }
+    rx.baud = old;
+    if rx
+        .poll(&ubx::poll_mon_ver(), "MON-VER poll", Frame::version)
+        .is_ok()
+    {
+        return Err(anyhow!(
+            "the receiver still answers at {} baud: the change didn't take, or {} is not the port {} is on",
+            old,
+            config.port_name(),
+            rx.device
+        ));
+    }
+    Err(anyhow!(
+        "the receiver answers at neither {} nor {} baud; power-cycle it to get back its saved settings, or try `ubloxcli baud --set {} --baud {}`",
+        config.baud,
+        old,
+        old,
+        config.baud
+    ))
+}
+
+/// Turns `class` and `id` on for the UARTs and USB, the ports a serial device
+/// can be on, keeping the rates of the others; returns the rates before
+fn enable_output(rx: &mut Receiver, class: u8, id: u8) -> Result<[u8; 5], Error> {
+    let current = rx.msg_rates(class, id)?;
+    let rates = with_rate(current, &[1, 2, 3], 1);
+    if rates != current {
+        let name = ubx::message_name(class, id).unwrap_or("the message");
+        rx.configure(
+            &msg_packet(class, id, rates),
+            &format!("CFG-MSG {}=1", name),
+        )?;
+    }
+    Ok(current)
+}
+
+/// Writes what the receiver sends to `out` as it comes, on a thread of its
+/// own so that slow writes don't hold up reading, until `seconds` passed or
+/// `stop` is set
+fn log_raw(
+    rx: &mut Receiver,
+    out: &Path,
+    seconds: Option<u64>,
+    stop: &AtomicBool,
+) -> Result<(), Error> {
+    let file =
+        fs::File::create(out).with_context(|| format!("failed to create {}", out.display()))?;
+    let (tx, chunks) = mpsc::channel::<Vec<u8>>();
+    let writer = thread::spawn(move || -> io::Result<()> {
+        let mut writer = io::BufWriter::with_capacity(1 << 20, file);
+        for chunk in chunks {
+            writer.write_all(&chunk)?;
+        }
+        writer.flush()
+    });
+
+    let deadline = seconds.map(|secs| Instant::now() + Duration::from_secs(secs));
+    let (mut epochs, mut satellites, mut size) = (0, 0, 0);
+    let mut reader = FrameReader::default();
+    let mut shown = Instant::now();
+    let mut buf = [0u8; 4096];
+    let mut failed = None;
+    while !stop.load(Ordering::SeqCst) && !deadline.is_some_and(|d| Instant::now() >= d) {
+        let bytes = match read_some(&mut rx.port, &mut buf) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                failed = Some(e);
+                break;
+            }
+        };
+        size += bytes.len();
+        // a closed channel means the writer failed, which is reported below
+        if tx.send(bytes.to_vec()).is_err() {
+            break;
+        }
+        for epoch in reader.push(bytes).iter().filter_map(Frame::rawx) {
+            epochs += 1;
+            satellites = epoch.satellites;
+        }
+        if shown.elapsed() >= Duration::from_secs(1) {
+            eprint!(
+                "\r{} epochs, {} satellites in the last one, {:.1} MB",
+                epochs,
+                satellites,
+                size as f64 / 1e6
+            );
+            shown = Instant::now();
+        }
+    }
+    eprintln!();
+    drop(tx);
+    writer
+        .join()
+        .expect("the writer doesn't panic")
+        .with_context(|| format!("failed to write {}", out.display()))?;
+    if let Some(e) = failed {
+        return Err(e).with_context(|| format!("failed to read from {}", rx.device));
+    }
+    if epochs == 0 {
+        eprintln!("warning: no RXM-RAWX came; does the receiver output raw measurements?");
+    }
+    eprintln!(
+        "{} epochs, {} bytes written to {}",
+        epochs,
+        size,
+        out.display()
+    );
+    Ok(())
+}
+
+/// Logs raw measurements and puts the rates of RXM-RAWX and RXM-SFRBX back
+/// afterwards, also after Ctrl-C
+fn raw(rx: &mut Receiver, out: &Path, seconds: Option<u64>) -> Result<(), Error> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler_stop = Arc::clone(&stop);
+    ctrlc::set_handler(move || handler_stop.store(true, Ordering::SeqCst))
+        .context("failed to catch Ctrl-C")?;
+
+    let mut before = Vec::new();
+    let mut logged = Ok(());
+    for &id in &[ubx::ID_RXM_RAWX, ubx::ID_RXM_SFRBX] {
+        match enable_output(rx, ubx::CLASS_RXM, id) {
+            Ok(rates) => before.push((id, rates)),
+            Err(e) => {
+                logged = Err(e);
+                break;
+            }
+        }
+    }
+    if logged.is_ok() {
+        logged = log_raw(rx, out, seconds, &stop);
+    }
+    for (id, rates) in before {
+        let name = ubx::message_name(ubx::CLASS_RXM, id).expect("RXM messages are known");
+        let what = format!("CFG-MSG {} back to {:?}", name, rates);
+        if let Err(e) = rx.configure(&msg_packet(ubx::CLASS_RXM, id, rates), &what) {
+            eprintln!("warning: {}", e);
+        }
+    }
+    logged
+}
+
+/// Fetches AssistNow Online data: ephemerides, almanacs and ionosphere
+/// parameters of the systems M8 receivers track
+fn fetch_assistnow(token: &str) -> Result<Vec<u8>, Error> {
+    let url = format!(
+        "{}?token={};gnss=gps,glo,gal,bds;datatype=eph,alm,aux;format=mga",
+        ASSISTNOW_URL, token
+    );
+    eprintln!("fetching AssistNow Online data");
+    let mut data = Vec::new();
+    ureq::get(&url)
+        .call()
+        .context("failed to fetch AssistNow Online data")?
+        .into_reader()
+        .read_to_end(&mut data)
+        .context("failed to fetch AssistNow Online data")?;
+    Ok(data)
+}
+
+/// Sends the time and then the aiding messages. With MGA-ACK each message
+/// waits for the last one to be acked, otherwise writes are spaced out so
+/// the receiver keeps up.
+fn assist(rx: &mut Receiver, data: &[u8]) -> Result<(), Error> {
+    let frames = ubx::split_capture(data)
+        .into_iter()
+        .filter_map(|(_, piece)| match piece {
+            Piece::Frame(frame) => Some(frame),
+            _ => None,
+        })
+        .collect();
+    let now = Utc::now().naive_utc();
+    let (mut frames, left_out) = ubx::mga_to_send(frames, now.date());
+    if frames.is_empty() {
+        return Err(anyhow!("no MGA messages to send"));
+    }
+    if left_out > 0 {
+        eprintln!("left out {} messages which aren't for today", left_out);
+    }
+
+    let acked = match rx.configure(&ubx::navx5_ack_aiding(), "CFG-NAVX5 ackAiding") {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("{}; sending without flow control", e);
+            false
+        }
     };
-    let bytes = rst.into_packet_bytes();
+    // the system clock is trusted to within 2 seconds
+    frames.insert(0, ubx::mga_ini_time_utc(Utc::now().naive_utc(), 2));
+
+    let total = frames.len();
+    let (mut accepted, mut unanswered) = (0, 0);
+    let mut rejected: BTreeMap<&str, u32> = BTreeMap::new();
+    for (i, frame) in frames.iter().enumerate() {
+        let packet = frame.packet();
+        rx.send(&packet)?;
+        if acked {
+            let ack = wait_for_frame(&mut rx.port, MGA_ACK_TIMEOUT, |reply| {
+                reply.mga_ack().filter(|ack| ack.is_for(frame))
+            })
+            .with_context(|| format!("failed to read from {}", rx.device))?;
+            match ack {
+                Some(ack) if ack.accepted => accepted += 1,
+                Some(ack) => *rejected.entry(ack.info_name()).or_insert(0) += 1,
+                None => unanswered += 1,
+            }
+        } else {
+            // twice the time the packet takes at the baud rate
+            let micros = packet.len() as u64 * 10 * 2_000_000 / u64::from(rx.baud);
+            std::thread::sleep(Duration::from_micros(micros) + MGA_PACING);
+        }
+        eprint!("\r{}/{} messages, {} acked", i + 1, total, accepted);
+    }
+    eprintln!();
+
+    if !acked {
+        eprintln!("sent {} messages; the receiver doesn't ack them", total);
+        return Ok(());
+    }
+    eprintln!(
+        "sent {} messages: {} accepted, {} rejected, {} unanswered",
+        total,
+        accepted,
+        rejected.values().sum::<u32>(),
+        unanswered
+    );
+    for (reason, count) in &rejected {
+        eprintln!("  {:>5} {}", count, reason);
+    }
+    Ok(())
+}
 
-    let mut file = std::fs::File::create("msg.bin")?;
-    file.write(&bytes[..])?;
+/// What `stream` does after giving a frame to its handler
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Next {
+    /// not the message streamed, or nothing to print yet
+    Skip,
+    Took,
+    Stop,
+}
+
+/// Reads frames and gives them to `handle` until it stops or `seconds`
+/// passed. Warns when `handle` took nothing for `--timeout` seconds.
+fn stream(
+    rx: &mut Receiver,
+    what: &str,
+    seconds: Option<u64>,
+    mut handle: impl FnMut(&Frame) -> Result<Next, Error>,
+) -> Result<(), Error> {
+    let deadline = seconds.map(|secs| Instant::now() + Duration::from_secs(secs));
+    let mut last = Instant::now();
+    let mut reader = FrameReader::default();
+    let mut buf = [0u8; 1024];
+    'read: while !deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+        let bytes = read_some(&mut rx.port, &mut buf)
+            .with_context(|| format!("failed to read from {}", rx.device))?;
+        for frame in reader.push(bytes) {
+            match handle(&frame)? {
+                Next::Skip => {}
+                Next::Took => last = Instant::now(),
+                Next::Stop => break 'read,
+            }
+        }
+        if last.elapsed() > rx.timeout {
+            eprintln!(
+                "no {} from {} for {}s; is the measurement rate that slow?",
+                what,
+                rx.device,
+                rx.timeout.as_secs()
+            );
+            last = Instant::now();
+        }
+    }
+    if reader.bad_checksums > 0 {
+        eprintln!("{} frames dropped for a bad checksum", reader.bad_checksums);
+    }
+    Ok(())
+}
+
+/// Polls the rates of the known messages of `class`, named without `prefix`
+fn list_rates(rx: &mut Receiver, class: u8, prefix: &str) -> Result<(), Error> {
+    print!("{:<10}", "message");
+    for port in ubx::PORTS {
+        print!("{:>6}", port);
+    }
+    println!();
+    for &(name, _, id) in ubx::MESSAGES.iter().filter(|&&(_, c, _)| c == class) {
+        let rates = rx.msg_rates(class, id)?;
+        print!("{:<10}", name.trim_start_matches(prefix));
+        for rate in &rates {
+            print!("{:>6}", rate);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn run(command: &Command, rx: &mut Receiver) -> Result<(), Error> {
+    match *command {
+        Command::Reset { hot, warm, .. } => {
+            // CFG-RST is never acked, the boot messages tell it worked
+            let text = rx.exchange(&reset_packet(hot, warm), "CFG-RST", |port, timeout| {
+                wait_for_boot(port, timeout)
+            })?;
+            eprintln!("receiver restarted: {}", text);
+        }
+        Command::Rate {
+            hz,
+            meas_ms,
+            nav_ratio,
+            time_ref,
+        } => {
+            let (meas_ms, nav_ratio) = meas_rate(hz, meas_ms, nav_ratio);
+            rx.configure(&rate_packet(meas_ms, nav_ratio, time_ref), "CFG-RATE")?;
+            // some receivers clamp rates they can't do without a NAK
+            let rate = rx.poll(&ubx::poll_rate(), "CFG-RATE poll", Frame::rate)?;
+            println!(
+                "measuring every {} ms, {} measurements per solution: {:.2} Hz, aligned to {} time",
+                rate.meas_ms,
+                rate.nav_ratio,
+                rate.hz(),
+                rate.time_ref_name()
+            );
+            if (rate.meas_ms, rate.nav_ratio) != (meas_ms, nav_ratio) {
+                eprintln!(
+                    "warning: asked for {} ms and {} measurements per solution",
+                    meas_ms, nav_ratio
+                );
+            }
+            warn_baud(rate.hz(), rx.baud);
+        }
+        Command::Msg {
+            list,
+            ref set,
+            ref port,
+        } => {
+            if list {
+                list_rates(rx, 0xf0, "NMEA-")?;
+            }
+            for msg in set {
+                // the other ports keep their rates
+                let current = if port.is_empty() {
+                    [0; 5]
+                } else {
+                    rx.msg_rates(msg.class, msg.id)?
+                };
+                let rates = with_rate(current, port, msg.rate);
+                let what = format!("CFG-MSG {}={}", msg.name, msg.rate);
+                rx.configure(&msg_packet(msg.class, msg.id, rates), &what)?;
+            }
+        }
+        Command::Nav5 {
+            model,
+            min_elev,
+            fix_mode,
+            show,
+        } => {
+            if show {
+                let nav5 = rx.poll(&ubx::poll_nav5(), "CFG-NAV5 poll", Frame::nav5)?;
+                print_nav5(&nav5);
+            } else {
+                let change = Nav5Change {
+                    dyn_model: model,
+                    min_elev,
+                    fix_mode,
+                };
+                rx.configure(&change.packet(), "CFG-NAV5")?;
+            }
+        }
+        Command::Gnss {
+            ref enable,
+            ref disable,
+            show,
+        } => {
+            let mut gnss = rx.poll(&ubx::poll_gnss(), "CFG-GNSS poll", Frame::gnss)?;
+            if !show {
+                let changes = enable.iter().map(|&id| (id, true));
+                for (id, on) in changes.chain(disable.iter().map(|&id| (id, false))) {
+                    if !gnss.set_enabled(id, on) {
+                        let name = ubx::gnss_name(id);
+                        return Err(anyhow!("the receiver doesn't support {}", name));
+                    }
+                }
+                for warning in gnss.warnings() {
+                    eprintln!("warning: {}", warning);
+                }
+                rx.configure(&gnss.packet(), "CFG-GNSS")?;
+                gnss = rx.poll(&ubx::poll_gnss(), "CFG-GNSS poll", Frame::gnss)?;
+            }
+            print_gnss(&gnss);
+        }
+        Command::Save { .. } | Command::LoadDefaults { .. } => {
+            if let Command::LoadDefaults { yes: false, .. } = command {
+                return Err(anyhow!(
+                    "load-defaults loses the saved configuration; run it again with --yes"
+                ));
+            }
+            let cfg = cfg_cfg(command).expect("save or load-defaults");
+            eprintln!("CFG-CFG: {}", cfg.describe());
+            rx.configure(&cfg.packet(), "CFG-CFG")?;
+        }
+        Command::Version { json } => {
+            let version = rx.poll(&ubx::poll_mon_ver(), "MON-VER poll", Frame::version)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&version)?);
+            } else {
+                print_version(&version);
+            }
+        }
+        Command::Id { json } => {
+            let version = rx.poll(&ubx::poll_mon_ver(), "MON-VER poll", Frame::version)?;
+            let unique_id = rx.try_poll(&ubx::poll_sec_uniqid(), Frame::unique_id)?;
+            if json {
+                let id = serde_json::json!({
+                    "unique_id": unique_id,
+                    "module": version.extension("MOD"),
+                    "firmware": version.extension("FWVER"),
+                    "protocol": version.extension("PROTVER"),
+                    "software": version.software,
+                    "hardware": version.hardware,
+                });
+                println!("{}", serde_json::to_string_pretty(&id)?);
+            } else {
+                let unknown = "unknown";
+                match &unique_id {
+                    Some(id) => println!("unique id   {}", id),
+                    None => println!("unique id   none: the receiver doesn't support SEC-UNIQID"),
+                }
+                println!(
+                    "module      {}",
+                    version.extension("MOD").unwrap_or(unknown)
+                );
+                println!(
+                    "firmware    {}",
+                    version.extension("FWVER").unwrap_or(&version.software)
+                );
+                println!(
+                    "protocol    {}",
+                    version.extension("PROTVER").unwrap_or(unknown)
+                );
+                println!("hardware    {}", version.hardware);
+            }
+        }
+        Command::Baud {
+            set,
+            port,
+            ref in_proto,
+            ref out_proto,
+            show,
+        } => {
+            let port = port as u8;
+            let current = rx.poll(&ubx::poll_prt(port), "CFG-PRT poll", |frame| {
+                frame.port_config().filter(|config| config.port == port)
+            })?;
+            if show {
+                print_port(&current);
+                return Ok(());
+            }
+            let changed = changed_port(current, set, in_proto, out_proto)?;
+            if changed.baud == current.baud {
+                rx.configure(&changed.packet(), "CFG-PRT")?;
+            } else {
+                switch_baud(rx, &changed)?;
+            }
+        }
+        Command::Monitor {
+            json,
+            count,
+            seconds,
+        } => {
+            enable_output(rx, ubx::CLASS_NAV, ubx::ID_NAV_PVT)?;
+            let mut printed = 0;
+            stream(rx, "NAV-PVT", seconds, |frame| {
+                if count == Some(printed) {
+                    return Ok(Next::Stop);
+                }
+                let pvt = match frame.nav_pvt() {
+                    Some(pvt) => pvt,
+                    None => return Ok(Next::Skip),
+                };
+                if json {
+                    println!("{}", serde_json::to_string(&pvt)?);
+                } else {
+                    print_pvt(&pvt);
+                }
+                printed += 1;
+                Ok(if count == Some(printed) {
+                    Next::Stop
+                } else {
+                    Next::Took
+                })
+            })?;
+        }
+        Command::Hw { watch, .. } => {
+            let version = rx.poll(&ubx::poll_mon_ver(), "MON-VER poll", Frame::version)?;
+            // MON-RF came with generation 9, protocol version 27
+            let has_rf = version
+                .extension("PROTVER")
+                .and_then(|v| v.parse::<f64>().ok())
+                .is_some_and(|v| v >= 27.0);
+            loop {
+                let mut statuses =
+                    vec![rx.poll(&ubx::poll_mon_hw(), "MON-HW poll", Frame::mon_hw)?];
+                if has_rf {
+                    statuses.extend(rx.poll(&ubx::poll_mon_rf(), "MON-RF poll", Frame::mon_rf)?);
+                }
+                let warnings = print_hw(&statuses);
+                match watch {
+                    Some(secs) => thread::sleep(Duration::from_secs(secs)),
+                    None if warnings.is_empty() => break,
+                    None => return Err(anyhow!("{}", warnings.join(", "))),
+                }
+            }
+        }
+        Command::Hp { json, seconds } => {
+            enable_output(rx, ubx::CLASS_NAV, ubx::ID_NAV_HPPOSLLH)?;
+            enable_output(rx, ubx::CLASS_NAV, ubx::ID_NAV_RELPOSNED)?;
+            // a line of its own per solution when not on a terminal
+            let live = io::stdout().is_terminal();
+            let mut rel = None;
+            stream(rx, "NAV-HPPOSLLH", seconds, |frame| {
+                if let Some(relpos) = frame.rel_pos_ned() {
+                    if json {
+                        println!("{}", rel_pos_json(&relpos));
+                    }
+                    rel = Some(relpos);
+                    return Ok(Next::Skip);
+                }
+                let hp = match frame.hp_pos_llh() {
+                    Some(hp) => hp,
+                    None => return Ok(Next::Skip),
+                };
+                if json {
+                    println!("{}", hp_json(&hp));
+                    return Ok(Next::Took);
+                }
+                let baseline = rel
+                    .filter(|rel: &RelPosNed| rel.itow == hp.itow)
+                    .map_or_else(|| "no NAV-RELPOSNED".to_owned(), |rel| rel.describe());
+                let line = format!(
+                    "{:.3} s  {}  |  {}",
+                    f64::from(hp.itow) / 1000.0,
+                    hp.describe(),
+                    baseline
+                );
+                if live {
+                    print!("\r\x1b[K{}", line);
+                    io::stdout().flush()?;
+                } else {
+                    println!("{}", line);
+                }
+                Ok(Next::Took)
+            })?;
+            if live && !json {
+                println!();
+            }
+        }
+        Command::Sats {
+            once,
+            min_cno,
+            seconds,
+        } => {
+            enable_output(rx, ubx::CLASS_NAV, ubx::ID_NAV_SAT)?;
+            stream(rx, "NAV-SAT", seconds, |frame| {
+                let sat = match frame.nav_sat() {
+                    Some(sat) => sat,
+                    None => return Ok(Next::Skip),
+                };
+                print_sats(&sat, min_cno);
+                Ok(if once { Next::Stop } else { Next::Took })
+            })?;
+        }
+        Command::Rtcm {
+            ref enable,
+            ref disable,
+            rate,
+            port,
+            show,
+        } => {
+            if !enable.is_empty() {
+                let config = rx.poll(&ubx::poll_prt(port as u8), "CFG-PRT poll", |frame| {
+                    frame
+                        .port_config()
+                        .filter(|config| config.port == port as u8)
+                })?;
+                let rtcm3 = ubx::protocol_bit("rtcm3").expect("rtcm3 is a protocol");
+                if config.out_proto & rtcm3 == 0 {
+                    eprintln!(
+                        "warning: {} doesn't send RTCM3; turn it on with `ubloxcli baud --port {} --out-proto {},rtcm3`",
+                        config.port_name(),
+                        config.port_name(),
+                        ubx::protocol_names(config.out_proto).join(",")
+                    );
+                }
+            }
+            for (id, rate) in rtcm_changes(enable, disable, rate) {
+                let class = ubx::CLASS_RTCM3;
+                let rates = with_rate(rx.msg_rates(class, id)?, &[port], rate);
+                let name = ubx::message_name(class, id).expect("RTCM3 types are known");
+                let what = format!("CFG-MSG {}={}", name, rate);
+                rx.configure(&msg_packet(class, id, rates), &what)?;
+            }
+            if !enable.is_empty() {
+                println!(
+                    "RTCM3 goes out on {} once every {} solutions",
+                    ubx::PORTS[port],
+                    rate
+                );
+            }
+            if show {
+                list_rates(rx, ubx::CLASS_RTCM3, "RTCM3-")?;
+            }
+        }
+        Command::Raw { .. } => unreachable!("main logs raw measurements"),
+        Command::Assist {
+            ref file,
+            ref token,
+            ..
+        } => {
+            let data = match (file, token) {
+                (Some(file), _) => {
+                    fs::read(file).with_context(|| format!("failed to read {}", file.display()))?
+                }
+                (None, Some(token)) => fetch_assistnow(token)?,
+                (None, None) => unreachable!("clap requires --file or --online --token"),
+            };
+            assist(rx, &data)?;
+        }
+        Command::Base { ref mode } => {
+            rx.configure(&time_mode(mode).packet(), "CFG-TMODE3")?;
+            if let BaseMode::Survey { .. } = mode {
+                enable_output(rx, ubx::CLASS_NAV, ubx::ID_NAV_SVIN)?;
+                stream(rx, "NAV-SVIN", None, |frame| {
+                    let svin = match frame.nav_svin() {
+                        Some(svin) => svin,
+                        None => return Ok(Next::Skip),
+                    };
+                    print_svin(&svin);
+                    if svin.active || !svin.valid {
+                        return Ok(Next::Took);
+                    }
+                    let [x, y, z] = svin.mean.map(|v| ubx::format_fixed(v, 4));
+                    let acc = ubx::format_fixed(i64::from(svin.mean_acc), 4);
+                    eprintln!(
+                        "survey-in done; to skip it next time:\n  ubloxcli base fixed --ecef-x {} --ecef-y {} --ecef-z {} --accuracy {}",
+                        x, y, z, acc
+                    );
+                    Ok(Next::Stop)
+                })?;
+            }
+        }
+        Command::Timepulse { pin, show, .. } => {
+            let poll = |rx: &mut Receiver| {
+                rx.poll(&ubx::poll_tp5(pin), "CFG-TP5 poll", |frame| {
+                    frame.time_pulse().filter(|tp| tp.tp_idx == pin)
+                })
+            };
+            let mut tp = poll(rx)?;
+            if !show {
+                let asked = time_pulse(tp, command)?;
+                rx.configure(&asked.packet(), "CFG-TP5")?;
+                tp = poll(rx)?;
+                if tp != asked {
+                    eprintln!("warning: the receiver changed the settings it was given");
+                }
+            }
+            for line in tp.describe() {
+                println!("{}", line);
+            }
+        }
+        Command::Cfg {
+            action: CfgAction::Get { ref keys, layer },
+        } => {
+            for chunk in keys.chunks(ubxcfg::MAX_KEYS) {
+                let items = rx.poll(&ubxcfg::valget(layer, chunk), "CFG-VALGET", |frame| {
+                    ubxcfg::values(frame)
+                        .filter(|_| frame.id == ubx::ID_CFG_VALGET)
+                        .map(|(_, items)| items)
+                })?;
+                for (key, value) in items {
+                    // the type given for an unknown key over the one guessed
+                    let ty = chunk
+                        .iter()
+                        .find(|asked| asked.id == key.id)
+                        .map_or(key.ty, |asked| asked.ty);
+                    println!(
+                        "{:<36} 0x{:08x}  {:<2}  {}",
+                        key.name(),
+                        key.id,
+                        ty.name(),
+                        ty.decode(&value)
+                    );
+                }
+            }
+        }
+        Command::Cfg {
+            action:
+                CfgAction::Set {
+                    ref items,
+                    ref layer,
+                },
+        } => {
+            let layers = layer.iter().fold(0, |layers, bit| layers | bit);
+            for chunk in items.chunks(ubxcfg::MAX_KEYS) {
+                rx.configure(&ubxcfg::valset(layers, chunk), "CFG-VALSET")?;
+            }
+        }
+        Command::Apply { .. } => unreachable!("main applies files"),
+        Command::Decode { .. } => unreachable!("main decodes captures"),
+    }
+    Ok(())
+}
+
+fn hex_dump(bytes: &[u8]) {
+    for (i, line) in bytes.chunks(16).enumerate() {
+        let hex: Vec<String> = line.iter().map(|b| format!("{:02x}", b)).collect();
+        println!("{:10}{:04x}  {}", "", i * 16, hex.join(" "));
+    }
+}
+
+/// Lists the pieces of a capture file, or counts them with `stats`
+fn decode(file: &Path, filter: &[Filter], stats: bool) -> Result<(), Error> {
+    let bytes = fs::read(file).with_context(|| format!("failed to read {}", file.display()))?;
+    let wanted = |f: Filter| filter.is_empty() || filter.contains(&f);
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+    let (mut bad, mut junk) = (0, 0);
+    for (offset, piece) in ubx::split_capture(&bytes) {
+        match piece {
+            Piece::Frame(frame) => {
+                let name = ubx::frame_name(frame.class, frame.id);
+                if stats {
+                    *counts.entry(name).or_insert(0) += 1;
+                } else if wanted(Filter::Frame(frame.class, frame.id)) {
+                    let len = frame.payload.len();
+                    match frame.summary() {
+                        Some(summary) => {
+                            println!("{:08x}  {:<12} {:>5}  {}", offset, name, len, summary)
+                        }
+                        None => {
+                            println!("{:08x}  {:<12} {:>5}", offset, name, len);
+                            hex_dump(&frame.payload);
+                        }
+                    }
+                }
+            }
+            Piece::BadChecksum(class, id, len) => {
+                bad += 1;
+                if !stats && wanted(Filter::Frame(class, id)) {
+                    let name = ubx::frame_name(class, id);
+                    println!("{:08x}  {:<12} {:>5}  bad checksum", offset, name, len);
+                }
+            }
+            Piece::Text(text) => {
+                if stats {
+                    let name = match Sentence::parse(&text) {
+                        Some(sentence) => format!("NMEA-{}", sentence.name()),
+                        None => "text".to_owned(),
+                    };
+                    *counts.entry(name).or_insert(0) += 1;
+                } else if wanted(Filter::Nmea) {
+                    println!("{:08x}  {:<12} {:>5}  {}", offset, "NMEA", text.len(), text);
+                }
+            }
+            Piece::Junk(len) => {
+                junk += len;
+                if !stats && filter.is_empty() {
+                    println!("{:08x}  {:<12} {:>5}", offset, "unknown", len);
+                    hex_dump(&bytes[offset..offset + len]);
+                }
+            }
+        }
+    }
+    if stats {
+        for (name, count) in &counts {
+            println!("{:<14} {:>8}", name, count);
+        }
+        println!("{:<14} {:>8}", "bad checksums", bad);
+        println!("{:<14} {:>8}", "unknown bytes", junk);
+    }
+    Ok(())
+}
+
+/// The packets `command` sends, for `--out`
+fn packets(command: &Command) -> Result<Vec<Vec<u8>>, Error> {
+    Ok(match *command {
+        Command::Reset { hot, warm, .. } => vec![reset_packet(hot, warm)],
+        Command::Rate {
+            hz,
+            meas_ms,
+            nav_ratio,
+            time_ref,
+        } => {
+            let (meas_ms, nav_ratio) = meas_rate(hz, meas_ms, nav_ratio);
+            vec![rate_packet(meas_ms, nav_ratio, time_ref)]
+        }
+        Command::Msg {
+            list,
+            ref set,
+            ref port,
+        } => {
+            if list || !port.is_empty() {
+                return Err(anyhow!(
+                    "--list and --port read the receiver's rates and need --device"
+                ));
+            }
+            set.iter()
+                .map(|msg| msg_packet(msg.class, msg.id, [msg.rate; 5]))
+                .collect()
+        }
+        Command::Nav5 {
+            model,
+            min_elev,
+            fix_mode,
+            show,
+        } => {
+            if show {
+                return Err(anyhow!("--show reads the receiver and needs --device"));
+            }
+            let change = Nav5Change {
+                dyn_model: model,
+                min_elev,
+                fix_mode,
+            };
+            vec![change.packet()]
+        }
+        Command::Gnss { .. } => {
+            return Err(anyhow!(
+                "gnss writes back the receiver's whole configuration and needs --device"
+            ))
+        }
+        Command::Save { .. } | Command::LoadDefaults { .. } => {
+            let cfg = cfg_cfg(command).expect("save or load-defaults");
+            eprintln!("CFG-CFG: {}", cfg.describe());
+            vec![cfg.packet()]
+        }
+        Command::Version { .. } | Command::Id { .. } => {
+            return Err(anyhow!(
+                "version and id read the receiver and need --device"
+            ))
+        }
+        Command::Baud {
+            set,
+            port,
+            ref in_proto,
+            ref out_proto,
+            show,
+        } => {
+            let config = PortConfig::uart(port as u8, set.unwrap_or(9600));
+            if show || !config.is_uart() {
+                return Err(anyhow!(
+                    "--show and ports other than UARTs read the receiver's settings and need --device"
+                ));
+            }
+            vec![changed_port(config, set, in_proto, out_proto)?.packet()]
+        }
+        Command::Monitor { .. }
+        | Command::Sats { .. }
+        | Command::Hw { .. }
+        | Command::Hp { .. } => {
+            return Err(anyhow!(
+                "monitor, sats, hw and hp read the receiver and need --device"
+            ))
+        }
+        Command::Rtcm {
+            ref enable,
+            ref disable,
+            rate,
+            port,
+            show,
+        } => {
+            if show {
+                return Err(anyhow!("--show reads the receiver and needs --device"));
+            }
+            rtcm_changes(enable, disable, rate)
+                .into_iter()
+                .map(|(id, rate)| {
+                    msg_packet(ubx::CLASS_RTCM3, id, with_rate([0; 5], &[port], rate))
+                })
+                .collect()
+        }
+        Command::Raw { .. } => unreachable!("main logs raw measurements"),
+        Command::Assist { .. } => {
+            return Err(anyhow!(
+                "assist sends the time of the upload and needs --device"
+            ))
+        }
+        Command::Base { ref mode } => vec![time_mode(mode).packet()],
+        Command::Timepulse { pin, show, .. } => {
+            if show {
+                return Err(anyhow!("--show reads the receiver and needs --device"));
+            }
+            // the delays the receiver has are unknown, so they start over
+            let tp = time_pulse(TimePulse::factory(pin), command)?;
+            for line in tp.describe() {
+                eprintln!("CFG-TP5: {}", line);
+            }
+            vec![tp.packet()]
+        }
+        Command::Cfg {
+            action: CfgAction::Get { .. },
+        } => return Err(anyhow!("cfg get reads the receiver and needs --device")),
+        Command::Cfg {
+            action:
+                CfgAction::Set {
+                    ref items,
+                    ref layer,
+                },
+        } => {
+            let layers = layer.iter().fold(0, |layers, bit| layers | bit);
+            items
+                .chunks(ubxcfg::MAX_KEYS)
+                .map(|chunk| ubxcfg::valset(layers, chunk))
+                .collect()
+        }
+        Command::Apply { .. } => unreachable!("main applies files"),
+        Command::Decode { .. } => unreachable!("main decodes captures"),
+    })
+}
+
+/// The steps of a file with their commands, checking all of them before
+/// anything is sent
+fn load_steps(file: &Path) -> Result<Vec<(Step, Command)>, Error> {
+    let text =
+        fs::read_to_string(file).with_context(|| format!("failed to read {}", file.display()))?;
+    let steps = provision::parse(&text).map_err(|e| anyhow!("{}: {}", file.display(), e))?;
+    let mut commands = Vec::new();
+    for step in steps {
+        let argv = std::iter::once("ubloxcli").chain(step.args.iter().map(String::as_str));
+        let command = match Args::try_parse_from(argv) {
+            Ok(args) => args.command,
+            Err(e) => {
+                let e = e.to_string();
+                let message = e.lines().next().unwrap_or_default();
+                return Err(anyhow!(
+                    "{}: {}: {}",
+                    file.display(),
+                    step.place,
+                    message.trim_start_matches("error: ")
+                ));
+            }
+        };
+        if matches!(
+            command,
+            Command::Monitor { .. }
+                | Command::Sats { .. }
+                | Command::Hw { .. }
+                | Command::Hp { .. }
+                | Command::Raw { .. }
+                | Command::Decode { .. }
+                | Command::Apply { .. }
+        ) {
+            return Err(anyhow!(
+                "{}: {}: {} isn't a configuration step",
+                file.display(),
+                step.place,
+                step.args[0]
+            ));
+        }
+        commands.push((step, command));
+    }
+    Ok(commands)
+}
+
+/// Runs the steps, or prints their packets with `dry_run`, and prints a
+/// table of what became of each
+fn apply(
+    mut rx: Option<&mut Receiver>,
+    steps: &[(Step, Command)],
+    keep_going: bool,
+) -> Result<(), Error> {
+    let mut results: Vec<String> = Vec::new();
+    for (i, (step, command)) in steps.iter().enumerate() {
+        if !keep_going && results.iter().any(|r| r.starts_with("failed")) {
+            results.push("skipped".to_owned());
+            continue;
+        }
+        eprintln!("step {} ({}): {}", i + 1, step.place, step.args.join(" "));
+        let outcome = match rx.as_deref_mut() {
+            Some(rx) => run(command, rx),
+            None => packets(command).map(|packets| {
+                for packet in packets {
+                    let hex: Vec<String> = packet.iter().map(|b| format!("{:02x}", b)).collect();
+                    println!("{}", hex.join(" "));
+                }
+            }),
+        };
+        results.push(match outcome {
+            Ok(()) => "ok".to_owned(),
+            Err(e) => {
+                eprintln!("error: {:#}", e);
+                format!("failed: {}", e)
+            }
+        });
+    }
 
+    let width = steps
+        .iter()
+        .map(|(step, _)| step.args.join(" ").len())
+        .max()
+        .unwrap_or(0)
+        .min(48);
+    eprintln!();
+    eprintln!(
+        "{:>4}  {:<8}  {:<width$}  result",
+        "step",
+        "where",
+        "command",
+        width = width
+    );
+    for (i, ((step, _), result)) in steps.iter().zip(&results).enumerate() {
+        eprintln!(
+            "{:>4}  {:<8}  {:<width$}  {}",
+            i + 1,
+            step.place,
+            step.args.join(" "),
+            result,
+            width = width
+        );
+    }
+    let failed = results.iter().filter(|r| r.starts_with("failed")).count();
+    if failed > 0 {
+        return Err(anyhow!("{} of {} steps failed", failed, steps.len()));
+    }
     Ok(())
 }
+
+fn main() -> Result<(), Error> {
+    let args = Args::parse();
+
+    if let Command::Decode {
+        file,
+        filter,
+        stats,
+    } = &args.command
+    {
+        return decode(file, filter, *stats);
+    }
+
+    if let Command::Apply {
+        file,
+        keep_going,
+        dry_run,
+    } = &args.command
+    {
+        let steps = load_steps(file)?;
+        if *dry_run {
+            return apply(None, &steps, *keep_going);
+        }
+        let device = args
+            .device
+            .as_deref()
+            .ok_or_else(|| anyhow!("apply needs --device PATH, or --dry-run"))?;
+        let mut rx = Receiver::open(&args, device)?;
+        return apply(Some(&mut rx), &steps, *keep_going);
+    }
+
+    if let Command::Raw { seconds } = args.command {
+        let out = args
+            .out
+            .as_deref()
+            .ok_or_else(|| anyhow!("raw needs --out FILE to write the measurements to"))?;
+        let device = args
+            .device
+            .as_deref()
+            .ok_or_else(|| anyhow!("raw needs --device PATH"))?;
+        let mut rx = Receiver::open(&args, device)?;
+        return raw(&mut rx, out, seconds);
+    }
+
+    if let Some(out) = &args.out {
+        if args.device.is_some() {
+            return Err(anyhow!(
+                "--out writes the packets instead of sending them; give --device or --out"
+            ));
+        }
+        let bytes = packets(&args.command)?.concat();
+        fs::write(out, bytes).with_context(|| format!("failed to write {}", out.display()))?;
+        return Ok(());
+    }
+    let device = args
+        .device
+        .as_deref()
+        .ok_or_else(|| anyhow!("no receiver: give --device PATH, or --out FILE"))?;
+    let mut rx = Receiver::open(&args, device)?;
+    run(&args.command, &mut rx)
+}
@@ -0,0 +1,302 @@
+//! Validation of recorded logs for nmea-check: every line parsed as nmeacli
+//! would, summed up into what the log holds and what is wrong with it
+
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDateTime, NaiveTime};
+use nmea::Nmea;
+
+use crate::{
+    app::LOCAL_SENTENCES,
+    inspect::{self, Checksum},
+    sentence::Sentence,
+};
+
+/// Lines kept as examples of each kind of error
+const EXAMPLES: usize = 3;
+
+/// Lines failing for the same reason
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorGroup {
+    pub count: u64,
+    /// the first few of them
+    pub examples: Vec<String>,
+}
+
+/// Corners of the fixes seen, in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+impl Bounds {
+    fn extend(bounds: Option<Bounds>, lat: f64, lon: f64) -> Bounds {
+        match bounds {
+            Some(b) => Bounds {
+                south: b.south.min(lat),
+                west: b.west.min(lon),
+                north: b.north.max(lat),
+                east: b.east.max(lon),
+            },
+            None => Bounds {
+                south: lat,
+                west: lon,
+                north: lat,
+                east: lon,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    /// lines seen, blank ones included
+    pub lines: u64,
+    pub blank: u64,
+    /// sentences with a valid checksum, by `Sentence::name`
+    pub sentences: BTreeMap<String, u64>,
+    /// lines which failed, by why
+    pub errors: BTreeMap<String, ErrorGroup>,
+    /// lines with a wrong or missing checksum, among the errors
+    pub checksum_failures: u64,
+    /// first and last fix time with a date
+    pub datetimes: Option<(NaiveDateTime, NaiveDateTime)>,
+    /// earliest and latest fix time of day, for logs without a date
+    pub times: Option<(NaiveTime, NaiveTime)>,
+    /// GGA and RMC sentences, and those of them with a fix
+    pub position_reports: u64,
+    pub fixes: u64,
+    pub bounds: Option<Bounds>,
+    nmea: Nmea,
+}
+
+impl Report {
+    pub fn new() -> Report {
+        Report::default()
+    }
+
+    /// Takes in a line of the log; why it failed, if it did
+    pub fn check_line(&mut self, line: &str) -> Result<(), String> {
+        self.lines += 1;
+        if line.trim().is_empty() {
+            self.blank += 1;
+            return Ok(());
+        }
+        let result = self.check_sentence(line);
+        if let Err(reason) = &result {
+            let group = self.errors.entry(reason.clone()).or_default();
+            group.count += 1;
+            if group.examples.len() < EXAMPLES {
+                group.examples.push(line.trim().to_owned());
+            }
+        }
+        result
+    }
+
+    fn check_sentence(&mut self, line: &str) -> Result<(), String> {
+        let inspection = inspect::inspect(line).ok_or("not a sentence")?;
+        match inspection.checksum {
+            Checksum::Valid(_) => {}
+            Checksum::Invalid { .. } => {
+                self.checksum_failures += 1;
+                return Err("bad checksum".to_owned());
+            }
+            Checksum::Missing { .. } => {
+                self.checksum_failures += 1;
+                return Err("missing checksum".to_owned());
+            }
+        }
+        let sentence = Sentence::parse(line).ok_or("no sentence type")?;
+        *self
+            .sentences
+            .entry(sentence.name().to_owned())
+            .or_default() += 1;
+
+        // as in nmeacli, what it parses itself isn't held against the log
+        if let Err(e) = self.nmea.parse(line) {
+            if !LOCAL_SENTENCES.contains(&sentence.kind) {
+                return Err(e.to_string());
+            }
+        }
+        self.update_times();
+
+        let (fix, position) = match sentence.kind {
+            "GGA" => (sentence.field(5).is_some_and(|q| q != "0"), 1),
+            "RMC" => (sentence.field(1) == Some("A"), 2),
+            _ => return Ok(()),
+        };
+        self.position_reports += 1;
+        if fix {
+            self.fixes += 1;
+            if let (Some(lat), Some(lon)) = (
+                sentence.coord_field(position),
+                sentence.coord_field(position + 2),
+            ) {
+                self.bounds = Some(Bounds::extend(self.bounds, lat, lon));
+            }
+        }
+        Ok(())
+    }
+
+    fn update_times(&mut self) {
+        let time = match self.nmea.fix_time {
+            Some(time) => time,
+            None => return,
+        };
+        let (first, last) = self.times.unwrap_or((time, time));
+        self.times = Some((first.min(time), last.max(time)));
+        if let Some(date) = self.nmea.fix_date {
+            let datetime = date.and_time(time);
+            let (first, last) = self.datetimes.unwrap_or((datetime, datetime));
+            self.datetimes = Some((first.min(datetime), last.max(datetime)));
+        }
+    }
+
+    pub fn error_count(&self) -> u64 {
+        self.errors.values().map(|group| group.count).sum()
+    }
+
+    /// Percentage of the lines which aren't blank that failed
+    pub fn error_rate(&self) -> f64 {
+        let lines = self.lines - self.blank;
+        if lines == 0 {
+            return 0.0;
+        }
+        self.error_count() as f64 * 100.0 / lines as f64
+    }
+
+    /// Percentage of position reports with a fix, `None` without any
+    pub fn fix_rate(&self) -> Option<f64> {
+        if self.position_reports == 0 {
+            return None;
+        }
+        Some(self.fixes as f64 * 100.0 / self.position_reports as f64)
+    }
+
+    /// The report as printed, a line at a time
+    pub fn summary(&self) -> Vec<String> {
+        let mut out = vec![format!(
+            "lines      : {} ({} blank)",
+            self.lines, self.blank
+        )];
+        let counts: Vec<String> = self
+            .sentences
+            .iter()
+            .map(|(name, count)| format!("{} {}", name, count))
+            .collect();
+        out.push(format!("sentences  : {}", counts.join(", ")));
+        out.push(format!(
+            "errors     : {} ({:.1}%), {} checksum failures",
+            self.error_count(),
+            self.error_rate(),
+            self.checksum_failures
+        ));
+        for (reason, group) in &self.errors {
+            out.push(format!("  {} x{}", reason, group.count));
+            out.extend(group.examples.iter().map(|line| format!("    {}", line)));
+        }
+        out.push(match (self.datetimes, self.times) {
+            (Some((first, last)), _) => format!(
+                "time       : {} to {} UTC ({})",
+                first,
+                last,
+                duration_str(last - first)
+            ),
+            (None, Some((first, last))) => format!(
+                "time       : {} to {} UTC, no date",
+                first.format("%H:%M:%S"),
+                last.format("%H:%M:%S")
+            ),
+            (None, None) => "time       : none".to_owned(),
+        });
+        out.push(match self.fix_rate() {
+            Some(rate) => format!(
+                "fix        : {:.1}% of {} GGA and RMC",
+                rate, self.position_reports
+            ),
+            None => "fix        : no GGA or RMC".to_owned(),
+        });
+        out.push(match self.bounds {
+            Some(b) => format!(
+                "bounds     : {:.6},{:.6} to {:.6},{:.6}",
+                b.south, b.west, b.north, b.east
+            ),
+            None => "bounds     : no fix".to_owned(),
+        });
+        out
+    }
+}
+
+/// `1h02m03s`
+fn duration_str(duration: chrono::Duration) -> String {
+    let secs = duration.num_seconds();
+    format!("{}h{:02}m{:02}s", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &[&str] = &[
+        "$GPRMC,060433.00,A,3732.93104,N,12654.85121,E,0.212,,070620,,,A*71",
+        "$GPGGA,060433.00,3732.93104,N,12654.85121,E,1,04,1.22,46.3,M,18.3,M,,*61",
+        "",
+        "$GPGGA,060434.00,3732.93104,N,12654.85121,E,1,04,1.22,46.3,M,18.3,M,,*00",
+        "garbage",
+        "$GPGGA,060434.50,,,,,0,00,99.99,,,,,,*66",
+        "$GPGGA,060435.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,*6D",
+    ];
+
+    fn check_log() -> Report {
+        let mut report = Report::new();
+        for line in LOG {
+            report.check_line(line).ok();
+        }
+        report
+    }
+
+    #[test]
+    fn errors_are_grouped_and_counted() {
+        let mut report = Report::new();
+        assert_eq!(report.check_line(LOG[3]), Err("bad checksum".to_owned()));
+        assert_eq!(report.check_line(LOG[4]), Err("not a sentence".to_owned()));
+        assert_eq!(
+            report.check_line("$GPGGA,060434.00"),
+            Err("missing checksum".to_owned())
+        );
+        assert_eq!(report.check_line(LOG[2]), Ok(()));
+
+        let report = check_log();
+        assert_eq!((report.lines, report.blank), (7, 1));
+        assert_eq!(report.error_count(), 2);
+        assert_eq!(report.checksum_failures, 1);
+        assert_eq!(report.errors["bad checksum"].examples, [LOG[3]]);
+        assert!((report.error_rate() - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.sentences["GGA"], 3);
+        assert_eq!(report.sentences["RMC"], 1);
+    }
+
+    #[test]
+    fn time_fixes_and_bounds() {
+        let report = check_log();
+        let date = chrono::NaiveDate::from_ymd_opt(2020, 6, 7).unwrap();
+        assert_eq!(
+            report.datetimes,
+            Some((
+                date.and_hms_opt(6, 4, 33).unwrap(),
+                date.and_hms_opt(6, 4, 35).unwrap()
+            ))
+        );
+        assert_eq!((report.position_reports, report.fixes), (4, 3));
+        let bounds = report.bounds.unwrap();
+        assert!((bounds.south - 37.548851).abs() < 1e-6);
+        assert!((bounds.north - 37.565510).abs() < 1e-6);
+        assert!(bounds.west < bounds.east);
+        assert!(report
+            .summary()
+            .contains(&"fix        : 75.0% of 4 GGA and RMC".to_owned()));
+    }
+}
@@ -0,0 +1,69 @@
+//! Copying text for `y`. Built with the `clipboard` feature, the local
+//! clipboard is tried first; without it, or when there is none like over
+//! ssh, the terminal is asked to copy with an OSC 52 escape sequence.
+
+use std::io::{self, Write};
+
+use crate::ntrip::base64;
+
+/// Where copied text went
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Copied {
+    Clipboard,
+    /// asked the terminal, which may not support it
+    Terminal,
+}
+
+#[cfg(feature = "clipboard")]
+thread_local! {
+    /// kept for the whole run, as on X11 the copied text goes with its owner
+    static CLIPBOARD: std::cell::RefCell<Option<arboard::Clipboard>> =
+        std::cell::RefCell::new(None);
+}
+
+#[cfg(feature = "clipboard")]
+fn copy_local(text: &str) -> bool {
+    CLIPBOARD.with(|clipboard| {
+        let mut clipboard = clipboard.borrow_mut();
+        if clipboard.is_none() {
+            *clipboard = arboard::Clipboard::new().ok();
+        }
+        match clipboard.as_mut() {
+            Some(clipboard) => clipboard.set_text(text.to_owned()).is_ok(),
+            None => false,
+        }
+    })
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn copy_local(_text: &str) -> bool {
+    false
+}
+
+pub fn copy(text: &str) -> io::Result<Copied> {
+    if copy_local(text) {
+        return Ok(Copied::Clipboard);
+    }
+    let mut stdout = io::stdout();
+    stdout.write_all(osc52(text).as_bytes())?;
+    stdout.flush()?;
+    Ok(Copied::Terminal)
+}
+
+/// Escape sequence asking the terminal to put `text` on the clipboard
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terminal_is_asked_in_base64() {
+        assert_eq!(
+            osc52("37.548843,126.914181"),
+            "\x1b]52;c;MzcuNTQ4ODQzLDEyNi45MTQxODE=\x07"
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! The datum positions are given in, as DTM tells. A receiver set to a local
+//! datum like Tokyo shifts its positions by hundreds of meters from WGS84,
+//! which maps and the exports here take them to be in.
+
+use crate::sentence::Sentence;
+
+/// DTM codes of the datums receivers know, and their names
+pub const NAMES: &[(&str, &str)] = &[
+    ("W84", "WGS84"),
+    ("W72", "WGS72"),
+    ("S85", "SGS85"),
+    ("P90", "PE90"),
+    ("999", "user defined"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Datum {
+    /// the local datum, like `W84` or `999`
+    pub code: String,
+    pub subdivision: Option<String>,
+    /// from the reference datum, in minutes, north positive
+    pub latitude_offset: f64,
+    /// in minutes, east positive
+    pub longitude_offset: f64,
+    /// in meters
+    pub altitude_offset: Option<f64>,
+    /// what the offsets are from, `W84` as a rule
+    pub reference: Option<String>,
+}
+
+impl Datum {
+    pub fn is_wgs84(&self) -> bool {
+        self.code == "W84"
+    }
+
+    /// `WGS84`, or the code as given for a datum not in the table
+    pub fn name(&self) -> &str {
+        datum_name(&self.code)
+    }
+}
+
+/// Name of a datum code of DTM, the code itself when unknown
+pub fn datum_name(code: &str) -> &str {
+    NAMES
+        .iter()
+        .find(|&&(c, _)| c == code)
+        .map_or(code, |&(_, name)| name)
+}
+
+/// The local datum and its offsets, from DTM
+pub fn dtm_datum(dtm: &Sentence) -> Option<Datum> {
+    let offset = |i: usize, negative: &str| {
+        let minutes: f64 = dtm.parse_field(i).unwrap_or(0.0);
+        match dtm.field(i + 1) {
+            Some(sense) if sense == negative => -minutes,
+            _ => minutes,
+        }
+    };
+    Some(Datum {
+        code: dtm.field(0)?.to_owned(),
+        subdivision: dtm.field(1).map(str::to_owned),
+        latitude_offset: offset(2, "S"),
+        longitude_offset: offset(4, "W"),
+        altitude_offset: dtm.parse_field(6),
+        reference: dtm.field(7).map(str::to_owned),
+    })
+}
+
+/// `WGS84`, or `user defined, offset 0.1234' N 0.5678' W` when it is moved
+/// from the reference
+pub fn datum_str(datum: &Datum) -> String {
+    let mut text = datum.name().to_owned();
+    if let Some(subdivision) = &datum.subdivision {
+        text.push_str(&format!(" ({})", subdivision));
+    }
+    if datum.latitude_offset != 0.0 || datum.longitude_offset != 0.0 {
+        let ns = if datum.latitude_offset < 0.0 {
+            'S'
+        } else {
+            'N'
+        };
+        let ew = if datum.longitude_offset < 0.0 {
+            'W'
+        } else {
+            'E'
+        };
+        text.push_str(&format!(
+            ", offset {:.4}' {} {:.4}' {}",
+            datum.latitude_offset.abs(),
+            ns,
+            datum.longitude_offset.abs(),
+            ew
+        ));
+    }
+    if let Some(reference) = datum.reference.as_deref().filter(|&r| r != "W84") {
+        text.push_str(&format!(" from {}", datum_name(reference)));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wgs84_and_local_datums() {
+        let dtm = Sentence::parse("$GPDTM,W84,,0.0,N,0.0,E,0.0,W84*6F").unwrap();
+        let datum = dtm_datum(&dtm).unwrap();
+        assert!(datum.is_wgs84());
+        assert_eq!(datum_str(&datum), "WGS84");
+
+        let dtm = Sentence::parse("$GPDTM,999,,0.08,N,0.07,E,-47.7,W84*1B").unwrap();
+        let datum = dtm_datum(&dtm).unwrap();
+        assert!(!datum.is_wgs84());
+        assert_eq!(datum.altitude_offset, Some(-47.7));
+        assert_eq!(
+            datum_str(&datum),
+            "user defined, offset 0.0800' N 0.0700' E"
+        );
+
+        let dtm = Sentence::parse("$GPDTM,TOKYO,,0.1930,S,0.1310,W,,W84*5B").unwrap();
+        let datum = dtm_datum(&dtm).unwrap();
+        assert_eq!(datum.name(), "TOKYO");
+        assert_eq!(datum.latitude_offset, -0.193);
+        assert_eq!(datum.longitude_offset, -0.131);
+    }
+}
@@ -0,0 +1,206 @@
+//! Epochs: the burst of sentences a receiver sends for each solution, told
+//! apart by the UTC time they carry. Sentences without one, like GSA and
+//! GSV, go with the epoch in progress.
+
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Duration, Local, NaiveTime};
+
+use crate::sentence::Sentence;
+
+/// Epochs which started longer ago than this are left out of the statistics
+pub const WINDOW_SECS: i64 = 60;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// UTC time of the solution a sentence belongs to, for those carrying one
+pub fn sentence_time(sentence: &Sentence) -> Option<NaiveTime> {
+    let field = match sentence.name() {
+        "GGA" | "RMC" | "GNS" | "GST" | "GBS" | "GRS" | "ZDA" => 0,
+        "GLL" => 4,
+        "PUBX00" | "PUBX04" => 1,
+        _ => return None,
+    };
+    sentence.time_field(field)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Epoch {
+    /// UTC time of the solution
+    pub time: NaiveTime,
+    /// arrival of its first sentence
+    pub start: DateTime<Local>,
+    /// sentence types in order of arrival, with how many of each
+    pub sentences: Vec<(String, usize)>,
+}
+
+impl Epoch {
+    fn add(&mut self, kind: &str) {
+        match self.sentences.iter_mut().find(|(k, _)| k == kind) {
+            Some((_, count)) => *count += 1,
+            None => self.sentences.push((kind.to_owned(), 1)),
+        }
+    }
+
+    /// Like `RMC VTG GGA GSA GSV×3 GLL`
+    pub fn describe(&self) -> String {
+        let names: Vec<String> = self
+            .sentences
+            .iter()
+            .map(|(kind, count)| match count {
+                1 => kind.clone(),
+                n => format!("{}×{}", kind, n),
+            })
+            .collect();
+        names.join(" ")
+    }
+}
+
+/// The epochs of the last minute, summed up
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpochStats {
+    /// solutions a second, by the receiver's clock
+    pub rate_hz: f64,
+    /// shortest, average and longest time between the arrivals of
+    /// consecutive epochs, in milliseconds
+    pub min_ms: i64,
+    pub avg_ms: i64,
+    pub max_ms: i64,
+    /// the latest complete epoch
+    pub last: Epoch,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EpochTracker {
+    /// epoch whose sentences are still arriving
+    current: Option<Epoch>,
+    /// complete epochs of the last minute, oldest first
+    done: VecDeque<Epoch>,
+}
+
+impl EpochTracker {
+    pub fn push(&mut self, local: DateTime<Local>, sentence: &Sentence) {
+        if let Some(time) = sentence_time(sentence) {
+            if self.current.as_ref().map(|epoch| epoch.time) != Some(time) {
+                self.start(local, time);
+            }
+        }
+        // until the first time there is no epoch to go with
+        if let Some(epoch) = self.current.as_mut() {
+            epoch.add(sentence.name());
+        }
+    }
+
+    fn start(&mut self, local: DateTime<Local>, time: NaiveTime) {
+        let epoch = Epoch {
+            time,
+            start: local,
+            sentences: Vec::new(),
+        };
+        if let Some(done) = self.current.replace(epoch) {
+            self.done.push_back(done);
+        }
+        let window = Duration::seconds(WINDOW_SECS);
+        while self
+            .done
+            .front()
+            .is_some_and(|epoch| local - epoch.start > window)
+        {
+            self.done.pop_front();
+        }
+    }
+
+    /// `None` until an epoch is complete and the next one started
+    pub fn stats(&self) -> Option<EpochStats> {
+        let last = self.done.back()?.clone();
+        let epochs: Vec<&Epoch> = self.done.iter().chain(&self.current).collect();
+        let pairs = || epochs.iter().zip(epochs.iter().skip(1));
+
+        let gaps: Vec<i64> = pairs()
+            .map(|(a, b)| (b.start - a.start).num_milliseconds())
+            .collect();
+        // a step back is taken for midnight
+        let elapsed_ms: i64 = pairs()
+            .map(|(a, b)| (b.time - a.time).num_milliseconds().rem_euclid(DAY_MS))
+            .sum();
+        if elapsed_ms == 0 {
+            return None;
+        }
+        Some(EpochStats {
+            rate_hz: gaps.len() as f64 * 1000.0 / elapsed_ms as f64,
+            min_ms: *gaps.iter().min()?,
+            avg_ms: gaps.iter().sum::<i64>() / gaps.len() as i64,
+            max_ms: *gaps.iter().max()?,
+            last,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::sentence::with_checksum;
+
+    const EXAMPLE: &str = include_str!("../example.txt");
+
+    fn push(tracker: &mut EpochTracker, local: DateTime<Local>, line: &str) {
+        tracker.push(local, &Sentence::parse(line).unwrap());
+    }
+
+    #[test]
+    fn one_hertz_log_is_summed_up() {
+        let mut tracker = EpochTracker::default();
+        // one epoch a second, as example.txt was received
+        let mut local = Local.timestamp(1_591_509_873, 0);
+        for (i, line) in EXAMPLE.lines().enumerate() {
+            if line.starts_with("$GPRMC") && i > 0 {
+                local = local + Duration::seconds(1);
+            }
+            push(&mut tracker, local, line);
+            if i == 0 {
+                assert_eq!(tracker.stats(), None);
+            }
+        }
+
+        let stats = tracker.stats().unwrap();
+        assert_eq!(stats.rate_hz, 1.0);
+        assert_eq!(
+            (stats.min_ms, stats.avg_ms, stats.max_ms),
+            (1000, 1000, 1000)
+        );
+        assert_eq!(stats.last.describe(), "RMC VTG GGA GSA GSV×3 GLL");
+        assert_eq!(stats.last.time, NaiveTime::from_hms_opt(6, 4, 35).unwrap());
+    }
+
+    #[test]
+    fn batched_arrivals_show_as_jitter() {
+        let mut tracker = EpochTracker::default();
+        let start = Local.timestamp(1_591_509_873, 0);
+        // 10 Hz, delivered two epochs at a time every 200 ms
+        for i in 0..20 {
+            let local = start + Duration::milliseconds(i / 2 * 200);
+            let time = format!("2359{:02}.{}0", 59 - (19 - i) / 10, i % 10);
+            let gga = with_checksum(&format!(
+                "GPGGA,{},3732.93104,N,12654.85121,E,1,05,1.17,46.7,M,18.3,M,,",
+                time
+            ));
+            push(&mut tracker, local, &gga);
+            push(
+                &mut tracker,
+                local,
+                "$GPGSA,A,3,06,03,17,22,28,,,,,,,,2.63,1.17,2.36*0C",
+            );
+        }
+        // past midnight
+        let gga =
+            with_checksum("GPGGA,000000.00,3732.93104,N,12654.85121,E,1,05,1.17,46.7,M,18.3,M,,");
+        push(&mut tracker, start + Duration::seconds(2), &gga);
+
+        let stats = tracker.stats().unwrap();
+        assert!((stats.rate_hz - 10.0).abs() < 1e-9);
+        assert_eq!((stats.min_ms, stats.max_ms), (0, 200));
+        assert_eq!(stats.last.describe(), "GGA GSA");
+    }
+}
@@ -0,0 +1,409 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+};
+
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use clap::ValueEnum;
+use nmea::Nmea;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{Corrections, ErrorStats, Waypoint},
+    datum,
+    sky::Sky,
+    ttff::Measurement,
+};
+
+/// Which height exported altitudes are
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Altitude {
+    /// above mean sea level, as GGA tells
+    Msl,
+    /// above the WGS84 ellipsoid: MSL plus the geoid separation
+    Ellipsoidal,
+}
+
+impl Altitude {
+    /// Name of the altitude column in CSV exports
+    fn csv_column(self) -> &'static str {
+        match self {
+            Altitude::Msl => "altitude_msl",
+            Altitude::Ellipsoidal => "altitude_ellipsoidal",
+        }
+    }
+}
+
+const SAT_CSV_HEADER: &str = "prn,constellation,elevation,azimuth,snr,used";
+
+fn csv_field<T: ToString>(v: Option<T>) -> String {
+    v.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// One CSV row per satellite, in `SAT_CSV_HEADER` column order
+pub fn sat_csv_rows(sky: &Sky) -> Vec<String> {
+    sky.satellites()
+        .map(|sat| {
+            format!(
+                "{},{},{},{},{},{}",
+                sat.prn,
+                sat.constellation(),
+                csv_field(sat.elevation),
+                csv_field(sat.azimuth),
+                csv_field(sat.snr),
+                sat.used,
+            )
+        })
+        .collect()
+}
+
+enum CsvJob {
+    Snapshot(PathBuf, Vec<String>),
+    Cycle(DateTime<Local>, Vec<String>),
+    Marker(DateTime<Local>, String),
+}
+
+/// Writes CSV files from a background thread so the render loop never waits
+/// on the disk. Outcomes worth showing to the user come back on `notices`,
+/// failures as `Err`.
+pub struct CsvWriter {
+    tx: mpsc::Sender<CsvJob>,
+    logging: bool,
+}
+
+impl CsvWriter {
+    pub fn spawn(
+        snr_log: Option<PathBuf>,
+        notices: mpsc::Sender<Result<String, String>>,
+    ) -> CsvWriter {
+        let (tx, rx) = mpsc::channel();
+        let logging = snr_log.is_some();
+
+        thread::spawn(move || {
+            let mut snr_log = snr_log;
+            for job in rx {
+                match job {
+                    CsvJob::Snapshot(path, rows) => {
+                        let msg = match write_sat_snapshot(&path, &rows) {
+                            Ok(()) => Ok(format!("exported satellites to {}", path.display())),
+                            Err(e) => Err(format!("failed to export {}: {}", path.display(), e)),
+                        };
+                        notices.send(msg).ok();
+                    }
+                    CsvJob::Cycle(local, rows) => {
+                        let path = match &snr_log {
+                            Some(path) => path,
+                            None => continue,
+                        };
+                        if let Err(e) = append_snr_cycle(path, local, &rows) {
+                            notices
+                                .send(Err(format!("snr log {} disabled: {}", path.display(), e)))
+                                .ok();
+                            snr_log = None;
+                        }
+                    }
+                    CsvJob::Marker(local, text) => {
+                        let path = match &snr_log {
+                            Some(path) => path,
+                            None => continue,
+                        };
+                        if let Err(e) = append_snr_marker(path, local, &text) {
+                            notices
+                                .send(Err(format!("snr log {} disabled: {}", path.display(), e)))
+                                .ok();
+                            snr_log = None;
+                        }
+                    }
+                }
+            }
+        });
+
+        CsvWriter { tx, logging }
+    }
+
+    pub fn snapshot(&self, path: PathBuf, rows: Vec<String>) {
+        self.tx.send(CsvJob::Snapshot(path, rows)).ok();
+    }
+
+    pub fn log_cycle(&self, local: DateTime<Local>, rows: Vec<String>) {
+        if self.logging {
+            self.tx.send(CsvJob::Cycle(local, rows)).ok();
+        }
+    }
+
+    /// Notes an event between the cycles of the SNR log
+    pub fn log_marker(&self, local: DateTime<Local>, text: &str) {
+        if self.logging {
+            self.tx.send(CsvJob::Marker(local, text.to_owned())).ok();
+        }
+    }
+}
+
+fn write_sat_snapshot(path: &Path, rows: &[String]) -> io::Result<()> {
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    writeln!(file, "{}", SAT_CSV_HEADER)?;
+    for row in rows {
+        writeln!(file, "{}", row)?;
+    }
+    file.flush()
+}
+
+fn append_snr_cycle(path: &Path, local: DateTime<Local>, rows: &[String]) -> io::Result<()> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let empty = file.metadata()?.len() == 0;
+
+    let mut file = io::BufWriter::new(file);
+    if empty {
+        writeln!(file, "epoch,{}", SAT_CSV_HEADER)?;
+    }
+    // all satellites of a cycle share the epoch so they can be grouped later
+    let epoch = local.timestamp_millis() as f64 / 1000.0;
+    for row in rows {
+        writeln!(file, "{:.3},{}", epoch, row)?;
+    }
+    file.flush()
+}
+
+/// A `#` comment line, which CSV readers can be told to skip; none in a
+/// log without cycles yet
+fn append_snr_marker(path: &Path, local: DateTime<Local>, text: &str) -> io::Result<()> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(());
+    }
+    let mut file = io::BufWriter::new(file);
+    writeln!(
+        file,
+        "# {} {}",
+        local.to_rfc3339_opts(SecondsFormat::Millis, true),
+        text
+    )?;
+    file.flush()
+}
+
+pub fn append_average(path: &Path, local: DateTime<Local>, summary: &str) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(
+        file,
+        "{} {}",
+        local.to_rfc3339_opts(SecondsFormat::Secs, true),
+        summary
+    )
+}
+
+const TTFF_CSV_HEADER: &str = "started,start_type,ttff_s,satellites";
+
+/// Appends a row for a time to first fix, with the header when the file is
+/// new; the start type is empty when it isn't known
+pub fn append_ttff(path: &Path, measurement: &Measurement) -> io::Result<()> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let empty = file.metadata()?.len() == 0;
+
+    let mut file = io::BufWriter::new(file);
+    if empty {
+        writeln!(file, "{}", TTFF_CSV_HEADER)?;
+    }
+    writeln!(
+        file,
+        "{},{},{:.3},{}",
+        measurement
+            .started
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+        csv_field(measurement.start_type),
+        measurement.seconds,
+        csv_field(measurement.satellites)
+    )?;
+    file.flush()
+}
+
+/// Everything the receiver reported so far, for field notes
+pub fn write_freeze_frame(
+    path: &Path,
+    local: DateTime<Local>,
+    nmea: &Nmea,
+    sky: &Sky,
+    geoid_separation: Option<f32>,
+    corrections: Option<&Corrections>,
+    errors: Option<&ErrorStats>,
+) -> io::Result<()> {
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    writeln!(
+        file,
+        "captured    : {}",
+        local.to_rfc3339_opts(SecondsFormat::Millis, true)
+    )?;
+    writeln!(file, "fix date    : {}", csv_field(nmea.fix_date))?;
+    writeln!(file, "fix time    : {}", csv_field(nmea.fix_time))?;
+    writeln!(
+        file,
+        "fix type    : {}",
+        csv_field(nmea.fix_type.map(|t| format!("{:?}", t)))
+    )?;
+    writeln!(file, "latitude    : {}", csv_field(nmea.latitude))?;
+    writeln!(file, "longitude   : {}", csv_field(nmea.longitude))?;
+    writeln!(file, "altitude    : {}", csv_field(nmea.altitude))?;
+    writeln!(file, "geoid sep.  : {}", csv_field(geoid_separation))?;
+    writeln!(
+        file,
+        "ellipsoid h : {}",
+        csv_field(ellipsoidal_height(nmea.altitude, geoid_separation))
+    )?;
+    writeln!(file, "speed (kn)  : {}", csv_field(nmea.speed_over_ground))?;
+    writeln!(file, "course      : {}", csv_field(nmea.true_course))?;
+    writeln!(
+        file,
+        "dop (h/v/p) : {} / {} / {}",
+        csv_field(nmea.hdop),
+        csv_field(nmea.vdop),
+        csv_field(nmea.pdop)
+    )?;
+    writeln!(
+        file,
+        "fix sats    : {}",
+        csv_field(nmea.num_of_fix_satellites)
+    )?;
+    writeln!(
+        file,
+        "corr. age   : {}",
+        csv_field(corrections.and_then(|c| c.age))
+    )?;
+    writeln!(
+        file,
+        "corr. stn   : {}",
+        csv_field(corrections.and_then(|c| c.station.clone()))
+    )?;
+    let error = |f: fn(&ErrorStats) -> Option<f32>| csv_field(errors.and_then(f));
+    writeln!(
+        file,
+        "err (l/l/a) : {} / {} / {}",
+        error(|e| e.latitude),
+        error(|e| e.longitude),
+        error(|e| e.altitude)
+    )?;
+    writeln!(
+        file,
+        "err ellipse : {} / {} / {}",
+        error(|e| e.semi_major),
+        error(|e| e.semi_minor),
+        error(|e| e.orientation)
+    )?;
+    writeln!(file, "err rms     : {}", error(|e| e.rms))?;
+    writeln!(file)?;
+    writeln!(file, "{}", SAT_CSV_HEADER)?;
+    for row in sat_csv_rows(sky) {
+        writeln!(file, "{}", row)?;
+    }
+    file.flush()
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// MSL altitude plus the geoid separation, if the receiver told both
+pub fn ellipsoidal_height(msl: Option<f32>, geoid_separation: Option<f32>) -> Option<f32> {
+    Some(msl? + geoid_separation?)
+}
+
+/// Writes GPX when the extension is .gpx and CSV otherwise, with `altitude`
+/// heights, which the file names
+pub fn write_waypoints(path: &Path, waypoints: &[Waypoint], altitude: Altitude) -> io::Result<()> {
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    let gpx = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gpx"));
+
+    if gpx {
+        writeln!(file, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            file,
+            r#"<gpx version="1.1" creator="nmeacli" xmlns="http://www.topografix.com/GPX/1/1">"#
+        )?;
+        // GPX has no place for the reference of <ele> or the datum but a
+        // description
+        let mut desc = match altitude {
+            Altitude::Msl => "ele is the altitude above mean sea level".to_owned(),
+            Altitude::Ellipsoidal => "ele is the height above the WGS84 ellipsoid".to_owned(),
+        };
+        let mut datums: Vec<&str> = waypoints
+            .iter()
+            .filter_map(|wp| wp.datum.as_deref())
+            .filter(|&code| code != "W84")
+            .map(datum::datum_name)
+            .collect();
+        datums.sort_unstable();
+        datums.dedup();
+        if !datums.is_empty() {
+            desc.push_str(&format!(
+                "; positions are in the {} datum, not WGS84",
+                datums.join(" and ")
+            ));
+        }
+        writeln!(
+            file,
+            "  <metadata><desc>{}</desc></metadata>",
+            xml_escape(&desc)
+        )?;
+        for wp in waypoints {
+            writeln!(
+                file,
+                r#"  <wpt lat="{:.8}" lon="{:.8}">"#,
+                wp.latitude, wp.longitude
+            )?;
+            if let Some(alt) = wp.altitude_as(altitude) {
+                writeln!(file, "    <ele>{:.2}</ele>", alt)?;
+            }
+            let time = wp.local.with_timezone(&Utc);
+            writeln!(
+                file,
+                "    <time>{}</time>",
+                time.to_rfc3339_opts(SecondsFormat::Secs, true)
+            )?;
+            if let Some(separation) = wp.geoid_separation {
+                writeln!(file, "    <geoidheight>{:.2}</geoidheight>", separation)?;
+            }
+            writeln!(file, "    <name>{}</name>", xml_escape(&wp.name))?;
+            writeln!(file, "  </wpt>")?;
+        }
+        writeln!(file, "</gpx>")?;
+    } else {
+        writeln!(
+            file,
+            "seq,time,latitude,longitude,{},datum,name",
+            altitude.csv_column()
+        )?;
+        for wp in waypoints {
+            writeln!(
+                file,
+                "{},{},{:.8},{:.8},{},{},\"{}\"",
+                wp.seq,
+                wp.local.to_rfc3339_opts(SecondsFormat::Secs, true),
+                wp.latitude,
+                wp.longitude,
+                csv_field(wp.altitude_as(altitude)),
+                csv_field(wp.datum.as_deref().map(datum::datum_name)),
+                wp.name.replace('"', "\"\""),
+            )?;
+        }
+    }
+    file.flush()
+}
@@ -0,0 +1,104 @@
+//! Which sentences reach the parser. Filtered ones are still shown, so a
+//! device with bogus data can be kept from stomping on a good receiver
+//! without going unnoticed.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::sentence::Sentence;
+
+#[derive(Debug, Clone, Default)]
+pub struct SentenceFilter {
+    /// talkers whose sentences are parsed, all of them when empty
+    talkers: BTreeSet<String>,
+    /// sentence types which are never parsed, like `GSV` or `PUBX00`
+    ignored: BTreeSet<String>,
+    /// sentences held back so far, by type
+    counts: BTreeMap<String, u64>,
+}
+
+fn normalize(names: &[String]) -> BTreeSet<String> {
+    names
+        .iter()
+        .map(|name| name.trim().to_ascii_uppercase())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+impl SentenceFilter {
+    pub fn new(talkers: &[String], ignored: &[String]) -> SentenceFilter {
+        SentenceFilter {
+            talkers: normalize(talkers),
+            ignored: normalize(ignored),
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// Whether `sentence` gets parsed. Proprietary sentences have no talker,
+    /// so only their type can hold them back.
+    pub fn accepts(&self, sentence: &Sentence) -> bool {
+        let talker_ok = sentence.talker == "P"
+            || self.talkers.is_empty()
+            || self.talkers.contains(sentence.talker);
+        talker_ok && !self.ignored.contains(sentence.name())
+    }
+
+    /// Like `accepts`, counting the sentences held back
+    pub fn check(&mut self, sentence: &Sentence) -> bool {
+        let accepted = self.accepts(sentence);
+        if !accepted {
+            *self.counts.entry(sentence.name().to_owned()).or_insert(0) += 1;
+        }
+        accepted
+    }
+
+    pub fn is_ignored(&self, kind: &str) -> bool {
+        self.ignored.contains(kind)
+    }
+
+    /// Stops or resumes parsing sentences of type `kind`; true when they are
+    /// ignored from now on
+    pub fn toggle(&mut self, kind: &str) -> bool {
+        if self.ignored.remove(kind) {
+            false
+        } else {
+            self.ignored.insert(kind.to_owned());
+            true
+        }
+    }
+
+    /// Sentences held back so far, by type
+    pub fn counts(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.counts
+            .iter()
+            .map(|(kind, &count)| (kind.as_str(), count))
+    }
+
+    /// Starts counting again, keeping what is filtered
+    pub fn reset_counts(&mut self) {
+        self.counts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn talkers_and_types_are_filtered() {
+        let mut filter = SentenceFilter::new(&["gn".to_owned(), " GP".to_owned()], &[]);
+        let gp_rmc = "$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68";
+        let hc_rmc = "$HCRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*74";
+        let pubx = "$PUBX,00,081350.00,4717.113210,N,00833.915187,E,546.589,G3,2.1,2.0,0.007,77.52,0.007,,0.92,1.19,0.77,9,0,0*5F";
+        assert!(filter.check(&Sentence::parse(gp_rmc).unwrap()));
+        assert!(!filter.check(&Sentence::parse(hc_rmc).unwrap()));
+        assert!(filter.check(&Sentence::parse(pubx).unwrap()));
+
+        assert!(filter.toggle("PUBX00"));
+        assert!(!filter.check(&Sentence::parse(pubx).unwrap()));
+        assert!(!filter.toggle("PUBX00"));
+        assert!(filter.check(&Sentence::parse(pubx).unwrap()));
+
+        let counts: Vec<(&str, u64)> = filter.counts().collect();
+        assert_eq!(counts, vec![("PUBX00", 1), ("RMC", 1)]);
+    }
+}
@@ -0,0 +1,147 @@
+//! `--format` templates: one line of plain text per fix, for scripts
+
+use std::fmt::Write;
+
+use nmea::Nmea;
+
+use crate::{app::gps_datetime, metrics::fix_quality};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Lat,
+    Lon,
+    Alt,
+    Hdop,
+    Vdop,
+    Pdop,
+    Speed,
+    Course,
+    Sats,
+    Fix,
+    Time,
+    Date,
+    Datetime,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Field> {
+        Some(match name {
+            "lat" => Field::Lat,
+            "lon" => Field::Lon,
+            "alt" => Field::Alt,
+            "hdop" => Field::Hdop,
+            "vdop" => Field::Vdop,
+            "pdop" => Field::Pdop,
+            "speed" => Field::Speed,
+            "course" => Field::Course,
+            "sats" => Field::Sats,
+            "fix" => Field::Fix,
+            "time" => Field::Time,
+            "date" => Field::Date,
+            "datetime" => Field::Datetime,
+            _ => return None,
+        })
+    }
+
+    fn value(self, nmea: &Nmea) -> Option<String> {
+        let value = match self {
+            Field::Lat => nmea.latitude?.to_string(),
+            Field::Lon => nmea.longitude?.to_string(),
+            Field::Alt => nmea.altitude?.to_string(),
+            Field::Hdop => nmea.hdop?.to_string(),
+            Field::Vdop => nmea.vdop?.to_string(),
+            Field::Pdop => nmea.pdop?.to_string(),
+            Field::Speed => nmea.speed_over_ground?.to_string(),
+            Field::Course => nmea.true_course?.to_string(),
+            Field::Sats => nmea.num_of_fix_satellites?.to_string(),
+            Field::Fix => fix_quality(nmea.fix_type).to_string(),
+            Field::Time => nmea.fix_time?.to_string(),
+            Field::Date => gps_datetime(nmea)?.date().to_string(),
+            Field::Datetime => gps_datetime(nmea)?
+                .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+                .to_string(),
+        };
+        Some(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(String),
+    Field(Field),
+}
+
+/// A template like `{lat},{lon},{alt}`. Placeholders name fields of the last
+/// fix and are left empty while a field is unknown; anything in braces which
+/// isn't a field name is kept as it is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusFormat {
+    tokens: Vec<Token>,
+}
+
+impl StatusFormat {
+    pub fn render(&self, nmea: &Nmea) -> String {
+        let mut out = String::new();
+        for token in &self.tokens {
+            match token {
+                Token::Literal(s) => out.push_str(s),
+                Token::Field(field) => {
+                    if let Some(value) = field.value(nmea) {
+                        write!(out, "{}", value).ok();
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+impl std::str::FromStr for StatusFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<StatusFormat, String> {
+        let mut tokens = Vec::new();
+        let mut literal = String::new();
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            literal.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let field = rest
+                .find('}')
+                .and_then(|end| Some((Field::from_name(&rest[1..end])?, end)));
+            match field {
+                Some((field, end)) => {
+                    if !literal.is_empty() {
+                        tokens.push(Token::Literal(std::mem::take(&mut literal)));
+                    }
+                    tokens.push(Token::Field(field));
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    literal.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(literal));
+        }
+        Ok(StatusFormat { tokens })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_placeholders_stay_literal() {
+        let mut nmea = Nmea::new();
+        nmea.latitude = Some(37.5);
+        nmea.hdop = Some(0.9);
+
+        let format: StatusFormat = "{lat},{lon},{hdop} {nope} {".parse().unwrap();
+        assert_eq!(format.render(&nmea), "37.5,,0.9 {nope} {");
+    }
+}
@@ -0,0 +1,141 @@
+//! Circular zones from the config file, with events when the fix enters or
+//! leaves one
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::app::distance_bearing;
+
+/// A `[[zones]]` entry of the config file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Zone {
+    pub name: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// meters
+    pub radius: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZoneEvent {
+    Entered(String),
+    Left(String),
+}
+
+#[derive(Debug, Clone)]
+struct ZoneState {
+    zone: Zone,
+    /// since when the fix has been inside
+    entered: Option<DateTime<Local>>,
+    /// time spent inside during earlier visits
+    time_inside: chrono::Duration,
+}
+
+/// Which zones the fix is in. A fix has to get a few meters past the boundary
+/// before it counts as crossed, so jitter on the edge doesn't flip it back
+/// and forth.
+#[derive(Debug, Clone, Default)]
+pub struct Geofence {
+    zones: Vec<ZoneState>,
+}
+
+impl Geofence {
+    /// Meters past the boundary which make a crossing
+    const MARGIN: f64 = 5.0;
+
+    pub fn new(zones: Vec<Zone>) -> Geofence {
+        let zones = zones
+            .into_iter()
+            .map(|zone| ZoneState {
+                zone,
+                entered: None,
+                time_inside: chrono::Duration::zero(),
+            })
+            .collect();
+        Geofence { zones }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+
+    /// Takes a new fix and tells which zones were entered or left
+    pub fn update(&mut self, local: DateTime<Local>, lat: f64, lon: f64) -> Vec<ZoneEvent> {
+        let mut events = Vec::new();
+        for state in &mut self.zones {
+            let zone = &state.zone;
+            let (distance, _) = distance_bearing((zone.lat, zone.lon), (lat, lon));
+            // small zones would never be entered with the full margin
+            let margin = Geofence::MARGIN.min(zone.radius / 4.0);
+            match state.entered {
+                None if distance < zone.radius - margin => {
+                    state.entered = Some(local);
+                    events.push(ZoneEvent::Entered(zone.name.clone()));
+                }
+                Some(entered) if distance > zone.radius + margin => {
+                    state.time_inside = state.time_inside + (local - entered);
+                    state.entered = None;
+                    events.push(ZoneEvent::Left(zone.name.clone()));
+                }
+                _ => {}
+            }
+        }
+        events
+    }
+
+    /// Name, whether the fix is inside and the time spent inside up to `now`,
+    /// for each zone
+    pub fn summary(&self, now: DateTime<Local>) -> Vec<(&str, bool, chrono::Duration)> {
+        self.zones
+            .iter()
+            .map(|state| {
+                let current = state
+                    .entered
+                    .map_or_else(chrono::Duration::zero, |entered| now - entered);
+                (
+                    state.zone.name.as_str(),
+                    state.entered.is_some(),
+                    state.time_inside + current,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jitter_on_the_edge_makes_one_event_each_way() {
+        let mut fence = Geofence::new(vec![Zone {
+            name: "home".to_owned(),
+            lat: 0.0,
+            lon: 0.0,
+            radius: 100.0,
+        }]);
+        // one meter of latitude is about 1 / 111_195 degrees
+        let meters = |m: f64| m / 111_195.0;
+        let start = Local::now();
+        let at = |secs: i64| start + chrono::Duration::seconds(secs);
+
+        assert_eq!(
+            fence.update(at(0), meters(50.0), 0.0),
+            vec![ZoneEvent::Entered("home".to_owned())]
+        );
+        for (i, m) in [98.0, 102.0, 99.0, 103.0].iter().enumerate() {
+            assert!(fence.update(at(i as i64 + 1), meters(*m), 0.0).is_empty());
+        }
+        assert_eq!(
+            fence.update(at(10), meters(110.0), 0.0),
+            vec![ZoneEvent::Left("home".to_owned())]
+        );
+        assert!(fence.update(at(20), meters(101.0), 0.0).is_empty());
+
+        let summary = fence.summary(at(30));
+        assert_eq!(
+            summary,
+            vec![("home", false, chrono::Duration::seconds(10))]
+        );
+    }
+}
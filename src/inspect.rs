@@ -0,0 +1,463 @@
+//! Sentences broken into named fields for the inspector, whatever their
+//! checksum says
+
+use crate::{datum, sentence::checksum};
+
+/// How a field reads
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Meaning {
+    /// `hhmmss.ss`
+    Time,
+    /// `ddmmyy`
+    Date,
+    /// `ddmm.mmmm` or `dddmm.mmmm`
+    Coord,
+    Hemisphere,
+    /// a number followed by its unit
+    Number(&'static str),
+    /// a letter or number standing for the text given
+    Code(&'static [(&'static str, &'static str)]),
+    /// shown as it is
+    Plain,
+}
+
+const QUALITY: &[(&str, &str)] = &[
+    ("0", "invalid"),
+    ("1", "GPS fix"),
+    ("2", "DGPS fix"),
+    ("3", "PPS fix"),
+    ("4", "RTK fixed"),
+    ("5", "RTK float"),
+    ("6", "estimated"),
+    ("7", "manual input"),
+    ("8", "simulation"),
+];
+const STATUS: &[(&str, &str)] = &[("A", "valid"), ("V", "void")];
+const FAA_MODE: &[(&str, &str)] = &[
+    ("A", "autonomous"),
+    ("D", "differential"),
+    ("E", "estimated"),
+    ("F", "RTK float"),
+    ("M", "manual input"),
+    ("N", "not valid"),
+    ("P", "precise"),
+    ("R", "RTK fixed"),
+    ("S", "simulator"),
+];
+const SELECTION: &[(&str, &str)] = &[("M", "manual"), ("A", "automatic")];
+const FIX_TYPE: &[(&str, &str)] = &[("1", "no fix"), ("2", "2D"), ("3", "3D")];
+const METERS: &[(&str, &str)] = &[("M", "meters")];
+const TRUE: &[(&str, &str)] = &[("T", "true")];
+const MAGNETIC: &[(&str, &str)] = &[("M", "magnetic")];
+const KNOTS: &[(&str, &str)] = &[("N", "knots")];
+const KMH: &[(&str, &str)] = &[("K", "km/h")];
+
+const GGA: &[(&str, Meaning)] = &[
+    ("time", Meaning::Time),
+    ("latitude", Meaning::Coord),
+    ("N/S", Meaning::Hemisphere),
+    ("longitude", Meaning::Coord),
+    ("E/W", Meaning::Hemisphere),
+    ("quality", Meaning::Code(QUALITY)),
+    ("satellites", Meaning::Number("")),
+    ("HDOP", Meaning::Number("")),
+    ("altitude", Meaning::Number(" m")),
+    ("altitude unit", Meaning::Code(METERS)),
+    ("geoid separation", Meaning::Number(" m")),
+    ("separation unit", Meaning::Code(METERS)),
+    ("DGPS age", Meaning::Number(" s")),
+    ("DGPS station", Meaning::Plain),
+];
+const RMC: &[(&str, Meaning)] = &[
+    ("time", Meaning::Time),
+    ("status", Meaning::Code(STATUS)),
+    ("latitude", Meaning::Coord),
+    ("N/S", Meaning::Hemisphere),
+    ("longitude", Meaning::Coord),
+    ("E/W", Meaning::Hemisphere),
+    ("speed", Meaning::Number(" kn")),
+    ("course", Meaning::Number("°")),
+    ("date", Meaning::Date),
+    ("magnetic variation", Meaning::Number("°")),
+    ("variation E/W", Meaning::Hemisphere),
+    ("mode", Meaning::Code(FAA_MODE)),
+    ("navigational status", Meaning::Plain),
+];
+const GSA: &[(&str, Meaning)] = &[
+    ("selection", Meaning::Code(SELECTION)),
+    ("fix type", Meaning::Code(FIX_TYPE)),
+    ("satellite 1", Meaning::Plain),
+    ("satellite 2", Meaning::Plain),
+    ("satellite 3", Meaning::Plain),
+    ("satellite 4", Meaning::Plain),
+    ("satellite 5", Meaning::Plain),
+    ("satellite 6", Meaning::Plain),
+    ("satellite 7", Meaning::Plain),
+    ("satellite 8", Meaning::Plain),
+    ("satellite 9", Meaning::Plain),
+    ("satellite 10", Meaning::Plain),
+    ("satellite 11", Meaning::Plain),
+    ("satellite 12", Meaning::Plain),
+    ("PDOP", Meaning::Number("")),
+    ("HDOP", Meaning::Number("")),
+    ("VDOP", Meaning::Number("")),
+    ("system", Meaning::Plain),
+];
+const VTG: &[(&str, Meaning)] = &[
+    ("course", Meaning::Number("°")),
+    ("reference", Meaning::Code(TRUE)),
+    ("magnetic course", Meaning::Number("°")),
+    ("reference", Meaning::Code(MAGNETIC)),
+    ("speed", Meaning::Number(" kn")),
+    ("unit", Meaning::Code(KNOTS)),
+    ("speed", Meaning::Number(" km/h")),
+    ("unit", Meaning::Code(KMH)),
+    ("mode", Meaning::Code(FAA_MODE)),
+];
+const GLL: &[(&str, Meaning)] = &[
+    ("latitude", Meaning::Coord),
+    ("N/S", Meaning::Hemisphere),
+    ("longitude", Meaning::Coord),
+    ("E/W", Meaning::Hemisphere),
+    ("time", Meaning::Time),
+    ("status", Meaning::Code(STATUS)),
+    ("mode", Meaning::Code(FAA_MODE)),
+];
+const ZDA: &[(&str, Meaning)] = &[
+    ("time", Meaning::Time),
+    ("day", Meaning::Plain),
+    ("month", Meaning::Plain),
+    ("year", Meaning::Plain),
+    ("zone hours", Meaning::Number(" h")),
+    ("zone minutes", Meaning::Number(" min")),
+];
+const DTM: &[(&str, Meaning)] = &[
+    ("local datum", Meaning::Code(datum::NAMES)),
+    ("subdivision", Meaning::Plain),
+    ("latitude offset", Meaning::Number("'")),
+    ("N/S", Meaning::Hemisphere),
+    ("longitude offset", Meaning::Number("'")),
+    ("E/W", Meaning::Hemisphere),
+    ("altitude offset", Meaning::Number(" m")),
+    ("reference datum", Meaning::Code(datum::NAMES)),
+];
+const GSV_HEAD: &[(&str, Meaning)] = &[
+    ("messages", Meaning::Plain),
+    ("message", Meaning::Plain),
+    ("satellites in view", Meaning::Plain),
+];
+const GSV_SATELLITE: &[(&str, Meaning)] = &[
+    ("PRN", Meaning::Plain),
+    ("elevation", Meaning::Number("°")),
+    ("azimuth", Meaning::Number("°")),
+    ("SNR", Meaning::Number(" dBHz")),
+];
+
+/// Name and meaning of field `i` of `kind` with `count` fields, or `None`
+/// for a sentence type without a table
+fn field_meaning(kind: &str, i: usize, count: usize) -> Option<(String, Meaning)> {
+    let table = match kind {
+        "GGA" => GGA,
+        "RMC" => RMC,
+        "GSA" => GSA,
+        "VTG" => VTG,
+        "GLL" => GLL,
+        "ZDA" => ZDA,
+        "DTM" => DTM,
+        // the header, then four fields per satellite and maybe a signal ID
+        "GSV" if i < GSV_HEAD.len() => GSV_HEAD,
+        "GSV" => {
+            let j = i - GSV_HEAD.len();
+            if i + 1 == count && (count - GSV_HEAD.len()) % 4 == 1 {
+                return Some(("signal".to_owned(), Meaning::Plain));
+            }
+            let (name, meaning) = GSV_SATELLITE[j % 4];
+            return Some((format!("{} {}", name, j / 4 + 1), meaning));
+        }
+        _ => return None,
+    };
+    let (name, meaning) = table.get(i).copied().unwrap_or(("extra", Meaning::Plain));
+    Some((name.to_owned(), meaning))
+}
+
+/// What `raw` says, or why it can't be read
+fn interpret(raw: &str, meaning: Meaning) -> Option<String> {
+    if raw.is_empty() {
+        return None;
+    }
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let value = match meaning {
+        Meaning::Time => {
+            let hms = raw.split('.').next().unwrap_or_default();
+            if hms.len() != 6 || !digits(hms) {
+                return Some("invalid time".to_owned());
+            }
+            let frac = &raw[hms.len()..];
+            format!("{}:{}:{}{} UTC", &hms[..2], &hms[2..4], &hms[4..], frac)
+        }
+        Meaning::Date => {
+            if raw.len() != 6 || !digits(raw) {
+                return Some("invalid date".to_owned());
+            }
+            format!("20{}-{}-{}", &raw[4..], &raw[2..4], &raw[..2])
+        }
+        Meaning::Coord => match raw.parse::<f64>() {
+            Ok(value) => {
+                let degrees = (value / 100.0).trunc();
+                format!("{:.6}°", degrees + (value - degrees * 100.0) / 60.0)
+            }
+            Err(_) => "invalid coordinate".to_owned(),
+        },
+        Meaning::Hemisphere => match raw {
+            "N" => "north",
+            "S" => "south",
+            "E" => "east",
+            "W" => "west",
+            _ => "invalid hemisphere",
+        }
+        .to_owned(),
+        Meaning::Number(unit) => match raw.parse::<f64>() {
+            Ok(_) if unit.is_empty() => return None,
+            Ok(_) => format!("{}{}", raw, unit),
+            Err(_) => "not a number".to_owned(),
+        },
+        Meaning::Code(codes) => codes
+            .iter()
+            .find(|&&(code, _)| code == raw)
+            .map_or("unknown", |&(_, text)| text)
+            .to_owned(),
+        Meaning::Plain => return None,
+    };
+    Some(value)
+}
+
+/// A field of a sentence
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    /// like `quality`, or `field 3` for an unknown sentence type
+    pub name: String,
+    pub raw: String,
+    /// what the raw value says, when it says more than itself
+    pub value: Option<String>,
+}
+
+/// Whether the checksum after `*` matches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Checksum {
+    Valid(u8),
+    Invalid {
+        given: u8,
+        computed: u8,
+    },
+    /// no `*hh` at the end, or not hex
+    Missing {
+        computed: u8,
+    },
+}
+
+impl Checksum {
+    pub fn is_valid(&self) -> bool {
+        matches!(self, Checksum::Valid(_))
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Checksum::Valid(sum) => format!("checksum {:02X} is valid", sum),
+            Checksum::Invalid { given, computed } => format!(
+                "checksum {:02X} doesn't match, the sentence adds up to {:02X}",
+                given, computed
+            ),
+            Checksum::Missing { computed } => {
+                format!("no checksum, the sentence adds up to {:02X}", computed)
+            }
+        }
+    }
+}
+
+/// A sentence broken into fields
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inspection {
+    /// like `GP`, or `P` for proprietary sentences
+    pub talker: String,
+    /// like `GGA`
+    pub kind: String,
+    /// the fields have names
+    pub known: bool,
+    pub fields: Vec<Field>,
+    pub checksum: Checksum,
+}
+
+/// Breaks `line` into fields, named for the common sentence types and
+/// numbered for the others; `None` unless it starts like a sentence
+pub fn inspect(line: &str) -> Option<Inspection> {
+    let line = line.trim();
+    let body = line.strip_prefix('$').or_else(|| line.strip_prefix('!'))?;
+    let (body, checksum) = match body.rsplit_once('*') {
+        Some((body, given)) => {
+            let computed = self::checksum(body);
+            let checksum = match u8::from_str_radix(given, 16) {
+                Ok(given) if given == computed => Checksum::Valid(given),
+                Ok(given) => Checksum::Invalid { given, computed },
+                Err(_) => Checksum::Missing { computed },
+            };
+            (body, checksum)
+        }
+        None => (
+            body,
+            Checksum::Missing {
+                computed: self::checksum(body),
+            },
+        ),
+    };
+    let mut parts = body.split(',');
+    let address = parts.next()?;
+    let (talker, kind) = if address.starts_with('P') {
+        address.split_at(1)
+    } else {
+        // a talker of two characters, which may not be ASCII in a garbled line
+        let talker = address
+            .char_indices()
+            .nth(2)
+            .map_or(address.len(), |(i, _)| i);
+        address.split_at(talker)
+    };
+    let raws: Vec<&str> = parts.collect();
+    let known = field_meaning(kind, 0, raws.len()).is_some();
+    let fields = raws
+        .iter()
+        .enumerate()
+        .map(|(i, &raw)| {
+            let (name, meaning) = field_meaning(kind, i, raws.len())
+                .unwrap_or_else(|| (format!("field {}", i + 1), Meaning::Plain));
+            Field {
+                name,
+                raw: raw.to_owned(),
+                value: interpret(raw, meaning),
+            }
+        })
+        .collect();
+    Some(Inspection {
+        talker: talker.to_owned(),
+        kind: kind.to_owned(),
+        known,
+        fields,
+        checksum,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(inspection: &Inspection) -> Vec<(&str, &str, Option<&str>)> {
+        inspection
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.raw.as_str(), f.value.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn gga_fields_are_named_and_read() {
+        let gga = inspect(
+            "$GPGGA,060437.00,3733.93059,N,12654.85088,E,4,04,1.22,46.3,M,18.3,M,1.0,0042*43",
+        )
+        .unwrap();
+        assert_eq!((gga.talker.as_str(), gga.kind.as_str()), ("GP", "GGA"));
+        assert!(gga.known);
+        assert_eq!(
+            fields(&gga),
+            vec![
+                ("time", "060437.00", Some("06:04:37.00 UTC")),
+                ("latitude", "3733.93059", Some("37.565510°")),
+                ("N/S", "N", Some("north")),
+                ("longitude", "12654.85088", Some("126.914181°")),
+                ("E/W", "E", Some("east")),
+                ("quality", "4", Some("RTK fixed")),
+                ("satellites", "04", None),
+                ("HDOP", "1.22", None),
+                ("altitude", "46.3", Some("46.3 m")),
+                ("altitude unit", "M", Some("meters")),
+                ("geoid separation", "18.3", Some("18.3 m")),
+                ("separation unit", "M", Some("meters")),
+                ("DGPS age", "1.0", Some("1.0 s")),
+                ("DGPS station", "0042", None),
+            ]
+        );
+        assert_eq!(gga.checksum, Checksum::Valid(0x43));
+    }
+
+    #[test]
+    fn gsv_repeats_satellite_fields() {
+        let gsv = inspect("$GPGSV,3,1,11,03,03,111,00,04,15,270,,1*00").unwrap();
+        let names: Vec<&str> = gsv.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "messages",
+                "message",
+                "satellites in view",
+                "PRN 1",
+                "elevation 1",
+                "azimuth 1",
+                "SNR 1",
+                "PRN 2",
+                "elevation 2",
+                "azimuth 2",
+                "SNR 2",
+                "signal",
+            ]
+        );
+        assert_eq!(gsv.fields[5].value.as_deref(), Some("111°"));
+        assert_eq!(gsv.fields[10].value, None);
+        assert!(!gsv.checksum.is_valid());
+        assert!(gsv
+            .checksum
+            .describe()
+            .starts_with("checksum 00 doesn't match"));
+    }
+
+    #[test]
+    fn datum_offsets_are_named() {
+        let dtm = inspect("$GPDTM,999,,0.08,N,0.07,E,-47.7,W84*1B").unwrap();
+        assert!(dtm.known);
+        assert_eq!(
+            fields(&dtm)[..4],
+            [
+                ("local datum", "999", Some("user defined")),
+                ("subdivision", "", None),
+                ("latitude offset", "0.08", Some("0.08'")),
+                ("N/S", "N", Some("north")),
+            ]
+        );
+        assert_eq!(dtm.fields[6].value.as_deref(), Some("-47.7 m"));
+        assert_eq!(dtm.fields[7].value.as_deref(), Some("WGS84"));
+    }
+
+    #[test]
+    fn unknown_sentences_are_numbered() {
+        let pubx = inspect("$PUBX,00,060437.00,abc").unwrap();
+        assert_eq!((pubx.talker.as_str(), pubx.kind.as_str()), ("P", "UBX"));
+        assert!(!pubx.known);
+        assert_eq!(
+            fields(&pubx),
+            vec![
+                ("field 1", "00", None),
+                ("field 2", "060437.00", None),
+                ("field 3", "abc", None),
+            ]
+        );
+        assert!(matches!(pubx.checksum, Checksum::Missing { .. }));
+
+        let rmc = inspect("$GNRMC,0604,X,,,,,,,321").unwrap();
+        assert_eq!(rmc.fields[0].value.as_deref(), Some("invalid time"));
+        assert_eq!(rmc.fields[1].value.as_deref(), Some("unknown"));
+        assert_eq!(rmc.fields[2].value, None);
+        assert_eq!(rmc.fields[8].value.as_deref(), Some("invalid date"));
+        assert_eq!(inspect("UBX NAV-PVT: 3D fix"), None);
+
+        // garbled, with the talker cut in the middle of a character
+        let garbled = inspect("$Gé,1*00").unwrap();
+        assert_eq!((garbled.talker.as_str(), garbled.kind.as_str()), ("Gé", ""));
+    }
+}
@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Error};
+
+/// Key press, independent of the terminal backend. Enter and Tab are
+/// `Char('\n')` and `Char('\t')` as in termion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Backspace,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Delete,
+    Insert,
+    F(u8),
+    Char(char),
+    Alt(char),
+    Ctrl(char),
+    Esc,
+}
+
+/// Mouse event, independent of the terminal backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mouse {
+    pub kind: MouseKind,
+    /// cell under the pointer, counted from 0 at the top left
+    pub column: u16,
+    pub row: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseKind {
+    /// left button pressed
+    Click,
+    WheelUp,
+    WheelDown,
+}
+
+/// Everything a key press can do outside of the waypoint name prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Help,
+    ExportSatellites,
+    ToggleAverage,
+    CycleUnits,
+    CycleCoordFormat,
+    ToggleTrack,
+    MarkWaypoint,
+    ExportWaypoints,
+    FreezeFrame,
+    ScrollTextUp,
+    ScrollTextDown,
+    ToggleHex,
+    QuickWaypoint,
+    CycleTarget,
+    ToggleRejected,
+    NextRoutePoint,
+    PrevRoutePoint,
+    SelectSentence,
+    ToggleSentenceFilter,
+    Command,
+    PmtkMenu,
+    SelectNewerMessage,
+    SelectOlderMessage,
+    InspectMessage,
+    Search,
+    NextMatch,
+    PrevMatch,
+    Copy,
+    NextPanel,
+    Reopen,
+    HidePanel,
+    ShowPanel,
+    Zen,
+    ClearMessages,
+    ResetState,
+}
+
+impl Action {
+    /// Help overlay order
+    pub const ALL: [Action; 36] = [
+        Action::Quit,
+        Action::Help,
+        Action::ExportSatellites,
+        Action::ToggleAverage,
+        Action::CycleUnits,
+        Action::CycleCoordFormat,
+        Action::ToggleTrack,
+        Action::MarkWaypoint,
+        Action::ExportWaypoints,
+        Action::FreezeFrame,
+        Action::ScrollTextUp,
+        Action::ScrollTextDown,
+        Action::ToggleHex,
+        Action::QuickWaypoint,
+        Action::CycleTarget,
+        Action::ToggleRejected,
+        Action::NextRoutePoint,
+        Action::PrevRoutePoint,
+        Action::SelectSentence,
+        Action::ToggleSentenceFilter,
+        Action::Command,
+        Action::PmtkMenu,
+        Action::SelectNewerMessage,
+        Action::SelectOlderMessage,
+        Action::InspectMessage,
+        Action::Search,
+        Action::NextMatch,
+        Action::PrevMatch,
+        Action::Copy,
+        Action::NextPanel,
+        Action::Reopen,
+        Action::HidePanel,
+        Action::ShowPanel,
+        Action::Zen,
+        Action::ClearMessages,
+        Action::ResetState,
+    ];
+
+    /// Key of the action in the `[keys]` config table
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::ExportSatellites => "export_satellites",
+            Action::ToggleAverage => "toggle_average",
+            Action::CycleUnits => "cycle_units",
+            Action::CycleCoordFormat => "cycle_coord_format",
+            Action::ToggleTrack => "toggle_track",
+            Action::MarkWaypoint => "mark_waypoint",
+            Action::ExportWaypoints => "export_waypoints",
+            Action::FreezeFrame => "freeze_frame",
+            Action::ScrollTextUp => "scroll_text_up",
+            Action::ScrollTextDown => "scroll_text_down",
+            Action::ToggleHex => "toggle_hex",
+            Action::QuickWaypoint => "quick_waypoint",
+            Action::CycleTarget => "cycle_target",
+            Action::ToggleRejected => "toggle_rejected",
+            Action::NextRoutePoint => "next_route_point",
+            Action::PrevRoutePoint => "prev_route_point",
+            Action::SelectSentence => "select_sentence",
+            Action::ToggleSentenceFilter => "toggle_sentence_filter",
+            Action::Command => "command",
+            Action::PmtkMenu => "pmtk_menu",
+            Action::SelectNewerMessage => "select_newer_message",
+            Action::SelectOlderMessage => "select_older_message",
+            Action::InspectMessage => "inspect_message",
+            Action::Search => "search",
+            Action::NextMatch => "next_match",
+            Action::PrevMatch => "prev_match",
+            Action::Copy => "copy",
+            Action::NextPanel => "next_panel",
+            Action::Reopen => "reopen",
+            Action::HidePanel => "hide_panel",
+            Action::ShowPanel => "show_panel",
+            Action::Zen => "zen",
+            Action::ClearMessages => "clear_messages",
+            Action::ResetState => "reset_state",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q"],
+            Action::Help => &["?"],
+            Action::ExportSatellites => &["E"],
+            Action::ToggleAverage => &["a"],
+            Action::CycleUnits => &["u"],
+            Action::CycleCoordFormat => &["c"],
+            Action::ToggleTrack => &["m"],
+            Action::MarkWaypoint => &["w"],
+            Action::ExportWaypoints => &["W"],
+            Action::FreezeFrame => &["f"],
+            Action::ScrollTextUp => &["pageup"],
+            Action::ScrollTextDown => &["pagedown"],
+            Action::ToggleHex => &["x"],
+            Action::QuickWaypoint => &["s"],
+            Action::CycleTarget => &["t"],
+            Action::ToggleRejected => &["X"],
+            Action::NextRoutePoint => &["]"],
+            Action::PrevRoutePoint => &["["],
+            Action::SelectSentence => &["r"],
+            Action::ToggleSentenceFilter => &["i"],
+            Action::Command => &[":"],
+            Action::PmtkMenu => &["p"],
+            Action::SelectNewerMessage => &["up"],
+            Action::SelectOlderMessage => &["down"],
+            Action::InspectMessage => &["enter"],
+            Action::Search => &["/"],
+            Action::NextMatch => &["n"],
+            Action::PrevMatch => &["N"],
+            Action::Copy => &["y"],
+            Action::NextPanel => &["tab"],
+            Action::Reopen => &["R"],
+            Action::HidePanel => &["h"],
+            Action::ShowPanel => &["H"],
+            Action::Zen => &["z"],
+            Action::ClearMessages => &["C"],
+            // X shows the rejected lines
+            Action::ResetState => &["ctrl-x"],
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "show/hide this help",
+            Action::ExportSatellites => "export satellite table",
+            Action::ToggleAverage => "start/stop averaging",
+            Action::CycleUnits => "cycle units",
+            Action::CycleCoordFormat => "cycle coordinate format",
+            Action::ToggleTrack => "show/hide track map",
+            Action::MarkWaypoint => "mark waypoint",
+            Action::ExportWaypoints => "export waypoints",
+            Action::FreezeFrame => "write a freeze frame",
+            Action::ScrollTextUp => "scroll receiver text, or Messages once clicked, up",
+            Action::ScrollTextDown => "scroll receiver text, or Messages once clicked, down",
+            Action::ToggleHex => "show/hide raw bytes in hex",
+            Action::QuickWaypoint => "save a waypoint without naming it",
+            Action::CycleTarget => "cycle navigation target",
+            Action::ToggleRejected => "show rejected lines",
+            Action::NextRoutePoint => "skip to the next route point",
+            Action::PrevRoutePoint => "back to the previous route point",
+            Action::SelectSentence => "select a sentence type in the rates panel",
+            Action::ToggleSentenceFilter => "stop/resume parsing the selected sentence type",
+            Action::Command => "send a command to the receiver",
+            Action::PmtkMenu => "MediaTek command menu",
+            Action::SelectNewerMessage => "select the message above",
+            Action::SelectOlderMessage => "select the message below",
+            Action::InspectMessage => "break the selected message into fields",
+            Action::Search => "search the messages",
+            Action::NextMatch => "select the next older match",
+            Action::PrevMatch => "select the next newer match",
+            Action::Copy => "copy the position, or the selected message",
+            Action::NextPanel => "focus the next panel, shown alone when small",
+            Action::Reopen => "reopen the source once it ended",
+            Action::HidePanel => "hide the focused panel",
+            Action::ShowPanel => "show the panel hidden last again",
+            Action::Zen => "speed and course in big digits, or back",
+            Action::ClearMessages => "clear the Messages pane",
+            Action::ResetState => "forget all receiver state, after confirming",
+        }
+    }
+}
+
+/// Parses key names like `q`, `ctrl-c`, `alt-x`, `F5`, `esc` or `pagedown`
+pub fn parse_key(name: &str) -> Result<Key, Error> {
+    let char_of = |s: &str| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    if let Some(c) = char_of(name) {
+        return Ok(Key::Char(c));
+    }
+
+    let lower = name.to_ascii_lowercase();
+    if let Some(c) = lower.strip_prefix("ctrl-").and_then(char_of) {
+        return Ok(Key::Ctrl(c));
+    }
+    if let Some(c) = name
+        .get(4..)
+        .filter(|_| lower.starts_with("alt-"))
+        .and_then(char_of)
+    {
+        return Ok(Key::Alt(c));
+    }
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+        if (1..=12).contains(&n) {
+            return Ok(Key::F(n));
+        }
+    }
+
+    let key = match lower.as_str() {
+        "space" => Key::Char(' '),
+        "enter" => Key::Char('\n'),
+        "tab" => Key::Char('\t'),
+        "esc" => Key::Esc,
+        "backspace" => Key::Backspace,
+        "delete" => Key::Delete,
+        "insert" => Key::Insert,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        _ => return Err(anyhow!("unknown key '{}'", name)),
+    };
+    Ok(key)
+}
+
+/// Inverse of `parse_key`, for the help overlay
+pub fn key_name(key: Key) -> String {
+    match key {
+        Key::Char(' ') => "space".to_owned(),
+        Key::Char('\n') => "enter".to_owned(),
+        Key::Char('\t') => "tab".to_owned(),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("ctrl-{}", c),
+        Key::Alt(c) => format!("alt-{}", c),
+        Key::F(n) => format!("F{}", n),
+        Key::Esc => "esc".to_owned(),
+        Key::Backspace => "backspace".to_owned(),
+        Key::Delete => "delete".to_owned(),
+        Key::Insert => "insert".to_owned(),
+        Key::Home => "home".to_owned(),
+        Key::End => "end".to_owned(),
+        Key::PageUp => "pageup".to_owned(),
+        Key::PageDown => "pagedown".to_owned(),
+        Key::Up => "up".to_owned(),
+        Key::Down => "down".to_owned(),
+        Key::Left => "left".to_owned(),
+        Key::Right => "right".to_owned(),
+    }
+}
+
+/// Effective key bindings: the defaults, with the actions listed in the
+/// `[keys]` config table replaced
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        KeyMap::new(&HashMap::new()).expect("default key bindings are valid")
+    }
+}
+
+impl KeyMap {
+    pub fn new(overrides: &HashMap<String, Vec<String>>) -> Result<KeyMap, Error> {
+        if let Some(name) = overrides
+            .keys()
+            .find(|name| !Action::ALL.iter().any(|a| a.name() == name.as_str()))
+        {
+            return Err(anyhow!("unknown action '{}' in [keys]", name));
+        }
+
+        let mut bindings: Vec<(Key, Action)> = Vec::new();
+        for &action in Action::ALL.iter() {
+            let names: Vec<&str> = match overrides.get(action.name()) {
+                Some(names) => names.iter().map(String::as_str).collect(),
+                None => action.default_keys().to_vec(),
+            };
+            for name in names {
+                let key = parse_key(name)?;
+                if let Some((_, other)) = bindings.iter().find(|(k, _)| *k == key) {
+                    return Err(anyhow!(
+                        "key '{}' is bound to both {} and {}",
+                        name,
+                        other.name(),
+                        action.name()
+                    ));
+                }
+                bindings.push((key, action));
+            }
+        }
+        Ok(KeyMap { bindings })
+    }
+
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|&(_, action)| action)
+    }
+
+    pub fn keys(&self, action: Action) -> Vec<Key> {
+        self.bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|&(key, _)| key)
+            .collect()
+    }
+}
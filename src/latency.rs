@@ -0,0 +1,108 @@
+//! Gaps between the arrivals of consecutive lines. A bridge which buffers,
+//! like a USB serial adapter with its latency timer, delivers lines in
+//! batches: most gaps are then close to zero and the rest pile up at
+//! multiples of the timer.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Gaps kept for the histogram and the percentiles
+pub const CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone, Default)]
+pub struct LineGaps {
+    last: Option<Instant>,
+    /// latest gaps, oldest first
+    gaps: VecDeque<Duration>,
+}
+
+impl LineGaps {
+    /// Arrival of a line, as taken by the reader thread
+    pub fn push(&mut self, arrival: Instant) {
+        if let Some(last) = self.last.replace(arrival) {
+            if self.gaps.len() == CAPACITY {
+                self.gaps.pop_front();
+            }
+            self.gaps.push_back(arrival.saturating_duration_since(last));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.gaps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.gaps.is_empty()
+    }
+
+    /// The gap `p` percent of the gaps are as short as or shorter than
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        let mut sorted: Vec<Duration> = self.gaps.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+        sorted.get(rank.clamp(1, sorted.len().max(1)) - 1).copied()
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.gaps.iter().max().copied()
+    }
+
+    /// Gaps counted in `buckets` buckets of `width` each, from zero up; the
+    /// last bucket takes the longer gaps too
+    pub fn histogram(&self, width: Duration, buckets: usize) -> Vec<u64> {
+        let mut counts = vec![0; buckets];
+        if buckets == 0 || width.is_zero() {
+            return counts;
+        }
+        for gap in &self.gaps {
+            let bucket = (gap.as_nanos() / width.as_nanos()) as usize;
+            counts[bucket.min(buckets - 1)] += 1;
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batched_lines_pile_up_at_the_timer() {
+        let mut gaps = LineGaps::default();
+        let start = Instant::now();
+        // eight lines every 64 ms, a millisecond apart
+        for batch in 0..10u32 {
+            for line in 0..8u32 {
+                gaps.push(
+                    start + Duration::from_millis(64) * batch + Duration::from_millis(line as u64),
+                );
+            }
+        }
+        assert_eq!(gaps.len(), 79);
+
+        assert_eq!(gaps.percentile(50.0), Some(Duration::from_millis(1)));
+        assert_eq!(gaps.percentile(95.0), Some(Duration::from_millis(57)));
+        assert_eq!(gaps.max(), Some(Duration::from_millis(57)));
+
+        let histogram = gaps.histogram(Duration::from_millis(8), 4);
+        assert_eq!(histogram, vec![70, 0, 0, 9]);
+    }
+
+    #[test]
+    fn only_the_latest_gaps_are_kept() {
+        let mut gaps = LineGaps::default();
+        assert_eq!(gaps.percentile(50.0), None);
+        let start = Instant::now();
+        for i in 0..=CAPACITY as u64 + 10 {
+            gaps.push(start + Duration::from_millis(i * 100));
+        }
+        assert_eq!(gaps.len(), CAPACITY);
+        assert_eq!(gaps.percentile(0.0), Some(Duration::from_millis(100)));
+        assert_eq!(
+            gaps.histogram(Duration::from_millis(50), 3),
+            vec![0, 0, 1000]
+        );
+    }
+}
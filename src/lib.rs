@@ -0,0 +1,44 @@
+//! State and rendering of nmeacli, the TUI-based NMEA data stream visualizer.
+//!
+//! `App` is fed lines from a receiver, key presses and ticks; `ui::draw`
+//! renders it into any tui backend. Reading the receiver and the terminal is
+//! left to the binary.
+
+pub mod ais;
+pub mod app;
+pub mod autopilot;
+pub mod bigdigits;
+pub mod check;
+pub mod clipboard;
+pub mod datum;
+pub mod epoch;
+pub mod export;
+pub mod filter;
+pub mod format;
+pub mod geofence;
+pub mod inspect;
+pub mod keys;
+pub mod latency;
+pub mod logfile;
+pub mod magnetic;
+pub mod metrics;
+pub mod ntrip;
+pub mod output;
+pub mod pmtk;
+pub mod prefs;
+pub mod provision;
+pub mod route;
+pub mod search;
+pub mod sentence;
+pub mod simulate;
+pub mod sky;
+pub mod source;
+pub mod theme;
+pub mod ttff;
+pub mod ubx;
+pub mod ubxcfg;
+pub mod ui;
+pub mod vessel;
+
+pub use app::{App, Options};
+pub use keys::Key;
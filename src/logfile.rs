@@ -0,0 +1,94 @@
+//! Diagnostics written to a file through the `log` facade, since stderr
+//! can't be seen behind the TUI and would garble it
+
+use std::{
+    fs,
+    io::{self, LineWriter, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use log::{LevelFilter, Log, Metadata, Record};
+
+struct FileLogger {
+    level: LevelFilter,
+    /// a line at a time, so what led up to a crash is on disk
+    file: Mutex<LineWriter<fs::File>>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Ok(mut file) = self.file.lock() {
+            // nowhere else to tell about a failed write
+            writeln!(file, "{}", format_record(Local::now(), record)).ok();
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            file.flush().ok();
+        }
+    }
+}
+
+/// `2020-06-07T15:04:33.120+09:00 DEBUG nmeacli::source: message`
+fn format_record(time: DateTime<Local>, record: &Record) -> String {
+    format!(
+        "{} {:<5} {}: {}",
+        time.to_rfc3339_opts(SecondsFormat::Millis, false),
+        record.level(),
+        record.target(),
+        record.args()
+    )
+}
+
+/// Appends what is logged at `level` or above to `path`. Once only, as the
+/// logger is global.
+pub fn init(path: &Path, level: LevelFilter) -> io::Result<()> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let logger = FileLogger {
+        level,
+        file: Mutex::new(LineWriter::new(file)),
+    };
+    log::set_boxed_logger(Box::new(logger))
+        .map_err(|e| io::Error::new(io::ErrorKind::AlreadyExists, e.to_string()))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use log::Level;
+
+    use super::*;
+
+    #[test]
+    fn records_carry_time_level_and_target() {
+        let time = Local.timestamp_millis(1_591_509_873_120);
+        let line = format_record(
+            time,
+            &Record::builder()
+                .level(Level::Info)
+                .target("nmeacli::source")
+                .args(format_args!("reconnected to {}", "localhost:2947"))
+                .build(),
+        );
+        let expected = format!(
+            "{} INFO  nmeacli::source: reconnected to localhost:2947",
+            time.to_rfc3339_opts(SecondsFormat::Millis, false)
+        );
+        assert_eq!(line, expected);
+    }
+}
@@ -0,0 +1,184 @@
+//! Magnetic variation, from RMC or, built with the `magnetic-model` feature,
+//! from a low-order model of the earth's field when the receiver leaves it
+//! out. Variation is east positive throughout: a compass pointing 6° west of
+//! true north is -6°, and turns true courses into magnetic ones by adding 6°.
+
+use crate::sentence::Sentence;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Variation {
+    /// degrees, east positive
+    pub degrees: f64,
+    /// computed here instead of told by the receiver
+    pub modeled: bool,
+}
+
+impl Variation {
+    /// Magnetic course for a `true_course`, in 0..360
+    pub fn magnetic(self, true_course: f64) -> f64 {
+        (true_course - self.degrees).rem_euclid(360.0)
+    }
+}
+
+/// Variation in RMC fields 9 and 10, as in `6.3,W`
+pub fn rmc_variation(rmc: &Sentence) -> Option<f64> {
+    let degrees: f64 = rmc.parse_field(9)?;
+    match rmc.field(10)? {
+        "E" => Some(degrees),
+        "W" => Some(-degrees),
+        _ => None,
+    }
+}
+
+/// `6.3°W`, or `6.3°W (modeled)`
+pub fn variation_str(variation: Variation) -> String {
+    let sense = if variation.degrees < 0.0 { 'W' } else { 'E' };
+    let modeled = if variation.modeled { " (modeled)" } else { "" };
+    format!("{:.1}°{}{}", variation.degrees.abs(), sense, modeled)
+}
+
+/// Schmidt semi-normalized Gauss coefficients of IGRF-13 at 2020.0 in nT,
+/// and their change per year, as (n, m, g, h, g', h')
+#[cfg(feature = "magnetic-model")]
+const COEFFS: &[(usize, usize, f64, f64, f64, f64)] = &[
+    (1, 0, -29404.8, 0.0, 5.7, 0.0),
+    (1, 1, -1450.9, 4652.5, 7.4, -25.9),
+    (2, 0, -2499.6, 0.0, -11.0, 0.0),
+    (2, 1, 2982.0, -2991.6, -7.0, -30.2),
+    (2, 2, 1677.0, -734.6, -2.1, -22.4),
+    (3, 0, 1363.2, 0.0, 2.2, 0.0),
+    (3, 1, -2381.2, -82.1, -5.9, 6.0),
+    (3, 2, 1236.2, 241.9, 3.1, -1.1),
+    (3, 3, 525.7, -543.4, -12.0, 0.5),
+    (4, 0, 903.0, 0.0, -1.2, 0.0),
+    (4, 1, 809.5, 281.9, -1.6, -0.1),
+    (4, 2, 86.3, -158.4, -5.9, 6.5),
+    (4, 3, -309.4, 199.7, 5.2, 3.6),
+    (4, 4, 48.0, -349.7, -5.1, -5.0),
+];
+#[cfg(feature = "magnetic-model")]
+const DEGREE: usize = 4;
+#[cfg(feature = "magnetic-model")]
+const EPOCH: f64 = 2020.0;
+
+/// Variation at a position and decimal `year`, from the field up to degree
+/// 4 on a spherical earth: within a few degrees away from the poles, and
+/// none close to them
+#[cfg(feature = "magnetic-model")]
+pub fn model_variation(latitude: f64, longitude: f64, year: f64) -> Option<Variation> {
+    let theta = (90.0 - latitude).to_radians();
+    let (sin, cos) = theta.sin_cos();
+    if sin.abs() < 1e-6 {
+        return None;
+    }
+
+    // associated Legendre functions of cos(theta) and their derivatives by
+    // theta, indexed [n][m]
+    let mut p = [[0.0; DEGREE + 1]; DEGREE + 1];
+    let mut dp = [[0.0; DEGREE + 1]; DEGREE + 1];
+    p[0][0] = 1.0;
+    for m in 1..=DEGREE {
+        let k = if m == 1 {
+            1.0
+        } else {
+            ((2 * m - 1) as f64 / (2 * m) as f64).sqrt()
+        };
+        p[m][m] = k * sin * p[m - 1][m - 1];
+        dp[m][m] = k * (cos * p[m - 1][m - 1] + sin * dp[m - 1][m - 1]);
+    }
+    for m in 0..DEGREE {
+        for n in m + 1..=DEGREE {
+            let a = (2 * n - 1) as f64;
+            let b = (((n - 1) * (n - 1) - m * m) as f64).sqrt();
+            let c = ((n * n - m * m) as f64).sqrt();
+            let (p2, dp2) = if n >= 2 {
+                (p[n - 2][m], dp[n - 2][m])
+            } else {
+                (0.0, 0.0)
+            };
+            p[n][m] = (a * cos * p[n - 1][m] - b * p2) / c;
+            dp[n][m] = (a * (cos * dp[n - 1][m] - sin * p[n - 1][m]) - b * dp2) / c;
+        }
+    }
+
+    let years = year - EPOCH;
+    let lon = longitude.to_radians();
+    let (mut north, mut east) = (0.0, 0.0);
+    for &(n, m, g, h, dg, dh) in COEFFS {
+        let (g, h) = (g + dg * years, h + dh * years);
+        let (sin_m, cos_m) = (m as f64 * lon).sin_cos();
+        north += (g * cos_m + h * sin_m) * dp[n][m];
+        east += m as f64 * (g * sin_m - h * cos_m) * p[n][m] / sin;
+    }
+    Some(Variation {
+        degrees: east.atan2(north).to_degrees(),
+        modeled: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variation(degrees: f64) -> Variation {
+        Variation {
+            degrees,
+            modeled: false,
+        }
+    }
+
+    #[test]
+    fn west_is_negative_and_east_positive() {
+        let rmc =
+            Sentence::parse("$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68")
+                .unwrap();
+        assert_eq!(rmc_variation(&rmc), Some(20.3));
+        let rmc =
+            Sentence::parse("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A")
+                .unwrap();
+        assert_eq!(rmc_variation(&rmc), Some(-3.1));
+        let rmc =
+            Sentence::parse("$GPRMC,060433.00,A,3732.93104,N,12654.85121,E,0.212,,070620,,,A*71")
+                .unwrap();
+        assert_eq!(rmc_variation(&rmc), None);
+
+        assert_eq!(variation_str(variation(20.3)), "20.3°E");
+        assert_eq!(variation_str(variation(-3.1)), "3.1°W");
+    }
+
+    #[test]
+    fn east_is_least_and_west_is_best() {
+        // true to magnetic: subtract easterly variation, add westerly
+        assert!((variation(20.3).magnetic(54.7) - 34.4).abs() < 1e-9);
+        assert!((variation(-3.1).magnetic(84.4) - 87.5).abs() < 1e-9);
+        // and around north both ways
+        assert!((variation(10.0).magnetic(5.0) - 355.0).abs() < 1e-9);
+        assert!((variation(-10.0).magnetic(355.0) - 5.0).abs() < 1e-9);
+        assert_eq!(variation(0.0).magnetic(360.0), 0.0);
+    }
+
+    #[cfg(feature = "magnetic-model")]
+    #[test]
+    fn model_gets_the_sense_and_rough_size() {
+        // charted variation around 2025, east positive
+        for &(lat, lon, charted) in &[
+            (37.55, 126.99, -9.0),
+            (47.6, -122.3, 15.0),
+            (40.7, -74.0, -12.6),
+            (-33.9, 151.2, 12.8),
+            (-33.9, 18.4, -25.5),
+        ] {
+            let modeled = model_variation(lat, lon, 2025.5).unwrap();
+            assert!(modeled.modeled);
+            assert!(
+                (modeled.degrees - charted).abs() < 4.0,
+                "{} {}: {} instead of {}",
+                lat,
+                lon,
+                modeled.degrees,
+                charted
+            );
+        }
+        assert_eq!(model_variation(90.0, 0.0, 2025.5), None);
+    }
+}
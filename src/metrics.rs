@@ -0,0 +1,168 @@
+//! Prometheus metrics served over HTTP, for monitoring a base station
+//! without watching the TUI
+
+use std::{
+    fmt::Write,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use anyhow::{anyhow, Error};
+use nmea::FixType;
+use tiny_http::{Header, Response, Server};
+
+use crate::App;
+
+/// What `/metrics` reports, copied from the app by the main loop
+#[derive(Debug, Clone, Default)]
+pub struct MetricValues {
+    pub hdop: Option<f32>,
+    pub vdop: Option<f32>,
+    pub pdop: Option<f32>,
+    pub satellites_total: usize,
+    pub satellites_used: usize,
+    /// GGA fix quality, 0 without a fix
+    pub fix_quality: u8,
+    pub sentences_parsed: u64,
+    pub sentences_failed: u64,
+}
+
+/// GGA fix quality, 0 without a fix
+pub(crate) fn fix_quality(fix_type: Option<FixType>) -> u8 {
+    match fix_type {
+        None | Some(FixType::Invalid) => 0,
+        Some(FixType::Gps) => 1,
+        Some(FixType::DGps) => 2,
+        Some(FixType::Pps) => 3,
+        Some(FixType::Rtk) => 4,
+        Some(FixType::FloatRtk) => 5,
+        Some(FixType::Estimated) => 6,
+        Some(FixType::Manual) => 7,
+        Some(FixType::Simulation) => 8,
+    }
+}
+
+impl MetricValues {
+    pub fn from_app(app: &App) -> MetricValues {
+        MetricValues {
+            hdop: app.nmea.hdop,
+            vdop: app.nmea.vdop,
+            pdop: app.nmea.pdop,
+            satellites_total: app.sky.len(),
+            satellites_used: app.sky.used_count(),
+            fix_quality: fix_quality(app.nmea.fix_type),
+            sentences_parsed: app.sentences_parsed,
+            sentences_failed: app.sentences_failed,
+        }
+    }
+
+    /// Prometheus text format; DOPs the receiver hasn't reported are left out
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: Option<String>| {
+            if let Some(value) = value {
+                writeln!(out, "# HELP nmeacli_{} {}", name, help).ok();
+                writeln!(out, "# TYPE nmeacli_{} {}", name, kind).ok();
+                writeln!(out, "nmeacli_{} {}", name, value).ok();
+            }
+        };
+        let dop = |v: Option<f32>| v.map(|v| v.to_string());
+
+        metric(
+            "hdop",
+            "gauge",
+            "Horizontal dilution of precision",
+            dop(self.hdop),
+        );
+        metric(
+            "vdop",
+            "gauge",
+            "Vertical dilution of precision",
+            dop(self.vdop),
+        );
+        metric(
+            "pdop",
+            "gauge",
+            "Position dilution of precision",
+            dop(self.pdop),
+        );
+        metric(
+            "satellites_total",
+            "gauge",
+            "Satellites in view",
+            Some(self.satellites_total.to_string()),
+        );
+        metric(
+            "satellites_used",
+            "gauge",
+            "Satellites used in the fix",
+            Some(self.satellites_used.to_string()),
+        );
+        metric(
+            "fix_quality",
+            "gauge",
+            "GGA fix quality, 0 without a fix",
+            Some(self.fix_quality.to_string()),
+        );
+        metric(
+            "sentences_parsed_total",
+            "counter",
+            "Lines accepted as sentences",
+            Some(self.sentences_parsed.to_string()),
+        );
+        metric(
+            "sentences_failed_total",
+            "counter",
+            "Lines rejected",
+            Some(self.sentences_failed.to_string()),
+        );
+        out
+    }
+}
+
+/// Serves `values` on `http://addr/metrics` from a thread of its own
+pub fn serve(addr: &str, values: Arc<Mutex<MetricValues>>) -> Result<(), Error> {
+    let server = Server::http(addr).map_err(|e| anyhow!("failed to listen on {}: {}", addr, e))?;
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = if request.url() == "/metrics" {
+                let body = match values.lock() {
+                    Ok(values) => values.render(),
+                    Err(_) => break,
+                };
+                let content_type =
+                    Header::from_bytes(&b"Content-Type"[..], &b"text/plain; version=0.0.4"[..])
+                        .expect("valid header");
+                Response::from_string(body).with_header(content_type)
+            } else {
+                Response::from_string("not found\n").with_status_code(404)
+            };
+            request.respond(response).ok();
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_dops_are_left_out() {
+        let values = MetricValues {
+            hdop: Some(0.9),
+            satellites_total: 12,
+            satellites_used: 8,
+            fix_quality: 4,
+            sentences_parsed: 100,
+            sentences_failed: 2,
+            ..MetricValues::default()
+        };
+        let text = values.render();
+        assert!(text.contains("nmeacli_hdop 0.9\n"));
+        assert!(!text.contains("nmeacli_vdop"));
+        assert!(text.contains("# TYPE nmeacli_sentences_failed_total counter\n"));
+        assert!(text.contains("nmeacli_satellites_used 8\n"));
+        assert!(text.contains("nmeacli_fix_quality 4\n"));
+    }
+}
@@ -0,0 +1,471 @@
+//! NTRIP client: RTCM3 corrections from a caster, forwarded to the receiver
+//! for an RTK fix, with the receiver's GGA sent back for VRS mountpoints
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    io::{self, BufRead, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+
+use crate::source::DeviceWriter;
+
+/// Where corrections come from: `user:pass@caster:2101/MOUNTPOINT`, the
+/// credentials and the port being optional
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mountpoint {
+    /// `user:pass`, sent with Basic authentication
+    pub credentials: Option<String>,
+    pub host: String,
+    pub port: u16,
+    pub mount: String,
+}
+
+impl Mountpoint {
+    pub const DEFAULT_PORT: u16 = 2101;
+
+    /// The NTRIP 1.0 request for the stream of the mountpoint
+    pub fn request(&self) -> String {
+        let mut request = format!(
+            "GET /{} HTTP/1.0\r\nUser-Agent: NTRIP nmeacli/{}\r\nAccept: */*\r\n",
+            self.mount,
+            env!("CARGO_PKG_VERSION"),
+        );
+        if let Some(credentials) = &self.credentials {
+            request += &format!(
+                "Authorization: Basic {}\r\n",
+                base64(credentials.as_bytes())
+            );
+        }
+        request + "Connection: close\r\n\r\n"
+    }
+}
+
+impl std::str::FromStr for Mountpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Mountpoint, String> {
+        let s = s.strip_prefix("ntrip://").unwrap_or(s);
+        // passwords may hold '@', host names can't
+        let (credentials, rest) = match s.rsplit_once('@') {
+            Some((credentials, rest)) => (Some(credentials), rest),
+            None => (None, s),
+        };
+        if credentials.is_some_and(|c| !c.contains(':')) {
+            return Err(format!("expected user:pass before '@', got {:?}", s));
+        }
+        let (addr, mount) = match rest.split_once('/') {
+            Some((addr, mount)) if !mount.is_empty() => (addr, mount),
+            _ => {
+                return Err(format!(
+                    "the mountpoint is missing, like caster:2101/MOUNTPOINT in {:?}",
+                    s
+                ))
+            }
+        };
+        let (host, port) = match addr.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port
+                    .parse()
+                    .map_err(|_| format!("invalid port {:?}", port))?;
+                (host, port)
+            }
+            None => (addr, Mountpoint::DEFAULT_PORT),
+        };
+        if host.is_empty() {
+            return Err(format!("the caster is missing in {:?}", s));
+        }
+        Ok(Mountpoint {
+            credentials: credentials.map(str::to_owned),
+            host: host.to_owned(),
+            port,
+            mount: mount.to_owned(),
+        })
+    }
+}
+
+/// Without the credentials, so it can be shown
+impl fmt::Display for Mountpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}/{}", self.host, self.port, self.mount)
+    }
+}
+
+pub(crate) fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Reads the caster's reply up to the data: `ICY 200 OK` from NTRIP 1.0
+/// casters, or an HTTP status line and headers
+pub fn read_response(reader: &mut impl BufRead) -> Result<(), String> {
+    let mut status = String::new();
+    reader
+        .read_line(&mut status)
+        .map_err(|e| format!("no reply from the caster: {}", e))?;
+    let status = status.trim_end();
+    if status.is_empty() {
+        return Err("the caster closed the connection without a reply".to_owned());
+    }
+    if status.starts_with("SOURCETABLE") {
+        return Err("the caster has no such mountpoint and sent its source table".to_owned());
+    }
+    let code = status.split_whitespace().nth(1).unwrap_or_default();
+    if status.starts_with("ICY ") && code == "200" {
+        return Ok(());
+    }
+    if !status.starts_with("HTTP/") {
+        return Err(format!("unexpected reply from the caster: {:?}", status));
+    }
+    match code {
+        "200" => {}
+        "401" => return Err(format!("the caster refused the credentials: {}", status)),
+        _ => return Err(format!("the caster refused the request: {}", status)),
+    }
+    loop {
+        let mut header = String::new();
+        match reader.read_line(&mut header) {
+            Ok(0) => return Err("the caster closed the connection".to_owned()),
+            Ok(_) if header.trim_end().is_empty() => return Ok(()),
+            Ok(_) if header.to_ascii_lowercase().contains("chunked") => {
+                return Err("the caster sent a chunked NTRIP 2.0 stream".to_owned())
+            }
+            Ok(_) => {}
+            Err(e) => return Err(format!("reading the caster's reply: {}", e)),
+        }
+    }
+}
+
+/// Finds RTCM3 frames in the byte stream, counting them: a 0xD3 preamble,
+/// six reserved bits and a ten bit length, the message and a 24 bit CRC
+#[derive(Debug, Clone, Default)]
+pub struct RtcmFramer {
+    header: Vec<u8>,
+    /// bytes of the frame in progress still to come
+    remaining: usize,
+    /// frames seen whole
+    pub frames: u64,
+}
+
+impl RtcmFramer {
+    const PREAMBLE: u8 = 0xd3;
+
+    pub fn push(&mut self, data: &[u8]) {
+        for &b in data {
+            if self.remaining > 0 {
+                self.remaining -= 1;
+                if self.remaining == 0 {
+                    self.frames += 1;
+                }
+                continue;
+            }
+            match self.header.len() {
+                0 if b == RtcmFramer::PREAMBLE => self.header.push(b),
+                // not within a frame
+                0 => {}
+                1 if b & 0xfc != 0 => self.header.clear(),
+                1 => self.header.push(b),
+                _ => {
+                    let length = ((self.header[1] & 0x03) as usize) << 8 | b as usize;
+                    self.header.clear();
+                    self.remaining = length + 3;
+                }
+            }
+        }
+    }
+}
+
+/// What the client is up to, shared with the UI
+#[derive(Debug, Clone, Default)]
+pub struct Status {
+    /// the caster, without the credentials
+    pub caster: String,
+    pub connected: bool,
+    /// why the last connection failed, while waiting to connect again
+    pub error: Option<String>,
+    /// bytes received since launch
+    pub bytes: u64,
+    pub frames: u64,
+    pub last_data: Option<DateTime<Local>>,
+    /// latest GGA of the receiver, sent to the caster now and then
+    pub gga: Option<String>,
+    recent: VecDeque<(DateTime<Local>, usize)>,
+}
+
+impl Status {
+    /// Seconds the throughput is averaged over
+    const RATE_SECS: i64 = 5;
+
+    pub fn received(&mut self, now: DateTime<Local>, bytes: usize) {
+        self.bytes += bytes as u64;
+        self.last_data = Some(now);
+        self.recent.push_back((now, bytes));
+        let window = chrono::Duration::seconds(Status::RATE_SECS);
+        while self.recent.front().is_some_and(|&(t, _)| now - t > window) {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Bytes per second lately
+    pub fn rate(&self, now: DateTime<Local>) -> f64 {
+        let window = chrono::Duration::seconds(Status::RATE_SECS);
+        let bytes: usize = self
+            .recent
+            .iter()
+            .filter(|&&(t, _)| now - t <= window)
+            .map(|&(_, n)| n)
+            .sum();
+        bytes as f64 / Status::RATE_SECS as f64
+    }
+}
+
+/// Time allowed for reaching the caster
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Silence after which the caster is dialed again
+const DATA_TIMEOUT: Duration = Duration::from_secs(30);
+const GGA_INTERVAL: Duration = Duration::from_secs(10);
+const MIN_RETRY: Duration = Duration::from_secs(1);
+const MAX_RETRY: Duration = Duration::from_secs(60);
+
+/// Connects to the caster and forwards its corrections to `writer` in a
+/// thread of its own, dialing again with a growing delay when it fails
+pub fn spawn(mount: Mountpoint, mut writer: DeviceWriter) -> Arc<Mutex<Status>> {
+    let status = Arc::new(Mutex::new(Status {
+        caster: mount.to_string(),
+        ..Status::default()
+    }));
+    let shared = status.clone();
+    thread::spawn(move || {
+        let mut delay = MIN_RETRY;
+        loop {
+            let bytes = shared.lock().unwrap().bytes;
+            let result = session(&mount, &mut writer, &shared);
+            let mut status = shared.lock().unwrap();
+            status.connected = false;
+            // a connection which delivered starts the backoff over
+            if status.bytes > bytes {
+                delay = MIN_RETRY;
+            }
+            status.error = Some(match result {
+                Ok(()) => "the caster closed the connection".to_owned(),
+                Err(e) => e,
+            });
+            drop(status);
+            thread::sleep(delay);
+            delay = (delay * 2).min(MAX_RETRY);
+        }
+    });
+    status
+}
+
+fn connect(mount: &Mountpoint) -> io::Result<TcpStream> {
+    let mut last = io::Error::new(io::ErrorKind::NotFound, "no address for the caster");
+    for addr in (mount.host.as_str(), mount.port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last = e,
+        }
+    }
+    Err(last)
+}
+
+/// A read timeout, which Unix and Windows report differently
+fn timed_out(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+/// One connection to the caster, until it drops or goes quiet
+fn session(
+    mount: &Mountpoint,
+    writer: &mut DeviceWriter,
+    status: &Mutex<Status>,
+) -> Result<(), String> {
+    let stream = connect(mount).map_err(|e| format!("connecting to {}: {}", mount, e))?;
+    stream
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .map_err(|e| e.to_string())?;
+    let mut reader = io::BufReader::new(stream);
+    reader
+        .get_mut()
+        .write_all(mount.request().as_bytes())
+        .map_err(|e| format!("sending the request: {}", e))?;
+    read_response(&mut reader)?;
+    {
+        let mut status = status.lock().unwrap();
+        status.connected = true;
+        status.error = None;
+    }
+
+    let mut framer = RtcmFramer::default();
+    let mut last_data = Instant::now();
+    let mut last_gga: Option<Instant> = None;
+    let mut buf = [0; 4096];
+    loop {
+        if last_gga.is_none_or(|t| t.elapsed() >= GGA_INTERVAL) {
+            let gga = status.lock().unwrap().gga.clone();
+            if let Some(gga) = gga {
+                reader
+                    .get_mut()
+                    .write_all(format!("{}\r\n", gga).as_bytes())
+                    .map_err(|e| format!("sending GGA: {}", e))?;
+                last_gga = Some(Instant::now());
+            }
+        }
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e) if timed_out(&e) => {
+                if last_data.elapsed() >= DATA_TIMEOUT {
+                    return Err(format!("no data for {}s", DATA_TIMEOUT.as_secs()));
+                }
+                continue;
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+        last_data = Instant::now();
+        writer
+            .write_bytes(&buf[..n])
+            .map_err(|e| format!("writing to the receiver: {}", e))?;
+        let frames = framer.frames;
+        framer.push(&buf[..n]);
+        let mut status = status.lock().unwrap();
+        status.received(Local::now(), n);
+        status.frames += framer.frames - frames;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mountpoints_are_parsed() {
+        let mount: Mountpoint = "me:p@ss@rtk2go.com:2101/BASE".parse().unwrap();
+        assert_eq!(mount.credentials.as_deref(), Some("me:p@ss"));
+        assert_eq!((mount.host.as_str(), mount.port), ("rtk2go.com", 2101));
+        assert_eq!(mount.to_string(), "rtk2go.com:2101/BASE");
+
+        let mount: Mountpoint = "caster.example/VRS".parse().unwrap();
+        assert_eq!(mount.credentials, None);
+        assert_eq!(mount.port, Mountpoint::DEFAULT_PORT);
+
+        assert!("user:pass@caster:2101".parse::<Mountpoint>().is_err());
+        assert!("user@caster:2101/BASE".parse::<Mountpoint>().is_err());
+        assert!("caster:port/BASE".parse::<Mountpoint>().is_err());
+
+        let request = "user:pass@caster/BASE"
+            .parse::<Mountpoint>()
+            .unwrap()
+            .request();
+        assert!(request.starts_with("GET /BASE HTTP/1.0\r\n"));
+        assert!(request.contains("\r\nAuthorization: Basic dXNlcjpwYXNz\r\n"));
+        assert!(request.ends_with("\r\n\r\n"));
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"a"), "YQ==");
+    }
+
+    #[test]
+    fn replies_are_told_apart() {
+        let mut icy = io::Cursor::new(&b"ICY 200 OK\r\n\xd3\x00"[..]);
+        assert_eq!(read_response(&mut icy), Ok(()));
+        assert_eq!(icy.position(), 12);
+
+        let mut http = io::Cursor::new(&b"HTTP/1.1 200 OK\r\nServer: x\r\n\r\n\xd3"[..]);
+        assert_eq!(read_response(&mut http), Ok(()));
+        assert_eq!(http.position(), 30);
+
+        let mut table = io::Cursor::new(&b"SOURCETABLE 200 OK\r\n"[..]);
+        assert!(read_response(&mut table)
+            .unwrap_err()
+            .contains("mountpoint"));
+        let mut denied = io::Cursor::new(&b"HTTP/1.0 401 Unauthorized\r\n\r\n"[..]);
+        assert!(read_response(&mut denied)
+            .unwrap_err()
+            .contains("credentials"));
+    }
+
+    #[test]
+    fn rtcm_frames_are_counted_across_reads() {
+        // a 1005 message of 19 bytes, split over two reads, after some noise
+        let mut frame = vec![0xd3, 0x00, 0x13];
+        frame.extend([0x3e; 19]);
+        frame.extend([0x12, 0x34, 0x56]);
+        let mut framer = RtcmFramer::default();
+        framer.push(b"\r\n");
+        framer.push(&frame[..10]);
+        assert_eq!(framer.frames, 0);
+        framer.push(&frame[10..]);
+        framer.push(&frame[..2]);
+        assert_eq!(framer.frames, 1);
+    }
+
+    #[test]
+    fn corrections_reach_the_receiver_and_gga_the_caster() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let caster = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = io::BufReader::new(stream);
+            let mut request = String::new();
+            while !request.ends_with("\r\n\r\n") {
+                reader.read_line(&mut request).unwrap();
+            }
+            reader
+                .get_mut()
+                .write_all(b"ICY 200 OK\r\n\xd3\x00\x00\xaa\xbb\xcc")
+                .unwrap();
+            let mut gga = String::new();
+            reader.read_line(&mut gga).unwrap();
+            (request, gga)
+        });
+
+        let mount: Mountpoint = format!("127.0.0.1:{}/BASE", port).parse().unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut writer = DeviceWriter::new(Shared(received.clone()));
+        let status = Mutex::new(Status::default());
+        status.lock().unwrap().gga = Some("$GPGGA,1".to_owned());
+        assert_eq!(session(&mount, &mut writer, &status), Ok(()));
+
+        let (request, gga) = caster.join().unwrap();
+        assert!(request.starts_with("GET /BASE HTTP/1.0\r\n"));
+        assert_eq!(gga, "$GPGGA,1\r\n");
+        assert_eq!(*received.lock().unwrap(), b"\xd3\x00\x00\xaa\xbb\xcc");
+        let status = status.lock().unwrap();
+        assert_eq!((status.bytes, status.frames), (6, 1));
+        assert!(status.connected);
+    }
+
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
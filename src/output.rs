@@ -0,0 +1,72 @@
+//! Sentences sent out to other devices: a serial port or file, and clients
+//! of a TCP server
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+#[derive(Default)]
+pub struct Output {
+    device: Option<File>,
+    clients: Option<Arc<Mutex<Vec<TcpStream>>>>,
+}
+
+impl Output {
+    pub fn new() -> Output {
+        Output::default()
+    }
+
+    /// Writes to `path`, a serial port or a file, from now on
+    pub fn open_device(&mut self, path: &Path) -> io::Result<()> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(true)
+            .open(path)?;
+        self.device = Some(file);
+        Ok(())
+    }
+
+    /// Listens on `addr` and sends to each client which connects, until it
+    /// goes away
+    pub fn serve(&mut self, addr: &str) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        self.clients = Some(clients.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                stream.set_nodelay(true).ok();
+                clients.lock().unwrap().push(stream);
+            }
+        });
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.device.is_none() && self.clients.is_none()
+    }
+
+    /// Sends one sentence, adding the line ending. Clients which fail are
+    /// dropped, and so is the device, whose error is returned.
+    pub fn send(&mut self, sentence: &str) -> io::Result<()> {
+        let line = format!("{}\r\n", sentence);
+        if let Some(clients) = &self.clients {
+            clients
+                .lock()
+                .unwrap()
+                .retain_mut(|client| client.write_all(line.as_bytes()).is_ok());
+        }
+        if let Some(device) = &mut self.device {
+            if let Err(e) = device.write_all(line.as_bytes()) {
+                self.device = None;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+//! Canned PMTK commands for MediaTek receivers, and the PMTK001 replies
+//! telling whether they took
+
+use chrono::{DateTime, Local};
+
+use crate::sentence::Sentence;
+
+/// A command from the menu
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Preset {
+    pub label: &'static str,
+    /// sentence body, without `$` and checksum
+    pub body: &'static str,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        label: "update rate 1 Hz",
+        body: "PMTK220,1000",
+    },
+    Preset {
+        label: "update rate 5 Hz",
+        body: "PMTK220,200",
+    },
+    Preset {
+        label: "update rate 10 Hz",
+        body: "PMTK220,100",
+    },
+    Preset {
+        label: "output RMC and GGA only",
+        body: "PMTK314,0,1,0,1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0",
+    },
+    Preset {
+        label: "output RMC, GGA, GSA and GSV",
+        body: "PMTK314,0,1,0,1,1,5,0,0,0,0,0,0,0,0,0,0,0,0,0",
+    },
+    Preset {
+        label: "output the default sentences",
+        body: "PMTK314,-1",
+    },
+    Preset {
+        label: "enable SBAS",
+        body: "PMTK313,1",
+    },
+    Preset {
+        label: "disable SBAS",
+        body: "PMTK313,0",
+    },
+    Preset {
+        label: "hot restart",
+        body: "PMTK101",
+    },
+    Preset {
+        label: "warm restart",
+        body: "PMTK102",
+    },
+    Preset {
+        label: "cold restart",
+        body: "PMTK103",
+    },
+    Preset {
+        label: "full cold restart (factory defaults)",
+        body: "PMTK104",
+    },
+];
+
+/// Command number of a PMTK sentence body, like 220 for `PMTK220,100`.
+/// Restarts are left out: the receiver restarts instead of replying.
+pub fn acked_command(body: &str) -> Option<u16> {
+    let number: u16 = body.strip_prefix("PMTK")?.split(',').next()?.parse().ok()?;
+    Some(number).filter(|n| !(101..=104).contains(n))
+}
+
+/// A PMTK001 reply
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ack {
+    pub command: u16,
+    pub flag: u8,
+}
+
+impl Ack {
+    pub fn parse(sentence: &Sentence) -> Option<Ack> {
+        if (sentence.talker, sentence.kind) != ("P", "MTK001") {
+            return None;
+        }
+        Some(Ack {
+            command: sentence.parse_field(0)?,
+            flag: sentence.parse_field(1)?,
+        })
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.flag == 3
+    }
+
+    pub fn describe(&self) -> String {
+        let outcome = match self.flag {
+            0 => "invalid command",
+            1 => "unsupported command",
+            2 => "valid command, but it failed",
+            3 => "succeeded",
+            _ => "unknown reply",
+        };
+        format!("PMTK{:03}: {} (flag {})", self.command, outcome, self.flag)
+    }
+}
+
+/// Commands sent and not replied to yet
+#[derive(Debug, Clone, Default)]
+pub struct AckWatch {
+    pending: Vec<(u16, DateTime<Local>)>,
+}
+
+impl AckWatch {
+    /// Seconds to wait for a reply
+    pub const TIMEOUT_SECS: i64 = 3;
+
+    pub fn expect(&mut self, command: u16, sent: DateTime<Local>) {
+        self.pending.push((command, sent));
+    }
+
+    /// The reply to a pending command; replies to commands sent by others
+    /// are ignored
+    pub fn reply(&mut self, sentence: &Sentence) -> Option<Ack> {
+        let ack = Ack::parse(sentence)?;
+        let i = self
+            .pending
+            .iter()
+            .position(|&(command, _)| command == ack.command)?;
+        self.pending.remove(i);
+        Some(ack)
+    }
+
+    /// Commands given up on
+    pub fn expire(&mut self, now: DateTime<Local>) -> Vec<u16> {
+        let timeout = chrono::Duration::seconds(AckWatch::TIMEOUT_SECS);
+        let (expired, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|&(_, sent)| now - sent > timeout);
+        self.pending = pending;
+        expired.into_iter().map(|(command, _)| command).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replies_are_matched_to_commands() {
+        assert_eq!(acked_command("PMTK220,100"), Some(220));
+        assert_eq!(acked_command("PMTK314,-1"), Some(314));
+        assert_eq!(acked_command("PMTK103"), None);
+        assert_eq!(acked_command("PUBX,40,GLL,0,0,0,0"), None);
+
+        let mut watch = AckWatch::default();
+        let sent = Local::now();
+        watch.expect(220, sent);
+        watch.expect(313, sent);
+
+        let ack = watch
+            .reply(&Sentence::parse("$PMTK001,220,3*30").unwrap())
+            .unwrap();
+        assert!(ack.succeeded());
+        assert_eq!(ack.describe(), "PMTK220: succeeded (flag 3)");
+        // already replied to
+        assert!(watch
+            .reply(&Sentence::parse("$PMTK001,220,3*30").unwrap())
+            .is_none());
+
+        assert!(watch.expire(sent + chrono::Duration::seconds(1)).is_empty());
+        assert_eq!(watch.expire(sent + chrono::Duration::seconds(5)), vec![313]);
+    }
+}
@@ -0,0 +1,319 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use tui::layout::Constraint;
+
+use crate::app::Waypoint;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    Metric,
+    Imperial,
+    Nautical,
+}
+
+impl Units {
+    pub fn next(self) -> Units {
+        match self {
+            Units::Metric => Units::Imperial,
+            Units::Imperial => Units::Nautical,
+            Units::Nautical => Units::Metric,
+        }
+    }
+
+    pub fn altitude(self, meters: f32) -> String {
+        match self {
+            Units::Metric => format!("{:.1}m", meters),
+            Units::Imperial | Units::Nautical => format!("{:.1}ft", meters * 3.28084),
+        }
+    }
+
+    pub fn distance(self, meters: f64) -> String {
+        match self {
+            Units::Metric if meters < 1000.0 => format!("{:.0}m", meters),
+            Units::Metric => format!("{:.2}km", meters / 1000.0),
+            Units::Imperial if meters < 1609.344 => format!("{:.0}ft", meters * 3.28084),
+            Units::Imperial => format!("{:.2}mi", meters / 1609.344),
+            Units::Nautical => format!("{:.2}nm", meters / 1852.0),
+        }
+    }
+
+    pub fn speed(self, knots: f32) -> String {
+        let (speed, unit) = self.speed_in(knots);
+        format!("{:.1} {}", speed, unit)
+    }
+
+    /// Speed in these units, and their name
+    pub fn speed_in(self, knots: f32) -> (f32, &'static str) {
+        match self {
+            Units::Metric => (knots * 1.852, "km/h"),
+            Units::Imperial => (knots * 1.150_779, "mph"),
+            Units::Nautical => (knots, "kn"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoordFormat {
+    /// 37.548843
+    Decimal,
+    /// 37°32.93059'N
+    Dm,
+    /// 37°32'55.835"N
+    Dms,
+}
+
+impl CoordFormat {
+    pub fn next(self) -> CoordFormat {
+        match self {
+            CoordFormat::Decimal => CoordFormat::Dm,
+            CoordFormat::Dm => CoordFormat::Dms,
+            CoordFormat::Dms => CoordFormat::Decimal,
+        }
+    }
+
+    pub fn format(self, value: f64, pos: char, neg: char) -> String {
+        let hemisphere = if value < 0.0 { neg } else { pos };
+        let deg = value.abs().trunc();
+        let min = (value.abs() - deg) * 60.0;
+        match self {
+            CoordFormat::Decimal => format!("{:.6}", value),
+            CoordFormat::Dm => format!("{}°{:08.5}'{}", deg, min, hemisphere),
+            CoordFormat::Dms => {
+                let sec = (min - min.trunc()) * 60.0;
+                format!("{}°{:02}'{:06.3}\"{}", deg, min.trunc(), sec, hemisphere)
+            }
+        }
+    }
+
+    pub fn latlon(self, lat: f64, lon: f64) -> String {
+        format!(
+            "{} / {}",
+            self.format(lat, 'N', 'S'),
+            self.format(lon, 'E', 'W')
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Panel {
+    Status,
+    Sky,
+    Gsa,
+    Track,
+    Waypoints,
+    Rates,
+    /// gaps between the arrivals of lines
+    Latency,
+    Text,
+    /// two receivers side by side
+    Compare,
+    /// the active leg of the route
+    Navigation,
+    /// vessels heard over AIS
+    Ais,
+    /// depth, wind and the other instruments on a boat
+    Vessel,
+    Messages,
+}
+
+impl std::str::FromStr for Panel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Panel, String> {
+        match s.trim() {
+            "status" => Ok(Panel::Status),
+            "sky" | "satellites" => Ok(Panel::Sky),
+            "gsa" | "dop" => Ok(Panel::Gsa),
+            "track" | "map" => Ok(Panel::Track),
+            "waypoints" => Ok(Panel::Waypoints),
+            "rates" => Ok(Panel::Rates),
+            "latency" | "gaps" => Ok(Panel::Latency),
+            "text" | "txt" => Ok(Panel::Text),
+            "compare" => Ok(Panel::Compare),
+            "navigation" | "nav" => Ok(Panel::Navigation),
+            "ais" => Ok(Panel::Ais),
+            "vessel" | "boat" => Ok(Panel::Vessel),
+            "messages" => Ok(Panel::Messages),
+            other => Err(format!(
+                "unknown panel '{}', expected status, sky, gsa, track, waypoints, rates, latency, text, compare, navigation, ais, vessel or messages",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Panel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Panel::Status => "status",
+            Panel::Sky => "sky",
+            Panel::Gsa => "gsa",
+            Panel::Track => "track",
+            Panel::Waypoints => "waypoints",
+            Panel::Rates => "rates",
+            Panel::Latency => "latency",
+            Panel::Text => "text",
+            Panel::Compare => "compare",
+            Panel::Navigation => "navigation",
+            Panel::Ais => "ais",
+            Panel::Vessel => "vessel",
+            Panel::Messages => "messages",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Panels stacked top to bottom; panels joined with '+' share a row, e.g.
+/// `status,sky+track,messages`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PanelLayout(Vec<Vec<Panel>>);
+
+impl Default for PanelLayout {
+    fn default() -> PanelLayout {
+        "status,sky+track+waypoints,messages".parse().unwrap()
+    }
+}
+
+impl std::str::FromStr for PanelLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<PanelLayout, String> {
+        let rows = s
+            .split(',')
+            .map(|row| row.split('+').map(str::parse).collect())
+            .collect::<Result<Vec<Vec<Panel>>, String>>()?;
+        Ok(PanelLayout(rows))
+    }
+}
+
+impl std::convert::TryFrom<String> for PanelLayout {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<PanelLayout, String> {
+        s.parse()
+    }
+}
+
+impl From<PanelLayout> for String {
+    fn from(layout: PanelLayout) -> String {
+        let rows: Vec<String> = layout
+            .0
+            .iter()
+            .map(|row| {
+                let names: Vec<String> = row.iter().map(Panel::to_string).collect();
+                names.join("+")
+            })
+            .collect();
+        rows.join(",")
+    }
+}
+
+impl PanelLayout {
+    /// Rows with the panels `show` accepts, dropping rows left empty
+    pub fn visible(&self, show: impl Fn(Panel) -> bool) -> Vec<Vec<Panel>> {
+        self.0
+            .iter()
+            .map(|row| row.iter().copied().filter(|&p| show(p)).collect::<Vec<_>>())
+            .filter(|row| !row.is_empty())
+            .collect()
+    }
+
+    /// Heights of `rows` on a screen `height` rows high. When they don't all
+    /// fit, each row gets its share of the screen instead, so none is
+    /// squeezed out.
+    pub fn row_constraints(rows: &[Vec<Panel>], height: u16) -> Vec<Constraint> {
+        let mut constraints: Vec<Constraint> = rows
+            .iter()
+            .map(|row| {
+                if row.contains(&Panel::Status) {
+                    Constraint::Length(12)
+                } else if row.contains(&Panel::Compare) {
+                    Constraint::Length(7)
+                } else if row == &[Panel::Navigation] {
+                    Constraint::Length(6)
+                } else if row == &[Panel::Vessel] {
+                    Constraint::Length(9)
+                } else if row == &[Panel::Messages] {
+                    Constraint::Length(20)
+                } else {
+                    Constraint::Min(15)
+                }
+            })
+            .collect();
+        let wanted = |c: &Constraint| match *c {
+            Constraint::Length(n) | Constraint::Min(n) => u32::from(n),
+            _ => 0,
+        };
+        let total: u32 = constraints.iter().map(wanted).sum();
+        if total > u32::from(height) {
+            return constraints
+                .iter()
+                .map(|c| Constraint::Ratio(wanted(c), total))
+                .collect();
+        }
+        // without a flexible row the screen would not be filled
+        if !constraints.iter().any(|c| matches!(c, Constraint::Min(_))) {
+            if let Some(last) = constraints.last_mut() {
+                *last = Constraint::Min(0);
+            }
+        }
+        constraints
+    }
+}
+
+/// UI state which survives restarts, waypoints included, stored in the user's
+/// config dir
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Prefs {
+    pub units: Units,
+    pub coord_format: CoordFormat,
+    pub track_map: bool,
+    pub panels: PanelLayout,
+    /// waypoints marked in earlier sessions
+    pub waypoints: Vec<Waypoint>,
+}
+
+impl Default for Prefs {
+    fn default() -> Prefs {
+        Prefs {
+            units: Units::Metric,
+            coord_format: CoordFormat::Decimal,
+            track_map: true,
+            panels: PanelLayout::default(),
+            waypoints: Vec::new(),
+        }
+    }
+}
+
+impl Prefs {
+    fn path() -> Option<PathBuf> {
+        let dirs = directories::ProjectDirs::from("", "", "nmeacli")?;
+        Some(dirs.config_dir().join("prefs.toml"))
+    }
+
+    /// Missing or unreadable preferences silently fall back to the defaults
+    pub fn load() -> Prefs {
+        Prefs::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|s| toml::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let path = match Prefs::path() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
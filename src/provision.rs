@@ -0,0 +1,209 @@
+//! Configuration files for `ubloxcli apply`: a YAML list of steps, each a
+//! subcommand with its options, taken as if typed on the command line.
+//!
+//! ```yaml
+//! - baud: {port: uart1, set: 115200}
+//! - rate: {hz: 5}
+//! - msg: {set: [NAV-PVT=1, GSV=0]}
+//! - cfg set: {args: [CFG-RATE-MEAS=100], layer: [ram, flash]}
+//! - save
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+/// Options of the whole command line, which steps can't change
+const GLOBAL_OPTIONS: &[&str] = &["device", "baud", "out", "timeout", "retries"];
+
+/// A step of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    /// where the step is, like `line 4`, or `step 2` when the line can't be
+    /// told
+    pub place: String,
+    /// the subcommand and its options, like `rate --hz=5`
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Scalar {
+    Flag(bool),
+    Number(serde_yaml::Number),
+    Text(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Value {
+    One(Scalar),
+    List(Vec<Scalar>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawStep {
+    Bare(String),
+    Options(BTreeMap<String, Option<BTreeMap<String, Value>>>),
+}
+
+impl Scalar {
+    fn text(&self) -> String {
+        match self {
+            Scalar::Flag(flag) => flag.to_string(),
+            Scalar::Number(number) => number.to_string(),
+            Scalar::Text(text) => text.clone(),
+        }
+    }
+}
+
+/// Lines of the items of the top-level list
+fn step_lines(text: &str) -> Vec<usize> {
+    text.lines()
+        .enumerate()
+        .filter(|&(_, line)| line == "-" || line.starts_with("- "))
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+/// The command line of a step. Flags are given as `true`, lists repeat
+/// their option, and `args` holds the positional arguments.
+fn step_args(raw: RawStep) -> Result<Vec<String>, String> {
+    let (name, options) = match raw {
+        RawStep::Bare(name) => (name, None),
+        RawStep::Options(map) if map.len() == 1 => {
+            map.into_iter().next().expect("a map of one entry")
+        }
+        RawStep::Options(_) => {
+            return Err("a step is a single subcommand, like `rate: {hz: 5}`".to_owned())
+        }
+    };
+    let mut args: Vec<String> = name.split_whitespace().map(str::to_owned).collect();
+    if args.is_empty() {
+        return Err("the subcommand is missing".to_owned());
+    }
+    let mut positional = Vec::new();
+    for (option, value) in options.unwrap_or_default() {
+        let values = match value {
+            Value::One(scalar) => vec![scalar],
+            Value::List(list) => list,
+        };
+        if option == "args" {
+            positional.extend(values.iter().map(Scalar::text));
+            continue;
+        }
+        if GLOBAL_OPTIONS.contains(&option.as_str()) {
+            return Err(format!(
+                "{} goes on the command line, not in a step",
+                option
+            ));
+        }
+        let flag = format!("--{}", option.replace('_', "-"));
+        for value in values {
+            match value {
+                Scalar::Flag(true) => args.push(flag.clone()),
+                Scalar::Flag(false) => {}
+                // with `=`, negative numbers aren't taken for options
+                other => args.push(format!("{}={}", flag, other.text())),
+            }
+        }
+    }
+    if !positional.is_empty() {
+        args.push("--".to_owned());
+        args.extend(positional);
+    }
+    Ok(args)
+}
+
+/// The steps of a file, or what is wrong with it
+pub fn parse(text: &str) -> Result<Vec<Step>, String> {
+    if text.trim().is_empty() {
+        return Err("no steps".to_owned());
+    }
+    let raw: Vec<RawStep> = serde_yaml::from_str(text).map_err(|e| e.to_string())?;
+    if raw.is_empty() {
+        return Err("no steps".to_owned());
+    }
+    let lines = step_lines(text);
+    let count = raw.len();
+    raw.into_iter()
+        .enumerate()
+        .map(|(i, raw)| {
+            let place = match lines.get(i) {
+                Some(line) if lines.len() == count => format!("line {}", line),
+                _ => format!("step {}", i + 1),
+            };
+            match step_args(raw) {
+                Ok(args) => Ok(Step { place, args }),
+                Err(e) => Err(format!("{}: {}", place, e)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROVER: &str = "\
+# a rover on UART1
+- baud: {port: uart1, set: 115200}
+
+- rate:
+    hz: 5
+- msg: {set: [NAV-PVT=1, GSV=0], port: usb}
+- nav5: {model: automotive, min_elev: -5}
+- cfg set: {args: [CFG-RATE-MEAS=100], layer: [ram, flash]}
+- save
+- load-defaults: {yes: true, devices: [bbr, flash]}
+- reset: {hot: false}
+";
+
+    #[test]
+    fn steps_become_command_lines() {
+        let steps = parse(ROVER).unwrap();
+        let lines: Vec<(&str, String)> = steps
+            .iter()
+            .map(|step| (step.place.as_str(), step.args.join(" ")))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("line 2", "baud --port=uart1 --set=115200".to_owned()),
+                ("line 4", "rate --hz=5".to_owned()),
+                (
+                    "line 6",
+                    "msg --port=usb --set=NAV-PVT=1 --set=GSV=0".to_owned()
+                ),
+                ("line 7", "nav5 --min-elev=-5 --model=automotive".to_owned()),
+                (
+                    "line 8",
+                    "cfg set --layer=ram --layer=flash -- CFG-RATE-MEAS=100".to_owned()
+                ),
+                ("line 9", "save".to_owned()),
+                (
+                    "line 10",
+                    "load-defaults --devices=bbr --devices=flash --yes".to_owned()
+                ),
+                ("line 11", "reset".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn mistakes_are_placed() {
+        assert_eq!(
+            parse("- rate: {hz: 5}\n- msg: {set: [GSV=0]}\n  save: {}\n"),
+            Err("line 2: a step is a single subcommand, like `rate: {hz: 5}`".to_owned())
+        );
+        assert_eq!(
+            parse("- rate: {hz: 5, device: /dev/ttyUSB0}\n"),
+            Err("line 1: device goes on the command line, not in a step".to_owned())
+        );
+        assert!(parse("- rate: {hz: 5\n- save\n")
+            .unwrap_err()
+            .contains("line"));
+        assert!(parse("\n# nothing\n").is_err());
+    }
+}
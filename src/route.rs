@@ -0,0 +1,288 @@
+//! Following a route loaded from GPX, one leg at a time
+
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Error};
+
+use crate::app::distance_bearing;
+
+const EARTH_RADIUS: f64 = 6_371_000.0;
+const KNOTS_TO_MPS: f64 = 1852.0 / 3600.0;
+
+/// Points closer than this to the one before are dropped as duplicates
+const MIN_LEG: f64 = 1.0;
+/// Below this speed in knots there is no ETA
+const MIN_ETA_SPEED: f32 = 0.5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoutePoint {
+    pub name: String,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Content of each `<tag ...>...</tag>` or `<tag .../>` in `xml`, opening
+/// tag included
+fn elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let elem = &rest[start..];
+        // `<rtept` also starts `<rteptx`
+        if !elem[open.len()..].starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/') {
+            rest = &elem[open.len()..];
+            continue;
+        }
+        let head_end = match elem.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+        let end = if elem[..head_end].ends_with('/') {
+            head_end + 1
+        } else {
+            elem.find(&close)
+                .map_or(elem.len(), |end| end + close.len())
+        };
+        found.push(&elem[..end]);
+        rest = &elem[end..];
+    }
+    found
+}
+
+fn attr<'a>(elem: &'a str, name: &str) -> Option<&'a str> {
+    let head = &elem[..elem.find('>')?];
+    for quote in &['"', '\''] {
+        let key = format!(" {}={}", name, quote);
+        if let Some(start) = head.find(&key) {
+            let value = &head[start + key.len()..];
+            return Some(&value[..value.find(*quote)?]);
+        }
+    }
+    None
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn child_text(elem: &str, tag: &str) -> Option<String> {
+    let child = elements(elem, tag).into_iter().next()?;
+    let text = &child[child.find('>')? + 1..];
+    let text = text.strip_suffix(&format!("</{}>", tag))?;
+    Some(xml_unescape(text.trim()))
+}
+
+/// Route points of the first `<rte>` of a GPX file, or its `<wpt>`s when it
+/// has no route
+pub fn parse_gpx(xml: &str) -> Result<Vec<RoutePoint>, Error> {
+    let points = match elements(xml, "rte").first() {
+        Some(rte) => elements(rte, "rtept"),
+        None => elements(xml, "wpt"),
+    };
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, elem)| {
+            let coord = |name| {
+                attr(elem, name)
+                    .and_then(|v| v.trim().parse::<f64>().ok())
+                    .ok_or_else(|| anyhow!("point {} has no valid {}", i + 1, name))
+            };
+            Ok(RoutePoint {
+                name: child_text(elem, "name").unwrap_or_else(|| format!("RP{:03}", i + 1)),
+                lat: coord("lat")?,
+                lon: coord("lon")?,
+            })
+        })
+        .collect()
+}
+
+pub fn load(path: &Path) -> Result<Vec<RoutePoint>, Error> {
+    let xml = fs::read_to_string(path)
+        .map_err(|e| anyhow!("failed to read {}: {}", path.display(), e))?;
+    let points = parse_gpx(&xml).map_err(|e| anyhow!("invalid route {}: {}", path.display(), e))?;
+    if points.is_empty() {
+        return Err(anyhow!("no route points in {}", path.display()));
+    }
+    Ok(points)
+}
+
+/// Where the active leg stands from the current position
+#[derive(Debug, Clone, PartialEq)]
+pub struct Navigation {
+    /// the point the active leg leads to
+    pub to: String,
+    /// meters
+    pub distance: f64,
+    /// degrees
+    pub bearing: f64,
+    /// bearing of the leg in degrees; on the first leg, the bearing to the
+    /// point
+    pub track: f64,
+    /// within the arrival radius of the point
+    pub arrived: bool,
+    /// abeam of the point or beyond it
+    pub passed: bool,
+    /// meters off the leg line, right of it when positive; none on the first
+    /// leg, which starts wherever the receiver was
+    pub xte: Option<f64>,
+    pub eta: Option<chrono::Duration>,
+}
+
+/// A route and the point being headed for
+#[derive(Debug, Clone)]
+pub struct Route {
+    pub points: Vec<RoutePoint>,
+    /// index of the point being headed for, `points.len()` once finished
+    pub active: usize,
+    /// meters from a point at which it counts as reached
+    arrival_radius: f64,
+}
+
+impl Route {
+    pub const DEFAULT_ARRIVAL_RADIUS: f64 = 20.0;
+
+    /// Consecutive points at the same place are merged, so no leg has zero
+    /// length
+    pub fn new(points: Vec<RoutePoint>, arrival_radius: f64) -> Route {
+        let mut merged: Vec<RoutePoint> = Vec::with_capacity(points.len());
+        for point in points {
+            let duplicate = merged.last().is_some_and(|last| {
+                distance_bearing((last.lat, last.lon), (point.lat, point.lon)).0 < MIN_LEG
+            });
+            if !duplicate {
+                merged.push(point);
+            }
+        }
+        Route {
+            points: merged,
+            active: 0,
+            arrival_radius,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.active >= self.points.len()
+    }
+
+    pub fn next(&mut self) {
+        self.active = (self.active + 1).min(self.points.len());
+    }
+
+    pub fn prev(&mut self) {
+        self.active = self.active.saturating_sub(1);
+    }
+
+    pub fn target(&self) -> Option<&RoutePoint> {
+        self.points.get(self.active)
+    }
+
+    /// Moves on to the next point once the active one is reached, and tells
+    /// which one was
+    pub fn update(&mut self, lat: f64, lon: f64) -> Option<String> {
+        let target = self.target()?;
+        let (distance, _) = distance_bearing((lat, lon), (target.lat, target.lon));
+        if distance > self.arrival_radius {
+            return None;
+        }
+        let name = target.name.clone();
+        self.next();
+        Some(name)
+    }
+
+    pub fn navigation(&self, lat: f64, lon: f64, speed_knots: Option<f32>) -> Option<Navigation> {
+        let to = self.target()?;
+        let (distance, bearing) = distance_bearing((lat, lon), (to.lat, to.lon));
+        let from = self.active.checked_sub(1).map(|i| &self.points[i]);
+        let track = match from {
+            Some(from) => distance_bearing((from.lat, from.lon), (to.lat, to.lon)).1,
+            None => bearing,
+        };
+        let xte = from.map(|from| {
+            let (from_distance, from_bearing) = distance_bearing((from.lat, from.lon), (lat, lon));
+            let angle = (from_bearing - track).to_radians();
+            ((from_distance / EARTH_RADIUS).sin() * angle.sin()).asin() * EARTH_RADIUS
+        });
+        // the point is behind once the bearing to it turns more than 90
+        // degrees away from the leg
+        let passed = (bearing - track).to_radians().cos() < 0.0;
+        let eta = speed_knots
+            .filter(|&speed| speed >= MIN_ETA_SPEED)
+            .map(|speed| {
+                let secs = distance / (speed as f64 * KNOTS_TO_MPS);
+                chrono::Duration::seconds(secs.round() as i64)
+            });
+        Some(Navigation {
+            to: to.name.clone(),
+            distance,
+            bearing,
+            track,
+            arrived: distance <= self.arrival_radius,
+            passed,
+            xte,
+            eta,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <wpt lat="1.0" lon="1.0"><name>ignored</name></wpt>
+  <rte>
+    <name>loop</name>
+    <rtept lat="0.0" lon="0.0"><name>start</name></rtept>
+    <rtept lat='0.0' lon='0.0'><name>again</name></rtept>
+    <rtept lat="0.0" lon="0.01"><name>east &amp; more</name></rtept>
+    <rtept lat="0.01" lon="0.01"/>
+  </rte>
+</gpx>
+"#;
+
+    #[test]
+    fn gpx_route_points_are_read_and_duplicates_merged() {
+        let points = parse_gpx(GPX).unwrap();
+        assert_eq!(points.len(), 4);
+        assert_eq!(points[2].name, "east & more");
+        assert_eq!(points[3].name, "RP004");
+
+        let route = Route::new(points, Route::DEFAULT_ARRIVAL_RADIUS);
+        let names: Vec<&str> = route.points.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["start", "east & more", "RP004"]);
+    }
+
+    #[test]
+    fn legs_advance_on_arrival_with_cross_track_error() {
+        let points = parse_gpx(GPX).unwrap();
+        let mut route = Route::new(points, Route::DEFAULT_ARRIVAL_RADIUS);
+
+        assert_eq!(route.update(0.0, 0.0), Some("start".to_owned()));
+        // halfway along the eastward leg, about 111m north of it
+        let nav = route.navigation(0.001, 0.005, Some(10.0)).unwrap();
+        assert_eq!(nav.to, "east & more");
+        let xte = nav.xte.unwrap();
+        assert!((xte + 111.2).abs() < 0.5, "{}", xte);
+        assert!(nav.eta.is_some());
+        assert!(route
+            .navigation(0.0, 0.005, Some(0.1))
+            .unwrap()
+            .eta
+            .is_none());
+
+        route.next();
+        route.next();
+        assert!(route.finished());
+        assert!(route.navigation(0.0, 0.0, None).is_none());
+        route.prev();
+        assert_eq!(route.target().unwrap().name, "RP004");
+    }
+}
@@ -0,0 +1,68 @@
+//! Searching the Messages pane: a substring, or a regex after `re:`
+
+use std::fmt;
+
+use regex::Regex;
+
+#[derive(Debug, Clone)]
+pub struct Query {
+    /// as typed, `re:` included
+    input: String,
+    regex: Option<Regex>,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, String> {
+        let regex = match input.strip_prefix("re:") {
+            Some(pattern) => {
+                Some(Regex::new(pattern).map_err(|e| format!("invalid regex: {}", e))?)
+            }
+            None if input.is_empty() => return Err("nothing to search for".to_owned()),
+            None => None,
+        };
+        Ok(Query {
+            input: input.to_owned(),
+            regex,
+        })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match &self.regex {
+            Some(regex) => regex.is_match(text),
+            None => text.contains(&self.input),
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substrings_and_regexes_match() {
+        let line = "2021-06-20T10:00:00+09:00 $GPGGA,092750.000,5321.6802,N,00630.3372,W,1,8,1.03,61.7,M,55.2,M,,*76";
+
+        let query = Query::parse("GGA").unwrap();
+        assert!(query.is_match(line));
+        assert!(!query.is_match("$GPRMC,092750.000,A"));
+        assert_eq!(query.to_string(), "GGA");
+
+        // the timestamp is searched as well
+        assert!(Query::parse("T10:00").unwrap().is_match(line));
+
+        let query = Query::parse(r"re:^\S+ \$..GGA,[^,]*,[^,]*,N").unwrap();
+        assert!(query.is_match(line));
+        assert!(!query.is_match("2021-06-20T10:00:00+09:00 $GPGGA,,,S"));
+
+        assert!(Query::parse("").is_err());
+        assert!(Query::parse("re:(GGA")
+            .unwrap_err()
+            .starts_with("invalid regex"));
+    }
+}
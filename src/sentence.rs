@@ -0,0 +1,161 @@
+//! Just enough parsing of raw sentences for what the nmea crate doesn't keep
+
+use std::str::FromStr;
+
+use chrono::{NaiveDate, NaiveTime};
+
+/// XOR of the bytes between `$` (or `!`) and `*`
+pub fn checksum(body: &str) -> u8 {
+    body.bytes().fold(0, |acc, b| acc ^ b)
+}
+
+/// `$body*hh`, ready to send
+pub fn with_checksum(body: &str) -> String {
+    format!("${}*{:02X}", body, checksum(body))
+}
+
+/// A sentence with a valid checksum, split into fields
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sentence<'a> {
+    /// `GP`, `GL`, `GN`, ...; `P` for proprietary sentences
+    pub talker: &'a str,
+    /// `GGA`, `GSV`, ...; the manufacturer and type for proprietary ones,
+    /// like `UBX` or `MTK001`
+    pub kind: &'a str,
+    /// fields after the address
+    pub fields: Vec<&'a str>,
+}
+
+impl<'a> Sentence<'a> {
+    /// `None` unless `line` looks like `$TTSSS,...*hh` with a matching
+    /// checksum; encapsulated ones like AIS start with `!` instead
+    pub fn parse(line: &'a str) -> Option<Sentence<'a>> {
+        let line = line.trim();
+        let body = line.strip_prefix('$').or_else(|| line.strip_prefix('!'))?;
+        let (body, checksum) = body.rsplit_once('*')?;
+        let checksum = u8::from_str_radix(checksum, 16).ok()?;
+        if self::checksum(body) != checksum {
+            return None;
+        }
+
+        let mut fields = body.split(',');
+        let address = fields.next()?;
+        let (talker, kind) = if address.starts_with('P') {
+            address.split_at(1)
+        } else {
+            (address.get(..2)?, address.get(2..)?)
+        };
+        if kind.is_empty() {
+            return None;
+        }
+        Some(Sentence {
+            talker,
+            kind,
+            fields: fields.collect(),
+        })
+    }
+
+    /// `kind`, with PUBX messages told apart by their ID, as in `PUBX00`
+    pub fn name(&self) -> &'a str {
+        match (self.talker, self.kind, self.field(0)) {
+            ("P", "UBX", Some("00")) => "PUBX00",
+            ("P", "UBX", Some("03")) => "PUBX03",
+            ("P", "UBX", Some("04")) => "PUBX04",
+            _ => self.kind,
+        }
+    }
+
+    /// Field `i`, `None` when missing or empty
+    pub fn field(&self, i: usize) -> Option<&'a str> {
+        self.fields.get(i).copied().filter(|f| !f.is_empty())
+    }
+
+    pub fn parse_field<T: FromStr>(&self, i: usize) -> Option<T> {
+        self.field(i)?.parse().ok()
+    }
+
+    /// `hhmmss.ss` in field `i`
+    pub fn time_field(&self, i: usize) -> Option<NaiveTime> {
+        let field = self.field(i)?;
+        let (hms, frac) = match field.split_once('.') {
+            Some((hms, frac)) => (hms, frac),
+            None => (field, ""),
+        };
+        if hms.len() != 6 || !hms.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let num = |range: std::ops::Range<usize>| hms[range].parse::<u32>().ok();
+        let nanos = if frac.is_empty() {
+            0
+        } else {
+            (format!("0.{}", frac).parse::<f64>().ok()? * 1e9).round() as u32
+        };
+        NaiveTime::from_hms_nano_opt(num(0..2)?, num(2..4)?, num(4..6)?, nanos)
+    }
+
+    /// `ddmmyy` in field `i`
+    pub fn date_field(&self, i: usize) -> Option<NaiveDate> {
+        let field = self.field(i)?;
+        if field.len() != 6 || !field.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let num = |range: std::ops::Range<usize>| field[range].parse::<u32>().ok();
+        NaiveDate::from_ymd_opt(2000 + num(4..6)? as i32, num(2..4)?, num(0..2)?)
+    }
+
+    /// Degrees from `ddmm.mmmm` in field `i` and its hemisphere in `i + 1`,
+    /// negative for south and west
+    pub fn coord_field(&self, i: usize) -> Option<f64> {
+        let value: f64 = self.parse_field(i)?;
+        let degrees = (value / 100.0).trunc();
+        let degrees = degrees + (value - degrees * 100.0) / 60.0;
+        match self.field(i + 1)? {
+            "N" | "E" => Some(degrees),
+            "S" | "W" => Some(-degrees),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_standard_and_proprietary_sentences() {
+        let gsa =
+            Sentence::parse("$GPGSA,A,3,06,03,17,22,28,,,,,,,,2.63,1.17,2.36*0C\r\n").unwrap();
+        assert_eq!(gsa.talker, "GP");
+        assert_eq!(gsa.kind, "GSA");
+        assert_eq!(gsa.field(1), Some("3"));
+        assert_eq!(gsa.field(7), None);
+        assert_eq!(gsa.parse_field::<f32>(15), Some(2.36));
+
+        let gll = Sentence::parse("$GPGLL,3732.93104,N,12654.85121,W,060433.50,A,A*7D").unwrap();
+        assert!((gll.coord_field(0).unwrap() - 37.548851).abs() < 1e-6);
+        assert!((gll.coord_field(2).unwrap() + 126.914187).abs() < 1e-6);
+        assert_eq!(
+            gll.time_field(4),
+            NaiveTime::from_hms_milli_opt(6, 4, 33, 500)
+        );
+
+        let txt = Sentence::parse("$PUBX,00*33").unwrap();
+        assert_eq!(txt.talker, "P");
+        assert_eq!(txt.kind, "UBX");
+        assert_eq!(txt.name(), "PUBX00");
+    }
+
+    #[test]
+    fn rejects_bad_checksum() {
+        assert!(Sentence::parse("$GPGSA,A,3,06,03,17,22,28,,,,,,,,2.63,1.17,2.36*0D").is_none());
+        assert!(Sentence::parse("GPGSA,A,3*0C").is_none());
+        assert!(Sentence::parse("$GPGSA,A,3").is_none());
+    }
+
+    #[test]
+    fn parses_encapsulated_sentences() {
+        let vdm = Sentence::parse("!AIVDM,2,2,1,A,88888888880,2*25").unwrap();
+        assert_eq!((vdm.talker, vdm.kind), ("AI", "VDM"));
+        assert_eq!(vdm.field(4), Some("88888888880"));
+    }
+}
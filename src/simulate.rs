@@ -0,0 +1,594 @@
+//! A receiver made up from a seed, to try nmeacli without hardware and to
+//! feed tests: it moves along a circle or a route with 8 to 14 GPS and
+//! GLONASS satellites in view, their SNR drifting, and now and then loses its
+//! fix or gains DGPS. Each epoch is RMC, GGA, a GSA per constellation and
+//! the GSVs.
+
+use std::{
+    collections::VecDeque,
+    io,
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::{NaiveDateTime, Timelike};
+
+use crate::{app::distance_bearing, route::RoutePoint, sentence::with_checksum, source::Source};
+
+/// Speed along the path in meters per second
+pub const SPEED: f64 = 5.0;
+
+const METERS_PER_DEGREE: f64 = 111_320.0;
+const KNOTS_TO_MPS: f64 = 1852.0 / 3600.0;
+
+const MIN_SATELLITES: usize = 8;
+const MAX_SATELLITES: usize = 14;
+/// Satellites set below this elevation, and new ones rise from it
+const HORIZON: f64 = 5.0;
+/// C/N0 a satellite needs to be used in the fix
+const MIN_USED_SNR: f64 = 25.0;
+
+/// splitmix64: tiny, and plenty for made-up data
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[low, high)`
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        low + unit * (high - low)
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        self.range(0.0, 1.0) < p
+    }
+}
+
+/// A circle driven clockwise, given as `LAT,LON,RADIUS` with the radius in
+/// meters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circle {
+    pub lat: f64,
+    pub lon: f64,
+    pub radius: f64,
+}
+
+impl Default for Circle {
+    /// Around where example.txt was recorded
+    fn default() -> Circle {
+        Circle {
+            lat: 37.548_851,
+            lon: 126.914_187,
+            radius: 200.0,
+        }
+    }
+}
+
+impl FromStr for Circle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Circle, String> {
+        let values: Vec<Option<f64>> = s.split(',').map(|v| v.trim().parse().ok()).collect();
+        match values.as_slice() {
+            [Some(lat), Some(lon), Some(radius)]
+                if lat.abs() < 90.0 && lon.abs() <= 180.0 && *radius > 0.0 =>
+            {
+                Ok(Circle {
+                    lat: *lat,
+                    lon: *lon,
+                    radius: *radius,
+                })
+            }
+            _ => Err(format!(
+                "expected LAT,LON,RADIUS with the radius in meters above 0, got '{}'",
+                s
+            )),
+        }
+    }
+}
+
+/// Where the simulated receiver goes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Path {
+    Circle(Circle),
+    /// from the first point to the last and back to the first, round and
+    /// round
+    Route(Vec<RoutePoint>),
+}
+
+impl Path {
+    /// Latitude, longitude and course in degrees after `distance` meters
+    fn position(&self, distance: f64) -> (f64, f64, f64) {
+        match self {
+            Path::Circle(circle) => {
+                // clockwise from north of the center
+                let angle = distance / circle.radius;
+                let north = circle.radius * angle.cos();
+                let east = circle.radius * angle.sin();
+                (
+                    circle.lat + north / METERS_PER_DEGREE,
+                    circle.lon + east / (METERS_PER_DEGREE * circle.lat.to_radians().cos()),
+                    (angle.to_degrees() + 90.0).rem_euclid(360.0),
+                )
+            }
+            Path::Route(points) => {
+                let legs = || {
+                    points
+                        .iter()
+                        .zip(points.iter().cycle().skip(1))
+                        .map(|(a, b)| {
+                            let (length, bearing) =
+                                distance_bearing((a.lat, a.lon), (b.lat, b.lon));
+                            (a, b, length, bearing)
+                        })
+                };
+                let total: f64 = legs().map(|(_, _, length, _)| length).sum();
+                let mut left = if total > 0.0 {
+                    distance.rem_euclid(total)
+                } else {
+                    0.0
+                };
+                for (a, b, length, bearing) in legs() {
+                    if left < length {
+                        let f = left / length;
+                        return (
+                            a.lat + (b.lat - a.lat) * f,
+                            a.lon + (b.lon - a.lon) * f,
+                            bearing,
+                        );
+                    }
+                    left -= length;
+                }
+                // a single point, or the same one over and over
+                points
+                    .first()
+                    .map_or((0.0, 0.0, 0.0), |p| (p.lat, p.lon, 0.0))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SimSatellite {
+    talker: &'static str,
+    prn: u32,
+    elevation: f64,
+    azimuth: f64,
+    snr: f64,
+    /// degrees a second, negative once past the top
+    climb: f64,
+}
+
+impl SimSatellite {
+    fn system_id(&self) -> u8 {
+        match self.talker {
+            "GP" => 1,
+            _ => 2,
+        }
+    }
+}
+
+/// SNR a satellite drifts around: better high up than near the horizon
+fn usual_snr(elevation: f64) -> f64 {
+    22.0 + elevation * 0.25
+}
+
+/// `ddmm.mmmmm,N` or `dddmm.mmmmm,E`
+fn coord(value: f64, degree_digits: usize, positive: char, negative: char) -> String {
+    let degrees = value.abs().trunc();
+    let minutes = (value.abs() - degrees) * 60.0;
+    format!(
+        "{:0width$}{:08.5},{}",
+        degrees as u32,
+        minutes,
+        if value < 0.0 { negative } else { positive },
+        width = degree_digits
+    )
+}
+
+fn with_decimals(value: Option<f64>, decimals: usize) -> String {
+    value.map_or_else(String::new, |v| format!("{:.*}", decimals, v))
+}
+
+/// Generates the sentences of one epoch after another, the same ones for the
+/// same seed, start and rate
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    rng: Rng,
+    path: Path,
+    /// time between epochs
+    interval_ms: i64,
+    /// UTC time of the next epoch
+    time: NaiveDateTime,
+    /// meters travelled
+    distance: f64,
+    satellites: Vec<SimSatellite>,
+    /// how many satellites to keep in view
+    in_view: usize,
+    /// GGA fix quality: 0 none, 1 GPS, 2 DGPS
+    quality: u8,
+    /// seconds until the fix quality changes
+    hold: f64,
+}
+
+impl Simulator {
+    /// `rate` epochs a second, the first at `start`
+    pub fn new(path: Path, rate: f64, seed: u64, start: NaiveDateTime) -> Simulator {
+        let mut rng = Rng(seed);
+        let in_view = rng.range(MIN_SATELLITES as f64, MAX_SATELLITES as f64 + 1.0) as usize;
+        let hold = rng.range(20.0, 90.0);
+        let mut sim = Simulator {
+            rng,
+            path,
+            interval_ms: ((1000.0 / rate).round() as i64).max(1),
+            time: start,
+            distance: 0.0,
+            satellites: Vec::new(),
+            in_view,
+            quality: 1,
+            hold,
+        };
+        for _ in 0..in_view {
+            let elevation = sim.rng.range(HORIZON, 85.0);
+            let climb = sim.rng.range(-0.05, 0.05);
+            sim.rise(elevation, climb);
+        }
+        sim
+    }
+
+    /// Time between epochs
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms as u64)
+    }
+
+    /// Adds a satellite at `elevation` with a PRN not in view yet
+    fn rise(&mut self, elevation: f64, climb: f64) {
+        let (talker, first) = if self.rng.chance(0.6) {
+            ("GP", 1)
+        } else {
+            ("GL", 65)
+        };
+        let prn = loop {
+            let prn = first + self.rng.range(0.0, 24.0) as u32;
+            if self.satellites.iter().all(|sat| sat.prn != prn) {
+                break prn;
+            }
+        };
+        let azimuth = self.rng.range(0.0, 360.0);
+        let snr = usual_snr(elevation) + self.rng.range(-3.0, 3.0);
+        self.satellites.push(SimSatellite {
+            talker,
+            prn,
+            elevation,
+            azimuth,
+            snr,
+            climb,
+        });
+    }
+
+    /// Sentences of the next epoch
+    pub fn epoch(&mut self) -> Vec<String> {
+        let lines = self.sentences();
+        let dt = self.interval_ms as f64 / 1000.0;
+        self.time += chrono::Duration::milliseconds(self.interval_ms);
+        self.distance += SPEED * dt;
+        self.drift(dt);
+        self.change_fix(dt);
+        lines
+    }
+
+    fn drift(&mut self, dt: f64) {
+        let rng = &mut self.rng;
+        for sat in &mut self.satellites {
+            sat.elevation += sat.climb * dt;
+            if sat.elevation > 89.0 {
+                sat.elevation = 89.0;
+                sat.climb = -sat.climb.abs();
+            }
+            sat.azimuth = (sat.azimuth + 0.02 * dt).rem_euclid(360.0);
+            sat.snr += rng.range(-1.0, 1.0) * dt.sqrt()
+                + (usual_snr(sat.elevation) - sat.snr) * (0.05 * dt).min(1.0);
+        }
+        self.satellites.retain(|sat| sat.elevation >= HORIZON);
+
+        if self.rng.chance(dt / 60.0) {
+            self.in_view = if self.rng.chance(0.5) {
+                self.in_view + 1
+            } else {
+                self.in_view - 1
+            }
+            .clamp(MIN_SATELLITES, MAX_SATELLITES);
+        }
+        while self.satellites.len() < self.in_view {
+            let climb = self.rng.range(0.01, 0.05);
+            self.rise(HORIZON, climb);
+        }
+        while self.satellites.len() > self.in_view {
+            // the lowest one sets first
+            let lowest = (0..self.satellites.len())
+                .min_by(|&a, &b| {
+                    self.satellites[a]
+                        .elevation
+                        .total_cmp(&self.satellites[b].elevation)
+                })
+                .unwrap_or(0);
+            self.satellites.remove(lowest);
+        }
+    }
+
+    fn change_fix(&mut self, dt: f64) {
+        self.hold -= dt;
+        if self.hold > 0.0 {
+            return;
+        }
+        self.quality = match self.quality {
+            0 => 1,
+            1 if self.rng.chance(0.3) => 0,
+            1 => 2,
+            _ => 1,
+        };
+        self.hold = if self.quality == 0 {
+            self.rng.range(3.0, 8.0)
+        } else {
+            self.rng.range(20.0, 90.0)
+        };
+    }
+
+    fn sentences(&self) -> Vec<String> {
+        let mut used: Vec<&SimSatellite> = self
+            .satellites
+            .iter()
+            .filter(|sat| sat.snr >= MIN_USED_SNR)
+            .collect();
+        let quality = if used.len() >= 4 { self.quality } else { 0 };
+        if quality == 0 {
+            used.clear();
+        }
+        used.sort_by_key(|sat| sat.prn);
+        let fix = quality > 0;
+        let hdop = Some(0.5 + 4.0 / used.len() as f64).filter(|_| fix);
+        let vdop = hdop.map(|h| h * 1.5);
+        let pdop = hdop.and_then(|h| vdop.map(|v| h.hypot(v)));
+
+        let (lat, lon, course) = self.path.position(self.distance);
+        let position = if fix {
+            format!("{},{}", coord(lat, 2, 'N', 'S'), coord(lon, 3, 'E', 'W'))
+        } else {
+            ",,,".to_owned()
+        };
+        let time = format!(
+            "{}.{:02}",
+            self.time.format("%H%M%S"),
+            self.time.nanosecond() / 10_000_000
+        );
+        let altitude = Some(40.0 + 3.0 * (self.distance / 100.0).sin()).filter(|_| fix);
+        let dgps = if quality == 2 { "1.0,0000" } else { "," };
+
+        let mut lines = vec![
+            with_checksum(&format!(
+                "GNRMC,{},{},{},{},{},{},,,{}",
+                time,
+                if fix { 'A' } else { 'V' },
+                position,
+                with_decimals(Some(SPEED / KNOTS_TO_MPS).filter(|_| fix), 2),
+                with_decimals(Some(course).filter(|_| fix), 1),
+                self.time.format("%d%m%y"),
+                match quality {
+                    0 => 'N',
+                    1 => 'A',
+                    _ => 'D',
+                }
+            )),
+            with_checksum(&format!(
+                "GNGGA,{},{},{},{:02},{},{},M,18.3,M,{}",
+                time,
+                position,
+                quality,
+                used.len(),
+                with_decimals(hdop, 2),
+                with_decimals(altitude, 1),
+                dgps
+            )),
+        ];
+
+        for (talker, system_id) in &[("GP", 1), ("GL", 2)] {
+            let mut prns: Vec<String> = used
+                .iter()
+                .filter(|sat| sat.system_id() == *system_id)
+                .map(|sat| format!("{:02}", sat.prn))
+                .take(12)
+                .collect();
+            prns.resize(12, String::new());
+            lines.push(with_checksum(&format!(
+                "GNGSA,A,{},{},{},{},{},{}",
+                if fix { 3 } else { 1 },
+                prns.join(","),
+                with_decimals(pdop, 2),
+                with_decimals(hdop, 2),
+                with_decimals(vdop, 2),
+                system_id
+            )));
+
+            let mut in_view: Vec<&SimSatellite> = self
+                .satellites
+                .iter()
+                .filter(|sat| sat.talker == *talker)
+                .collect();
+            in_view.sort_by_key(|sat| sat.prn);
+            let total = in_view.len().div_ceil(4);
+            for (i, group) in in_view.chunks(4).enumerate() {
+                let sats: Vec<String> = group
+                    .iter()
+                    .map(|sat| {
+                        format!(
+                            "{:02},{:02.0},{:03.0},{:02.0}",
+                            sat.prn,
+                            sat.elevation,
+                            sat.azimuth.min(359.0),
+                            sat.snr.clamp(0.0, 99.0)
+                        )
+                    })
+                    .collect();
+                lines.push(with_checksum(&format!(
+                    "{}GSV,{},{},{:02},{}",
+                    talker,
+                    total,
+                    i + 1,
+                    in_view.len(),
+                    sats.join(",")
+                )));
+            }
+        }
+        lines
+    }
+}
+
+/// The simulator as a source, paced in real time
+pub struct SimSource {
+    sim: Simulator,
+    lines: VecDeque<String>,
+    /// when the next epoch is due
+    next: Instant,
+    bytes: u64,
+}
+
+impl SimSource {
+    pub fn new(sim: Simulator) -> SimSource {
+        SimSource {
+            sim,
+            lines: VecDeque::new(),
+            next: Instant::now(),
+            bytes: 0,
+        }
+    }
+}
+
+impl Source for SimSource {
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.lines.is_empty() {
+            let now = Instant::now();
+            if self.next > now {
+                thread::sleep(self.next - now);
+            }
+            self.next += self.sim.interval();
+            self.lines.extend(self.sim.epoch());
+        }
+        let line = self.lines.pop_front();
+        if let Some(line) = &line {
+            self.bytes += line.len() as u64 + 2;
+        }
+        Ok(line.map(String::into_bytes))
+    }
+
+    fn bytes_read(&self) -> u64 {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::{app::App, sentence::Sentence};
+
+    fn start() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2021, 6, 20)
+            .unwrap()
+            .and_hms_opt(23, 59, 0)
+            .unwrap()
+    }
+
+    fn run(sim: &mut Simulator, epochs: usize) -> Vec<String> {
+        (0..epochs).flat_map(|_| sim.epoch()).collect()
+    }
+
+    #[test]
+    fn seeded_runs_repeat_and_stay_plausible() {
+        let path = Path::Circle(Circle::default());
+        let mut sim = Simulator::new(path.clone(), 1.0, 42, start());
+        let lines = run(&mut sim, 600);
+        assert_eq!(
+            lines,
+            run(&mut Simulator::new(path.clone(), 1.0, 42, start()), 600)
+        );
+        assert_ne!(lines, run(&mut Simulator::new(path, 1.0, 43, start()), 600));
+
+        let mut qualities = Vec::new();
+        for line in &lines {
+            let sentence = Sentence::parse(line).expect("valid checksum");
+            match sentence.name() {
+                "GGA" => {
+                    let quality: u8 = sentence.parse_field(5).unwrap();
+                    if !qualities.contains(&quality) {
+                        qualities.push(quality);
+                    }
+                }
+                "GSV" => {
+                    let count: usize = sentence.parse_field(2).unwrap();
+                    assert!(count <= MAX_SATELLITES);
+                }
+                _ => {}
+            }
+        }
+        qualities.sort_unstable();
+        assert_eq!(qualities, vec![0, 1, 2]);
+        // midnight went by
+        assert!(lines.iter().any(|line| line.contains("GNRMC,000000.00,")));
+        assert!(lines.iter().any(|line| line.contains(",210621,")));
+    }
+
+    #[test]
+    fn app_follows_the_circle() {
+        let circle = Circle::from_str("52.0, 4.0, 100").unwrap();
+        let mut sim = Simulator::new(Path::Circle(circle), 5.0, 7, start());
+        let mut app = App::with_defaults();
+        for line in run(&mut sim, 50) {
+            app.handle_line(&line);
+        }
+
+        let satellites = app.sky.len();
+        assert!((MIN_SATELLITES..=MAX_SATELLITES).contains(&satellites));
+        assert_eq!(app.sentences_failed, 0);
+        let (distance, _) = distance_bearing(
+            (circle.lat, circle.lon),
+            (app.nmea.latitude.unwrap(), app.nmea.longitude.unwrap()),
+        );
+        assert!((distance - 100.0).abs() < 1.0);
+
+        assert!(Circle::from_str("52,4").is_err());
+        assert!(Circle::from_str("52,4,0").is_err());
+    }
+
+    #[test]
+    fn routes_are_driven_round_and_round() {
+        let point = |lat, lon| RoutePoint {
+            name: String::new(),
+            lat,
+            lon,
+        };
+        let path = Path::Route(vec![
+            point(0.0, 0.0),
+            point(0.0, 0.001),
+            point(0.001, 0.001),
+        ]);
+        let (lat, lon, course) = path.position(55.66);
+        assert!(lat.abs() < 1e-9 && (lon - 0.0005).abs() < 1e-5);
+        assert!((course - 90.0).abs() < 1e-6);
+        // the closing leg heads back to the start
+        let (_, _, course) = path.position(111.32 * 2.0 + 10.0);
+        assert!((course - 225.0).abs() < 0.1);
+        let total = 111.195 * (2.0 + 2f64.sqrt());
+        let (lat, lon, _) = path.position(total + 1e-6);
+        assert!(lat.abs() < 1e-6 && lon.abs() < 1e-6);
+    }
+}
@@ -0,0 +1,562 @@
+//! Satellites in view across all constellations.
+//!
+//! With several talkers streaming GSV, the nmea crate keeps only what the
+//! latest group reported, so satellites are tracked here per talker and PRN
+//! instead, and dropped once no GSV has mentioned them for a while.
+//!
+//! Consecutive reports of a constellation are compared to tell which
+//! satellites were acquired and which were lost; frequent changes point at
+//! multipath or obstructions.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use chrono::{DateTime, Local};
+
+use crate::sentence::Sentence;
+
+/// Satellites and used lists not refreshed for this long are dropped
+pub const EXPIRE_SECS: i64 = 10;
+
+/// How long a satellite counts as just acquired
+pub const ACQUIRED_SECS: i64 = 10;
+
+/// Acquisitions and losses older than this are left out of `churn`
+pub const CHURN_WINDOW_SECS: i64 = 60;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Satellite {
+    /// talker of the GSV which reported it, `BD` folded into `GB`
+    pub talker: String,
+    pub prn: u32,
+    pub elevation: Option<f32>,
+    pub azimuth: Option<f32>,
+    pub snr: Option<f32>,
+    /// listed by a GSA of the same constellation
+    pub used: bool,
+    /// when it appeared in a report of its constellation, unless it was in
+    /// the first one
+    pub acquired: Option<DateTime<Local>>,
+    /// when a report of its constellation left it out; it stays until it
+    /// expires
+    pub lost: Option<DateTime<Local>>,
+    seen: DateTime<Local>,
+}
+
+pub fn constellation(talker: &str) -> &'static str {
+    match talker {
+        "GP" => "GPS",
+        "GL" => "GLONASS",
+        "GA" => "Galileo",
+        "GB" => "BeiDou",
+        "GQ" => "QZSS",
+        "GI" => "NavIC",
+        _ => "GNSS",
+    }
+}
+
+/// RINEX-style satellite ID, like `G14` or `R02`
+pub fn sv_id(talker: &str, prn: u32) -> String {
+    let system = match talker {
+        "GP" => 'G',
+        "GL" => 'R',
+        "GA" => 'E',
+        "GB" => 'C',
+        "GQ" => 'J',
+        "GI" => 'I',
+        _ => '?',
+    };
+    format!("{}{:02}", system, prn)
+}
+
+impl Satellite {
+    pub fn constellation(&self) -> &'static str {
+        constellation(&self.talker)
+    }
+
+    pub fn just_acquired(&self, now: DateTime<Local>) -> bool {
+        self.lost.is_none()
+            && self
+                .acquired
+                .is_some_and(|t| now - t < chrono::Duration::seconds(ACQUIRED_SECS))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Acquired,
+    Lost,
+}
+
+/// A satellite which appeared in or went missing from the reports of its
+/// constellation
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkyChange {
+    pub time: DateTime<Local>,
+    pub change: Change,
+    pub talker: String,
+    pub prn: u32,
+}
+
+/// The latest GSA of one constellation
+#[derive(Debug, Clone)]
+pub struct GsaReport {
+    /// `GN` when the constellation couldn't be told
+    pub talker: String,
+    /// satellites used in the fix
+    pub prns: Vec<u32>,
+    /// DOPs of the combined solution, repeated in every GSA of an epoch
+    pub pdop: Option<f32>,
+    pub hdop: Option<f32>,
+    pub vdop: Option<f32>,
+    seen: DateTime<Local>,
+}
+
+impl std::fmt::Display for Satellite {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let field = |v: Option<f32>| v.map_or("-".to_owned(), |v| v.to_string());
+        write!(
+            f,
+            "{} {}: elv={} az={} snr={}{}",
+            self.constellation(),
+            self.prn,
+            field(self.elevation),
+            field(self.azimuth),
+            field(self.snr),
+            if self.used { " used" } else { "" }
+        )
+    }
+}
+
+fn talker_of(talker: &str) -> &str {
+    match talker {
+        "BD" => "GB",
+        other => other,
+    }
+}
+
+/// Constellation of a satellite from the PRN numbering ranges used by
+/// receivers that mix constellations in one list, u-blox ones included
+fn prn_talker(prn: u32) -> &'static str {
+    match prn {
+        1..=64 => "GP",
+        65..=96 => "GL",
+        159..=163 | 201..=264 | 401..=437 => "GB",
+        193..=199 => "GQ",
+        301..=336 => "GA",
+        _ => "GN",
+    }
+}
+
+/// Talker a combined `GN` GSA stands for, from its NMEA 4.10 system ID or,
+/// in older receivers, from the PRN numbering ranges
+fn gsa_talker(system_id: Option<u8>, prns: &[u32]) -> &'static str {
+    match system_id {
+        Some(1) => "GP",
+        Some(2) => "GL",
+        Some(3) => "GA",
+        Some(4) => "GB",
+        Some(5) => "GQ",
+        Some(6) => "GI",
+        _ => prns.first().map_or("GN", |&prn| prn_talker(prn)),
+    }
+}
+
+/// GSV messages of a group still being received
+struct PendingGroup {
+    total: u32,
+    last: u32,
+    sats: Vec<Satellite>,
+}
+
+#[derive(Default)]
+pub struct Sky {
+    sats: BTreeMap<(String, u32), Satellite>,
+    pending: HashMap<String, PendingGroup>,
+    /// latest GSA per constellation
+    used: HashMap<String, GsaReport>,
+    /// acquisitions and losses of the last `CHURN_WINDOW_SECS`, oldest first
+    changes: VecDeque<SkyChange>,
+}
+
+impl Sky {
+    pub fn new() -> Sky {
+        Sky::default()
+    }
+
+    /// Takes GSV, GSA and PUBX,03 sentences, ignores anything else
+    pub fn handle(&mut self, local: DateTime<Local>, sentence: &Sentence) {
+        match sentence.name() {
+            "GSV" => self.handle_gsv(local, sentence),
+            "GSA" => self.handle_gsa(local, sentence),
+            "PUBX03" => self.handle_pubx03(local, sentence),
+            _ => return,
+        }
+        self.mark_used();
+    }
+
+    fn handle_gsv(&mut self, local: DateTime<Local>, gsv: &Sentence) {
+        let talker = talker_of(gsv.talker).to_owned();
+        let (total, num) = match (gsv.parse_field::<u32>(0), gsv.parse_field::<u32>(1)) {
+            (Some(total), Some(num)) => (total, num),
+            _ => return,
+        };
+
+        if num == 1 {
+            self.pending.insert(
+                talker.clone(),
+                PendingGroup {
+                    total,
+                    last: 0,
+                    sats: Vec::new(),
+                },
+            );
+        }
+        let group = match self.pending.get_mut(&talker) {
+            Some(group) if group.total == total && group.last + 1 == num => group,
+            // a message went missing, wait for the next group
+            _ => {
+                self.pending.remove(&talker);
+                return;
+            }
+        };
+        group.last = num;
+
+        // four fields per satellite, NMEA 4.10 adds a signal ID at the end
+        let mut i = 3;
+        while i + 4 <= gsv.fields.len() {
+            if let Some(prn) = gsv.parse_field(i) {
+                group.sats.push(Satellite {
+                    talker: talker.clone(),
+                    prn,
+                    elevation: gsv.parse_field(i + 1),
+                    azimuth: gsv.parse_field(i + 2),
+                    snr: gsv.parse_field(i + 3),
+                    used: false,
+                    acquired: None,
+                    lost: None,
+                    seen: local,
+                });
+            }
+            i += 4;
+        }
+
+        if num == total {
+            if let Some(group) = self.pending.remove(&talker) {
+                self.update(local, Some(&talker), group.sats);
+            }
+        }
+    }
+
+    /// Takes the satellites of a complete report of `talker`, or of every
+    /// constellation for `None`, telling which appeared and which went
+    /// missing since the previous one
+    fn update(&mut self, local: DateTime<Local>, talker: Option<&str>, sats: Vec<Satellite>) {
+        let in_scope = |sat: &Satellite| talker.is_none_or(|t| sat.talker == t);
+        // the first report is where satellites start from, not a change
+        let first = !self.sats.values().any(in_scope);
+        let mut changes = Vec::new();
+
+        for old in self.sats.values_mut().filter(|sat| in_scope(sat)) {
+            let reported = sats
+                .iter()
+                .any(|sat| sat.talker == old.talker && sat.prn == old.prn);
+            if !reported && old.lost.is_none() {
+                old.lost = Some(local);
+                changes.push((Change::Lost, old.talker.clone(), old.prn));
+            }
+        }
+        for mut sat in sats {
+            let key = (sat.talker.clone(), sat.prn);
+            match self.sats.get(&key) {
+                Some(old) if old.lost.is_none() => sat.acquired = old.acquired,
+                _ if first => {}
+                _ => {
+                    sat.acquired = Some(local);
+                    changes.push((Change::Acquired, sat.talker.clone(), sat.prn));
+                }
+            }
+            self.sats.insert(key, sat);
+        }
+
+        for (change, talker, prn) in changes {
+            let what = match change {
+                Change::Acquired => "acquired",
+                Change::Lost => "lost",
+            };
+            log::info!("SV {} {}", sv_id(&talker, prn), what);
+            self.changes.push_back(SkyChange {
+                time: local,
+                change,
+                talker,
+                prn,
+            });
+        }
+        self.trim_changes(local);
+    }
+
+    fn trim_changes(&mut self, now: DateTime<Local>) {
+        let window = chrono::Duration::seconds(CHURN_WINDOW_SECS);
+        while self
+            .changes
+            .front()
+            .is_some_and(|change| now - change.time > window)
+        {
+            self.changes.pop_front();
+        }
+    }
+
+    fn handle_gsa(&mut self, local: DateTime<Local>, gsa: &Sentence) {
+        let prns: Vec<u32> = (2..14).filter_map(|i| gsa.parse_field(i)).collect();
+        let talker = match talker_of(gsa.talker) {
+            "GN" => gsa_talker(gsa.parse_field(17), &prns),
+            other => other,
+        };
+        let report = GsaReport {
+            talker: talker.to_owned(),
+            prns,
+            pdop: gsa.parse_field(14),
+            hdop: gsa.parse_field(15),
+            vdop: gsa.parse_field(16),
+            seen: local,
+        };
+        self.used.insert(talker.to_owned(), report);
+    }
+
+    /// u-blox satellite status: all satellites in one sentence, with the used
+    /// ones marked
+    fn handle_pubx03(&mut self, local: DateTime<Local>, pubx: &Sentence) {
+        let mut used = Vec::new();
+        let mut sats = Vec::new();
+        // six fields per satellite: PRN, status, azimuth, elevation, C/N0
+        // and lock time
+        let mut i = 2;
+        while i + 6 <= pubx.fields.len() {
+            if let Some(prn) = pubx.parse_field(i) {
+                if pubx.field(i + 1) == Some("U") {
+                    used.push(prn);
+                }
+                sats.push(Satellite {
+                    talker: prn_talker(prn).to_owned(),
+                    prn,
+                    elevation: pubx.parse_field(i + 3),
+                    azimuth: pubx.parse_field(i + 2),
+                    snr: pubx.parse_field(i + 4),
+                    used: false,
+                    acquired: None,
+                    lost: None,
+                    seen: local,
+                });
+            }
+            i += 6;
+        }
+        self.update(local, None, sats);
+        // PRNs are unique across constellations in u-blox numbering
+        let report = GsaReport {
+            talker: "GN".to_owned(),
+            prns: used,
+            pdop: None,
+            hdop: None,
+            vdop: None,
+            seen: local,
+        };
+        self.used.insert("GN".to_owned(), report);
+    }
+
+    fn mark_used(&mut self) {
+        let used = &self.used;
+        let listed = |talker: &str, prn: u32| {
+            used.get(talker)
+                .is_some_and(|report| report.prns.contains(&prn))
+        };
+        for sat in self.sats.values_mut() {
+            // a GN list whose constellation couldn't be told covers all
+            sat.used = listed(&sat.talker, sat.prn) || listed("GN", sat.prn);
+        }
+    }
+
+    /// Drops satellites and used lists not refreshed within `EXPIRE_SECS`
+    pub fn expire(&mut self, now: DateTime<Local>) {
+        let max_age = chrono::Duration::seconds(EXPIRE_SECS);
+        self.sats.retain(|_, sat| now - sat.seen <= max_age);
+        self.used.retain(|_, report| now - report.seen <= max_age);
+        self.mark_used();
+        self.trim_changes(now);
+    }
+
+    /// Satellites acquired and lost within the last `CHURN_WINDOW_SECS`
+    pub fn churn(&self) -> (usize, usize) {
+        let count = |change| self.changes.iter().filter(|c| c.change == change).count();
+        (count(Change::Acquired), count(Change::Lost))
+    }
+
+    /// Acquisitions and losses of the last `CHURN_WINDOW_SECS`, oldest first
+    pub fn changes(&self) -> impl Iterator<Item = &SkyChange> {
+        self.changes.iter()
+    }
+
+    /// Satellites ordered by talker and PRN
+    pub fn satellites(&self) -> impl Iterator<Item = &Satellite> {
+        self.sats.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sats.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sats.is_empty()
+    }
+
+    /// Latest GSA of each constellation, ordered by talker
+    pub fn gsa_reports(&self) -> Vec<&GsaReport> {
+        let mut reports: Vec<&GsaReport> = self.used.values().collect();
+        reports.sort_by(|a, b| a.talker.cmp(&b.talker));
+        reports
+    }
+
+    pub fn used_count(&self) -> usize {
+        self.sats.values().filter(|sat| sat.used).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sentence::with_checksum;
+    use chrono::TimeZone;
+
+    /// GSV groups of four constellations interleaved, as from a multi-GNSS
+    /// receiver, with NMEA 4.10 system IDs on the combined GSAs
+    const INTERLEAVED: &[&str] = &[
+        "$GPGSV,2,1,05,02,45,120,38,05,30,250,41,12,60,080,44,25,10,310,22*79",
+        "$GLGSV,1,1,03,65,50,040,35,72,20,200,30,81,70,300,*59",
+        "$GPGSV,2,2,05,29,15,170,28*4F",
+        "$GAGSV,1,1,02,301,40,100,36,305,25,220,33,7*72",
+        "$GBGSV,1,1,01,201,55,150,39*67",
+        "$GNGSA,A,3,02,05,12,,,,,,,,,,1.8,1.0,1.5,1*39",
+        "$GNGSA,A,3,65,72,,,,,,,,,,,1.8,1.0,1.5,2*38",
+        "$GNGSA,A,3,305,,,,,,,,,,,,1.8,1.0,1.5,3*09",
+    ];
+
+    fn feed(sky: &mut Sky, local: DateTime<Local>, lines: &[&str]) {
+        for line in lines {
+            sky.handle(local, &Sentence::parse(line).expect("valid sentence"));
+        }
+    }
+
+    fn sat(sky: &Sky, prn: u32) -> &Satellite {
+        sky.satellites().find(|sat| sat.prn == prn).unwrap()
+    }
+
+    fn prns(sky: &Sky, talker: &str) -> Vec<u32> {
+        sky.satellites()
+            .filter(|sat| sat.talker == talker)
+            .map(|sat| sat.prn)
+            .collect()
+    }
+
+    #[test]
+    fn interleaved_constellations_are_merged() {
+        let mut sky = Sky::new();
+        feed(&mut sky, Local.timestamp(1_591_509_873, 0), INTERLEAVED);
+
+        assert_eq!(prns(&sky, "GP"), vec![2, 5, 12, 25, 29]);
+        assert_eq!(prns(&sky, "GL"), vec![65, 72, 81]);
+        assert_eq!(prns(&sky, "GA"), vec![301, 305]);
+        assert_eq!(prns(&sky, "GB"), vec![201]);
+        assert_eq!(sky.len(), 11);
+        assert_eq!(sky.used_count(), 6);
+
+        let used: Vec<u32> = sky.satellites().filter(|s| s.used).map(|s| s.prn).collect();
+        // no GSA for BeiDou
+        assert_eq!(used, vec![305, 65, 72, 2, 5, 12]);
+
+        let reports: Vec<(&str, usize)> = sky
+            .gsa_reports()
+            .into_iter()
+            .map(|r| (r.talker.as_str(), r.prns.len()))
+            .collect();
+        assert_eq!(reports, vec![("GA", 1), ("GL", 2), ("GP", 3)]);
+        assert_eq!(sky.gsa_reports()[0].hdop, Some(1.0));
+    }
+
+    #[test]
+    fn incomplete_group_is_not_applied() {
+        let mut sky = Sky::new();
+        let local = Local.timestamp(1_591_509_873, 0);
+        feed(&mut sky, local, &[INTERLEAVED[0]]);
+        assert!(sky.is_empty());
+        // the second message of a different group
+        feed(
+            &mut sky,
+            local,
+            &[INTERLEAVED[1], "$GPGSV,3,2,09,29,15,170,28*42"],
+        );
+        assert_eq!(prns(&sky, "GP"), Vec::<u32>::new());
+        assert_eq!(prns(&sky, "GL"), vec![65, 72, 81]);
+    }
+
+    #[test]
+    fn acquisitions_and_losses_are_told() {
+        let mut sky = Sky::new();
+        let start = Local.timestamp(1_591_509_873, 0);
+        feed(&mut sky, start, INTERLEAVED);
+        // where satellites start from, not a change
+        assert_eq!(sky.churn(), (0, 0));
+        assert!(sky.satellites().all(|sat| sat.acquired.is_none()));
+
+        // GP 25 set and GP 31 rose
+        let gsv = [
+            with_checksum("GPGSV,2,1,05,02,45,120,38,05,30,250,41,12,60,080,44,29,15,170,28"),
+            with_checksum("GPGSV,2,2,05,31,05,020,18"),
+        ];
+        let gsv: Vec<&str> = gsv.iter().map(String::as_str).collect();
+        let next = start + chrono::Duration::seconds(1);
+        feed(&mut sky, next, &gsv);
+        let changes: Vec<(Change, String)> = sky
+            .changes()
+            .map(|c| (c.change, sv_id(&c.talker, c.prn)))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (Change::Lost, "G25".to_owned()),
+                (Change::Acquired, "G31".to_owned())
+            ]
+        );
+        assert!(sat(&sky, 31).just_acquired(next));
+        assert!(!sat(&sky, 31).just_acquired(next + chrono::Duration::seconds(10)));
+        assert_eq!(sat(&sky, 25).lost, Some(next));
+        assert!(!sat(&sky, 2).just_acquired(next) && sat(&sky, 2).lost.is_none());
+        // kept, greyed out, until it expires
+        assert_eq!(sky.len(), 12);
+
+        // back again
+        let later = next + chrono::Duration::seconds(1);
+        feed(&mut sky, later, &[INTERLEAVED[0], INTERLEAVED[2]]);
+        assert_eq!(sky.churn(), (2, 2));
+        assert!(sat(&sky, 25).just_acquired(later));
+        assert_eq!(sat(&sky, 31).lost, Some(later));
+
+        sky.expire(start + chrono::Duration::seconds(CHURN_WINDOW_SECS + 3));
+        assert_eq!(sky.churn(), (0, 0));
+    }
+
+    #[test]
+    fn silent_constellations_expire() {
+        let mut sky = Sky::new();
+        let start = Local.timestamp(1_591_509_873, 0);
+        feed(&mut sky, start, INTERLEAVED);
+        feed(
+            &mut sky,
+            start + chrono::Duration::seconds(8),
+            &[INTERLEAVED[0], INTERLEAVED[2]],
+        );
+
+        sky.expire(start + chrono::Duration::seconds(12));
+        assert_eq!(sky.len(), 5);
+        assert_eq!(prns(&sky, "GP"), vec![2, 5, 12, 25, 29]);
+        // the GSAs expired along with the other constellations
+        assert_eq!(sky.used_count(), 0);
+    }
+}
@@ -0,0 +1,1153 @@
+use std::{
+    collections::VecDeque,
+    fmt, fs,
+    io::{self, BufRead, Write},
+    net::{TcpStream, UdpSocket},
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use flate2::read::GzDecoder;
+
+use crate::{sentence::Sentence, ubx};
+
+/// Where NMEA lines come from
+pub trait Source: Send {
+    /// Next line as received, without its line ending; `None` once the input
+    /// has ended. Lines stay bytes so stray binary data can be inspected.
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>>;
+
+    /// Bytes received so far, line endings and skipped data included
+    fn bytes_read(&self) -> u64;
+
+    /// Bytes dropped so far to find the start of a sentence, after opening
+    /// the source and after each reconnection
+    fn bytes_skipped(&self) -> u64 {
+        0
+    }
+
+    /// What to do with a line once the UI is too far behind. Live sources
+    /// drop old lines to stay current.
+    fn overflow(&self) -> Overflow {
+        Overflow::DropOldest
+    }
+
+    /// A handle for sending to the device this source reads from, taken
+    /// before the source moves to its reader thread. Logs, stdin and UDP
+    /// can't be written to.
+    fn writer(&self) -> io::Result<DeviceWriter> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the source is read-only",
+        ))
+    }
+}
+
+/// Writes lines to the device a source reads from
+pub struct DeviceWriter {
+    inner: Box<dyn Write + Send>,
+}
+
+impl DeviceWriter {
+    pub fn new(inner: impl Write + Send + 'static) -> DeviceWriter {
+        DeviceWriter {
+            inner: Box::new(inner),
+        }
+    }
+
+    /// Sends `line` with a CRLF ending
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.inner.write_all(line.as_bytes())?;
+        self.inner.write_all(b"\r\n")?;
+        self.inner.flush()
+    }
+
+    /// Sends `bytes` as they are, like RTCM corrections
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.inner.write_all(bytes)?;
+        self.inner.flush()
+    }
+}
+
+/// The stream of the current TCP connection, replaced when it is dialed
+/// again
+#[derive(Clone, Default)]
+struct SharedStream(Arc<Mutex<Option<TcpStream>>>);
+
+impl Write for SharedStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut *self.0.lock().unwrap() {
+            Some(stream) => stream.write(buf),
+            None => Err(io::Error::new(io::ErrorKind::NotConnected, "not connected")),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut *self.0.lock().unwrap() {
+            Some(stream) => stream.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Splits a byte stream into lines, counting what goes through. UBX packets
+/// mixed in are passed on whole, line ends in their binary included.
+///
+/// A stream joined in the middle starts with part of a sentence, so bytes
+/// are dropped until a sentence with a valid checksum or a UBX packet; a log
+/// starting with a whole sentence loses nothing.
+struct LineReader<R> {
+    inner: R,
+    bytes: u64,
+    /// bytes dropped before the first sentence
+    skipped: u64,
+    /// set until the first sentence went by
+    syncing: bool,
+}
+
+impl<R: BufRead> LineReader<R> {
+    fn new(inner: R) -> LineReader<R> {
+        LineReader {
+            inner,
+            bytes: 0,
+            skipped: 0,
+            syncing: true,
+        }
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            let mut buf = Vec::new();
+            if ubx::read_packet(&mut self.inner, &mut buf)? {
+                self.bytes += buf.len() as u64;
+                // a packet is whole, so what follows it is too
+                self.syncing = false;
+                return Ok(Some(buf));
+            }
+            self.inner.read_until(b'\n', &mut buf)?;
+            if buf.is_empty() {
+                return Ok(None);
+            }
+            self.bytes += buf.len() as u64;
+            if self.syncing {
+                match sentence_start(&buf) {
+                    Some(start) => {
+                        self.skipped += start as u64;
+                        buf.drain(..start);
+                        self.syncing = false;
+                        log::debug!("first sentence found, {} bytes dropped", self.skipped);
+                    }
+                    None => {
+                        self.skipped += buf.len() as u64;
+                        continue;
+                    }
+                }
+            }
+            while buf.last().is_some_and(|&b| b == b'\n' || b == b'\r') {
+                buf.pop();
+            }
+            return Ok(Some(buf));
+        }
+    }
+}
+
+/// Where in `line` the first sentence with a valid checksum starts
+fn sentence_start(line: &[u8]) -> Option<usize> {
+    line.iter()
+        .enumerate()
+        .filter(|&(_, &b)| b == b'$' || b == b'!')
+        .map(|(i, _)| i)
+        .find(|&i| {
+            std::str::from_utf8(&line[i..])
+                .ok()
+                .and_then(Sentence::parse)
+                .is_some()
+        })
+}
+
+/// NMEA over TCP, e.g. from gpsd's raw port or an ntrip relay. The connection
+/// is dialed again whenever it drops or goes quiet.
+pub struct TcpSource {
+    addr: String,
+    reader: LineReader<io::BufReader<TcpStream>>,
+    stream: SharedStream,
+}
+
+impl TcpSource {
+    /// Silence after which the connection is considered dead
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+    pub fn connect(addr: impl Into<String>) -> io::Result<TcpSource> {
+        let addr = addr.into();
+        let reader = TcpSource::dial(&addr)?;
+        let stream = SharedStream::default();
+        *stream.0.lock().unwrap() = reader.inner.get_ref().try_clone().ok();
+        Ok(TcpSource {
+            addr,
+            reader,
+            stream,
+        })
+    }
+
+    fn dial(addr: &str) -> io::Result<LineReader<io::BufReader<TcpStream>>> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_read_timeout(Some(TcpSource::TIMEOUT))?;
+        Ok(LineReader::new(io::BufReader::new(stream)))
+    }
+}
+
+impl Source for TcpSource {
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            if let Ok(Some(line)) = self.reader.read_line() {
+                return Ok(Some(line));
+            }
+            log::warn!("lost the connection to {}, dialing again", self.addr);
+            let (bytes, skipped) = (self.reader.bytes, self.reader.skipped);
+            loop {
+                thread::sleep(TcpSource::RETRY_DELAY);
+                match TcpSource::dial(&self.addr) {
+                    Ok(reader) => {
+                        log::info!("reconnected to {}", self.addr);
+                        *self.stream.0.lock().unwrap() = reader.inner.get_ref().try_clone().ok();
+                        self.reader = reader;
+                        self.reader.bytes = bytes;
+                        self.reader.skipped = skipped;
+                        break;
+                    }
+                    Err(e) => log::debug!("failed to dial {}: {}", self.addr, e),
+                }
+            }
+        }
+    }
+
+    fn bytes_read(&self) -> u64 {
+        self.reader.bytes
+    }
+
+    fn bytes_skipped(&self) -> u64 {
+        self.reader.skipped
+    }
+
+    fn writer(&self) -> io::Result<DeviceWriter> {
+        Ok(DeviceWriter::new(self.stream.clone()))
+    }
+}
+
+/// NMEA broadcast over UDP, one or more lines per datagram
+pub struct UdpSource {
+    socket: UdpSocket,
+    lines: VecDeque<Vec<u8>>,
+    bytes: u64,
+}
+
+impl UdpSource {
+    /// Largest datagram UDP can carry
+    const MAX_DATAGRAM: usize = 65_535;
+
+    /// Listens on `addr`, like `0.0.0.0:10110`
+    pub fn bind(addr: &str) -> io::Result<UdpSource> {
+        Ok(UdpSource {
+            socket: UdpSocket::bind(addr)?,
+            lines: VecDeque::new(),
+            bytes: 0,
+        })
+    }
+}
+
+impl Source for UdpSource {
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut buf = vec![0; UdpSource::MAX_DATAGRAM];
+        loop {
+            if let Some(line) = self.lines.pop_front() {
+                return Ok(Some(line));
+            }
+            let n = self.socket.recv(&mut buf)?;
+            self.bytes += n as u64;
+            let lines = buf[..n]
+                .split(|&b| b == b'\n')
+                .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+                .filter(|line| !line.is_empty())
+                .map(<[u8]>::to_vec);
+            self.lines.extend(lines);
+        }
+    }
+
+    fn bytes_read(&self) -> u64 {
+        self.bytes
+    }
+}
+
+/// NMEA from a local daemon over a Unix domain socket
+#[cfg(unix)]
+pub struct UnixSource {
+    reader: LineReader<io::BufReader<std::os::unix::net::UnixStream>>,
+}
+
+#[cfg(unix)]
+impl UnixSource {
+    pub fn connect(path: &Path) -> io::Result<UnixSource> {
+        let stream = std::os::unix::net::UnixStream::connect(path)?;
+        Ok(UnixSource {
+            reader: LineReader::new(io::BufReader::new(stream)),
+        })
+    }
+}
+
+#[cfg(unix)]
+impl Source for UnixSource {
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.reader.read_line()
+    }
+
+    fn bytes_read(&self) -> u64 {
+        self.reader.bytes
+    }
+
+    fn bytes_skipped(&self) -> u64 {
+        self.reader.skipped
+    }
+
+    fn writer(&self) -> io::Result<DeviceWriter> {
+        Ok(DeviceWriter::new(self.reader.inner.get_ref().try_clone()?))
+    }
+}
+
+/// A receiver on a serial port, read like a file
+pub struct SerialSource {
+    reader: LineReader<io::BufReader<fs::File>>,
+    /// opened for writing as well, which needs write permission
+    writable: bool,
+}
+
+impl SerialSource {
+    /// Bare Windows port names like `COM12` are turned into `\\.\COM12`, the
+    /// only form accepted for ports above COM9. Without write permission the
+    /// port is only read.
+    pub fn open(dev: &Path) -> io::Result<SerialSource> {
+        let path = match com_port(dev) {
+            Some(name) => PathBuf::from(format!(r"\\.\{}", name)),
+            None => dev.to_owned(),
+        };
+        let (file, writable) = match fs::OpenOptions::new().read(true).write(true).open(&path) {
+            Ok(file) => (file, true),
+            Err(_) => (fs::File::open(&path)?, false),
+        };
+        Ok(SerialSource {
+            reader: LineReader::new(io::BufReader::new(file)),
+            writable,
+        })
+    }
+}
+
+impl Source for SerialSource {
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.reader.read_line()
+    }
+
+    fn bytes_read(&self) -> u64 {
+        self.reader.bytes
+    }
+
+    fn bytes_skipped(&self) -> u64 {
+        self.reader.skipped
+    }
+
+    fn writer(&self) -> io::Result<DeviceWriter> {
+        if !self.writable {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "the serial port is not writable",
+            ));
+        }
+        Ok(DeviceWriter::new(self.reader.inner.get_ref().try_clone()?))
+    }
+}
+
+fn com_port(dev: &Path) -> Option<String> {
+    let name = dev.to_string_lossy();
+    let is_com_port = cfg!(windows)
+        && name.len() > 3
+        && name.get(..3).is_some_and(|p| p.eq_ignore_ascii_case("com"))
+        && name[3..].bytes().all(|b| b.is_ascii_digit());
+    if is_com_port {
+        Some(name.into_owned())
+    } else {
+        None
+    }
+}
+
+#[cfg(unix)]
+fn is_serial(dev: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    fs::metadata(dev).is_ok_and(|m| m.file_type().is_char_device())
+}
+
+#[cfg(not(unix))]
+fn is_serial(dev: &Path) -> bool {
+    com_port(dev).is_some()
+}
+
+/// A recorded log, optionally gzip-compressed
+pub struct FileSource {
+    reader: LineReader<io::BufReader<Box<dyn io::Read + Send>>>,
+}
+
+impl FileSource {
+    pub fn open(path: &Path, gzip: bool) -> io::Result<FileSource> {
+        let file = fs::File::open(path)?;
+        let read: Box<dyn io::Read + Send> = if gzip {
+            Box::new(GzDecoder::new(file))
+        } else {
+            Box::new(file)
+        };
+        Ok(FileSource {
+            reader: LineReader::new(io::BufReader::new(read)),
+        })
+    }
+}
+
+impl Source for FileSource {
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.reader.read_line()
+    }
+
+    fn bytes_read(&self) -> u64 {
+        self.reader.bytes
+    }
+
+    fn bytes_skipped(&self) -> u64 {
+        self.reader.skipped
+    }
+
+    fn overflow(&self) -> Overflow {
+        Overflow::Wait
+    }
+}
+
+/// Lines piped into nmeacli
+pub struct StdinSource {
+    reader: LineReader<io::BufReader<io::Stdin>>,
+}
+
+impl StdinSource {
+    pub fn new() -> StdinSource {
+        StdinSource {
+            reader: LineReader::new(io::BufReader::new(io::stdin())),
+        }
+    }
+}
+
+impl Default for StdinSource {
+    fn default() -> StdinSource {
+        StdinSource::new()
+    }
+}
+
+impl Source for StdinSource {
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.reader.read_line()
+    }
+
+    fn bytes_read(&self) -> u64 {
+        self.reader.bytes
+    }
+
+    fn bytes_skipped(&self) -> u64 {
+        self.reader.skipped
+    }
+
+    fn overflow(&self) -> Overflow {
+        Overflow::Wait
+    }
+}
+
+/// Canned lines, for tests and demos
+pub struct VecSource {
+    lines: VecDeque<String>,
+    bytes: u64,
+}
+
+impl VecSource {
+    pub fn new<S: Into<String>>(lines: impl IntoIterator<Item = S>) -> VecSource {
+        VecSource {
+            lines: lines.into_iter().map(Into::into).collect(),
+            bytes: 0,
+        }
+    }
+}
+
+impl Source for VecSource {
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let line = self.lines.pop_front();
+        if let Some(line) = &line {
+            self.bytes += line.len() as u64 + 2;
+        }
+        Ok(line.map(String::into_bytes))
+    }
+
+    fn bytes_read(&self) -> u64 {
+        self.bytes
+    }
+
+    fn overflow(&self) -> Overflow {
+        Overflow::Wait
+    }
+}
+
+/// Opens `dev`, telling serial ports and recorded logs apart; only logs are
+/// ever decompressed
+pub fn open_device(dev: &Path, gzip: bool) -> io::Result<Box<dyn Source>> {
+    if is_serial(dev) {
+        Ok(Box::new(SerialSource::open(dev)?))
+    } else {
+        Ok(Box::new(FileSource::open(dev, gzip)?))
+    }
+}
+
+/// When recorded logs are decompressed
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Gzip {
+    /// for names ending in `.gz`
+    Auto,
+    Always,
+    Never,
+}
+
+impl Gzip {
+    pub fn applies_to(self, dev: &Path) -> bool {
+        match self {
+            Gzip::Auto => dev
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("gz")),
+            Gzip::Always => true,
+            Gzip::Never => false,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_unix_socket(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    fs::metadata(path).is_ok_and(|m| m.file_type().is_socket())
+}
+
+#[cfg(not(unix))]
+fn is_unix_socket(_path: &Path) -> bool {
+    false
+}
+
+/// `host:port`, which names no file
+fn is_host_port(spec: &str) -> bool {
+    match spec.rsplit_once(':') {
+        Some((host, port)) => !host.is_empty() && port.parse::<u16>().is_ok(),
+        None => false,
+    }
+}
+
+/// Where to read from
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceSpec {
+    Tcp(String),
+    /// address to listen on
+    Udp(String),
+    Unix(PathBuf),
+    /// a serial port or a recorded log
+    Device(PathBuf),
+    Stdin,
+}
+
+impl SourceSpec {
+    /// Tells the kind of source from `spec`: `-` is stdin, `tcp://` and
+    /// `udp://` prefixes say so, existing paths are serial ports, sockets or
+    /// logs, and `host:port` is TCP
+    pub fn detect(spec: &str) -> io::Result<SourceSpec> {
+        if spec == "-" {
+            return Ok(SourceSpec::Stdin);
+        }
+        if let Some(addr) = spec.strip_prefix("tcp://") {
+            return Ok(SourceSpec::Tcp(addr.to_owned()));
+        }
+        if let Some(addr) = spec.strip_prefix("udp://") {
+            return Ok(SourceSpec::Udp(addr.to_owned()));
+        }
+        let path = Path::new(spec);
+        if is_unix_socket(path) {
+            return Ok(SourceSpec::Unix(path.to_owned()));
+        }
+        if path.exists() || com_port(path).is_some() {
+            return Ok(SourceSpec::Device(path.to_owned()));
+        }
+        if is_host_port(spec) {
+            return Ok(SourceSpec::Tcp(spec.to_owned()));
+        }
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is neither an existing path nor host:port", spec),
+        ))
+    }
+
+    pub fn open(&self, gzip: Gzip) -> io::Result<Box<dyn Source>> {
+        match self {
+            SourceSpec::Tcp(addr) => Ok(Box::new(TcpSource::connect(addr.as_str())?)),
+            SourceSpec::Udp(addr) => Ok(Box::new(UdpSource::bind(addr)?)),
+            #[cfg(unix)]
+            SourceSpec::Unix(path) => Ok(Box::new(UnixSource::connect(path)?)),
+            #[cfg(not(unix))]
+            SourceSpec::Unix(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Unix domain sockets are not supported on this platform",
+            )),
+            SourceSpec::Device(dev) => open_device(dev, gzip.applies_to(dev)),
+            SourceSpec::Stdin => Ok(Box::new(StdinSource::new())),
+        }
+    }
+}
+
+impl fmt::Display for SourceSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SourceSpec::Tcp(addr) => write!(f, "tcp://{}", addr),
+            SourceSpec::Udp(addr) => write!(f, "udp://{}", addr),
+            SourceSpec::Unix(path) | SourceSpec::Device(path) => write!(f, "{}", path.display()),
+            SourceSpec::Stdin => write!(f, "stdin"),
+        }
+    }
+}
+
+/// Opens whatever `spec` names, see `SourceSpec::detect`
+pub fn open_source(spec: &str, gzip: Gzip) -> io::Result<Box<dyn Source>> {
+    SourceSpec::detect(spec)?.open(gzip)
+}
+
+/// A line as sent by `spawn_reader`
+#[derive(Debug, Clone)]
+pub struct Line {
+    /// which reader, 0 for the first source
+    pub id: usize,
+    pub local: DateTime<Local>,
+    /// taken when the line was read, before it waited in the channel
+    pub arrival: Instant,
+    pub raw: Vec<u8>,
+    /// bytes the source dropped to find a sentence since the previous line
+    pub skipped: u64,
+}
+
+/// Why a source stopped
+#[derive(Debug, Clone, PartialEq)]
+pub enum End {
+    /// the input ended, like a log at its end or a socket closed by the peer
+    Eof,
+    /// reading failed, like a USB receiver unplugged
+    Error(String),
+}
+
+impl fmt::Display for End {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            End::Eof => write!(f, "ended (EOF)"),
+            End::Error(e) => write!(f, "failed ({})", e),
+        }
+    }
+}
+
+/// What `spawn_reader` sends
+#[derive(Debug, Clone)]
+pub enum SourceEvent {
+    Line(Line),
+    /// the last event of reader `id`
+    End {
+        id: usize,
+        end: End,
+    },
+}
+
+/// What a reader does with a line while the queue is full
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overflow {
+    /// wait for room, so a log is read no faster than it is shown
+    Wait,
+    /// drop the oldest line waiting, so what is shown keeps up with a
+    /// receiver even when rendering stalls
+    DropOldest,
+}
+
+/// A queue of events from the reader threads to the UI, holding a bounded
+/// number of lines
+pub fn line_queue(capacity: usize) -> (LineSender, LineReceiver) {
+    let queue = Arc::new(Queue {
+        state: Mutex::new(QueueState {
+            events: VecDeque::new(),
+            lines: 0,
+            dropped: 0,
+            senders: 1,
+            receiving: true,
+        }),
+        room: Condvar::new(),
+        filled: Condvar::new(),
+    });
+    let sender = LineSender {
+        queue: queue.clone(),
+        capacity,
+    };
+    (sender, LineReceiver { queue })
+}
+
+struct Queue {
+    state: Mutex<QueueState>,
+    /// signalled when a line is taken or the receiver is gone
+    room: Condvar,
+    /// signalled when an event is queued or a sender is gone
+    filled: Condvar,
+}
+
+struct QueueState {
+    events: VecDeque<SourceEvent>,
+    /// lines among `events`, which unlike the ends of sources are bounded
+    lines: usize,
+    /// lines dropped since the queue was made
+    dropped: u64,
+    senders: usize,
+    receiving: bool,
+}
+
+impl QueueState {
+    fn push(&mut self, event: SourceEvent) {
+        if let SourceEvent::Line(_) = event {
+            self.lines += 1;
+        }
+        self.events.push_back(event);
+    }
+
+    fn pop(&mut self) -> Option<SourceEvent> {
+        let event = self.events.pop_front()?;
+        if let SourceEvent::Line(_) = event {
+            self.lines -= 1;
+        }
+        Some(event)
+    }
+}
+
+pub struct LineSender {
+    queue: Arc<Queue>,
+    capacity: usize,
+}
+
+impl LineSender {
+    /// Queues `event`, treating a full queue as `overflow` says; the end of
+    /// a source is never dropped. `false` once nobody listens anymore.
+    pub fn send(&self, event: SourceEvent, overflow: Overflow) -> bool {
+        let mut state = self.queue.state.lock().unwrap();
+        if let SourceEvent::Line(_) = event {
+            while state.receiving && state.lines >= self.capacity.max(1) {
+                match overflow {
+                    Overflow::Wait => state = self.queue.room.wait(state).unwrap(),
+                    Overflow::DropOldest => {
+                        let oldest = state
+                            .events
+                            .iter()
+                            .position(|event| matches!(event, SourceEvent::Line(_)));
+                        if let Some(i) = oldest {
+                            state.events.remove(i);
+                            state.lines -= 1;
+                            state.dropped += 1;
+                        }
+                    }
+                }
+            }
+        }
+        if !state.receiving {
+            return false;
+        }
+        state.push(event);
+        self.queue.filled.notify_one();
+        true
+    }
+}
+
+impl Clone for LineSender {
+    fn clone(&self) -> LineSender {
+        self.queue.state.lock().unwrap().senders += 1;
+        LineSender {
+            queue: self.queue.clone(),
+            capacity: self.capacity,
+        }
+    }
+}
+
+impl Drop for LineSender {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().senders -= 1;
+        self.queue.filled.notify_all();
+    }
+}
+
+pub struct LineReceiver {
+    queue: Arc<Queue>,
+}
+
+impl LineReceiver {
+    /// The next event if one is waiting
+    pub fn try_recv(&self) -> Option<SourceEvent> {
+        let event = self.queue.state.lock().unwrap().pop();
+        self.queue.room.notify_all();
+        event
+    }
+
+    pub fn try_iter(&self) -> impl Iterator<Item = SourceEvent> + '_ {
+        std::iter::from_fn(move || self.try_recv())
+    }
+
+    /// Waits for the next event; `None` once every sender is gone and the
+    /// queue is empty
+    pub fn recv(&self) -> Option<SourceEvent> {
+        let mut state = self.queue.state.lock().unwrap();
+        loop {
+            if let Some(event) = state.pop() {
+                self.queue.room.notify_all();
+                return Some(event);
+            }
+            if state.senders == 0 {
+                return None;
+            }
+            state = self.queue.filled.wait(state).unwrap();
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = SourceEvent> + '_ {
+        std::iter::from_fn(move || self.recv())
+    }
+
+    /// Lines dropped so far because the UI was too slow to take them
+    pub fn dropped(&self) -> u64 {
+        self.queue.state.lock().unwrap().dropped
+    }
+
+    /// Events waiting to be taken
+    pub fn len(&self) -> usize {
+        self.queue.state.lock().unwrap().events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for LineReceiver {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().receiving = false;
+        self.queue.room.notify_all();
+    }
+}
+
+/// Reads `source` on a thread of its own and sends each line with `id`, so
+/// several readers can share a queue, and its arrival time. When the source
+/// ends or fails the thread says why and ends; it also ends when nobody
+/// listens anymore.
+///
+/// While the queue is full the thread does as the source's `overflow` says:
+/// a log waits, so it is read no faster than it is shown, and a receiver
+/// drops its oldest lines rather than have the screen fall behind.
+pub fn spawn_reader(
+    id: usize,
+    mut source: Box<dyn Source>,
+    tx: LineSender,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut skipped = 0;
+        let end = loop {
+            let raw = match source.read_line() {
+                Ok(Some(raw)) => raw,
+                Ok(None) => break End::Eof,
+                Err(e) => break End::Error(e.to_string()),
+            };
+            log::trace!("[{}] {}", id + 1, String::from_utf8_lossy(&raw));
+            let line = Line {
+                id,
+                local: Local::now(),
+                arrival: Instant::now(),
+                raw,
+                skipped: source.bytes_skipped() - skipped,
+            };
+            skipped = source.bytes_skipped();
+            if !tx.send(SourceEvent::Line(line), source.overflow()) {
+                return;
+            }
+        };
+        tx.send(SourceEvent::End { id, end }, Overflow::Wait);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use super::*;
+    use crate::sentence::with_checksum;
+
+    #[test]
+    fn tcp_source_writes_back_to_the_connection() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let source = TcpSource::connect(addr).unwrap();
+        let (server, _) = listener.accept().unwrap();
+
+        source
+            .writer()
+            .unwrap()
+            .write_line("$PMTK220,100*2F")
+            .unwrap();
+        let mut line = String::new();
+        io::BufReader::new(server).read_line(&mut line).unwrap();
+        assert_eq!(line, "$PMTK220,100*2F\r\n");
+
+        let log = VecSource::new(vec!["$GPGGA,1"]);
+        assert!(log.writer().is_err());
+    }
+
+    #[test]
+    fn reader_keeps_order_and_ends_with_source() {
+        let source = VecSource::new(vec!["$GPGGA,1", "$GPRMC,2", "$GPGSV,3"]);
+        let (tx, rx) = line_queue(1);
+        let handle = spawn_reader(1, Box::new(source), tx);
+
+        let mut lines = Vec::new();
+        let mut ends = Vec::new();
+        for event in rx.iter() {
+            match event {
+                SourceEvent::Line(line) => lines.push((line.id, line.raw)),
+                SourceEvent::End { id, end } => ends.push((id, end)),
+            }
+        }
+        assert_eq!(
+            lines,
+            vec![
+                (1, b"$GPGGA,1".to_vec()),
+                (1, b"$GPRMC,2".to_vec()),
+                (1, b"$GPGSV,3".to_vec())
+            ]
+        );
+        assert_eq!(ends, vec![(1, End::Eof)]);
+        handle.join().unwrap();
+    }
+
+    /// A line, then a read error like that of an unplugged device
+    struct Unplugged(bool);
+
+    impl Source for Unplugged {
+        fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+            if std::mem::replace(&mut self.0, true) {
+                return Err(io::Error::other("device gone"));
+            }
+            Ok(Some(b"$GPGGA,1".to_vec()))
+        }
+
+        fn bytes_read(&self) -> u64 {
+            0
+        }
+    }
+
+    #[test]
+    fn reader_tells_why_the_source_stopped() {
+        let (tx, rx) = line_queue(1);
+        spawn_reader(0, Box::new(Unplugged(false)), tx);
+        let end = rx.iter().find_map(|event| match event {
+            SourceEvent::End { end, .. } => Some(end),
+            SourceEvent::Line(_) => None,
+        });
+        let end = end.unwrap();
+        assert_eq!(end, End::Error("device gone".to_owned()));
+        assert_eq!(end.to_string(), "failed (device gone)");
+    }
+
+    #[test]
+    fn slow_ui_drops_the_oldest_lines_of_a_receiver() {
+        let lines: Vec<String> = (0..1000).map(|i| format!("$GPGGA,{}", i)).collect();
+        let (tx, rx) = line_queue(16);
+        // nothing is taken until the source ended
+        spawn_reader(0, Box::new(Live(VecSource::new(lines))), tx)
+            .join()
+            .unwrap();
+        assert_eq!(rx.len(), 17);
+        assert_eq!(rx.dropped(), 984);
+
+        let events: Vec<SourceEvent> = rx.iter().collect();
+        match &events[0] {
+            SourceEvent::Line(line) => assert_eq!(line.raw, b"$GPGGA,984"),
+            event => panic!("{:?}", event),
+        }
+        assert!(matches!(events[16], SourceEvent::End { end: End::Eof, .. }));
+    }
+
+    /// Canned lines read as if from a receiver
+    struct Live(VecSource);
+
+    impl Source for Live {
+        fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+            self.0.read_line()
+        }
+
+        fn bytes_read(&self) -> u64 {
+            self.0.bytes_read()
+        }
+    }
+
+    #[test]
+    fn reader_stops_when_receiver_is_gone() {
+        let (tx, rx) = line_queue(1);
+        drop(rx);
+        let handle = spawn_reader(0, Box::new(VecSource::new(vec!["a", "b"])), tx);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn specs_are_told_apart() {
+        let detect = |spec| SourceSpec::detect(spec).unwrap();
+        assert_eq!(detect("-"), SourceSpec::Stdin);
+        assert_eq!(
+            detect("udp://0.0.0.0:10110"),
+            SourceSpec::Udp("0.0.0.0:10110".to_owned())
+        );
+        assert_eq!(
+            detect("localhost:10021"),
+            SourceSpec::Tcp("localhost:10021".to_owned())
+        );
+        assert_eq!(
+            detect("example.txt"),
+            SourceSpec::Device(PathBuf::from("example.txt"))
+        );
+        assert!(SourceSpec::detect("no-such-log.nmea").is_err());
+    }
+
+    #[test]
+    fn udp_datagrams_are_split_into_lines() {
+        let mut source = UdpSource::bind("127.0.0.1:0").unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender
+            .send_to(
+                b"$GPGGA,1\r\n$GPRMC,2\r\n",
+                source.socket.local_addr().unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            source.read_line().unwrap().as_deref(),
+            Some(&b"$GPGGA,1"[..])
+        );
+        assert_eq!(
+            source.read_line().unwrap().as_deref(),
+            Some(&b"$GPRMC,2"[..])
+        );
+        assert_eq!(source.bytes_read(), 20);
+    }
+
+    #[test]
+    fn line_reader_drops_what_precedes_the_first_sentence() {
+        let gga = with_checksum("GPGGA,1");
+        let rmc = with_checksum("GPRMC,2");
+        // joined in the middle of a sentence, then a line with noise in front
+        let data = format!("GA,0*4A\r\n$GPG\x00\x01{}\r\n{}\n\u{ff}\n", gga, rmc);
+        let mut reader = LineReader::new(data.as_bytes());
+        assert_eq!(reader.read_line().unwrap(), Some(gga.into_bytes()));
+        assert_eq!(reader.skipped, 15);
+        assert_eq!(reader.read_line().unwrap(), Some(rmc.into_bytes()));
+        // once in sync, garbled bytes are passed on as they are
+        assert_eq!(
+            reader.read_line().unwrap().as_deref(),
+            Some("\u{ff}".as_bytes())
+        );
+        assert_eq!(reader.read_line().unwrap(), None);
+        assert_eq!(reader.bytes, data.len() as u64);
+        assert_eq!(reader.skipped, 15);
+    }
+
+    #[test]
+    fn line_reader_keeps_the_first_sentence_of_a_log() {
+        let data = include_str!("../example.txt");
+        let mut reader = LineReader::new(data.as_bytes());
+        let first = reader.read_line().unwrap().unwrap();
+        assert_eq!(first, data.lines().next().unwrap().as_bytes());
+        assert_eq!(reader.skipped, 0);
+
+        // nothing but noise is dropped whole
+        let mut reader = LineReader::new(&b"$GPGGA,1*00\r\nxyz"[..]);
+        assert_eq!(reader.read_line().unwrap(), None);
+        assert_eq!(reader.skipped, 16);
+    }
+
+    #[test]
+    fn line_reader_passes_ubx_packets_whole() {
+        // an ACK-ACK for MON-VER, whose class is a line end
+        let ack = ubx::packet(ubx::CLASS_ACK, 0x01, &[ubx::CLASS_MON, ubx::ID_MON_VER]);
+        let gga = with_checksum("GPGGA,1");
+        let mut data = format!("{}\r\n", gga).into_bytes();
+        data.extend(&ack);
+        data.extend(&ack);
+        data.extend(b"$GPRMC,2\r\n\xb5\x62\x05");
+        let mut reader = LineReader::new(&data[..]);
+        assert_eq!(reader.read_line().unwrap(), Some(gga.into_bytes()));
+        assert_eq!(reader.read_line().unwrap(), Some(ack.clone()));
+        assert_eq!(reader.read_line().unwrap(), Some(ack));
+        assert_eq!(
+            reader.read_line().unwrap().as_deref(),
+            Some(&b"$GPRMC,2"[..])
+        );
+        // cut off, so passed on as it is
+        assert_eq!(
+            reader.read_line().unwrap().as_deref(),
+            Some(&b"\xb5\x62\x05"[..])
+        );
+        assert_eq!(reader.read_line().unwrap(), None);
+        assert_eq!(reader.bytes, data.len() as u64);
+    }
+
+    fn example_line() -> impl Strategy<Value = &'static str> {
+        proptest::sample::select(include_str!("../example.txt").lines().collect::<Vec<_>>())
+    }
+
+    /// Bytes which neither start a sentence or a UBX packet nor end a line
+    fn noise() -> impl Strategy<Value = Vec<u8>> {
+        let byte = any::<u8>().prop_filter("no start", |b| !b"$!\xb5\n".contains(b));
+        vec(byte, 0..80)
+    }
+
+    proptest! {
+        #[test]
+        fn line_reader_recovers_sentences_from_noise(
+            before in vec(noise(), 0..4),
+            front in noise(),
+            lines in vec((example_line(), noise()), 1..20),
+        ) {
+            // noise lines first, then noise in front of the first sentence
+            // and a line of it after each
+            let mut data = Vec::new();
+            for noise in &before {
+                data.extend(noise);
+                data.push(b'\n');
+            }
+            data.extend(&front);
+            for (sentence, noise) in &lines {
+                data.extend(sentence.as_bytes());
+                data.extend(b"\r\n");
+                data.extend(noise);
+                data.push(b'\n');
+            }
+
+            let mut reader = LineReader::new(&data[..]);
+            let mut sentences = Vec::new();
+            while let Some(line) = reader.read_line().unwrap() {
+                let line = String::from_utf8_lossy(&line);
+                if Sentence::parse(&line).is_some() {
+                    sentences.push(line.into_owned());
+                }
+            }
+            let expected: Vec<&str> = lines.iter().map(|(sentence, _)| *sentence).collect();
+            prop_assert_eq!(sentences, expected);
+            prop_assert_eq!(reader.bytes, data.len() as u64);
+        }
+    }
+}
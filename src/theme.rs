@@ -0,0 +1,160 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use tui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeName {
+    Dark,
+    Light,
+    Mono,
+}
+
+/// Colors of the UI; `None` leaves the terminal's default untouched, which is
+/// all the mono theme ever does
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    timestamp: Option<Color>,
+    border: Option<Color>,
+    title: Option<Color>,
+    good: Option<Color>,
+    warn: Option<Color>,
+    bad: Option<Color>,
+    /// stale values and the track history
+    dim: Option<Color>,
+}
+
+impl Theme {
+    pub fn new(name: ThemeName) -> Theme {
+        match name {
+            ThemeName::Dark => Theme {
+                timestamp: Some(Color::Gray),
+                border: Some(Color::DarkGray),
+                title: Some(Color::Cyan),
+                good: Some(Color::Green),
+                warn: Some(Color::Yellow),
+                bad: Some(Color::LightRed),
+                dim: Some(Color::DarkGray),
+            },
+            ThemeName::Light => Theme {
+                timestamp: Some(Color::DarkGray),
+                border: Some(Color::Gray),
+                title: Some(Color::Blue),
+                good: Some(Color::Green),
+                warn: Some(Color::Magenta),
+                bad: Some(Color::Red),
+                dim: Some(Color::Gray),
+            },
+            ThemeName::Mono => Theme {
+                timestamp: None,
+                border: None,
+                title: None,
+                good: None,
+                warn: None,
+                bad: None,
+                dim: None,
+            },
+        }
+    }
+
+    fn style(color: Option<Color>) -> Style {
+        match color {
+            Some(color) => Style::default().fg(color),
+            None => Style::default(),
+        }
+    }
+
+    pub fn timestamp(&self) -> Style {
+        Theme::style(self.timestamp)
+    }
+
+    pub fn good(&self) -> Style {
+        Theme::style(self.good)
+    }
+
+    pub fn bad(&self) -> Style {
+        Theme::style(self.bad)
+    }
+
+    pub fn warn(&self) -> Style {
+        Theme::style(self.warn)
+    }
+
+    pub fn dim(&self) -> Style {
+        Theme::style(self.dim).add_modifier(Modifier::DIM)
+    }
+
+    /// Top line of the panel clicked last
+    pub fn focused(&self) -> Style {
+        Theme::style(self.title).add_modifier(Modifier::BOLD)
+    }
+
+    /// Canvas shapes take a plain color
+    fn color(color: Option<Color>) -> Color {
+        color.unwrap_or(Color::Reset)
+    }
+
+    pub fn warn_color(&self) -> Color {
+        Theme::color(self.warn)
+    }
+
+    pub fn dim_color(&self) -> Color {
+        Theme::color(self.dim)
+    }
+
+    /// A block whose title reports a problem
+    pub fn alert_block(&self, title: impl Into<String>) -> Block<'static> {
+        self.block("")
+            .title(Span::styled(title.into(), Theme::style(self.bad)))
+    }
+
+    pub fn block(&self, title: impl Into<String>) -> Block<'static> {
+        Block::default()
+            .title(Span::styled(title.into(), Theme::style(self.title)))
+            .borders(Borders::TOP)
+            .border_style(Theme::style(self.border))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tui::{backend::TestBackend, Terminal};
+
+    use super::*;
+    use crate::{app::App, keys::KeyMap, prefs::Prefs, ui, Options};
+
+    #[test]
+    fn mono_leaves_every_cell_uncolored() {
+        let mut app = App::new(
+            Options::default(),
+            Prefs::default(),
+            Theme::new(ThemeName::Mono),
+            KeyMap::default(),
+        );
+        app.prefs.panels = "status,sky+gsa+track,rates+latency+text,messages"
+            .parse()
+            .unwrap();
+        for line in include_str!("../example.txt").lines() {
+            app.handle_line(line);
+        }
+        // a rejected line, an alert and a notice
+        app.handle_line("$GPGGA,garbage*00");
+        app.alert("alert".to_owned());
+        app.notice("notice".to_owned());
+
+        for zen in [false, true] {
+            app.zen = zen;
+            let mut terminal = Terminal::new(TestBackend::new(120, 60)).unwrap();
+            terminal.draw(|f| ui::draw(f, &app)).unwrap();
+            let buffer = terminal.backend().buffer();
+            for cell in &buffer.content {
+                assert_eq!(cell.fg, Color::Reset, "{:?}", cell);
+                assert_eq!(cell.bg, Color::Reset, "{:?}", cell);
+            }
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Time to first fix: from the first sentence after the source was opened,
+//! or from a restart command sent to the receiver, to its first valid fix.
+//! The number receivers are compared by, after each kind of start.
+
+use std::{collections::VecDeque, fmt};
+
+use chrono::{DateTime, Local};
+
+use crate::sentence::Sentence;
+
+/// Measurements kept, newest first
+pub const HISTORY: usize = 10;
+
+/// How much the receiver was told to forget when restarting
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StartType {
+    /// all navigation data kept
+    Hot,
+    /// the ephemeris cleared
+    Warm,
+    /// all navigation data cleared
+    Cold,
+    /// a cold start which also restores the factory settings
+    FullCold,
+}
+
+impl fmt::Display for StartType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            StartType::Hot => "hot",
+            StartType::Warm => "warm",
+            StartType::Cold => "cold",
+            StartType::FullCold => "full cold",
+        })
+    }
+}
+
+/// The start a command sent to the receiver makes, like cold for `PMTK103`
+pub fn restart_command(body: &str) -> Option<StartType> {
+    match body.split(',').next()? {
+        "PMTK101" => Some(StartType::Hot),
+        "PMTK102" => Some(StartType::Warm),
+        "PMTK103" => Some(StartType::Cold),
+        "PMTK104" => Some(StartType::FullCold),
+        _ => None,
+    }
+}
+
+/// Whether a sentence is one receivers print when they start: PMTK010,001
+/// of MediaTek, or the TXT naming u-blox which opens the boot messages, in
+/// whichever case the firmware writes it
+pub fn boot_message(sentence: &Sentence) -> bool {
+    match sentence.name() {
+        "MTK010" => sentence.field(0) == Some("001"),
+        "TXT" => sentence
+            .field(3)
+            .is_some_and(|text| text.to_ascii_lowercase().starts_with("u-blox ag")),
+        _ => false,
+    }
+}
+
+/// Whether a sentence tells of a fix: RMC with status A and a mode other
+/// than N, or GGA with a quality of 1 or more. `None` for the others.
+pub fn sentence_fix(sentence: &Sentence) -> Option<bool> {
+    match sentence.kind {
+        "RMC" => Some(sentence.field(1) == Some("A") && sentence.field(11) != Some("N")),
+        "GGA" => Some(sentence.parse_field::<u8>(5).unwrap_or(0) >= 1),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    /// `None` when the receiver started on its own, or was powered up
+    pub start_type: Option<StartType>,
+    /// the first sentence, or when the restart command was sent
+    pub started: DateTime<Local>,
+    pub seconds: f64,
+    /// used in the first fix, as the receiver last told
+    pub satellites: Option<u32>,
+}
+
+impl Measurement {
+    /// `31.2 s after a cold start, 9 satellites`
+    pub fn describe(&self) -> String {
+        let start = match self.start_type {
+            Some(start_type) => format!("a {} start", start_type),
+            None => "the first sentence".to_owned(),
+        };
+        let mut text = format!("{:.1} s after {}", self.seconds, start);
+        if let Some(satellites) = self.satellites {
+            text.push_str(&format!(", {} satellites", satellites));
+        }
+        text
+    }
+}
+
+/// What a sentence did to the measurement
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Fix(Measurement),
+    /// the first sentence after the source was opened had a fix already,
+    /// so there was nothing to time
+    AlreadyFixed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// waiting for the first sentence of the source
+    Opened,
+    /// a restart command went out; fixes are taken to be from before the
+    /// restart until the receiver reports none
+    Restarting(Option<StartType>, DateTime<Local>),
+    /// timing, until the first fix
+    Waiting(Option<StartType>, DateTime<Local>),
+    Done,
+}
+
+#[derive(Debug, Clone)]
+pub struct Ttff {
+    state: State,
+    /// newest first
+    pub history: VecDeque<Measurement>,
+}
+
+impl Default for Ttff {
+    fn default() -> Ttff {
+        Ttff {
+            state: State::Opened,
+            history: VecDeque::with_capacity(HISTORY),
+        }
+    }
+}
+
+impl Ttff {
+    /// The source was opened again: timing starts over at its first
+    /// sentence, the history is kept
+    pub fn opened(&mut self) {
+        self.state = State::Opened;
+    }
+
+    /// A restart command was sent at `local`
+    pub fn command_sent(&mut self, local: DateTime<Local>, start_type: StartType) {
+        self.state = State::Restarting(Some(start_type), local);
+    }
+
+    /// The receiver printed its boot messages, restarted by a command or
+    /// by someone else
+    pub fn booted(&mut self, local: DateTime<Local>) {
+        self.state = match self.state {
+            State::Restarting(start_type, sent) => State::Waiting(start_type, sent),
+            _ => State::Waiting(None, local),
+        };
+    }
+
+    /// Since when and after what start a fix is being waited for
+    pub fn waiting(&self) -> Option<(Option<StartType>, DateTime<Local>)> {
+        match self.state {
+            State::Restarting(start_type, since) | State::Waiting(start_type, since) => {
+                Some((start_type, since))
+            }
+            State::Opened | State::Done => None,
+        }
+    }
+
+    /// Feeds a sentence which arrived at `local`, with the satellites the
+    /// receiver uses once it was parsed
+    pub fn push(
+        &mut self,
+        local: DateTime<Local>,
+        sentence: &Sentence,
+        satellites: Option<u32>,
+    ) -> Option<Outcome> {
+        let fix = sentence_fix(sentence);
+        match (self.state, fix) {
+            (State::Opened, Some(true)) => {
+                self.state = State::Done;
+                return Some(Outcome::AlreadyFixed);
+            }
+            (State::Opened, _) => self.state = State::Waiting(None, local),
+            (State::Restarting(start_type, sent), Some(false)) => {
+                self.state = State::Waiting(start_type, sent);
+            }
+            (State::Waiting(start_type, started), Some(true)) => {
+                self.state = State::Done;
+                let measurement = Measurement {
+                    start_type,
+                    started,
+                    seconds: (local - started).num_milliseconds() as f64 / 1000.0,
+                    satellites,
+                };
+                self.history.push_front(measurement.clone());
+                self.history.truncate(HISTORY);
+                return Some(Outcome::Fix(measurement));
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    const NO_FIX: &str = "$GPGGA,060436.00,,,,,0,00,99.99,,,,,,*61";
+    const FIX: &str = "$GPGGA,060437.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,*6F";
+
+    #[test]
+    fn fixes_are_timed_from_the_start_or_the_restart() {
+        let start = Local.timestamp(1_591_509_873, 0);
+        let at = |secs| start + Duration::seconds(secs);
+        let no_fix = Sentence::parse(NO_FIX).unwrap();
+        let fix = Sentence::parse(FIX).unwrap();
+        let gsv = Sentence::parse("$GPGSV,1,1,01,05,,,23*7C").unwrap();
+
+        let mut ttff = Ttff::default();
+        assert_eq!(ttff.push(start, &gsv, None), None);
+        assert_eq!(ttff.waiting(), Some((None, start)));
+        assert_eq!(ttff.push(at(10), &no_fix, None), None);
+        let outcome = ttff.push(at(32), &fix, Some(4));
+        let measurement = match outcome {
+            Some(Outcome::Fix(measurement)) => measurement,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(measurement.seconds, 32.0);
+        assert_eq!(
+            measurement.describe(),
+            "32.0 s after the first sentence, 4 satellites"
+        );
+        assert_eq!(ttff.push(at(33), &fix, Some(4)), None);
+
+        // a restart by someone else, like ubloxcli reset
+        let banner = Sentence::parse("$GPTXT,01,01,02,u-blox AG - www.u-blox.com*50").unwrap();
+        assert!(boot_message(&banner));
+        // as older firmware writes it
+        let banner = Sentence::parse("$GPTXT,01,01,02,u-blox ag - www.u-blox.com*50").unwrap();
+        assert!(boot_message(&banner));
+        ttff.booted(at(34));
+        assert_eq!(ttff.waiting(), Some((None, at(34))));
+        assert!(ttff.push(at(35), &fix, Some(4)).is_some());
+
+        // fixes still in the pipe when the command went out don't count
+        assert_eq!(restart_command("PMTK103"), Some(StartType::Cold));
+        ttff.command_sent(at(40), StartType::Cold);
+        assert_eq!(ttff.push(at(40), &fix, Some(4)), None);
+        assert_eq!(ttff.push(at(42), &no_fix, None), None);
+        assert!(matches!(
+            ttff.push(at(75), &fix, Some(6)),
+            Some(Outcome::Fix(Measurement { seconds, start_type: Some(StartType::Cold), .. }))
+                if seconds == 35.0
+        ));
+        assert_eq!(ttff.history.len(), 3);
+        assert_eq!(ttff.history[0].satellites, Some(6));
+
+        ttff.opened();
+        assert_eq!(ttff.push(at(90), &fix, None), Some(Outcome::AlreadyFixed));
+        assert_eq!(ttff.waiting(), None);
+    }
+}
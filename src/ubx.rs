@@ -0,0 +1,3010 @@
+//! UBX frames picked out of a receiver's byte stream, which mixes them with
+//! NMEA sentences
+
+use std::io::{self, BufRead, Read};
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use serde::Serialize;
+
+const SYNC: [u8; 2] = [0xb5, 0x62];
+/// Lengths above this are taken for a false sync in the middle of other data
+const MAX_PAYLOAD: usize = 4096;
+
+pub const CLASS_NAV: u8 = 0x01;
+pub const CLASS_RXM: u8 = 0x02;
+pub const CLASS_ACK: u8 = 0x05;
+pub const CLASS_CFG: u8 = 0x06;
+pub const CLASS_MON: u8 = 0x0a;
+pub const CLASS_MGA: u8 = 0x13;
+pub const CLASS_SEC: u8 = 0x27;
+/// Class of the RTCM3 messages in CFG-MSG
+pub const CLASS_RTCM3: u8 = 0xf5;
+pub const ID_CFG_PRT: u8 = 0x00;
+pub const ID_CFG_MSG: u8 = 0x01;
+pub const ID_CFG_RST: u8 = 0x04;
+pub const ID_CFG_RATE: u8 = 0x08;
+pub const ID_CFG_CFG: u8 = 0x09;
+pub const ID_CFG_NAVX5: u8 = 0x23;
+pub const ID_CFG_TP5: u8 = 0x31;
+pub const ID_CFG_NAV5: u8 = 0x24;
+pub const ID_CFG_GNSS: u8 = 0x3e;
+pub const ID_CFG_TMODE3: u8 = 0x71;
+pub const ID_CFG_VALSET: u8 = 0x8a;
+pub const ID_CFG_VALGET: u8 = 0x8b;
+pub const ID_RXM_SFRBX: u8 = 0x13;
+pub const ID_RXM_RAWX: u8 = 0x15;
+pub const ID_MGA_ANO: u8 = 0x20;
+pub const ID_MGA_INI: u8 = 0x40;
+pub const ID_MGA_ACK: u8 = 0x60;
+pub const ID_MON_VER: u8 = 0x04;
+pub const ID_MON_HW: u8 = 0x09;
+pub const ID_MON_RF: u8 = 0x38;
+pub const ID_SEC_UNIQID: u8 = 0x03;
+pub const ID_NAV_DOP: u8 = 0x04;
+pub const ID_NAV_PVT: u8 = 0x07;
+pub const ID_NAV_SAT: u8 = 0x35;
+pub const ID_NAV_HPPOSLLH: u8 = 0x14;
+pub const ID_NAV_SVIN: u8 = 0x3b;
+pub const ID_NAV_RELPOSNED: u8 = 0x3c;
+
+/// Ports by their id in CFG-PRT, which is also the order of the rates in
+/// CFG-MSG
+pub const PORTS: &[&str] = &["i2c", "uart1", "uart2", "usb", "spi"];
+
+/// Messages known by name, with their class and id
+pub const MESSAGES: &[(&str, u8, u8)] = &[
+    ("NMEA-GGA", 0xf0, 0x00),
+    ("NMEA-GLL", 0xf0, 0x01),
+    ("NMEA-GSA", 0xf0, 0x02),
+    ("NMEA-GSV", 0xf0, 0x03),
+    ("NMEA-RMC", 0xf0, 0x04),
+    ("NMEA-VTG", 0xf0, 0x05),
+    ("NMEA-GRS", 0xf0, 0x06),
+    ("NMEA-GST", 0xf0, 0x07),
+    ("NMEA-ZDA", 0xf0, 0x08),
+    ("NMEA-GBS", 0xf0, 0x09),
+    ("NMEA-DTM", 0xf0, 0x0a),
+    ("NMEA-GNS", 0xf0, 0x0d),
+    ("NMEA-VLW", 0xf0, 0x0f),
+    ("PUBX-POSITION", 0xf1, 0x00),
+    ("PUBX-SVSTATUS", 0xf1, 0x03),
+    ("PUBX-TIME", 0xf1, 0x04),
+    ("NAV-POSLLH", 0x01, 0x02),
+    ("NAV-STATUS", 0x01, 0x03),
+    ("NAV-DOP", 0x01, 0x04),
+    ("NAV-SOL", 0x01, 0x06),
+    ("NAV-PVT", 0x01, 0x07),
+    ("NAV-VELNED", 0x01, 0x12),
+    ("NAV-HPPOSLLH", 0x01, 0x14),
+    ("NAV-TIMEUTC", 0x01, 0x21),
+    ("NAV-SAT", 0x01, 0x35),
+    ("NAV-RELPOSNED", 0x01, 0x3c),
+    ("RXM-SFRBX", 0x02, 0x13),
+    ("RXM-RAWX", 0x02, 0x15),
+    ("RTCM3-1005", 0xf5, 0x05),
+    ("RTCM3-1074", 0xf5, 0x4a),
+    ("RTCM3-1077", 0xf5, 0x4d),
+    ("RTCM3-1084", 0xf5, 0x54),
+    ("RTCM3-1087", 0xf5, 0x57),
+    ("RTCM3-1094", 0xf5, 0x5e),
+    ("RTCM3-1097", 0xf5, 0x61),
+    ("RTCM3-1124", 0xf5, 0x7c),
+    ("RTCM3-1127", 0xf5, 0x7f),
+    ("RTCM3-1230", 0xf5, 0xe6),
+    ("RTCM3-4072.0", 0xf5, 0xfe),
+    ("RTCM3-4072.1", 0xf5, 0xfd),
+];
+
+/// Class and id of a message named like `NAV-PVT`, or `GSV` for the NMEA
+/// ones, or given in hex like `F0-03`
+pub fn message_id(name: &str) -> Option<(u8, u8)> {
+    let name = name.trim();
+    let known = |name: &str| {
+        MESSAGES
+            .iter()
+            .find(|(known, _, _)| known.eq_ignore_ascii_case(name))
+            .map(|&(_, class, id)| (class, id))
+    };
+    let hex = |part: &str| u8::from_str_radix(part.trim_start_matches("0x"), 16).ok();
+    known(name)
+        .or_else(|| known(&format!("NMEA-{}", name)))
+        .or_else(|| {
+            let (class, id) = name.split_once('-')?;
+            Some((hex(class)?, hex(id)?))
+        })
+}
+
+/// Id of an RTCM3 message type like `1005` or `4072.0` a base station can
+/// send
+pub fn rtcm_id(msg_type: &str) -> Option<u8> {
+    let name = format!("RTCM3-{}", msg_type.trim());
+    MESSAGES
+        .iter()
+        .find(|&&(known, class, _)| class == CLASS_RTCM3 && known == name)
+        .map(|&(_, _, id)| id)
+}
+
+pub fn message_name(class: u8, id: u8) -> Option<&'static str> {
+    MESSAGES
+        .iter()
+        .find(|&&(_, c, i)| (c, i) == (class, id))
+        .map(|&(name, _, _)| name)
+}
+
+/// Names of the packets `frame_name` knows besides `MESSAGES`
+const OTHER_NAMES: &[(&str, u8, u8)] = &[
+    ("ACK-NAK", CLASS_ACK, 0x00),
+    ("ACK-ACK", CLASS_ACK, 0x01),
+    ("CFG-PRT", CLASS_CFG, ID_CFG_PRT),
+    ("CFG-MSG", CLASS_CFG, ID_CFG_MSG),
+    ("CFG-RST", CLASS_CFG, ID_CFG_RST),
+    ("CFG-RATE", CLASS_CFG, ID_CFG_RATE),
+    ("CFG-CFG", CLASS_CFG, ID_CFG_CFG),
+    ("CFG-NAVX5", CLASS_CFG, ID_CFG_NAVX5),
+    ("CFG-TP5", CLASS_CFG, ID_CFG_TP5),
+    ("CFG-NAV5", CLASS_CFG, ID_CFG_NAV5),
+    ("CFG-GNSS", CLASS_CFG, ID_CFG_GNSS),
+    ("CFG-TMODE3", CLASS_CFG, ID_CFG_TMODE3),
+    ("CFG-VALSET", CLASS_CFG, ID_CFG_VALSET),
+    ("CFG-VALGET", CLASS_CFG, ID_CFG_VALGET),
+    ("NAV-SVIN", CLASS_NAV, ID_NAV_SVIN),
+    ("MGA-GPS", CLASS_MGA, 0x00),
+    ("MGA-GAL", CLASS_MGA, 0x02),
+    ("MGA-BDS", CLASS_MGA, 0x03),
+    ("MGA-QZSS", CLASS_MGA, 0x05),
+    ("MGA-GLO", CLASS_MGA, 0x06),
+    ("MGA-ANO", CLASS_MGA, ID_MGA_ANO),
+    ("MGA-INI", CLASS_MGA, ID_MGA_INI),
+    ("MGA-ACK", CLASS_MGA, ID_MGA_ACK),
+    ("MON-VER", CLASS_MON, ID_MON_VER),
+    ("MON-HW", CLASS_MON, ID_MON_HW),
+    ("MON-RF", CLASS_MON, ID_MON_RF),
+    ("SEC-UNIQID", CLASS_SEC, ID_SEC_UNIQID),
+];
+
+/// Name of any packet, like `ACK-ACK` or `NAV-PVT`, or its class and id in
+/// hex like `0D-01`
+pub fn frame_name(class: u8, id: u8) -> String {
+    OTHER_NAMES
+        .iter()
+        .find(|&&(_, c, i)| (c, i) == (class, id))
+        .map(|&(name, _, _)| name)
+        .or_else(|| message_name(class, id))
+        .map(str::to_owned)
+        .unwrap_or_else(|| format!("{:02X}-{:02X}", class, id))
+}
+
+/// Class and id of a packet named like `frame_name` names it, or like
+/// `message_id` takes
+pub fn frame_id(name: &str) -> Option<(u8, u8)> {
+    OTHER_NAMES
+        .iter()
+        .find(|(known, _, _)| known.eq_ignore_ascii_case(name.trim()))
+        .map(|&(_, class, id)| (class, id))
+        .or_else(|| message_id(name))
+}
+
+/// A whole packet: sync chars, class, id, length, payload and checksum
+pub fn packet(class: u8, id: u8, payload: &[u8]) -> Vec<u8> {
+    let len = payload.len() as u16;
+    let mut packet = SYNC.to_vec();
+    packet.extend_from_slice(&[class, id]);
+    packet.extend_from_slice(&len.to_le_bytes());
+    packet.extend_from_slice(payload);
+    let sum = checksum(&packet[2..]);
+    packet.extend_from_slice(&sum);
+    packet
+}
+
+/// CFG-MSG poll for the rates of a message
+pub fn poll_msg(class: u8, id: u8) -> Vec<u8> {
+    packet(CLASS_CFG, ID_CFG_MSG, &[class, id])
+}
+
+pub fn poll_rate() -> Vec<u8> {
+    packet(CLASS_CFG, ID_CFG_RATE, &[])
+}
+
+/// CFG-PRT poll for the settings of a port
+pub fn poll_prt(port: u8) -> Vec<u8> {
+    packet(CLASS_CFG, ID_CFG_PRT, &[port])
+}
+
+pub fn poll_mon_ver() -> Vec<u8> {
+    packet(CLASS_MON, ID_MON_VER, &[])
+}
+
+pub fn poll_mon_hw() -> Vec<u8> {
+    packet(CLASS_MON, ID_MON_HW, &[])
+}
+
+/// MON-RF poll, for generation 9 receivers and later
+pub fn poll_mon_rf() -> Vec<u8> {
+    packet(CLASS_MON, ID_MON_RF, &[])
+}
+
+/// SEC-UNIQID poll, for M8 receivers and later
+pub fn poll_sec_uniqid() -> Vec<u8> {
+    packet(CLASS_SEC, ID_SEC_UNIQID, &[])
+}
+
+pub fn poll_nav5() -> Vec<u8> {
+    packet(CLASS_CFG, ID_CFG_NAV5, &[])
+}
+
+/// Dynamic platform models of CFG-NAV5
+pub const DYN_MODELS: &[(&str, u8)] = &[
+    ("portable", 0),
+    ("stationary", 2),
+    ("pedestrian", 3),
+    ("automotive", 4),
+    ("sea", 5),
+    ("airborne1g", 6),
+    ("airborne2g", 7),
+    ("airborne4g", 8),
+    ("wrist", 9),
+    ("bike", 10),
+];
+
+/// Position fix modes of CFG-NAV5
+pub const FIX_MODES: &[(&str, u8)] = &[("2d", 1), ("3d", 2), ("auto", 3)];
+
+fn name_in(table: &[(&'static str, u8)], value: u8) -> &'static str {
+    table
+        .iter()
+        .find(|&&(_, v)| v == value)
+        .map_or("unknown", |&(name, _)| name)
+}
+
+/// Navigation engine settings, from CFG-NAV5
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Nav5 {
+    pub dyn_model: u8,
+    pub fix_mode: u8,
+    /// meters, for 2D fixes
+    pub fixed_alt: f64,
+    /// degrees above the horizon below which satellites are not used
+    pub min_elev: i8,
+    pub pdop_mask: f64,
+    pub tdop_mask: f64,
+    /// meters
+    pub pacc_mask: u16,
+    /// meters
+    pub tacc_mask: u16,
+    /// cm/s below which the position is held
+    pub static_hold: u8,
+    /// seconds
+    pub dgnss_timeout: u8,
+}
+
+impl Nav5 {
+    pub fn dyn_model_name(&self) -> &'static str {
+        name_in(DYN_MODELS, self.dyn_model)
+    }
+
+    pub fn fix_mode_name(&self) -> &'static str {
+        name_in(FIX_MODES, self.fix_mode)
+    }
+}
+
+/// Changes to CFG-NAV5. Only the fields given are flagged in the mask, the
+/// receiver keeps the others.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Nav5Change {
+    pub dyn_model: Option<u8>,
+    pub min_elev: Option<i8>,
+    pub fix_mode: Option<u8>,
+}
+
+impl Nav5Change {
+    pub fn mask(&self) -> u16 {
+        let mut mask = 0;
+        if self.dyn_model.is_some() {
+            mask |= 0x01;
+        }
+        if self.min_elev.is_some() {
+            mask |= 0x02;
+        }
+        if self.fix_mode.is_some() {
+            mask |= 0x04;
+        }
+        mask
+    }
+
+    pub fn packet(&self) -> Vec<u8> {
+        let mut payload = vec![0; 36];
+        payload[..2].copy_from_slice(&self.mask().to_le_bytes());
+        payload[2] = self.dyn_model.unwrap_or(0);
+        payload[3] = self.fix_mode.unwrap_or(0);
+        payload[12] = self.min_elev.unwrap_or(0) as u8;
+        packet(CLASS_CFG, ID_CFG_NAV5, &payload)
+    }
+}
+
+pub fn poll_gnss() -> Vec<u8> {
+    packet(CLASS_CFG, ID_CFG_GNSS, &[])
+}
+
+/// Systems in CFG-GNSS, by their id
+pub const GNSS_IDS: &[(&str, u8)] = &[
+    ("gps", 0),
+    ("sbas", 1),
+    ("galileo", 2),
+    ("beidou", 3),
+    ("imes", 4),
+    ("qzss", 5),
+    ("glonass", 6),
+];
+const GPS: u8 = 0;
+const SBAS: u8 = 1;
+const GALILEO: u8 = 2;
+const BEIDOU: u8 = 3;
+const QZSS: u8 = 5;
+const GLONASS: u8 = 6;
+
+pub fn gnss_name(gnss_id: u8) -> &'static str {
+    name_in(GNSS_IDS, gnss_id)
+}
+
+/// The settings of one system in CFG-GNSS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GnssBlock {
+    pub gnss_id: u8,
+    /// channels reserved for the system
+    pub res_trk_ch: u8,
+    /// channels the system may use at most
+    pub max_trk_ch: u8,
+    /// enable bit and signals
+    pub flags: u32,
+}
+
+impl GnssBlock {
+    pub fn name(&self) -> &'static str {
+        gnss_name(self.gnss_id)
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+}
+
+/// Which systems are tracked and with how many channels, from CFG-GNSS. It
+/// is written back whole.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gnss {
+    pub msg_ver: u8,
+    /// channels the hardware has
+    pub trk_ch_hw: u8,
+    /// channels in use
+    pub trk_ch_use: u8,
+    pub blocks: Vec<GnssBlock>,
+}
+
+impl Gnss {
+    /// Turns a system on or off; false when the receiver has no block for it
+    pub fn set_enabled(&mut self, gnss_id: u8, enable: bool) -> bool {
+        match self.blocks.iter_mut().find(|b| b.gnss_id == gnss_id) {
+            Some(block) => {
+                block.flags = (block.flags & !0x01) | u32::from(enable);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn is_enabled(&self, gnss_id: u8) -> bool {
+        self.blocks
+            .iter()
+            .any(|b| b.gnss_id == gnss_id && b.enabled())
+    }
+
+    /// Combinations M8 receivers refuse or handle badly
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let major = [GPS, GALILEO, BEIDOU, GLONASS]
+            .iter()
+            .filter(|&&id| self.is_enabled(id))
+            .count();
+        if major == 0 {
+            warnings.push("no GPS, Galileo, BeiDou or GLONASS is enabled".to_owned());
+        }
+        if major > 3 {
+            warnings.push(
+                "M8 receivers track at most three of GPS, Galileo, BeiDou and GLONASS".to_owned(),
+            );
+        }
+        if self.is_enabled(BEIDOU) && self.is_enabled(GLONASS) {
+            warnings.push("M8 receivers can't track BeiDou and GLONASS together".to_owned());
+        }
+        for &(id, name) in &[(SBAS, "SBAS"), (QZSS, "QZSS")] {
+            if self.is_enabled(id) && !self.is_enabled(GPS) {
+                warnings.push(format!("{} is meant to be used along with GPS", name));
+            }
+        }
+        let reserved: u32 = self
+            .blocks
+            .iter()
+            .filter(|b| b.enabled())
+            .map(|b| u32::from(b.res_trk_ch))
+            .sum();
+        if reserved > u32::from(self.trk_ch_use) {
+            warnings.push(format!(
+                "the enabled systems reserve {} channels, more than the {} in use",
+                reserved, self.trk_ch_use
+            ));
+        }
+        warnings
+    }
+
+    pub fn packet(&self) -> Vec<u8> {
+        let mut payload = vec![
+            self.msg_ver,
+            self.trk_ch_hw,
+            self.trk_ch_use,
+            self.blocks.len() as u8,
+        ];
+        for block in &self.blocks {
+            payload.extend_from_slice(&[block.gnss_id, block.res_trk_ch, block.max_trk_ch, 0]);
+            payload.extend_from_slice(&block.flags.to_le_bytes());
+        }
+        packet(CLASS_CFG, ID_CFG_GNSS, &payload)
+    }
+}
+
+/// Sections of the configuration in the masks of CFG-CFG
+pub const CFG_SECTIONS: &[(&str, u32)] = &[
+    ("ioport", 0x0001),
+    ("msgconf", 0x0002),
+    ("infmsg", 0x0004),
+    ("navconf", 0x0008),
+    ("rxmconf", 0x0010),
+    ("antconf", 0x0400),
+];
+
+/// Where CFG-CFG saves to
+pub const CFG_DEVICES: &[(&str, u8)] = &[
+    ("bbr", 0x01),
+    ("flash", 0x02),
+    ("eeprom", 0x04),
+    ("spi-flash", 0x10),
+];
+
+/// A CFG-CFG: which sections are cleared, saved and loaded, in that order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CfgCfg {
+    pub clear: u32,
+    pub save: u32,
+    pub load: u32,
+    pub devices: u8,
+}
+
+impl CfgCfg {
+    /// Saves the current configuration to `devices`
+    pub fn save(sections: u32, devices: u8) -> CfgCfg {
+        CfgCfg {
+            clear: 0,
+            save: sections,
+            load: 0,
+            devices,
+        }
+    }
+
+    /// Clears the saved configuration and loads the defaults in its place
+    pub fn load_defaults(sections: u32, devices: u8) -> CfgCfg {
+        CfgCfg {
+            clear: sections,
+            save: 0,
+            load: sections,
+            devices,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "clearMask 0x{:04x}, saveMask 0x{:04x}, loadMask 0x{:04x}, deviceMask 0x{:02x}",
+            self.clear, self.save, self.load, self.devices
+        )
+    }
+
+    pub fn packet(&self) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(13);
+        payload.extend_from_slice(&self.clear.to_le_bytes());
+        payload.extend_from_slice(&self.save.to_le_bytes());
+        payload.extend_from_slice(&self.load.to_le_bytes());
+        payload.push(self.devices);
+        packet(CLASS_CFG, ID_CFG_CFG, &payload)
+    }
+}
+
+/// A decimal like `-122.1234567891` in units of 10^-`decimals`, rounded half
+/// away from zero. Going through f64 could be off by a unit.
+pub fn parse_fixed(s: &str, decimals: u32) -> Option<i64> {
+    let s = s.trim();
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    if int.is_empty() && frac.is_empty() {
+        return None;
+    }
+    if !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut value: i64 = if int.is_empty() { 0 } else { int.parse().ok()? };
+    let mut frac = frac.bytes().map(|b| i64::from(b - b'0'));
+    for _ in 0..decimals {
+        value = value
+            .checked_mul(10)?
+            .checked_add(frac.next().unwrap_or(0))?;
+    }
+    if frac.next().is_some_and(|digit| digit >= 5) {
+        value += 1;
+    }
+    Some(if negative { -value } else { value })
+}
+
+/// `value` in units of 10^-`decimals` as a decimal, the way `parse_fixed`
+/// takes it
+pub fn format_fixed(value: i64, decimals: u32) -> String {
+    let scale = 10u64.pow(decimals);
+    let sign = if value < 0 { "-" } else { "" };
+    let abs = value.unsigned_abs();
+    format!(
+        "{}{}.{:0width$}",
+        sign,
+        abs / scale,
+        abs % scale,
+        width = decimals as usize
+    )
+}
+
+/// A coordinate in the high precision units of CFG-TMODE3 and NAV-SVIN,
+/// 1e-9 degrees or 0.1 mm, split into the standard part, 1e-7 degrees or
+/// cm, and the rest, -99 to 99. Both parts have the sign of the coordinate.
+pub fn split_hp(value: i64) -> (i32, i8) {
+    ((value / 100) as i32, (value % 100) as i8)
+}
+
+/// A value from its standard part and its high precision part, `factor`
+/// units of which make a standard unit: 100 for 1e-9 degrees from 1e-7
+/// degrees, 10 for 0.1 mm from mm
+pub fn join_hp(standard: i32, hp: i8, factor: i64) -> i64 {
+    i64::from(standard) * factor + i64::from(hp)
+}
+
+/// The time mode of a receiver, from CFG-TMODE3. A base station for RTK runs
+/// a survey-in, or is told where it is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeMode {
+    Disabled,
+    /// averages the position for `min_duration` seconds at least, until its
+    /// accuracy is within `acc_limit`, in 0.1 mm
+    SurveyIn {
+        min_duration: u32,
+        acc_limit: u32,
+    },
+    /// latitude and longitude in 1e-9 degrees, height above the ellipsoid
+    /// and accuracy in 0.1 mm
+    FixedLla {
+        lat: i64,
+        lon: i64,
+        alt: i64,
+        acc: u32,
+    },
+    /// ECEF coordinates and accuracy in 0.1 mm
+    FixedEcef {
+        x: i64,
+        y: i64,
+        z: i64,
+        acc: u32,
+    },
+}
+
+impl TimeMode {
+    pub fn packet(&self) -> Vec<u8> {
+        let (mode, position, acc, min_duration, acc_limit) = match *self {
+            TimeMode::Disabled => (0x000, None, 0, 0, 0),
+            TimeMode::SurveyIn {
+                min_duration,
+                acc_limit,
+            } => (0x001, None, 0, min_duration, acc_limit),
+            TimeMode::FixedLla { lat, lon, alt, acc } => (0x102, Some([lat, lon, alt]), acc, 0, 0),
+            TimeMode::FixedEcef { x, y, z, acc } => (0x002, Some([x, y, z]), acc, 0, 0),
+        };
+        let split = position.unwrap_or_default().map(split_hp);
+        let mut payload = vec![0, 0];
+        payload.extend_from_slice(&(mode as u16).to_le_bytes());
+        for &(standard, _) in &split {
+            payload.extend_from_slice(&standard.to_le_bytes());
+        }
+        for &(_, hp) in &split {
+            payload.push(hp as u8);
+        }
+        payload.push(0);
+        payload.extend_from_slice(&acc.to_le_bytes());
+        payload.extend_from_slice(&min_duration.to_le_bytes());
+        payload.extend_from_slice(&acc_limit.to_le_bytes());
+        payload.extend_from_slice(&[0; 8]);
+        packet(CLASS_CFG, ID_CFG_TMODE3, &payload)
+    }
+}
+
+/// Progress of a survey-in, from NAV-SVIN
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NavSvin {
+    /// seconds surveyed
+    pub dur: u32,
+    /// mean ECEF position, in 0.1 mm
+    pub mean: [i64; 3],
+    /// accuracy of the mean position, in 0.1 mm
+    pub mean_acc: u32,
+    /// positions averaged
+    pub obs: u32,
+    /// the survey-in met its limits
+    pub valid: bool,
+    /// the survey-in still runs
+    pub active: bool,
+}
+
+/// A high precision position, from NAV-HPPOSLLH
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HpPosLlh {
+    /// ms, GPS time of week
+    pub itow: u32,
+    /// 1e-9 degrees
+    pub lat: i64,
+    /// 1e-9 degrees
+    pub lon: i64,
+    /// above the ellipsoid, 0.1 mm
+    pub height: i64,
+    /// above mean sea level, 0.1 mm
+    pub h_msl: i64,
+    /// 0.1 mm
+    pub h_acc: u32,
+    /// 0.1 mm
+    pub v_acc: u32,
+    /// the receiver has no position to give
+    pub invalid: bool,
+}
+
+/// Where a rover is from its base, or a moving base from the rover heading
+/// it, from NAV-RELPOSNED
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RelPosNed {
+    pub itow: u32,
+    pub ref_station: u16,
+    /// north, east and down, 0.1 mm
+    pub ned: [i64; 3],
+    /// 0.1 mm; not in the version 0 of M8 receivers
+    pub length: Option<i64>,
+    /// 1e-5 degrees, when the receiver flags it valid
+    pub heading: Option<i32>,
+    /// of north, east and down, 0.1 mm
+    pub acc_ned: [u32; 3],
+    pub flags: u32,
+}
+
+impl HpPosLlh {
+    pub fn describe(&self) -> String {
+        if self.invalid {
+            return "no position".to_owned();
+        }
+        format!(
+            "{} {}  h {} m  msl {} m  acc {}/{} m",
+            format_fixed(self.lat, 9),
+            format_fixed(self.lon, 9),
+            format_fixed(self.height, 4),
+            format_fixed(self.h_msl, 4),
+            format_fixed(i64::from(self.h_acc), 4),
+            format_fixed(i64::from(self.v_acc), 4)
+        )
+    }
+}
+
+impl RelPosNed {
+    pub fn describe(&self) -> String {
+        if !self.rel_pos_valid() {
+            return "no baseline".to_owned();
+        }
+        let [n, e, d] = self.ned.map(|v| format_fixed(v, 4));
+        let mut line = format!("{}  N {} E {} D {} m", self.carr_soln_name(), n, e, d);
+        if let Some(length) = self.length {
+            line += &format!("  length {} m", format_fixed(length, 4));
+        }
+        if let Some(heading) = self.heading {
+            line += &format!("  heading {}°", format_fixed(i64::from(heading), 5));
+        }
+        let [n, e, d] = self.acc_ned.map(|v| format_fixed(i64::from(v), 4));
+        line + &format!("  acc {}/{}/{} m", n, e, d)
+    }
+
+    pub fn gnss_fix_ok(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+
+    pub fn diff_soln(&self) -> bool {
+        self.flags & 0x02 != 0
+    }
+
+    /// The other fields mean something
+    pub fn rel_pos_valid(&self) -> bool {
+        self.flags & 0x04 != 0
+    }
+
+    pub fn carr_soln(&self) -> u8 {
+        ((self.flags >> 3) & 0x03) as u8
+    }
+
+    pub fn carr_soln_name(&self) -> &'static str {
+        carr_soln_name(self.carr_soln())
+    }
+}
+
+/// CFG-NAVX5 asking for an MGA-ACK for each aiding message, leaving the
+/// other settings alone
+pub fn navx5_ack_aiding() -> Vec<u8> {
+    let mut payload = [0u8; 40];
+    payload[0] = 2;
+    // mask1: ackAid
+    payload[2..4].copy_from_slice(&0x0400u16.to_le_bytes());
+    payload[17] = 1;
+    packet(CLASS_CFG, ID_CFG_NAVX5, &payload)
+}
+
+/// MGA-INI-TIME_UTC giving the receiver the time, which it needs to make
+/// any use of aiding data. The leap seconds are left to the receiver.
+pub fn mga_ini_time_utc(now: NaiveDateTime, acc_secs: u16) -> Frame {
+    let mut payload = vec![0x10, 0x00, 0x00, -128i8 as u8];
+    payload.extend_from_slice(&(now.year() as u16).to_le_bytes());
+    payload.extend_from_slice(&[
+        now.month() as u8,
+        now.day() as u8,
+        now.hour() as u8,
+        now.minute() as u8,
+        now.second() as u8,
+        0,
+    ]);
+    payload.extend_from_slice(&now.nanosecond().min(999_999_999).to_le_bytes());
+    payload.extend_from_slice(&acc_secs.to_le_bytes());
+    payload.extend_from_slice(&[0, 0]);
+    payload.extend_from_slice(&0u32.to_le_bytes());
+    Frame {
+        class: CLASS_MGA,
+        id: ID_MGA_INI,
+        payload,
+    }
+}
+
+/// The MGA messages of an AssistNow file worth sending on `today`, and how
+/// many were left out. Offline files hold MGA-ANO for weeks ahead; only
+/// those for today are sent, when there are any.
+pub fn mga_to_send(frames: Vec<Frame>, today: NaiveDate) -> (Vec<Frame>, usize) {
+    let date = [
+        (today.year() - 2000) as u8,
+        today.month() as u8,
+        today.day() as u8,
+    ];
+    let is_ano = |frame: &Frame| (frame.class, frame.id) == (CLASS_MGA, ID_MGA_ANO);
+    let for_today = |frame: &Frame| frame.payload.get(4..7) == Some(&date[..]);
+    let any_today = frames.iter().any(|f| is_ano(f) && for_today(f));
+    let total = frames.len();
+    let kept: Vec<Frame> = frames
+        .into_iter()
+        .filter(|f| f.class == CLASS_MGA)
+        .filter(|f| !any_today || !is_ano(f) || for_today(f))
+        .collect();
+    let left_out = total - kept.len();
+    (kept, left_out)
+}
+
+/// An MGA-ACK-DATA0, telling whether an aiding message was used
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MgaAck {
+    pub accepted: bool,
+    pub info_code: u8,
+    /// id of the MGA message
+    pub msg_id: u8,
+    /// first bytes of its payload
+    pub payload_start: [u8; 4],
+}
+
+impl MgaAck {
+    /// Whether the ack is for `frame`, as far as its id and the start of
+    /// its payload tell
+    pub fn is_for(&self, frame: &Frame) -> bool {
+        frame.class == CLASS_MGA
+            && frame.id == self.msg_id
+            && frame
+                .payload
+                .iter()
+                .take(4)
+                .eq(self.payload_start.iter().take(frame.payload.len()))
+    }
+
+    pub fn info_name(&self) -> &'static str {
+        match self.info_code {
+            0 => "accepted",
+            1 => "no time known",
+            2 => "message version not supported",
+            3 => "message size doesn't match its version",
+            4 => "failed to store the data",
+            5 => "receiver not ready",
+            6 => "unknown message type",
+            _ => "unknown reason",
+        }
+    }
+}
+
+/// Firmware and hardware versions, from MON-VER
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Version {
+    pub software: String,
+    pub hardware: String,
+    /// like `PROTVER=18.00`, `GPS;GLO;GAL;BDS` or `MOD=NEO-M8N-0`
+    pub extensions: Vec<String>,
+}
+
+impl Version {
+    /// Value of an extension like `PROTVER=18.00`
+    pub fn extension(&self, key: &str) -> Option<&str> {
+        self.extensions.iter().find_map(|ext| {
+            let (k, value) = ext.split_once('=')?;
+            Some(value).filter(|_| k == key)
+        })
+    }
+}
+
+/// Antenna, noise and jamming state of an RF front end, from MON-HW or a
+/// MON-RF block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RfStatus {
+    /// block of MON-RF, like 0 for L1 and 1 for L2 or L5
+    pub block: Option<u8>,
+    pub ant_status: u8,
+    pub ant_power: u8,
+    /// noise level seen by the GPS front end
+    pub noise_per_ms: u16,
+    /// 0 to 8191
+    pub agc_cnt: u16,
+    pub jamming_state: u8,
+    /// continuous wave jamming, 0 to 255
+    pub jam_ind: u8,
+}
+
+impl RfStatus {
+    pub fn ant_status_name(&self) -> &'static str {
+        match self.ant_status {
+            0 => "init",
+            1 => "unknown",
+            2 => "ok",
+            3 => "short",
+            4 => "open",
+            _ => "invalid",
+        }
+    }
+
+    pub fn ant_power_name(&self) -> &'static str {
+        match self.ant_power {
+            0 => "off",
+            1 => "on",
+            _ => "unknown",
+        }
+    }
+
+    /// Jamming as the receiver judges it, `unknown` when it isn't monitored
+    pub fn jamming_name(&self) -> &'static str {
+        match self.jamming_state {
+            1 => "ok",
+            2 => "warning",
+            3 => "critical",
+            _ => "unknown",
+        }
+    }
+
+    /// Gain of the front end, 100% when the signal is weakest
+    pub fn agc_percent(&self) -> f64 {
+        f64::from(self.agc_cnt) / 8191.0 * 100.0
+    }
+
+    /// What needs a look: a shorted or open antenna, or jamming
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if matches!(self.ant_status, 3 | 4) {
+            warnings.push(format!("antenna {}", self.ant_status_name()));
+        }
+        if matches!(self.jamming_state, 2 | 3) {
+            warnings.push(format!("jamming {}", self.jamming_name()));
+        }
+        warnings
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "antenna {}, power {}, noise {}/ms, AGC {:.0}%, jamming {}, CW {}/255",
+            self.ant_status_name(),
+            self.ant_power_name(),
+            self.noise_per_ms,
+            self.agc_percent(),
+            self.jamming_name(),
+            self.jam_ind
+        )
+    }
+}
+
+/// Text of a NUL-padded field
+fn padded_str(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_owned()
+}
+
+/// Protocols in the masks of CFG-PRT
+pub const PROTOCOLS: &[(&str, u16)] = &[
+    ("ubx", 0x01),
+    ("nmea", 0x02),
+    ("rtcm", 0x04),
+    ("rtcm3", 0x20),
+];
+
+pub fn protocol_bit(name: &str) -> Option<u16> {
+    PROTOCOLS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(name.trim()))
+        .map(|&(_, bit)| bit)
+}
+
+pub fn protocol_names(mask: u16) -> Vec<&'static str> {
+    PROTOCOLS
+        .iter()
+        .filter(|&&(_, bit)| mask & bit != 0)
+        .map(|&(name, _)| name)
+        .collect()
+}
+
+/// Settings of a port, from CFG-PRT
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PortConfig {
+    /// index into `PORTS`
+    pub port: u8,
+    pub tx_ready: u16,
+    /// character length, parity and stop bits on UARTs, the address on I2C
+    pub mode: u32,
+    /// on UARTs only
+    pub baud: u32,
+    pub in_proto: u16,
+    pub out_proto: u16,
+    pub flags: u16,
+}
+
+impl PortConfig {
+    /// The default settings of a UART: 8N1, UBX and NMEA out, anything in
+    pub fn uart(port: u8, baud: u32) -> PortConfig {
+        PortConfig {
+            port,
+            tx_ready: 0,
+            mode: 0x08d0,
+            baud,
+            in_proto: 0x07,
+            out_proto: 0x03,
+            flags: 0,
+        }
+    }
+
+    pub fn is_uart(&self) -> bool {
+        matches!(self.port, 1 | 2)
+    }
+
+    pub fn port_name(&self) -> &'static str {
+        PORTS
+            .get(usize::from(self.port))
+            .copied()
+            .unwrap_or("unknown")
+    }
+
+    /// Like `8N1`, on UARTs
+    pub fn mode_name(&self) -> Option<String> {
+        if !self.is_uart() {
+            return None;
+        }
+        let bits = 5 + ((self.mode >> 6) & 0x3);
+        let parity = match (self.mode >> 9) & 0x7 {
+            0 => "E",
+            1 => "O",
+            4 | 5 => "N",
+            _ => "?",
+        };
+        let stop = match (self.mode >> 12) & 0x3 {
+            0 => "1",
+            1 => "1.5",
+            2 => "2",
+            _ => "0.5",
+        };
+        Some(format!("{}{}{}", bits, parity, stop))
+    }
+
+    pub fn packet(&self) -> Vec<u8> {
+        let mut payload = vec![self.port, 0];
+        payload.extend_from_slice(&self.tx_ready.to_le_bytes());
+        payload.extend_from_slice(&self.mode.to_le_bytes());
+        payload.extend_from_slice(&self.baud.to_le_bytes());
+        payload.extend_from_slice(&self.in_proto.to_le_bytes());
+        payload.extend_from_slice(&self.out_proto.to_le_bytes());
+        payload.extend_from_slice(&self.flags.to_le_bytes());
+        payload.extend_from_slice(&[0, 0]);
+        packet(CLASS_CFG, ID_CFG_PRT, &payload)
+    }
+}
+
+/// CFG-TP5 poll for the settings of TIMEPULSE, 0, or TIMEPULSE2, 1
+pub fn poll_tp5(tp_idx: u8) -> Vec<u8> {
+    packet(CLASS_CFG, ID_CFG_TP5, &[tp_idx])
+}
+
+/// Highest time pulse frequency of the timing receivers
+pub const MAX_TP_HZ: u32 = 25_000_000;
+
+const TP_ACTIVE: u32 = 0x01;
+const TP_LOCK_GNSS_FREQ: u32 = 0x02;
+const TP_LOCKED_OTHER_SET: u32 = 0x04;
+const TP_IS_FREQ: u32 = 0x08;
+const TP_IS_LENGTH: u32 = 0x10;
+const TP_ALIGN_TO_TOW: u32 = 0x20;
+const TP_POLARITY: u32 = 0x40;
+
+/// How often a time pulse comes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PulseRate {
+    Hz(u32),
+    PeriodUs(u32),
+}
+
+/// How long a time pulse lasts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PulseLen {
+    /// percent of the period
+    Duty(f64),
+    Us(u32),
+}
+
+/// Time pulse settings, from CFG-TP5. The flags tell whether the periods are
+/// frequencies and the lengths ratios, for both the settings used until the
+/// receiver locks to GNSS time and those used after.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimePulse {
+    pub tp_idx: u8,
+    /// ns
+    pub ant_cable_delay: i16,
+    /// ns
+    pub rf_group_delay: i16,
+    /// Hz or µs, until locked
+    pub freq_period: u32,
+    /// Hz or µs, once locked
+    pub freq_period_lock: u32,
+    /// µs, or a ratio in 2^-32, until locked
+    pub pulse_len_ratio: u32,
+    /// µs, or a ratio in 2^-32, once locked
+    pub pulse_len_ratio_lock: u32,
+    /// ns
+    pub user_config_delay: i32,
+    pub flags: u32,
+}
+
+impl TimePulse {
+    /// The factory settings: 100 ms pulses every second, aligned to the top
+    /// of the second
+    pub fn factory(tp_idx: u8) -> TimePulse {
+        TimePulse {
+            tp_idx,
+            ant_cable_delay: 50,
+            rf_group_delay: 0,
+            freq_period: 1_000_000,
+            freq_period_lock: 1_000_000,
+            pulse_len_ratio: 100_000,
+            pulse_len_ratio_lock: 100_000,
+            user_config_delay: 0,
+            flags: TP_ACTIVE
+                | TP_LOCK_GNSS_FREQ
+                | TP_LOCKED_OTHER_SET
+                | TP_IS_LENGTH
+                | TP_ALIGN_TO_TOW
+                | TP_POLARITY,
+        }
+    }
+
+    /// Sets the pulses, for both the locked and the unlocked state, or only
+    /// the locked one with no pulses until then. The delays, polarity and
+    /// time grid stay.
+    pub fn set(
+        &mut self,
+        rate: PulseRate,
+        len: PulseLen,
+        aligned_to_tow: bool,
+        only_when_locked: bool,
+    ) -> Result<(), String> {
+        let (freq_period, period_us, is_freq) = match rate {
+            PulseRate::Hz(hz) if (1..=MAX_TP_HZ).contains(&hz) => (hz, 1e6 / f64::from(hz), true),
+            PulseRate::Hz(_) => {
+                return Err(format!("the frequency must be 1 to {} Hz", MAX_TP_HZ));
+            }
+            PulseRate::PeriodUs(0) => return Err("the period must be 1 µs at least".to_owned()),
+            PulseRate::PeriodUs(us) => (us, f64::from(us), false),
+        };
+        let (pulse, is_length) = match len {
+            PulseLen::Duty(duty) if duty > 0.0 && duty < 100.0 => {
+                let ratio = (duty / 100.0 * 4_294_967_296.0).round();
+                (ratio.min(f64::from(u32::MAX)) as u32, false)
+            }
+            PulseLen::Duty(_) => {
+                return Err("the duty cycle must be above 0 and below 100%".to_owned())
+            }
+            PulseLen::Us(us) if us > 0 && f64::from(us) < period_us => (us, true),
+            PulseLen::Us(_) => {
+                return Err(format!(
+                    "the pulse must be 1 µs at least and shorter than the period of {} µs",
+                    period_us
+                ));
+            }
+        };
+        self.freq_period = freq_period;
+        self.freq_period_lock = freq_period;
+        self.pulse_len_ratio = if only_when_locked { 0 } else { pulse };
+        self.pulse_len_ratio_lock = pulse;
+        let mut flags = self.flags & !(TP_IS_FREQ | TP_IS_LENGTH | TP_ALIGN_TO_TOW);
+        flags |= TP_ACTIVE | TP_LOCK_GNSS_FREQ | TP_LOCKED_OTHER_SET;
+        if is_freq {
+            flags |= TP_IS_FREQ;
+        }
+        if is_length {
+            flags |= TP_IS_LENGTH;
+        }
+        if aligned_to_tow {
+            flags |= TP_ALIGN_TO_TOW;
+        }
+        self.flags = flags;
+        Ok(())
+    }
+
+    fn describe_set(&self, freq_period: u32, pulse: u32) -> String {
+        if pulse == 0 || freq_period == 0 {
+            return "no pulses".to_owned();
+        }
+        let rate = if self.flags & TP_IS_FREQ != 0 {
+            format!("{} Hz", freq_period)
+        } else {
+            format!("a pulse every {} µs", freq_period)
+        };
+        let len = if self.flags & TP_IS_LENGTH != 0 {
+            format!("{} µs long", pulse)
+        } else {
+            format!(
+                "{:.1}% duty cycle",
+                f64::from(pulse) / 4_294_967_296.0 * 100.0
+            )
+        };
+        format!("{}, {}", rate, len)
+    }
+
+    /// What the receiver does with these settings, a line per fact
+    pub fn describe(&self) -> Vec<String> {
+        let pin = if self.tp_idx == 0 {
+            "TIMEPULSE"
+        } else {
+            "TIMEPULSE2"
+        };
+        if self.flags & TP_ACTIVE == 0 {
+            return vec![format!("{} is off", pin)];
+        }
+        let mut lines = Vec::new();
+        let unlocked = self.describe_set(self.freq_period, self.pulse_len_ratio);
+        if self.flags & TP_LOCKED_OTHER_SET != 0 {
+            let locked = self.describe_set(self.freq_period_lock, self.pulse_len_ratio_lock);
+            lines.push(format!("{} locked to GNSS time: {}", pin, locked));
+            lines.push(format!("until locked: {}", unlocked));
+        } else {
+            lines.push(format!("{} always: {}", pin, unlocked));
+        }
+        if self.flags & TP_LOCK_GNSS_FREQ == 0 {
+            lines.push("the frequency runs free of GNSS time".to_owned());
+        }
+        let grid = match (self.flags >> 7) & 0x0f {
+            0 => "UTC",
+            1 => "GPS",
+            2 => "GLONASS",
+            3 => "BeiDou",
+            4 => "Galileo",
+            _ => "unknown",
+        };
+        if self.flags & TP_ALIGN_TO_TOW != 0 {
+            lines.push(format!("pulses aligned to the top of the {} second", grid));
+        } else {
+            lines.push("pulses not aligned to the second".to_owned());
+        }
+        let edge = if self.flags & TP_POLARITY != 0 {
+            "rising"
+        } else {
+            "falling"
+        };
+        lines.push(format!("the {} edge marks the time", edge));
+        lines.push(format!(
+            "antenna cable delay {} ns, RF group delay {} ns, user delay {} ns",
+            self.ant_cable_delay, self.rf_group_delay, self.user_config_delay
+        ));
+        lines
+    }
+
+    pub fn packet(&self) -> Vec<u8> {
+        let mut payload = vec![self.tp_idx, 0x01, 0, 0];
+        payload.extend_from_slice(&self.ant_cable_delay.to_le_bytes());
+        payload.extend_from_slice(&self.rf_group_delay.to_le_bytes());
+        for field in &[
+            self.freq_period,
+            self.freq_period_lock,
+            self.pulse_len_ratio,
+            self.pulse_len_ratio_lock,
+        ] {
+            payload.extend_from_slice(&field.to_le_bytes());
+        }
+        payload.extend_from_slice(&self.user_config_delay.to_le_bytes());
+        payload.extend_from_slice(&self.flags.to_le_bytes());
+        packet(CLASS_CFG, ID_CFG_TP5, &payload)
+    }
+}
+
+/// Measurement and navigation rate, from CFG-RATE
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    /// milliseconds between measurements
+    pub meas_ms: u16,
+    /// measurements per navigation solution
+    pub nav_ratio: u16,
+    /// 0 for UTC, 1 for GPS, and on later receivers 2 to 4 for GLONASS,
+    /// BeiDou and Galileo
+    pub time_ref: u16,
+}
+
+impl Rate {
+    /// Navigation solutions per second
+    pub fn hz(&self) -> f64 {
+        1000.0 / (f64::from(self.meas_ms) * f64::from(self.nav_ratio.max(1)))
+    }
+
+    pub fn time_ref_name(&self) -> &'static str {
+        match self.time_ref {
+            0 => "UTC",
+            1 => "GPS",
+            2 => "GLONASS",
+            3 => "BeiDou",
+            4 => "Galileo",
+            _ => "unknown",
+        }
+    }
+}
+
+/// A navigation solution, from NAV-PVT
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NavPvt {
+    /// GPS time of week of the solution, in milliseconds
+    pub itow: u32,
+    /// like `2021-03-04T05:06:07.250Z`, none until the receiver knows the
+    /// date and time
+    pub utc: Option<String>,
+    /// 0 no fix, 1 dead reckoning, 2 2D, 3 3D, 4 GNSS and dead reckoning,
+    /// 5 time only
+    pub fix_type: u8,
+    /// the fix is within the limits of CFG-NAV5
+    pub fix_ok: bool,
+    /// 0 no carrier phase solution, 1 float, 2 fixed
+    pub carr_soln: u8,
+    pub num_sv: u8,
+    /// degrees
+    pub lat: f64,
+    /// degrees
+    pub lon: f64,
+    /// meters above the ellipsoid
+    pub height: f64,
+    /// meters above mean sea level
+    pub h_msl: f64,
+    /// horizontal accuracy estimate, in meters
+    pub h_acc: f64,
+    /// vertical accuracy estimate, in meters
+    pub v_acc: f64,
+    /// ground speed, in m/s
+    pub g_speed: f64,
+    /// heading of motion, in degrees
+    pub heading: f64,
+    pub p_dop: f64,
+}
+
+impl NavPvt {
+    pub fn fix_type_name(&self) -> &'static str {
+        match self.fix_type {
+            0 => "none",
+            1 => "DR",
+            2 => "2D",
+            3 => "3D",
+            4 => "GNSS+DR",
+            5 => "time",
+            _ => "unknown",
+        }
+    }
+
+    pub fn carr_soln_name(&self) -> &'static str {
+        carr_soln_name(self.carr_soln)
+    }
+}
+
+/// Dilutions of precision, from NAV-DOP
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct NavDop {
+    /// GPS time of week of the solution, in milliseconds
+    pub itow: u32,
+    pub g_dop: f64,
+    pub p_dop: f64,
+    pub t_dop: f64,
+    pub v_dop: f64,
+    pub h_dop: f64,
+    pub n_dop: f64,
+    pub e_dop: f64,
+}
+
+/// Carrier phase range solution of NAV-PVT and NAV-RELPOSNED
+fn carr_soln_name(carr_soln: u8) -> &'static str {
+    match carr_soln {
+        0 => "none",
+        1 => "float",
+        2 => "fixed",
+        _ => "unknown",
+    }
+}
+
+/// A satellite in NAV-SAT
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SatInfo {
+    pub gnss_id: u8,
+    pub sv_id: u8,
+    /// carrier to noise ratio, in dBHz
+    pub cno: u8,
+    /// degrees, -91 when unknown
+    pub elev: i8,
+    /// degrees
+    pub azim: i16,
+    /// pseudorange residual, in meters
+    pub pr_res: f64,
+    pub flags: u32,
+}
+
+impl SatInfo {
+    pub fn gnss_name(&self) -> &'static str {
+        gnss_name(self.gnss_id)
+    }
+
+    /// Signal quality, 0 to 7
+    pub fn quality(&self) -> u8 {
+        (self.flags & 0x07) as u8
+    }
+
+    pub fn quality_name(&self) -> &'static str {
+        match self.quality() {
+            0 => "no signal",
+            1 => "searching",
+            2 => "acquired",
+            3 => "unusable",
+            4 => "code locked",
+            _ => "carrier locked",
+        }
+    }
+
+    /// Used in the navigation solution
+    pub fn used(&self) -> bool {
+        self.flags & 0x08 != 0
+    }
+
+    /// 0 unknown, 1 healthy, 2 unhealthy
+    pub fn health(&self) -> u8 {
+        ((self.flags >> 4) & 0x03) as u8
+    }
+
+    pub fn health_name(&self) -> &'static str {
+        match self.health() {
+            1 => "healthy",
+            2 => "unhealthy",
+            _ => "unknown",
+        }
+    }
+
+    /// Differential corrections are available
+    pub fn diff_corr(&self) -> bool {
+        self.flags & 0x40 != 0
+    }
+
+    /// Where the orbit comes from
+    pub fn orbit_source_name(&self) -> &'static str {
+        match (self.flags >> 8) & 0x07 {
+            0 => "none",
+            1 => "ephemeris",
+            2 => "almanac",
+            3 => "AssistNow Offline",
+            4 => "AssistNow Autonomous",
+            _ => "other",
+        }
+    }
+}
+
+/// The satellites of an epoch, from NAV-SAT
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavSat {
+    /// GPS time of week of the epoch, in milliseconds
+    pub itow: u32,
+    pub svs: Vec<SatInfo>,
+}
+
+/// An epoch of raw measurements, from RXM-RAWX
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawxEpoch {
+    /// receiver time of week, in seconds
+    pub rcv_tow: f64,
+    pub week: u16,
+    /// measurements, one per satellite and signal
+    pub signals: u8,
+    pub satellites: usize,
+}
+
+/// 8-bit Fletcher checksum over class, id, length and payload
+pub fn checksum(bytes: &[u8]) -> [u8; 2] {
+    bytes.iter().fold([0u8, 0u8], |[a, b], &x| {
+        let a = a.wrapping_add(x);
+        [a, b.wrapping_add(a)]
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub class: u8,
+    pub id: u8,
+    pub payload: Vec<u8>,
+}
+
+/// An ACK-ACK or ACK-NAK, for the packet of class and id given
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ack {
+    pub class: u8,
+    pub id: u8,
+    pub acked: bool,
+}
+
+impl Frame {
+    /// The frame of a whole packet, sync chars to checksum, when the
+    /// checksum matches
+    pub fn parse(packet: &[u8]) -> Option<Frame> {
+        match frame_at(packet)? {
+            (Piece::Frame(frame), len) if len == packet.len() => Some(frame),
+            _ => None,
+        }
+    }
+
+    pub fn ack(&self) -> Option<Ack> {
+        match (self.class, self.id, self.payload.as_slice()) {
+            (CLASS_ACK, id @ (0x00 | 0x01), &[class, acked_id]) => Some(Ack {
+                class,
+                id: acked_id,
+                acked: id == 0x01,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Class, id and rates per port, in the order of `PORTS`, from a
+    /// CFG-MSG reply
+    pub fn msg_rates(&self) -> Option<(u8, u8, [u8; 5])> {
+        match (self.class, self.id, self.payload.as_slice()) {
+            (CLASS_CFG, ID_CFG_MSG, &[class, id, a, b, c, d, e, _]) => {
+                Some((class, id, [a, b, c, d, e]))
+            }
+            _ => None,
+        }
+    }
+
+    /// The versions in a MON-VER reply: 30 bytes of software version, 10 of
+    /// hardware version, then any number of 30 byte extensions
+    pub fn version(&self) -> Option<Version> {
+        if (self.class, self.id) != (CLASS_MON, ID_MON_VER) {
+            return None;
+        }
+        let extensions = self.payload.get(40..)?.chunks_exact(30);
+        if !extensions.remainder().is_empty() {
+            return None;
+        }
+        Some(Version {
+            software: padded_str(&self.payload[..30]),
+            hardware: padded_str(&self.payload[30..40]),
+            extensions: extensions
+                .map(padded_str)
+                .filter(|ext| !ext.is_empty())
+                .collect(),
+        })
+    }
+
+    /// The front end state in a MON-HW reply, 60 bytes on M8 receivers and
+    /// more on older ones, with the same fields up to the jamming indicator
+    pub fn mon_hw(&self) -> Option<RfStatus> {
+        if (self.class, self.id) != (CLASS_MON, ID_MON_HW) || self.payload.len() < 60 {
+            return None;
+        }
+        let p = &self.payload;
+        Some(RfStatus {
+            block: None,
+            ant_status: p[20],
+            ant_power: p[21],
+            noise_per_ms: u16::from_le_bytes([p[16], p[17]]),
+            agc_cnt: u16::from_le_bytes([p[18], p[19]]),
+            jamming_state: (p[22] >> 2) & 0x03,
+            jam_ind: p[45],
+        })
+    }
+
+    /// The front ends in a MON-RF reply: a 4 byte header, then 24 bytes per
+    /// RF block
+    pub fn mon_rf(&self) -> Option<Vec<RfStatus>> {
+        if (self.class, self.id) != (CLASS_MON, ID_MON_RF) {
+            return None;
+        }
+        let (head, blocks) = self.payload.split_at(self.payload.len().min(4));
+        let &[_version, count, _, _] = head else {
+            return None;
+        };
+        if blocks.len() != usize::from(count) * 24 {
+            return None;
+        }
+        Some(
+            blocks
+                .chunks_exact(24)
+                .map(|b| RfStatus {
+                    block: Some(b[0]),
+                    ant_status: b[2],
+                    ant_power: b[3],
+                    noise_per_ms: u16::from_le_bytes([b[12], b[13]]),
+                    agc_cnt: u16::from_le_bytes([b[14], b[15]]),
+                    jamming_state: b[1] & 0x03,
+                    jam_ind: b[16],
+                })
+                .collect(),
+        )
+    }
+
+    /// The chip ID in a SEC-UNIQID reply, in hex: 5 bytes after the version
+    /// and 3 reserved ones, or 6 bytes from generation 10 on
+    pub fn unique_id(&self) -> Option<String> {
+        if (self.class, self.id) != (CLASS_SEC, ID_SEC_UNIQID) || self.payload.len() < 9 {
+            return None;
+        }
+        Some(
+            self.payload[4..]
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+        )
+    }
+
+    /// The settings in a CFG-GNSS reply
+    pub fn gnss(&self) -> Option<Gnss> {
+        if (self.class, self.id) != (CLASS_CFG, ID_CFG_GNSS) {
+            return None;
+        }
+        let (head, blocks) = self.payload.split_at(self.payload.len().min(4));
+        let &[msg_ver, trk_ch_hw, trk_ch_use, count] = head else {
+            return None;
+        };
+        if blocks.len() != usize::from(count) * 8 {
+            return None;
+        }
+        Some(Gnss {
+            msg_ver,
+            trk_ch_hw,
+            trk_ch_use,
+            blocks: blocks
+                .chunks_exact(8)
+                .map(|b| GnssBlock {
+                    gnss_id: b[0],
+                    res_trk_ch: b[1],
+                    max_trk_ch: b[2],
+                    flags: u32::from_le_bytes([b[4], b[5], b[6], b[7]]),
+                })
+                .collect(),
+        })
+    }
+
+    /// The settings in a CFG-NAV5 reply
+    pub fn nav5(&self) -> Option<Nav5> {
+        if (self.class, self.id, self.payload.len()) != (CLASS_CFG, ID_CFG_NAV5, 36) {
+            return None;
+        }
+        let p = &self.payload;
+        let u16_at = |i: usize| u16::from_le_bytes([p[i], p[i + 1]]);
+        Some(Nav5 {
+            dyn_model: p[2],
+            fix_mode: p[3],
+            fixed_alt: f64::from(i32::from_le_bytes([p[4], p[5], p[6], p[7]])) * 0.01,
+            min_elev: p[12] as i8,
+            pdop_mask: f64::from(u16_at(14)) * 0.1,
+            tdop_mask: f64::from(u16_at(16)) * 0.1,
+            pacc_mask: u16_at(18),
+            tacc_mask: u16_at(20),
+            static_hold: p[22],
+            dgnss_timeout: p[23],
+        })
+    }
+
+    /// The settings in a CFG-PRT reply
+    pub fn port_config(&self) -> Option<PortConfig> {
+        if (self.class, self.id, self.payload.len()) != (CLASS_CFG, ID_CFG_PRT, 20) {
+            return None;
+        }
+        let p = &self.payload;
+        let u16_at = |i: usize| u16::from_le_bytes([p[i], p[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes([p[i], p[i + 1], p[i + 2], p[i + 3]]);
+        Some(PortConfig {
+            port: p[0],
+            tx_ready: u16_at(2),
+            mode: u32_at(4),
+            baud: u32_at(8),
+            in_proto: u16_at(12),
+            out_proto: u16_at(14),
+            flags: u16_at(16),
+        })
+    }
+
+    /// The solution in a NAV-PVT
+    pub fn nav_pvt(&self) -> Option<NavPvt> {
+        if (self.class, self.id, self.payload.len()) != (CLASS_NAV, ID_NAV_PVT, 92) {
+            return None;
+        }
+        let p = &self.payload;
+        let u32_at = |i: usize| u32::from_le_bytes([p[i], p[i + 1], p[i + 2], p[i + 3]]);
+        let i32_at = |i: usize| u32_at(i) as i32;
+        // valid date and valid time
+        let utc = if p[11] & 0x03 == 0x03 {
+            NaiveDate::from_ymd_opt(
+                i32::from(u16::from_le_bytes([p[4], p[5]])),
+                u32::from(p[6]),
+                u32::from(p[7]),
+            )
+            .and_then(|date| date.and_hms_opt(u32::from(p[8]), u32::from(p[9]), u32::from(p[10])))
+            // the seconds are rounded, the nanoseconds correct them either
+            // way
+            .map(|time| time + chrono::Duration::nanoseconds(i64::from(i32_at(16))))
+            .map(|time| time.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+        } else {
+            None
+        };
+        Some(NavPvt {
+            itow: u32_at(0),
+            utc,
+            fix_type: p[20],
+            fix_ok: p[21] & 0x01 != 0,
+            carr_soln: p[21] >> 6,
+            num_sv: p[23],
+            lon: f64::from(i32_at(24)) * 1e-7,
+            lat: f64::from(i32_at(28)) * 1e-7,
+            height: f64::from(i32_at(32)) * 1e-3,
+            h_msl: f64::from(i32_at(36)) * 1e-3,
+            h_acc: f64::from(u32_at(40)) * 1e-3,
+            v_acc: f64::from(u32_at(44)) * 1e-3,
+            g_speed: f64::from(i32_at(60)) * 1e-3,
+            heading: f64::from(i32_at(64)) * 1e-5,
+            p_dop: f64::from(u16::from_le_bytes([p[76], p[77]])) * 0.01,
+        })
+    }
+
+    /// The DOPs in a NAV-DOP, each scaled by 100
+    pub fn nav_dop(&self) -> Option<NavDop> {
+        if (self.class, self.id, self.payload.len()) != (CLASS_NAV, ID_NAV_DOP, 18) {
+            return None;
+        }
+        let p = &self.payload;
+        let dop_at = |i: usize| f64::from(u16::from_le_bytes([p[i], p[i + 1]])) * 0.01;
+        Some(NavDop {
+            itow: u32::from_le_bytes([p[0], p[1], p[2], p[3]]),
+            g_dop: dop_at(4),
+            p_dop: dop_at(6),
+            t_dop: dop_at(8),
+            v_dop: dop_at(10),
+            h_dop: dop_at(12),
+            n_dop: dop_at(14),
+            e_dop: dop_at(16),
+        })
+    }
+
+    /// The satellites in a NAV-SAT: an 8 byte header, then 12 bytes per
+    /// satellite
+    pub fn nav_sat(&self) -> Option<NavSat> {
+        if (self.class, self.id) != (CLASS_NAV, ID_NAV_SAT) {
+            return None;
+        }
+        let (head, svs) = self.payload.split_at(self.payload.len().min(8));
+        let &[t0, t1, t2, t3, _version, count, _, _] = head else {
+            return None;
+        };
+        if svs.len() != usize::from(count) * 12 {
+            return None;
+        }
+        Some(NavSat {
+            itow: u32::from_le_bytes([t0, t1, t2, t3]),
+            svs: svs
+                .chunks_exact(12)
+                .map(|b| SatInfo {
+                    gnss_id: b[0],
+                    sv_id: b[1],
+                    cno: b[2],
+                    elev: b[3] as i8,
+                    azim: i16::from_le_bytes([b[4], b[5]]),
+                    pr_res: f64::from(i16::from_le_bytes([b[6], b[7]])) * 0.1,
+                    flags: u32::from_le_bytes([b[8], b[9], b[10], b[11]]),
+                })
+                .collect(),
+        })
+    }
+
+    /// The masks of a CFG-CFG, whose device mask is optional
+    pub fn cfg_cfg(&self) -> Option<CfgCfg> {
+        if (self.class, self.id) != (CLASS_CFG, ID_CFG_CFG) {
+            return None;
+        }
+        let p = &self.payload;
+        if p.len() != 12 && p.len() != 13 {
+            return None;
+        }
+        let u32_at = |i: usize| u32::from_le_bytes([p[i], p[i + 1], p[i + 2], p[i + 3]]);
+        Some(CfgCfg {
+            clear: u32_at(0),
+            save: u32_at(4),
+            load: u32_at(8),
+            devices: p.get(12).copied().unwrap_or(0),
+        })
+    }
+
+    /// A line on what the frame says, for the packets this module knows;
+    /// `poll` for the empty ones asking a receiver for its settings
+    pub fn summary(&self) -> Option<String> {
+        let p = &self.payload;
+        if p.is_empty() && matches!(self.class, CLASS_CFG | CLASS_MON) {
+            return Some("poll".to_owned());
+        }
+        match (self.class, self.id) {
+            (CLASS_NAV, ID_NAV_PVT) => self.nav_pvt().map(|pvt| {
+                format!(
+                    "{} {} fix, carrier {}, {:.7} {:.7}, {:.2} m above sea level, {} SV",
+                    pvt.utc.as_deref().unwrap_or("no time yet"),
+                    pvt.fix_type_name(),
+                    pvt.carr_soln_name(),
+                    pvt.lat,
+                    pvt.lon,
+                    pvt.h_msl,
+                    pvt.num_sv
+                )
+            }),
+            (CLASS_NAV, ID_NAV_DOP) => self.nav_dop().map(|dop| {
+                format!(
+                    "gdop {:.2}, pdop {:.2}, hdop {:.2}, vdop {:.2}, tdop {:.2}",
+                    dop.g_dop, dop.p_dop, dop.h_dop, dop.v_dop, dop.t_dop
+                )
+            }),
+            (CLASS_NAV, ID_NAV_HPPOSLLH) => self.hp_pos_llh().map(|hp| hp.describe()),
+            (CLASS_NAV, ID_NAV_RELPOSNED) => self.rel_pos_ned().map(|rel| rel.describe()),
+            (CLASS_NAV, ID_NAV_SAT) => self.nav_sat().map(|sat| {
+                let used = sat.svs.iter().filter(|sv| sv.used()).count();
+                format!("{} satellites, {} used", sat.svs.len(), used)
+            }),
+            (CLASS_ACK, _) => self.ack().map(|ack| frame_name(ack.class, ack.id)),
+            (CLASS_MON, ID_MON_VER) => self.version().map(|version| {
+                let mut parts = vec![version.software, format!("hardware {}", version.hardware)];
+                parts.extend(version.extensions);
+                parts.join(", ")
+            }),
+            (CLASS_SEC, ID_SEC_UNIQID) => self.unique_id().map(|id| format!("unique ID {}", id)),
+            (CLASS_MON, ID_MON_HW) => self.mon_hw().map(|hw| hw.describe()),
+            (CLASS_MON, ID_MON_RF) => self.mon_rf().map(|blocks| {
+                let blocks: Vec<String> = blocks
+                    .iter()
+                    .map(|rf| format!("block {}: {}", rf.block.unwrap_or(0), rf.describe()))
+                    .collect();
+                blocks.join("; ")
+            }),
+            (CLASS_CFG, ID_CFG_MSG) => match *p.as_slice() {
+                [class, id] => Some(format!("poll {}", frame_name(class, id))),
+                [class, id, rate] => Some(format!("{}: {}", frame_name(class, id), rate)),
+                _ => self.msg_rates().map(|(class, id, rates)| {
+                    let on: Vec<String> = PORTS
+                        .iter()
+                        .zip(&rates)
+                        .filter(|&(_, &rate)| rate > 0)
+                        .map(|(port, rate)| format!("{} {}", port, rate))
+                        .collect();
+                    let on = if on.is_empty() {
+                        "off".to_owned()
+                    } else {
+                        on.join(", ")
+                    };
+                    format!("{}: {}", frame_name(class, id), on)
+                }),
+            },
+            (CLASS_CFG, ID_CFG_PRT) => match *p.as_slice() {
+                [port] => Some(format!(
+                    "poll {}",
+                    PORTS.get(usize::from(port)).unwrap_or(&"unknown port")
+                )),
+                _ => self.port_config().map(|config| {
+                    let mut text = config.port_name().to_owned();
+                    if let Some(mode) = config.mode_name() {
+                        text += &format!(" {} baud {}", config.baud, mode);
+                    }
+                    format!(
+                        "{}, in {}, out {}",
+                        text,
+                        protocol_names(config.in_proto).join("+"),
+                        protocol_names(config.out_proto).join("+")
+                    )
+                }),
+            },
+            (CLASS_CFG, ID_CFG_RATE) => self.rate().map(|rate| {
+                format!(
+                    "{} ms x{}, {:.2} Hz, {} time",
+                    rate.meas_ms,
+                    rate.nav_ratio,
+                    rate.hz(),
+                    rate.time_ref_name()
+                )
+            }),
+            (CLASS_CFG, ID_CFG_NAV5) => self.nav5().map(|nav5| {
+                format!(
+                    "dynModel {}, fixMode {}, minElev {}°",
+                    nav5.dyn_model_name(),
+                    nav5.fix_mode_name(),
+                    nav5.min_elev
+                )
+            }),
+            (CLASS_CFG, ID_CFG_GNSS) => self.gnss().map(|gnss| {
+                let enabled: Vec<&str> = gnss
+                    .blocks
+                    .iter()
+                    .filter(|b| b.enabled())
+                    .map(GnssBlock::name)
+                    .collect();
+                format!("enabled: {}", enabled.join(", "))
+            }),
+            (CLASS_CFG, ID_CFG_CFG) => self.cfg_cfg().map(|cfg| cfg.describe()),
+            (CLASS_CFG, ID_CFG_RST) => match *p.as_slice() {
+                [m0, m1, mode, _] => Some(format!(
+                    "navBbrMask 0x{:04x}, resetMode {}",
+                    u16::from_le_bytes([m0, m1]),
+                    mode
+                )),
+                _ => None,
+            },
+            (CLASS_CFG, ID_CFG_VALSET) | (CLASS_CFG, ID_CFG_VALGET) => {
+                crate::ubxcfg::describe(self)
+            }
+            (CLASS_CFG, ID_CFG_TP5) => match *p.as_slice() {
+                [tp_idx] => Some(format!("poll timepulse {}", tp_idx)),
+                _ => self.time_pulse().map(|tp| tp.describe().join("; ")),
+            },
+            _ => None,
+        }
+    }
+
+    /// The frame as a packet to send
+    pub fn packet(&self) -> Vec<u8> {
+        packet(self.class, self.id, &self.payload)
+    }
+
+    pub fn mga_ack(&self) -> Option<MgaAck> {
+        match (self.class, self.id, self.payload.as_slice()) {
+            (CLASS_MGA, ID_MGA_ACK, &[kind, _, info_code, msg_id, a, b, c, d]) => Some(MgaAck {
+                accepted: kind == 1,
+                info_code,
+                msg_id,
+                payload_start: [a, b, c, d],
+            }),
+            _ => None,
+        }
+    }
+
+    /// The epoch of an RXM-RAWX: a 16 byte header, then 32 bytes per
+    /// measurement
+    pub fn rawx(&self) -> Option<RawxEpoch> {
+        if (self.class, self.id) != (CLASS_RXM, ID_RXM_RAWX) || self.payload.len() < 16 {
+            return None;
+        }
+        let p = &self.payload;
+        let signals = p[11];
+        let meas = p[16..].chunks_exact(32);
+        if meas.len() != usize::from(signals) || !meas.remainder().is_empty() {
+            return None;
+        }
+        let mut svs: Vec<(u8, u8)> = meas.map(|m| (m[20], m[21])).collect();
+        svs.sort_unstable();
+        svs.dedup();
+        let mut tow = [0; 8];
+        tow.copy_from_slice(&p[..8]);
+        Some(RawxEpoch {
+            rcv_tow: f64::from_le_bytes(tow),
+            week: u16::from_le_bytes([p[8], p[9]]),
+            signals,
+            satellites: svs.len(),
+        })
+    }
+
+    /// The settings in a CFG-TP5 reply
+    pub fn time_pulse(&self) -> Option<TimePulse> {
+        if (self.class, self.id, self.payload.len()) != (CLASS_CFG, ID_CFG_TP5, 32) {
+            return None;
+        }
+        let p = &self.payload;
+        let u32_at = |i: usize| u32::from_le_bytes([p[i], p[i + 1], p[i + 2], p[i + 3]]);
+        Some(TimePulse {
+            tp_idx: p[0],
+            ant_cable_delay: i16::from_le_bytes([p[4], p[5]]),
+            rf_group_delay: i16::from_le_bytes([p[6], p[7]]),
+            freq_period: u32_at(8),
+            freq_period_lock: u32_at(12),
+            pulse_len_ratio: u32_at(16),
+            pulse_len_ratio_lock: u32_at(20),
+            user_config_delay: u32_at(24) as i32,
+            flags: u32_at(28),
+        })
+    }
+
+    /// The progress in a NAV-SVIN
+    pub fn nav_svin(&self) -> Option<NavSvin> {
+        if (self.class, self.id, self.payload.len()) != (CLASS_NAV, ID_NAV_SVIN, 40) {
+            return None;
+        }
+        let p = &self.payload;
+        let u32_at = |i: usize| u32::from_le_bytes([p[i], p[i + 1], p[i + 2], p[i + 3]]);
+        let hp_at =
+            |i: usize, hp: usize| i64::from(u32_at(i) as i32) * 100 + i64::from(p[hp] as i8);
+        Some(NavSvin {
+            dur: u32_at(8),
+            mean: [hp_at(12, 24), hp_at(16, 25), hp_at(20, 26)],
+            mean_acc: u32_at(28),
+            obs: u32_at(32),
+            valid: p[36] != 0,
+            active: p[37] != 0,
+        })
+    }
+
+    /// The position in a NAV-HPPOSLLH, with the high precision parts joined
+    /// to the standard ones
+    pub fn hp_pos_llh(&self) -> Option<HpPosLlh> {
+        if (self.class, self.id, self.payload.len()) != (CLASS_NAV, ID_NAV_HPPOSLLH, 36) {
+            return None;
+        }
+        let p = &self.payload;
+        let u32_at = |i: usize| u32::from_le_bytes([p[i], p[i + 1], p[i + 2], p[i + 3]]);
+        let hp_at =
+            |i: usize, hp: usize, factor: i64| join_hp(u32_at(i) as i32, p[hp] as i8, factor);
+        Some(HpPosLlh {
+            itow: u32_at(4),
+            lon: hp_at(8, 24, 100),
+            lat: hp_at(12, 25, 100),
+            height: hp_at(16, 26, 10),
+            h_msl: hp_at(20, 27, 10),
+            h_acc: u32_at(28),
+            v_acc: u32_at(32),
+            invalid: p[3] & 0x01 != 0,
+        })
+    }
+
+    /// The baseline in a NAV-RELPOSNED: 40 bytes in version 0, 64 with the
+    /// length and heading in version 1
+    pub fn rel_pos_ned(&self) -> Option<RelPosNed> {
+        if (self.class, self.id) != (CLASS_NAV, ID_NAV_RELPOSNED) {
+            return None;
+        }
+        let p = &self.payload;
+        let u32_at = |i: usize| u32::from_le_bytes([p[i], p[i + 1], p[i + 2], p[i + 3]]);
+        let hp_at = |i: usize, hp: usize| join_hp(u32_at(i) as i32, p[hp] as i8, 100);
+        let ref_station = |p: &[u8]| u16::from_le_bytes([p[2], p[3]]);
+        match (p.first(), p.len()) {
+            (Some(0), 40) => Some(RelPosNed {
+                itow: u32_at(4),
+                ref_station: ref_station(p),
+                ned: [hp_at(8, 20), hp_at(12, 21), hp_at(16, 22)],
+                length: None,
+                heading: None,
+                acc_ned: [u32_at(24), u32_at(28), u32_at(32)],
+                flags: u32_at(36),
+            }),
+            (Some(1), 64) => {
+                let flags = u32_at(60);
+                Some(RelPosNed {
+                    itow: u32_at(4),
+                    ref_station: ref_station(p),
+                    ned: [hp_at(8, 32), hp_at(12, 33), hp_at(16, 34)],
+                    length: Some(hp_at(20, 35)),
+                    // relPosHeadingValid
+                    heading: Some(u32_at(24) as i32).filter(|_| flags & 0x100 != 0),
+                    acc_ned: [u32_at(36), u32_at(40), u32_at(44)],
+                    flags,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The setting in a CFG-RATE reply
+    pub fn rate(&self) -> Option<Rate> {
+        match (self.class, self.id, self.payload.as_slice()) {
+            (CLASS_CFG, ID_CFG_RATE, &[m0, m1, n0, n1, t0, t1]) => Some(Rate {
+                meas_ms: u16::from_le_bytes([m0, m1]),
+                nav_ratio: u16::from_le_bytes([n0, n1]),
+                time_ref: u16::from_le_bytes([t0, t1]),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Class and id of a packet built by the ublox crate
+pub fn packet_id(packet: &[u8]) -> Option<(u8, u8)> {
+    match packet {
+        [0xb5, 0x62, class, id, ..] => Some((*class, *id)),
+        _ => None,
+    }
+}
+
+/// Collects bytes until they make whole frames. Whatever lies between frames,
+/// like NMEA sentences, is dropped.
+#[derive(Debug, Clone, Default)]
+pub struct FrameReader {
+    buf: Vec<u8>,
+    /// frames dropped for a bad checksum
+    pub bad_checksums: u64,
+}
+
+impl FrameReader {
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Frame> {
+        self.buf.extend_from_slice(bytes);
+        let mut frames = Vec::new();
+        loop {
+            match self.buf.windows(2).position(|w| w == SYNC) {
+                Some(start) => {
+                    self.buf.drain(..start);
+                }
+                None => {
+                    // a sync char at the very end may be followed by the
+                    // second one in the next read
+                    let keep = usize::from(self.buf.last() == Some(&SYNC[0]));
+                    self.buf.drain(..self.buf.len() - keep);
+                    return frames;
+                }
+            }
+            if self.buf.len() < 6 {
+                return frames;
+            }
+            let len = usize::from(u16::from_le_bytes([self.buf[4], self.buf[5]]));
+            if len > MAX_PAYLOAD {
+                self.buf.drain(..1);
+                continue;
+            }
+            if self.buf.len() < len + 8 {
+                return frames;
+            }
+            if checksum(&self.buf[2..len + 6]) != self.buf[len + 6..len + 8] {
+                self.bad_checksums += 1;
+                self.buf.drain(..1);
+                continue;
+            }
+            frames.push(Frame {
+                class: self.buf[2],
+                id: self.buf[3],
+                payload: self.buf[6..len + 6].to_vec(),
+            });
+            self.buf.drain(..len + 8);
+        }
+    }
+}
+
+/// Reads the packet `reader` is at the start of, if any, onto `buf`.
+/// Packets are binary, so they are taken by their length rather than up to a
+/// line end, which lets a line reader pass them on whole. Returns `false`
+/// when there is no packet, leaving whatever a false start took in `buf` for
+/// the caller to read on to the line end.
+pub fn read_packet(reader: &mut impl BufRead, buf: &mut Vec<u8>) -> io::Result<bool> {
+    let start = buf.len();
+    let peek = reader.fill_buf()?;
+    if peek.is_empty() || !SYNC.starts_with(&peek[..peek.len().min(2)]) {
+        return Ok(false);
+    }
+    reader.by_ref().take(6).read_to_end(buf)?;
+    let header = &buf[start..];
+    if header.len() < 6 || header[..2] != SYNC {
+        return Ok(false);
+    }
+    let len = usize::from(u16::from_le_bytes([header[4], header[5]]));
+    if len > MAX_PAYLOAD {
+        return Ok(false);
+    }
+    reader.take(len as u64 + 2).read_to_end(buf)?;
+    Ok(buf.len() - start == len + 8)
+}
+
+/// What a capture file holds, piece by piece
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece {
+    Frame(Frame),
+    /// a frame whose checksum doesn't match: class, id and payload length
+    BadChecksum(u8, u8, usize),
+    /// a line of text, like an NMEA sentence, without its line end
+    Text(String),
+    /// bytes that are neither, like a frame cut off at the end
+    Junk(usize),
+}
+
+/// NMEA sentences are 82 chars at most; longer lines aren't taken for text
+const MAX_LINE: usize = 256;
+
+/// The frame `bytes` start with, and its length
+fn frame_at(bytes: &[u8]) -> Option<(Piece, usize)> {
+    if !bytes.starts_with(&SYNC) || bytes.len() < 8 {
+        return None;
+    }
+    let len = usize::from(u16::from_le_bytes([bytes[4], bytes[5]]));
+    if len > MAX_PAYLOAD || bytes.len() < len + 8 {
+        return None;
+    }
+    if checksum(&bytes[2..len + 6]) != bytes[len + 6..len + 8] {
+        // the length may be what's wrong, so only the sync chars are taken
+        return Some((Piece::BadChecksum(bytes[2], bytes[3], len), 2));
+    }
+    let frame = Frame {
+        class: bytes[2],
+        id: bytes[3],
+        payload: bytes[6..len + 6].to_vec(),
+    };
+    Some((Piece::Frame(frame), len + 8))
+}
+
+/// The line of text `bytes` start with, and its length with the line end
+fn text_at(bytes: &[u8]) -> Option<(Piece, usize)> {
+    if !matches!(bytes.first(), Some(b'$') | Some(b'!')) {
+        return None;
+    }
+    let end = bytes
+        .iter()
+        .take(MAX_LINE)
+        .position(|&b| b == b'\n')
+        .unwrap_or_else(|| bytes.len().min(MAX_LINE));
+    let line = &bytes[..end];
+    if !line
+        .iter()
+        .all(|&b| b == b'\r' || (b' '..=b'~').contains(&b))
+    {
+        return None;
+    }
+    let text = String::from_utf8_lossy(line).trim_end().to_owned();
+    Some((Piece::Text(text), (end + 1).min(bytes.len())))
+}
+
+/// Splits a capture, like a u-center log or the output of
+/// `cat /dev/ttyACM0`, into frames and lines of text, each with its offset
+pub fn split_capture(bytes: &[u8]) -> Vec<(usize, Piece)> {
+    let mut pieces = Vec::new();
+    let mut junk_start = None;
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let rest = &bytes[pos..];
+        let found = frame_at(rest).or_else(|| text_at(rest));
+        let line_end = matches!(rest[0], b'\r' | b'\n');
+        if found.is_none() && !line_end {
+            junk_start.get_or_insert(pos);
+            pos += 1;
+            continue;
+        }
+        if let Some(start) = junk_start.take() {
+            pieces.push((start, Piece::Junk(pos - start)));
+        }
+        match found {
+            Some((piece, len)) => {
+                pieces.push((pos, piece));
+                pos += len;
+            }
+            None => pos += 1,
+        }
+    }
+    if let Some(start) = junk_start {
+        pieces.push((start, Piece::Junk(pos - start)));
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A receiver's output after CFG-MSG was acked and CFG-RATE refused,
+    /// with NMEA in between
+    const DUMP: &[u8] =
+        b"$GPGGA,092750.000,5321.6802,N,00630.3372,W,1,8,1.03,61.7,M,55.2,M,,*76\r\n\
+\xb5\x62\x05\x01\x02\x00\x06\x01\x0f\x38\
+$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68\r\n\
+\xb5\x62\x05\x00\x02\x00\x06\x08\x15\x3a";
+
+    #[test]
+    fn acks_are_found_between_sentences() {
+        let mut reader = FrameReader::default();
+        let frames = reader.push(DUMP);
+        assert_eq!(frames.len(), 2);
+        let acks: Vec<Ack> = frames.iter().filter_map(Frame::ack).collect();
+        assert_eq!(
+            acks,
+            vec![
+                Ack {
+                    class: CLASS_CFG,
+                    id: 0x01,
+                    acked: true
+                },
+                Ack {
+                    class: CLASS_CFG,
+                    id: 0x08,
+                    acked: false
+                },
+            ]
+        );
+        assert_eq!(reader.bad_checksums, 0);
+    }
+
+    #[test]
+    fn frames_split_across_reads_and_bad_checksums() {
+        let mut reader = FrameReader::default();
+        let mut frames = Vec::new();
+        for chunk in DUMP.chunks(3) {
+            frames.extend(reader.push(chunk));
+        }
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].payload, vec![0x06, 0x01]);
+
+        let mut corrupt = DUMP.to_vec();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xff;
+        let frames = reader.push(&corrupt);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(reader.bad_checksums, 1);
+
+        assert_eq!(
+            packet_id(&[0xb5, 0x62, 0x06, 0x04, 0x04, 0x00]),
+            Some((CLASS_CFG, ID_CFG_RST))
+        );
+    }
+
+    #[test]
+    fn rtcm_types_are_known() {
+        assert_eq!(rtcm_id("1005"), Some(0x05));
+        assert_eq!(rtcm_id(" 1230"), Some(0xe6));
+        assert_eq!(rtcm_id("4072.1"), Some(0xfd));
+        // ephemerides aren't sent by u-blox bases
+        assert_eq!(rtcm_id("1019"), None);
+        assert_eq!(message_id("rtcm3-1077"), Some((CLASS_RTCM3, 0x4d)));
+        assert_eq!(message_name(CLASS_RTCM3, 0x5e), Some("RTCM3-1094"));
+    }
+
+    #[test]
+    fn msg_rates_are_polled() {
+        assert_eq!(message_id("gsv"), Some((0xf0, 0x03)));
+        assert_eq!(message_id("NAV-PVT"), Some((0x01, 0x07)));
+        assert_eq!(message_id("0x02-0x15"), Some((0x02, 0x15)));
+        assert_eq!(message_id("GSX"), None);
+        assert_eq!(message_name(0xf0, 0x03), Some("NMEA-GSV"));
+
+        let poll = poll_msg(0xf0, 0x03);
+        assert_eq!(
+            poll,
+            vec![0xb5, 0x62, 0x06, 0x01, 0x02, 0x00, 0xf0, 0x03, 0xfc, 0x14]
+        );
+
+        // the reply, GSV on UART1 and USB, then the ACK of the poll
+        let reply = b"\xb5\x62\x06\x01\x08\x00\xf0\x03\x00\x01\x00\x01\x00\x00\x04\x40\
+\xb5\x62\x05\x01\x02\x00\x06\x01\x0f\x38";
+        let frames = FrameReader::default().push(reply);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].msg_rates(), Some((0xf0, 0x03, [0, 1, 0, 1, 0])));
+        assert_eq!(frames[1].msg_rates(), None);
+    }
+
+    #[test]
+    fn rate_is_polled() {
+        assert_eq!(poll_rate(), b"\xb5\x62\x06\x08\x00\x00\x0e\x30".to_vec());
+        // 5 Hz asked for, the receiver acks and then says it runs at 200 ms,
+        // aligned to GPS time
+        let reply = b"$GPTXT,01,01,02,ANTSTATUS=OK*3B\r\n\
+\xb5\x62\x05\x01\x02\x00\x06\x08\x16\x3f\
+\xb5\x62\x06\x08\x06\x00\xc8\x00\x01\x00\x01\x00\xde\x6a";
+        let frames = FrameReader::default().push(reply);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].rate(), None);
+        let rate = frames[1].rate().unwrap();
+        assert_eq!(
+            rate,
+            Rate {
+                meas_ms: 200,
+                nav_ratio: 1,
+                time_ref: 1
+            }
+        );
+        assert_eq!(rate.hz(), 5.0);
+        assert_eq!(rate.time_ref_name(), "GPS");
+    }
+
+    #[test]
+    fn port_config_is_polled_and_changed() {
+        assert_eq!(
+            poll_prt(1),
+            b"\xb5\x62\x06\x00\x01\x00\x01\x08\x22".to_vec()
+        );
+
+        // the default settings of UART1
+        let reply = b"\xb5\x62\x06\x00\x14\x00\x01\x00\x00\x00\xd0\x08\x00\x00\x80\x25\
+\x00\x00\x07\x00\x03\x00\x00\x00\x00\x00\xa2\xb5";
+        let frames = FrameReader::default().push(reply);
+        let config = frames[0].port_config().unwrap();
+        assert_eq!(config, PortConfig::uart(1, 9600));
+        assert_eq!(config.port_name(), "uart1");
+        assert_eq!(config.mode_name().as_deref(), Some("8N1"));
+        assert_eq!(protocol_names(config.in_proto), vec!["ubx", "nmea", "rtcm"]);
+        assert_eq!(config.packet(), reply.to_vec());
+
+        // UBX only at 115200
+        let changed = PortConfig {
+            baud: 115_200,
+            in_proto: protocol_bit("UBX").unwrap(),
+            out_proto: protocol_bit("ubx").unwrap(),
+            ..config
+        };
+        assert_eq!(
+            changed.packet(),
+            b"\xb5\x62\x06\x00\x14\x00\x01\x00\x00\x00\xd0\x08\x00\x00\x00\xc2\x01\x00\
+\x01\x00\x01\x00\x00\x00\x00\x00\xb8\x42"
+                .to_vec()
+        );
+    }
+
+    #[test]
+    fn mon_ver_extensions_are_read() {
+        assert_eq!(poll_mon_ver(), b"\xb5\x62\x0a\x04\x00\x00\x0e\x34".to_vec());
+
+        // a NEO-M8N
+        let field = |text: &str, len: usize| {
+            let mut bytes = text.as_bytes().to_vec();
+            bytes.resize(len, 0);
+            bytes
+        };
+        let mut payload = field("ROM CORE 3.01 (107888)", 30);
+        payload.extend(field("00080000", 10));
+        for ext in &[
+            "FWVER=SPG 3.01",
+            "PROTVER=18.00",
+            "GPS;GLO;GAL;BDS",
+            "SBAS;IMES;QZSS",
+            "GNSS OTP=GPS;GLO",
+            "MOD=NEO-M8N-0",
+        ] {
+            payload.extend(field(ext, 30));
+        }
+        let bytes = packet(CLASS_MON, ID_MON_VER, &payload);
+        let frames = FrameReader::default().push(&bytes);
+        let version = frames[0].version().unwrap();
+        assert_eq!(version.software, "ROM CORE 3.01 (107888)");
+        assert_eq!(version.hardware, "00080000");
+        assert_eq!(version.extensions.len(), 6);
+        assert_eq!(version.extension("PROTVER"), Some("18.00"));
+        assert_eq!(version.extension("MOD"), Some("NEO-M8N-0"));
+        assert_eq!(version.extension("GPS"), None);
+
+        // an extension cut short is no MON-VER
+        let frames = FrameReader::default().push(&packet(CLASS_MON, ID_MON_VER, &payload[..60]));
+        assert_eq!(frames[0].version(), None);
+    }
+
+    #[test]
+    fn nav5_changes_only_touch_the_fields_given() {
+        assert_eq!(poll_nav5(), b"\xb5\x62\x06\x24\x00\x00\x2a\x84".to_vec());
+
+        let payload = |change: Nav5Change| {
+            FrameReader::default().push(&change.packet())[0]
+                .payload
+                .clone()
+        };
+        let model_only = Nav5Change {
+            dyn_model: Some(7),
+            ..Nav5Change::default()
+        };
+        let p = payload(model_only);
+        assert_eq!(p.len(), 36);
+        assert_eq!(&p[..4], &[0x01, 0x00, 7, 0]);
+        assert!(p[4..].iter().all(|&b| b == 0));
+
+        let elev_and_fix = Nav5Change {
+            min_elev: Some(10),
+            fix_mode: Some(3),
+            ..Nav5Change::default()
+        };
+        let p = payload(elev_and_fix);
+        assert_eq!(&p[..4], &[0x06, 0x00, 0, 3]);
+        assert_eq!(p[12], 10);
+
+        assert_eq!(Nav5Change::default().mask(), 0);
+    }
+
+    #[test]
+    fn nav5_settings_are_decoded() {
+        // the defaults of a NEO-M8N
+        let reply = b"\xb5\x62\x06\x24\x24\x00\xff\xff\x00\x03\x00\x00\x00\x00\x10\x27\x00\x00\
+\x05\x00\xfa\x00\xfa\x00\x64\x00\x2c\x01\x00\x3c\x00\x00\x00\x00\x00\x00\x00\x00\
+\x00\x00\x00\x00\x4c\x1c";
+        let frames = FrameReader::default().push(reply);
+        let nav5 = frames[0].nav5().unwrap();
+        assert_eq!(nav5.dyn_model_name(), "portable");
+        assert_eq!(nav5.fix_mode_name(), "auto");
+        assert_eq!(nav5.min_elev, 5);
+        assert_eq!(nav5.pdop_mask, 25.0);
+        assert_eq!((nav5.pacc_mask, nav5.tacc_mask), (100, 300));
+        assert_eq!(nav5.dgnss_timeout, 60);
+    }
+
+    /// CFG-GNSS of a NEO-M8N: GPS, SBAS, QZSS and GLONASS enabled
+    const GNSS_REPLY: &[u8] = b"\xb5\x62\x06\x3e\x3c\x00\x00\x20\x20\x07\
+\x00\x08\x10\x00\x01\x00\x01\x01\x01\x01\x03\x00\x01\x00\x01\x01\
+\x02\x04\x08\x00\x00\x00\x01\x01\x03\x08\x10\x00\x00\x00\x01\x01\
+\x04\x00\x08\x00\x00\x00\x01\x01\x05\x00\x03\x00\x01\x00\x01\x01\
+\x06\x08\x0e\x00\x01\x00\x01\x01\x4f\xe9";
+
+    #[test]
+    fn gnss_blocks_are_changed_and_written_back_whole() {
+        assert_eq!(poll_gnss(), b"\xb5\x62\x06\x3e\x00\x00\x44\xd2".to_vec());
+
+        let frames = FrameReader::default().push(GNSS_REPLY);
+        let mut gnss = frames[0].gnss().unwrap();
+        assert_eq!(gnss.blocks.len(), 7);
+        let enabled: Vec<&str> = gnss
+            .blocks
+            .iter()
+            .filter(|b| b.enabled())
+            .map(GnssBlock::name)
+            .collect();
+        assert_eq!(enabled, vec!["gps", "sbas", "qzss", "glonass"]);
+        assert!(gnss.warnings().is_empty());
+        // unchanged, it goes back as it came
+        assert_eq!(gnss.packet(), GNSS_REPLY.to_vec());
+
+        assert!(gnss.set_enabled(GALILEO, true));
+        assert!(gnss.set_enabled(GLONASS, false));
+        assert!(!gnss.set_enabled(9, true));
+        let changed = FrameReader::default().push(&gnss.packet())[0]
+            .gnss()
+            .unwrap();
+        assert_eq!(changed.blocks[2].flags, 0x0101_0001);
+        assert_eq!(changed.blocks[6].flags, 0x0101_0000);
+        assert_eq!(changed.blocks[0], gnss.blocks[0]);
+        assert!(changed.warnings().is_empty());
+
+        gnss.set_enabled(GLONASS, true);
+        gnss.set_enabled(BEIDOU, true);
+        let warnings = gnss.warnings();
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings[0].contains("at most three"));
+        assert!(warnings[1].contains("BeiDou and GLONASS"));
+
+        for id in &[GPS, GALILEO, BEIDOU, GLONASS] {
+            gnss.set_enabled(*id, false);
+        }
+        assert!(gnss.warnings()[0].starts_with("no GPS"));
+    }
+
+    #[test]
+    fn cfg_cfg_masks() {
+        let sections = CFG_SECTIONS.iter().fold(0, |mask, &(_, bit)| mask | bit);
+        let save = CfgCfg::save(sections, 0x03);
+        assert_eq!(
+            save.describe(),
+            "clearMask 0x0000, saveMask 0x041f, loadMask 0x0000, deviceMask 0x03"
+        );
+        assert_eq!(
+            save.packet(),
+            b"\xb5\x62\x06\x09\x0d\x00\x00\x00\x00\x00\x1f\x04\x00\x00\x00\x00\x00\x00\x03\x42\xf3"
+                .to_vec()
+        );
+
+        // navigation and receiver manager settings back to the defaults
+        let defaults = CfgCfg::load_defaults(0x08 | 0x10, 0x03);
+        assert_eq!(
+            defaults.packet(),
+            b"\xb5\x62\x06\x09\x0d\x00\x18\x00\x00\x00\x00\x00\x00\x00\x18\x00\x00\x00\x03\x4f\x6c"
+                .to_vec()
+        );
+    }
+
+    #[test]
+    fn packets_are_read_whole_between_lines() {
+        // tDOP 2.66 puts a line end inside the packet
+        let dop = packet(
+            CLASS_NAV,
+            ID_NAV_DOP,
+            b"\x18\x42\x18\x01\x96\x00\x7b\x00\x0a\x01\x6e\x00\x50\x00\x3c\x00\x46\x00",
+        );
+        let mut data = b"$GPGGA,1\r\n".to_vec();
+        data.extend(&dop);
+        data.extend(b"$GPRMC,2\r\n");
+        let mut reader = &data[..];
+        let mut buf = Vec::new();
+        assert!(!read_packet(&mut reader, &mut buf).unwrap());
+        assert!(buf.is_empty());
+        reader.read_until(b'\n', &mut buf).unwrap();
+
+        buf.clear();
+        assert!(read_packet(&mut reader, &mut buf).unwrap());
+        assert_eq!(buf, dop);
+        let frame = Frame::parse(&buf).unwrap();
+        let nav = frame.nav_dop().unwrap();
+        assert!((nav.h_dop - 0.8).abs() < 1e-9);
+        assert!((nav.e_dop - 0.7).abs() < 1e-9);
+        assert_eq!(
+            frame.summary().unwrap(),
+            "gdop 1.50, pdop 1.23, hdop 0.80, vdop 1.10, tdop 2.66"
+        );
+        assert_eq!(reader, b"$GPRMC,2\r\n");
+
+        // a packet cut off is left for the line reader
+        let mut reader = &data[10..20];
+        buf.clear();
+        assert!(!read_packet(&mut reader, &mut buf).unwrap());
+        assert_eq!(buf, &dop[..10]);
+        assert_eq!(Frame::parse(&buf), None);
+    }
+
+    #[test]
+    fn nav_pvt_is_decoded_between_sentences() {
+        // a 3D fix with a fixed carrier phase solution
+        let dump = b"$GPGGA,092750.000,5321.6802,N,00630.3372,W,1,8,1.03,61.7,M,55.2,M,,*76\r\n\
+\xb5\x62\x01\x07\x5c\x00\x18\x42\x18\x01\xe5\x07\x03\x04\x05\x06\x07\x07\x19\x00\x00\x00\x80\xb2\
+\xe6\x0e\x03\x81\xea\x0c\x10\xe1\xb6\x4b\xae\xbb\x59\x16\xf4\xb0\x00\x00\x84\x4e\x00\x00\xb0\x04\
+\x00\x00\xd0\x07\x00\x00\xf6\xff\xff\xff\x78\x00\x00\x00\x05\x00\x00\x00\x78\x00\x00\x00\x20\x4b\
+\xbc\x00\x2c\x01\x00\x00\x80\x38\x01\x00\x7b\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+\x00\x00\xd7\xe3\
+$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68\r\n";
+        let frames = FrameReader::default().push(dump);
+        let pvt = frames[0].nav_pvt().unwrap();
+        assert_eq!(pvt.utc.as_deref(), Some("2021-03-04T05:06:07.250Z"));
+        assert_eq!((pvt.fix_type_name(), pvt.fix_ok), ("3D", true));
+        assert_eq!(pvt.carr_soln_name(), "fixed");
+        assert_eq!(pvt.num_sv, 12);
+        assert!((pvt.lat - 37.4979502).abs() < 1e-9);
+        assert!((pvt.lon - 127.0276368).abs() < 1e-9);
+        assert!((pvt.height - 45.3).abs() < 1e-9);
+        assert!((pvt.h_msl - 20.1).abs() < 1e-9);
+        assert!((pvt.h_acc - 1.2).abs() < 1e-9);
+        assert!((pvt.g_speed - 0.12).abs() < 1e-9);
+        assert!((pvt.heading - 123.4).abs() < 1e-9);
+        assert!((pvt.p_dop - 1.23).abs() < 1e-9);
+
+        // no time yet
+        let mut payload = frames[0].payload.clone();
+        payload[11] = 0;
+        let cold = Frame {
+            payload,
+            ..frames[0].clone()
+        };
+        assert_eq!(cold.nav_pvt().unwrap().utc, None);
+    }
+
+    #[test]
+    fn nav_sat_flags_are_decoded() {
+        // GPS 5 used in the fix, GLONASS 72 still searching, Galileo 11
+        // unhealthy with corrections and an orbit from the almanac
+        let frame = b"\xb5\x62\x01\x35\x2c\x00\x18\x42\x18\x01\x01\x03\x00\x00\x00\x05\x2b\x3e\x7b\x00\xfd\xff\x1f\x19\
+\x00\x00\x06\x48\x12\x09\x2d\x01\x00\x00\x01\x00\x00\x00\x02\x0b\x1b\xfe\x2d\x00\x9a\x00\x64\x0a\
+\x00\x00\xe9\x49";
+        let frames = FrameReader::default().push(frame);
+        let sat = frames[0].nav_sat().unwrap();
+        assert_eq!(sat.itow, 18367000);
+        let described: Vec<_> = sat
+            .svs
+            .iter()
+            .map(|sv| {
+                (
+                    sv.gnss_name(),
+                    sv.sv_id,
+                    sv.quality_name(),
+                    sv.health_name(),
+                    sv.used(),
+                    sv.diff_corr(),
+                    sv.orbit_source_name(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            described,
+            vec![
+                (
+                    "gps",
+                    5,
+                    "carrier locked",
+                    "healthy",
+                    true,
+                    false,
+                    "ephemeris"
+                ),
+                ("glonass", 72, "searching", "unknown", false, false, "none"),
+                (
+                    "galileo",
+                    11,
+                    "code locked",
+                    "unhealthy",
+                    false,
+                    true,
+                    "almanac"
+                ),
+            ]
+        );
+        let gps = sat.svs[0];
+        assert_eq!((gps.cno, gps.elev, gps.azim), (43, 62, 123));
+        assert!((gps.pr_res + 0.3).abs() < 1e-9);
+        assert_eq!(sat.svs[2].elev, -2);
+        assert!((sat.svs[2].pr_res - 15.4).abs() < 1e-9);
+
+        // a satellite short
+        let mut short = frames[0].clone();
+        short.payload.truncate(32);
+        assert_eq!(short.nav_sat(), None);
+    }
+
+    #[test]
+    fn captures_are_split_into_frames_text_and_junk() {
+        // a sentence, a CFG-PRT poll and a CFG-MSG turning on NAV-PVT, noise,
+        // an ACK with a bad checksum, an unknown TIM-TP and a cut off NAV-PVT
+        let capture = b"$GPRMC,225446,A,4916.45,N,12311.12,W,000.5,054.7,191194,020.3,E*68\r\n\
+\xb5\x62\x06\x00\x01\x00\x01\x08\x22\
+\xb5\x62\x06\x01\x08\x00\x01\x07\x00\x01\x00\x01\x00\x00\x19\xe4\
+\x00\xff\x13\
+\xb5\x62\x05\x01\x02\x00\x06\x01\x0f\x39\
+\xb5\x62\x0d\x01\x04\x00\x01\x02\x03\x04\x1c\x9b\
+\xb5\x62\x01\x07\x5c\x00\x18\x42";
+        let pieces = split_capture(capture);
+        let offsets: Vec<usize> = pieces.iter().map(|&(offset, _)| offset).collect();
+        assert_eq!(offsets, vec![0, 68, 77, 93, 96, 98, 106, 118]);
+
+        let summaries: Vec<String> = pieces
+            .iter()
+            .map(|(_, piece)| match piece {
+                Piece::Frame(frame) => format!(
+                    "{} {}",
+                    frame_name(frame.class, frame.id),
+                    frame.summary().unwrap_or_default()
+                ),
+                Piece::BadChecksum(class, id, len) => {
+                    format!("bad {} {}", frame_name(*class, *id), len)
+                }
+                Piece::Text(text) => text[..6].to_owned(),
+                Piece::Junk(len) => format!("junk {}", len),
+            })
+            .collect();
+        assert_eq!(
+            summaries,
+            vec![
+                "$GPRMC",
+                "CFG-PRT poll uart1",
+                "CFG-MSG NAV-PVT: uart1 1, usb 1",
+                "junk 3",
+                "bad ACK-ACK 2",
+                "junk 8",
+                "0D-01 ",
+                "junk 8",
+            ]
+        );
+        assert_eq!(frame_id("nav-pvt"), Some((CLASS_NAV, ID_NAV_PVT)));
+        assert_eq!(frame_id("ACK-NAK"), Some((CLASS_ACK, 0x00)));
+        assert_eq!(frame_id("0d-01"), Some((0x0d, 0x01)));
+    }
+
+    #[test]
+    fn coordinates_are_scaled_exactly() {
+        assert_eq!(parse_fixed("37.497950234", 9), Some(37_497_950_234));
+        // one past the 1e-9 degrees, which f64 gets wrong for some values
+        assert_eq!(parse_fixed("-122.1234567891", 9), Some(-122_123_456_789));
+        assert_eq!(parse_fixed("45.30125", 4), Some(453_013));
+        assert_eq!(parse_fixed("-0.00005", 4), Some(-1));
+        assert_eq!(parse_fixed("2", 4), Some(20_000));
+        assert_eq!(parse_fixed(".5", 1), Some(5));
+        for bad in &["", "-", ".", "1e3", "12.3.4", "--1"] {
+            assert_eq!(parse_fixed(bad, 4), None, "{:?}", bad);
+        }
+        assert_eq!(format_fixed(-26_943_687_112, 4), "-2694368.7112");
+        assert_eq!(format_fixed(5, 4), "0.0005");
+
+        assert_eq!(split_hp(37_497_950_234), (374_979_502, 34));
+        assert_eq!(split_hp(-122_123_456_789), (-1_221_234_567, -89));
+
+        let fixed = TimeMode::FixedLla {
+            lat: 37_497_950_234,
+            lon: -122_123_456_789,
+            alt: 453_013,
+            acc: 20_000,
+        };
+        let packet = fixed.packet();
+        let p = &packet[6..46];
+        assert_eq!(u16::from_le_bytes([p[2], p[3]]), 0x102);
+        assert_eq!(i32::from_le_bytes([p[4], p[5], p[6], p[7]]), 374_979_502);
+        assert_eq!(
+            i32::from_le_bytes([p[8], p[9], p[10], p[11]]),
+            -1_221_234_567
+        );
+        assert_eq!(i32::from_le_bytes([p[12], p[13], p[14], p[15]]), 4530);
+        assert_eq!([p[16] as i8, p[17] as i8, p[18] as i8], [34, -89, 13]);
+        assert_eq!(u32::from_le_bytes([p[20], p[21], p[22], p[23]]), 20_000);
+    }
+
+    #[test]
+    fn survey_in_is_set_up_and_followed() {
+        let survey = TimeMode::SurveyIn {
+            min_duration: 300,
+            acc_limit: 20_000,
+        };
+        assert_eq!(
+            survey.packet(),
+            b"\xb5\x62\x06\x71\x28\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x2c\x01\x00\x00\x20\x4e\x00\x00\x00\x00\x00\x00\
+\x00\x00\x00\x00\x3b\x62"
+                .to_vec()
+        );
+        assert_eq!(
+            TimeMode::Disabled.packet()[46..],
+            [0x9f, 0x93],
+            "checksum of an all-zero CFG-TMODE3"
+        );
+
+        // two minutes in, 3.1 m off the limit
+        let frame = b"\xb5\x62\x01\x3b\x28\x00\x00\x00\x00\x00\x18\x42\x18\x01\x78\x00\x00\x00\x39\xb8\xf0\xef\xc0\x91\
+\xa9\x19\x1a\x36\x73\x17\xf4\x22\x05\x00\x02\x7a\x00\x00\x78\x00\x00\x00\x00\x01\x00\x00\x1c\x95";
+        let svin = FrameReader::default().push(frame)[0].nav_svin().unwrap();
+        assert_eq!(
+            (svin.dur, svin.obs, svin.valid, svin.active),
+            (120, 120, false, true)
+        );
+        let mean: Vec<String> = svin.mean.iter().map(|&v| format_fixed(v, 4)).collect();
+        assert_eq!(mean, vec!["-2694368.7112", "4305432.9634", "3934264.5805"]);
+        assert_eq!(format_fixed(i64::from(svin.mean_acc), 4), "3.1234");
+    }
+
+    #[test]
+    fn assistnow_messages_are_picked_and_acked() {
+        let ano = |day: u8, sv: u8| Frame {
+            class: CLASS_MGA,
+            id: ID_MGA_ANO,
+            payload: vec![0, 0, sv, 0, 21, 3, day, 0],
+        };
+        let eph = Frame {
+            class: CLASS_MGA,
+            id: 0x00,
+            payload: vec![1, 0, 5, 0],
+        };
+        let nav = Frame {
+            class: CLASS_NAV,
+            id: ID_NAV_PVT,
+            payload: vec![],
+        };
+        let frames = vec![ano(3, 1), ano(4, 1), ano(4, 2), ano(5, 1), eph.clone(), nav];
+        let today = NaiveDate::from_ymd_opt(2021, 3, 4).unwrap();
+        let (kept, left_out) = mga_to_send(frames.clone(), today);
+        assert_eq!(kept, vec![ano(4, 1), ano(4, 2), eph.clone()]);
+        assert_eq!(left_out, 3);
+        // a file from before today is sent whole
+        let later = NaiveDate::from_ymd_opt(2021, 4, 1).unwrap();
+        assert_eq!(mga_to_send(frames, later).0.len(), 5);
+
+        let time = NaiveDate::from_ymd_opt(2021, 3, 4)
+            .unwrap()
+            .and_hms_nano_opt(5, 6, 7, 250_000_000)
+            .unwrap();
+        let ini = mga_ini_time_utc(time, 2);
+        assert_eq!(
+            ini.packet(),
+            b"\xb5\x62\x13\x40\x18\x00\x10\x00\x00\x80\xe5\x07\x03\x04\x05\x06\x07\x00\
+\x80\xb2\xe6\x0e\x02\x00\x00\x00\x00\x00\x00\x00\x28\x63"
+                .to_vec()
+        );
+
+        let ack = Frame {
+            class: CLASS_MGA,
+            id: ID_MGA_ACK,
+            payload: vec![0, 0, 1, ID_MGA_INI, 0x10, 0x00, 0x00, 0x80],
+        }
+        .mga_ack()
+        .unwrap();
+        assert!(ack.is_for(&ini) && !ack.is_for(&eph));
+        assert_eq!((ack.accepted, ack.info_name()), (false, "no time known"));
+    }
+
+    #[test]
+    fn rawx_epochs_count_satellites_once() {
+        // GPS 5 on L1 C/A and L2C, GLONASS 2 on L1OF
+        let frame = b"\xb5\x62\x02\x15\x70\x00\x00\x00\x00\x00\x02\x18\x15\x41\x66\x08\x12\x03\x01\x01\x00\x00\x00\x00\
+\x00\x00\xf4\x06\x74\x41\x00\x00\x00\x00\xde\x39\x9a\x41\x00\x10\x96\xc4\x00\x05\x00\x00\x88\x13\
+\x2a\x05\x03\x02\x0f\x00\x00\x00\x00\x00\xf4\x06\x74\x41\x00\x00\x00\x00\xde\x39\x9a\x41\x00\x10\
+\x96\xc4\x00\x05\x03\x00\x88\x13\x2a\x05\x03\x02\x0f\x00\x00\x00\x00\x00\xf4\x06\x74\x41\x00\x00\
+\x00\x00\xde\x39\x9a\x41\x00\x10\x96\xc4\x06\x02\x00\x00\x88\x13\x2a\x05\x03\x02\x0f\x00\x4c\xad";
+        let frames = FrameReader::default().push(frame);
+        assert_eq!(
+            frames[0].rawx(),
+            Some(RawxEpoch {
+                rcv_tow: 345600.5,
+                week: 2150,
+                signals: 3,
+                satellites: 2,
+            })
+        );
+        let mut short = frames[0].clone();
+        short.payload.truncate(16 + 64);
+        assert_eq!(short.rawx(), None);
+    }
+
+    #[test]
+    fn time_pulse_units_follow_the_flags() {
+        let mut tp = TimePulse::factory(0);
+        assert_eq!(
+            tp.describe()[..2],
+            [
+                "TIMEPULSE locked to GNSS time: a pulse every 1000000 µs, 100000 µs long",
+                "until locked: a pulse every 1000000 µs, 100000 µs long",
+            ]
+        );
+
+        tp.set(PulseRate::Hz(10_000_000), PulseLen::Duty(50.0), true, true)
+            .unwrap();
+        assert_eq!(
+            (tp.freq_period, tp.freq_period_lock),
+            (10_000_000, 10_000_000)
+        );
+        assert_eq!(
+            (tp.pulse_len_ratio, tp.pulse_len_ratio_lock),
+            (0, 0x8000_0000)
+        );
+        assert_eq!(tp.flags, 0x6f);
+        assert_eq!(
+            tp.describe(),
+            vec![
+                "TIMEPULSE locked to GNSS time: 10000000 Hz, 50.0% duty cycle",
+                "until locked: no pulses",
+                "pulses aligned to the top of the UTC second",
+                "the rising edge marks the time",
+                "antenna cable delay 50 ns, RF group delay 0 ns, user delay 0 ns",
+            ]
+        );
+
+        // read back as it was sent
+        let frames = FrameReader::default().push(&tp.packet());
+        assert_eq!(frames[0].time_pulse(), Some(tp));
+
+        assert!(tp
+            .set(PulseRate::Hz(0), PulseLen::Duty(50.0), false, false)
+            .is_err());
+        assert!(tp
+            .set(PulseRate::Hz(1000), PulseLen::Duty(100.0), false, false)
+            .is_err());
+        // 1 kHz leaves 1000 µs for a pulse
+        assert!(tp
+            .set(PulseRate::Hz(1000), PulseLen::Us(1000), false, false)
+            .is_err());
+        tp.set(PulseRate::PeriodUs(1000), PulseLen::Us(250), false, false)
+            .unwrap();
+        assert_eq!(tp.flags & 0x3f, 0x17);
+        assert_eq!(
+            tp.describe()[0],
+            "TIMEPULSE locked to GNSS time: a pulse every 1000 µs, 250 µs long"
+        );
+    }
+
+    #[test]
+    fn antenna_and_jamming_state() {
+        let mut payload = vec![0u8; 60];
+        payload[16..20].copy_from_slice(&[0x57, 0x00, 0xff, 0x0f]);
+        payload[20] = 4; // open
+        payload[21] = 1;
+        payload[22] = 0x01 | (1 << 2);
+        payload[45] = 12;
+        let frames = FrameReader::default().push(&packet(CLASS_MON, ID_MON_HW, &payload));
+        let hw = frames[0].mon_hw().unwrap();
+        assert_eq!(
+            hw.describe(),
+            "antenna open, power on, noise 87/ms, AGC 50%, jamming ok, CW 12/255"
+        );
+        assert_eq!(hw.warnings(), vec!["antenna open"]);
+
+        // an F9P with its L1 and L2 blocks, the second one jammed
+        let mut payload = vec![0x00, 0x02, 0x00, 0x00];
+        for (block, flags, jam_ind) in [(0u8, 1u8, 3u8), (1, 3, 200)] {
+            let mut b = vec![0u8; 24];
+            b[0] = block;
+            b[1] = flags;
+            b[2] = 2;
+            b[3] = 1;
+            b[12..16].copy_from_slice(&[0x52, 0x00, 0x00, 0x10]);
+            b[16] = jam_ind;
+            payload.extend(b);
+        }
+        let frames = FrameReader::default().push(&packet(CLASS_MON, ID_MON_RF, &payload));
+        let blocks = frames[0].mon_rf().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert!(blocks[0].warnings().is_empty());
+        assert_eq!(blocks[1].block, Some(1));
+        assert_eq!(blocks[1].warnings(), vec!["jamming critical"]);
+        assert_eq!(
+            frames[0].summary().unwrap(),
+            "block 0: antenna ok, power on, noise 82/ms, AGC 50%, jamming ok, CW 3/255; \
+             block 1: antenna ok, power on, noise 82/ms, AGC 50%, jamming critical, CW 200/255"
+        );
+        // a block short
+        assert!(
+            FrameReader::default().push(&packet(CLASS_MON, ID_MON_RF, &payload[..50]))[0]
+                .mon_rf()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn unique_id_is_hex() {
+        let frames = FrameReader::default()
+            .push(b"\xb5\x62\x27\x03\x09\x00\x01\x00\x00\x00\xe0\x95\x65\x0f\x2a\x47\xb6");
+        assert_eq!(frames[0].unique_id().as_deref(), Some("e095650f2a"));
+        assert_eq!(frames[0].summary().unwrap(), "unique ID e095650f2a");
+        let short = FrameReader::default().push(&packet(CLASS_SEC, ID_SEC_UNIQID, &[1, 0, 0, 0]));
+        assert!(short[0].unique_id().is_none());
+    }
+
+    #[test]
+    fn high_precision_parts_are_joined_exactly() {
+        assert_eq!(join_hp(374_979_502, 34, 100), 37_497_950_234);
+        assert_eq!(join_hp(-1_224_194_155, -7, 100), -122_419_415_507);
+        assert_eq!(join_hp(0, -5, 100), -5);
+        assert_eq!(join_hp(62_310, 5, 10), 623_105);
+
+        let mut p = vec![0u8; 36];
+        p[4..8].copy_from_slice(&453_600_000u32.to_le_bytes());
+        p[8..12].copy_from_slice(&(-1_224_194_155i32).to_le_bytes());
+        p[12..16].copy_from_slice(&374_979_502i32.to_le_bytes());
+        p[16..20].copy_from_slice(&62_310i32.to_le_bytes());
+        p[20..24].copy_from_slice(&(-1_250i32).to_le_bytes());
+        p[24..28].copy_from_slice(&[-7i8 as u8, 34, 5, -3i8 as u8]);
+        p[28..32].copy_from_slice(&141u32.to_le_bytes());
+        p[32..36].copy_from_slice(&203u32.to_le_bytes());
+        let frames = FrameReader::default().push(&packet(CLASS_NAV, ID_NAV_HPPOSLLH, &p));
+        let hp = frames[0].hp_pos_llh().unwrap();
+        assert_eq!((hp.lat, hp.lon), (37_497_950_234, -122_419_415_507));
+        assert_eq!(hp.h_msl, -12_503);
+        assert_eq!(
+            hp.describe(),
+            "37.497950234 -122.419415507  h 62.3105 m  msl -1.2503 m  acc 0.0141/0.0203 m"
+        );
+
+        let mut p = vec![0u8; 64];
+        p[0] = 1;
+        for (i, cm) in [123i32, -57, 1, 136, 1_234_567].iter().enumerate() {
+            p[8 + 4 * i..12 + 4 * i].copy_from_slice(&cm.to_le_bytes());
+        }
+        p[32..36].copy_from_slice(&[45, -8i8 as u8, 23, 0]);
+        for i in 0..3 {
+            p[36 + 4 * i..40 + 4 * i].copy_from_slice(&100u32.to_le_bytes());
+        }
+        p[60..64].copy_from_slice(&0x117u32.to_le_bytes());
+        let frames = FrameReader::default().push(&packet(CLASS_NAV, ID_NAV_RELPOSNED, &p));
+        let rel = frames[0].rel_pos_ned().unwrap();
+        assert_eq!(rel.ned, [12_345, -5_708, 123]);
+        assert!(rel.gnss_fix_ok() && rel.diff_soln() && rel.rel_pos_valid());
+        assert_eq!(
+            rel.describe(),
+            "fixed  N 1.2345 E -0.5708 D 0.0123 m  length 1.3600 m  heading 12.34567°  acc 0.0100/0.0100/0.0100 m"
+        );
+        // without relPosHeadingValid
+        p[60..64].copy_from_slice(&0x0fu32.to_le_bytes());
+        let frames = FrameReader::default().push(&packet(CLASS_NAV, ID_NAV_RELPOSNED, &p));
+        let rel = frames[0].rel_pos_ned().unwrap();
+        assert_eq!((rel.heading, rel.carr_soln_name()), (None, "float"));
+    }
+}
@@ -0,0 +1,475 @@
+//! Key/value configuration of generation 9 u-blox receivers, like the ZED-F9P
+//! and NEO-M9N, with CFG-VALSET and CFG-VALGET. The old CFG messages still
+//! work on most of them but are deprecated.
+
+use crate::ubx::{packet, Frame, CLASS_CFG, ID_CFG_VALGET, ID_CFG_VALSET};
+
+/// Most keys a VALSET or VALGET takes
+pub const MAX_KEYS: usize = 64;
+
+/// Layers of a VALSET, as bits
+pub const LAYERS: &[(&str, u8)] = &[("ram", 0x01), ("bbr", 0x02), ("flash", 0x04)];
+
+/// Layers a VALGET reads, one at a time
+pub const GET_LAYERS: &[(&str, u8)] = &[("ram", 0), ("bbr", 1), ("flash", 2), ("default", 7)];
+
+/// Type of a value: L is a bool, E an enumeration and X a bitfield
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    L,
+    U1,
+    U2,
+    U4,
+    U8,
+    I1,
+    I2,
+    I4,
+    I8,
+    R4,
+    R8,
+    E1,
+    E2,
+    E4,
+    X1,
+    X2,
+    X4,
+    X8,
+}
+
+use ValueType::*;
+
+const TYPES: &[(&str, ValueType)] = &[
+    ("L", L),
+    ("U1", U1),
+    ("U2", U2),
+    ("U4", U4),
+    ("U8", U8),
+    ("I1", I1),
+    ("I2", I2),
+    ("I4", I4),
+    ("I8", I8),
+    ("R4", R4),
+    ("R8", R8),
+    ("E1", E1),
+    ("E2", E2),
+    ("E4", E4),
+    ("X1", X1),
+    ("X2", X2),
+    ("X4", X4),
+    ("X8", X8),
+];
+
+/// Known keys, from the interface descriptions of the F9P and M9 receivers
+pub const KEYS: &[(&str, u32, ValueType)] = &[
+    ("CFG-RATE-MEAS", 0x3021_0001, U2),
+    ("CFG-RATE-NAV", 0x3021_0002, U2),
+    ("CFG-RATE-TIMEREF", 0x2021_0003, E1),
+    ("CFG-UART1-BAUDRATE", 0x4052_0001, U4),
+    ("CFG-UART1-STOPBITS", 0x2052_0002, E1),
+    ("CFG-UART1-DATABITS", 0x2052_0003, E1),
+    ("CFG-UART1-PARITY", 0x2052_0004, E1),
+    ("CFG-UART1-ENABLED", 0x1052_0005, L),
+    ("CFG-UART2-BAUDRATE", 0x4053_0001, U4),
+    ("CFG-UART2-ENABLED", 0x1053_0005, L),
+    ("CFG-UART1INPROT-UBX", 0x1073_0001, L),
+    ("CFG-UART1INPROT-NMEA", 0x1073_0002, L),
+    ("CFG-UART1INPROT-RTCM3X", 0x1073_0004, L),
+    ("CFG-UART1OUTPROT-UBX", 0x1074_0001, L),
+    ("CFG-UART1OUTPROT-NMEA", 0x1074_0002, L),
+    ("CFG-UART1OUTPROT-RTCM3X", 0x1074_0004, L),
+    ("CFG-UART2INPROT-UBX", 0x1075_0001, L),
+    ("CFG-UART2INPROT-NMEA", 0x1075_0002, L),
+    ("CFG-UART2INPROT-RTCM3X", 0x1075_0004, L),
+    ("CFG-UART2OUTPROT-UBX", 0x1076_0001, L),
+    ("CFG-UART2OUTPROT-NMEA", 0x1076_0002, L),
+    ("CFG-UART2OUTPROT-RTCM3X", 0x1076_0004, L),
+    ("CFG-USBINPROT-UBX", 0x1077_0001, L),
+    ("CFG-USBINPROT-NMEA", 0x1077_0002, L),
+    ("CFG-USBINPROT-RTCM3X", 0x1077_0004, L),
+    ("CFG-USBOUTPROT-UBX", 0x1078_0001, L),
+    ("CFG-USBOUTPROT-NMEA", 0x1078_0002, L),
+    ("CFG-USBOUTPROT-RTCM3X", 0x1078_0004, L),
+    ("CFG-NAVSPG-FIXMODE", 0x2011_0011, E1),
+    ("CFG-NAVSPG-DYNMODEL", 0x2011_0021, E1),
+    ("CFG-NAVSPG-INFIL_MINCNO", 0x2011_00a3, U1),
+    ("CFG-NAVSPG-INFIL_MINELEV", 0x2011_00a4, I1),
+    ("CFG-SIGNAL-GPS_ENA", 0x1031_001f, L),
+    ("CFG-SIGNAL-GPS_L1CA_ENA", 0x1031_0001, L),
+    ("CFG-SIGNAL-GPS_L2C_ENA", 0x1031_0003, L),
+    ("CFG-SIGNAL-SBAS_ENA", 0x1031_0020, L),
+    ("CFG-SIGNAL-GAL_ENA", 0x1031_0021, L),
+    ("CFG-SIGNAL-GAL_E1_ENA", 0x1031_0007, L),
+    ("CFG-SIGNAL-GAL_E5B_ENA", 0x1031_000a, L),
+    ("CFG-SIGNAL-BDS_ENA", 0x1031_0022, L),
+    ("CFG-SIGNAL-BDS_B1_ENA", 0x1031_000d, L),
+    ("CFG-SIGNAL-BDS_B2_ENA", 0x1031_000e, L),
+    ("CFG-SIGNAL-QZSS_ENA", 0x1031_0024, L),
+    ("CFG-SIGNAL-GLO_ENA", 0x1031_0025, L),
+    ("CFG-SIGNAL-GLO_L1_ENA", 0x1031_0018, L),
+    ("CFG-SIGNAL-GLO_L2_ENA", 0x1031_001a, L),
+    ("CFG-TMODE-MODE", 0x2003_0001, E1),
+    ("CFG-TMODE-POS_TYPE", 0x2003_0002, E1),
+    ("CFG-TMODE-ECEF_X", 0x4003_0003, I4),
+    ("CFG-TMODE-ECEF_Y", 0x4003_0004, I4),
+    ("CFG-TMODE-ECEF_Z", 0x4003_0005, I4),
+    ("CFG-TMODE-LAT", 0x4003_0009, I4),
+    ("CFG-TMODE-LON", 0x4003_000a, I4),
+    ("CFG-TMODE-HEIGHT", 0x4003_000b, I4),
+    ("CFG-TMODE-FIXED_POS_ACC", 0x4003_000f, U4),
+    ("CFG-TMODE-SVIN_MIN_DUR", 0x4003_0010, U4),
+    ("CFG-TMODE-SVIN_ACC_LIMIT", 0x4003_0011, U4),
+    ("CFG-MSGOUT-UBX_NAV_PVT_I2C", 0x2091_0006, U1),
+    ("CFG-MSGOUT-UBX_NAV_PVT_UART1", 0x2091_0007, U1),
+    ("CFG-MSGOUT-UBX_NAV_PVT_UART2", 0x2091_0008, U1),
+    ("CFG-MSGOUT-UBX_NAV_PVT_USB", 0x2091_0009, U1),
+    ("CFG-MSGOUT-UBX_NAV_SAT_I2C", 0x2091_0015, U1),
+    ("CFG-MSGOUT-UBX_NAV_SAT_UART1", 0x2091_0016, U1),
+    ("CFG-MSGOUT-UBX_NAV_SAT_UART2", 0x2091_0017, U1),
+    ("CFG-MSGOUT-UBX_NAV_SAT_USB", 0x2091_0018, U1),
+    ("CFG-MSGOUT-UBX_RXM_RAWX_I2C", 0x2091_02a4, U1),
+    ("CFG-MSGOUT-UBX_RXM_RAWX_UART1", 0x2091_02a5, U1),
+    ("CFG-MSGOUT-UBX_RXM_RAWX_UART2", 0x2091_02a6, U1),
+    ("CFG-MSGOUT-UBX_RXM_RAWX_USB", 0x2091_02a7, U1),
+    ("CFG-MSGOUT-NMEA_ID_RMC_I2C", 0x2091_00ab, U1),
+    ("CFG-MSGOUT-NMEA_ID_RMC_UART1", 0x2091_00ac, U1),
+    ("CFG-MSGOUT-NMEA_ID_RMC_UART2", 0x2091_00ad, U1),
+    ("CFG-MSGOUT-NMEA_ID_RMC_USB", 0x2091_00ae, U1),
+    ("CFG-MSGOUT-NMEA_ID_VTG_I2C", 0x2091_00b0, U1),
+    ("CFG-MSGOUT-NMEA_ID_VTG_UART1", 0x2091_00b1, U1),
+    ("CFG-MSGOUT-NMEA_ID_VTG_UART2", 0x2091_00b2, U1),
+    ("CFG-MSGOUT-NMEA_ID_VTG_USB", 0x2091_00b3, U1),
+    ("CFG-MSGOUT-NMEA_ID_GGA_I2C", 0x2091_00ba, U1),
+    ("CFG-MSGOUT-NMEA_ID_GGA_UART1", 0x2091_00bb, U1),
+    ("CFG-MSGOUT-NMEA_ID_GGA_UART2", 0x2091_00bc, U1),
+    ("CFG-MSGOUT-NMEA_ID_GGA_USB", 0x2091_00bd, U1),
+    ("CFG-MSGOUT-NMEA_ID_GSA_I2C", 0x2091_00bf, U1),
+    ("CFG-MSGOUT-NMEA_ID_GSA_UART1", 0x2091_00c0, U1),
+    ("CFG-MSGOUT-NMEA_ID_GSA_UART2", 0x2091_00c1, U1),
+    ("CFG-MSGOUT-NMEA_ID_GSA_USB", 0x2091_00c2, U1),
+    ("CFG-MSGOUT-NMEA_ID_GSV_I2C", 0x2091_00c4, U1),
+    ("CFG-MSGOUT-NMEA_ID_GSV_UART1", 0x2091_00c5, U1),
+    ("CFG-MSGOUT-NMEA_ID_GSV_UART2", 0x2091_00c6, U1),
+    ("CFG-MSGOUT-NMEA_ID_GSV_USB", 0x2091_00c7, U1),
+    ("CFG-MSGOUT-NMEA_ID_GLL_I2C", 0x2091_00c9, U1),
+    ("CFG-MSGOUT-NMEA_ID_GLL_UART1", 0x2091_00ca, U1),
+    ("CFG-MSGOUT-NMEA_ID_GLL_UART2", 0x2091_00cb, U1),
+    ("CFG-MSGOUT-NMEA_ID_GLL_USB", 0x2091_00cc, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1005_I2C", 0x2091_02bd, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1005_UART1", 0x2091_02be, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1005_UART2", 0x2091_02bf, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1005_USB", 0x2091_02c0, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1074_I2C", 0x2091_035e, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1074_UART1", 0x2091_035f, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1074_UART2", 0x2091_0360, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1074_USB", 0x2091_0361, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1084_I2C", 0x2091_0363, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1084_UART1", 0x2091_0364, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1084_UART2", 0x2091_0365, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1084_USB", 0x2091_0366, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1094_I2C", 0x2091_0368, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1094_UART1", 0x2091_0369, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1094_UART2", 0x2091_036a, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1094_USB", 0x2091_036b, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1124_I2C", 0x2091_036d, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1124_UART1", 0x2091_036e, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1124_UART2", 0x2091_036f, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1124_USB", 0x2091_0370, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1230_I2C", 0x2091_0303, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1230_UART1", 0x2091_0304, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1230_UART2", 0x2091_0305, U1),
+    ("CFG-MSGOUT-RTCM_3X_TYPE1230_USB", 0x2091_0306, U1),
+];
+
+impl ValueType {
+    pub fn parse(s: &str) -> Option<ValueType> {
+        TYPES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s.trim()))
+            .map(|&(_, ty)| ty)
+    }
+
+    pub fn name(self) -> &'static str {
+        TYPES
+            .iter()
+            .find(|&&(_, ty)| ty == self)
+            .map(|&(name, _)| name)
+            .expect("every type is in TYPES")
+    }
+
+    /// Bytes of a value; bools take a whole byte
+    pub fn width(self) -> usize {
+        match self {
+            L | U1 | I1 | E1 | X1 => 1,
+            U2 | I2 | E2 | X2 => 2,
+            U4 | I4 | R4 | E4 | X4 => 4,
+            U8 | I8 | R8 | X8 => 8,
+        }
+    }
+
+    /// The size bits of the keys of this type
+    fn size(self) -> u32 {
+        match (self, self.width()) {
+            (L, _) => 1,
+            (_, 1) => 2,
+            (_, 2) => 3,
+            (_, 4) => 4,
+            _ => 5,
+        }
+    }
+
+    /// Little-endian bytes of a value: a number, `0x` hex for the unsigned
+    /// types, or true/false for bools
+    pub fn encode(self, text: &str) -> Result<Vec<u8>, String> {
+        let text = text.trim();
+        let invalid = || format!("invalid {} value {}", self.name(), text);
+        let bytes = match self {
+            L => match text.to_ascii_lowercase().as_str() {
+                "1" | "true" | "on" => vec![1],
+                "0" | "false" | "off" => vec![0],
+                _ => return Err(invalid()),
+            },
+            R4 => text
+                .parse::<f32>()
+                .map_err(|_| invalid())?
+                .to_le_bytes()
+                .to_vec(),
+            R8 => text
+                .parse::<f64>()
+                .map_err(|_| invalid())?
+                .to_le_bytes()
+                .to_vec(),
+            I1 | I2 | I4 | I8 => {
+                let value: i64 = text.parse().map_err(|_| invalid())?;
+                let bits = 8 * self.width() as u32;
+                if bits < 64 && (value < -(1 << (bits - 1)) || value >= (1 << (bits - 1))) {
+                    return Err(format!("{} is out of range for {}", value, self.name()));
+                }
+                value.to_le_bytes()[..self.width()].to_vec()
+            }
+            _ => {
+                let value = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+                    Some(hex) => u64::from_str_radix(hex, 16),
+                    None => text.parse(),
+                }
+                .map_err(|_| invalid())?;
+                let bits = 8 * self.width() as u32;
+                if bits < 64 && value >> bits != 0 {
+                    return Err(format!("{} is out of range for {}", value, self.name()));
+                }
+                value.to_le_bytes()[..self.width()].to_vec()
+            }
+        };
+        Ok(bytes)
+    }
+
+    /// A value as `encode` takes it
+    pub fn decode(self, bytes: &[u8]) -> String {
+        let mut raw = [0u8; 8];
+        raw[..bytes.len().min(8)].copy_from_slice(&bytes[..bytes.len().min(8)]);
+        let unsigned = u64::from_le_bytes(raw);
+        match self {
+            L => (unsigned != 0).to_string(),
+            R4 => f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]).to_string(),
+            R8 => f64::from_le_bytes(raw).to_string(),
+            I1 | I2 | I4 | I8 => {
+                let shift = 64 - 8 * self.width() as u32;
+                (((unsigned << shift) as i64) >> shift).to_string()
+            }
+            X1 | X2 | X4 | X8 => format!("0x{:0width$x}", unsigned, width = 2 * self.width()),
+            _ => unsigned.to_string(),
+        }
+    }
+}
+
+/// A configuration item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub id: u32,
+    pub ty: ValueType,
+}
+
+impl Key {
+    /// The key of a known id, or one typed as a bitfield of the width its
+    /// size bits give
+    pub fn from_id(id: u32) -> Key {
+        let known = KEYS.iter().find(|&&(_, known, _)| known == id);
+        let ty = match (known, (id >> 28) & 0x07) {
+            (Some(&(_, _, ty)), _) => ty,
+            (None, 1) => L,
+            (None, 2) => X1,
+            (None, 3) => X2,
+            (None, 4) => X4,
+            (None, _) => X8,
+        };
+        Key { id, ty }
+    }
+
+    /// A key by name, like `CFG-RATE-MEAS`, or by id and type, like
+    /// `0x30210001:U2`
+    pub fn parse(s: &str) -> Result<Key, String> {
+        let s = s.trim();
+        if let Some(&(_, id, ty)) = KEYS
+            .iter()
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(s))
+        {
+            return Ok(Key { id, ty });
+        }
+        let (id, ty) = match s.split_once(':') {
+            Some((id, ty)) if id.starts_with("0x") || id.starts_with("0X") => (id, ty),
+            _ => {
+                return Err(format!(
+                    "unknown key {}; give unknown keys by id and type, like 0x30210001:U2",
+                    s
+                ))
+            }
+        };
+        let id = u32::from_str_radix(&id[2..], 16).map_err(|_| format!("invalid key id {}", id))?;
+        let ty = ValueType::parse(ty).ok_or_else(|| {
+            let names: Vec<&str> = TYPES.iter().map(|&(name, _)| name).collect();
+            format!("unknown type {}; types are {}", ty, names.join(", "))
+        })?;
+        if (id >> 28) & 0x07 != ty.size() {
+            return Err(format!("key 0x{:08x} doesn't hold a {}", id, ty.name()));
+        }
+        Ok(Key { id, ty })
+    }
+
+    /// The name of a known key, or its id in hex
+    pub fn name(&self) -> String {
+        KEYS.iter()
+            .find(|&&(_, id, _)| id == self.id)
+            .map(|&(name, _, _)| name.to_owned())
+            .unwrap_or_else(|| format!("0x{:08x}", self.id))
+    }
+}
+
+/// A key and its encoded value
+pub type Item = (Key, Vec<u8>);
+
+/// CFG-VALSET of up to `MAX_KEYS` encoded values, to the layers given as
+/// bits of `LAYERS`
+pub fn valset(layers: u8, items: &[Item]) -> Vec<u8> {
+    let mut payload = vec![0x00, layers, 0, 0];
+    for (key, value) in items {
+        payload.extend_from_slice(&key.id.to_le_bytes());
+        payload.extend_from_slice(value);
+    }
+    packet(CLASS_CFG, ID_CFG_VALSET, &payload)
+}
+
+/// CFG-VALGET poll of up to `MAX_KEYS` keys, from a layer of `GET_LAYERS`
+pub fn valget(layer: u8, keys: &[Key]) -> Vec<u8> {
+    let mut payload = vec![0x00, layer, 0, 0];
+    for key in keys {
+        payload.extend_from_slice(&key.id.to_le_bytes());
+    }
+    packet(CLASS_CFG, ID_CFG_VALGET, &payload)
+}
+
+/// The layer and the items of a CFG-VALSET, or of a CFG-VALGET reply; the
+/// size bits of each key tell how long its value is
+pub fn values(frame: &Frame) -> Option<(u8, Vec<Item>)> {
+    if frame.class != CLASS_CFG || !matches!(frame.id, ID_CFG_VALSET | ID_CFG_VALGET) {
+        return None;
+    }
+    let (head, mut rest) = frame.payload.split_at(frame.payload.len().min(4));
+    let &[_version, layer, _, _] = head else {
+        return None;
+    };
+    let mut items = Vec::new();
+    while !rest.is_empty() {
+        let &[a, b, c, d] = rest.get(..4)? else {
+            return None;
+        };
+        let id = u32::from_le_bytes([a, b, c, d]);
+        let key = Key::from_id(id);
+        let value = rest.get(4..4 + key.ty.width())?;
+        items.push((key, value.to_vec()));
+        rest = &rest[4 + key.ty.width()..];
+    }
+    Some((layer, items))
+}
+
+/// A VALSET or VALGET reply as a line, like `ram: CFG-RATE-MEAS=100`
+pub fn describe(frame: &Frame) -> Option<String> {
+    let (layer, items) = values(frame)?;
+    let layers: Vec<&str> = if frame.id == ID_CFG_VALSET {
+        LAYERS
+            .iter()
+            .filter(|&&(_, bit)| layer & bit != 0)
+            .map(|&(name, _)| name)
+            .collect()
+    } else {
+        GET_LAYERS
+            .iter()
+            .filter(|&&(_, l)| l == layer)
+            .map(|&(name, _)| name)
+            .collect()
+    };
+    let items: Vec<String> = items
+        .iter()
+        .map(|(key, value)| format!("{}={}", key.name(), key.ty.decode(value)))
+        .collect();
+    Some(format!("{}: {}", layers.join(","), items.join(" ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ubx::FrameReader;
+
+    #[test]
+    fn key_sizes_match_types() {
+        for &(name, id, ty) in KEYS {
+            assert_eq!((id >> 28) & 0x07, ty.size(), "{}", name);
+            assert_eq!(Key::parse(name), Ok(Key { id, ty }));
+        }
+    }
+
+    #[test]
+    fn values_are_encoded_and_decoded() {
+        let meas = Key::parse("cfg-rate-meas").unwrap();
+        let baud = Key::parse("CFG-UART1-BAUDRATE").unwrap();
+        let gps = Key::parse("0x1031001f:L").unwrap();
+        assert_eq!(gps.name(), "CFG-SIGNAL-GPS_ENA");
+        assert!(Key::parse("0x30210001:U4").is_err());
+        assert!(Key::parse("CFG-RATE-WHATEVER").is_err());
+
+        let items = vec![
+            (meas, meas.ty.encode("100").unwrap()),
+            (baud, baud.ty.encode("115200").unwrap()),
+        ];
+        assert_eq!(
+            valset(0x05, &items),
+            b"\xb5\x62\x06\x8a\x12\x00\x00\x05\x00\x00\x01\x00\x21\x30\x64\x00\
+\x01\x00\x52\x40\x00\xc2\x01\x00\xb3\xa1"
+        );
+        assert_eq!(
+            valget(0, &[meas]),
+            b"\xb5\x62\x06\x8b\x08\x00\x00\x00\x00\x00\x01\x00\x21\x30\xeb\x07"
+        );
+
+        // a reply with a key not in the table
+        let reply = packet(
+            CLASS_CFG,
+            ID_CFG_VALGET,
+            b"\x01\x00\x00\x00\x01\x00\x21\x30\xe8\x03\x01\x00\x31\x10\x01\x07\x00\x99\x40\x78\x56\x34\x12",
+        );
+        let frames = FrameReader::default().push(&reply);
+        assert_eq!(
+            describe(&frames[0]).unwrap(),
+            "ram: CFG-RATE-MEAS=1000 CFG-SIGNAL-GPS_L1CA_ENA=true 0x40990007=0x12345678"
+        );
+
+        assert_eq!(ValueType::I1.encode("-5").unwrap(), vec![0xfb]);
+        assert_eq!(ValueType::I1.decode(&[0xfb]), "-5");
+        assert!(ValueType::I1.encode("128").is_err());
+        assert!(ValueType::U1.encode("256").is_err());
+        assert_eq!(ValueType::X2.encode("0x0102").unwrap(), vec![0x02, 0x01]);
+        assert_eq!(
+            ValueType::R8.decode(&ValueType::R8.encode("1.5").unwrap()),
+            "1.5"
+        );
+        assert!(ValueType::L.encode("maybe").is_err());
+    }
+}
@@ -0,0 +1,1805 @@
+use std::{cmp::Ordering, collections::VecDeque, time::Duration};
+
+use chrono::{DateTime, Local, SecondsFormat, TimeZone};
+use nmea::{FixType, Nmea};
+use tui::{
+    backend::Backend,
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    symbols,
+    text::{Span, Spans},
+    widgets::{
+        canvas::{Canvas, Points},
+        Borders, Cell, Clear, Paragraph, Row, Sparkline, Table, Widget, Wrap,
+    },
+    Frame,
+};
+
+use crate::{
+    ais::{self, Motion, Target},
+    app::{
+        estimated_accuracy_m, gps_datetime, has_fix, App, Corrections, DateWarning, ErrorStats,
+        MessageKind, NavStatus, Position, SentenceRates, TextLevel, Waypoint,
+    },
+    bigdigits, datum, epoch, export,
+    inspect::Inspection,
+    keys::{key_name, Action, KeyMap},
+    magnetic::{self, Variation},
+    ntrip,
+    pmtk::PRESETS,
+    prefs::{Panel, PanelLayout, Prefs},
+    sky::{constellation, Satellite, Sky},
+    theme::Theme,
+    vessel::{Reading, Wind},
+};
+
+/// Below this size the layout is replaced by a single notice
+pub const MIN_WIDTH: u16 = 40;
+pub const MIN_HEIGHT: u16 = 10;
+/// Below this height only one panel is shown
+pub const COMPACT_HEIGHT: u16 = 20;
+
+/// Position older than this is rendered dimmed
+const STALE_FIX_SECS: i64 = 5;
+
+/// Corrections older than this are rendered as a problem; casters send
+/// every second
+const STALE_NTRIP_SECS: i64 = 5;
+
+/// AIS target older than this is rendered dimmed; ships at anchor report
+/// every three minutes
+const STALE_AIS_SECS: i64 = 180;
+
+/// How long a toast stays in the Status title
+const TOAST_SECS: i64 = 5;
+
+/// Time between the arrival and the handling of a line beyond which the UI
+/// is behind
+const SLOW_UI_LAG: Duration = Duration::from_secs(1);
+
+/// "1h02m03s", "2m03s" or "3s"
+fn duration_str(duration: chrono::Duration) -> String {
+    let secs = duration.num_seconds();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, s) => format!("{}h{:02}m{:02}s", h, m, s),
+    }
+}
+
+/// UTC from the receiver, then the same instant in the local time zone
+fn datetime_str(nmea: &Nmea) -> Option<String> {
+    let utc = gps_datetime(nmea)?;
+    let local = Local.from_utc_datetime(&utc);
+    Some(format!(
+        "{} UTC, {} local",
+        utc,
+        local.format("%H:%M:%S%.3f (%:z)")
+    ))
+}
+
+/// "+0.812s (system ahead)", or a dash until there is something to compare
+fn clock_offset_str(offset: Option<chrono::Duration>) -> String {
+    let offset = match offset {
+        Some(offset) => offset,
+        None => return "—".to_owned(),
+    };
+    let secs = offset.num_milliseconds() as f64 / 1000.0;
+    let side = if secs > 0.0 {
+        " (system ahead)"
+    } else if secs < 0.0 {
+        " (system behind)"
+    } else {
+        ""
+    };
+    format!("{:+.3}s{}", secs, side)
+}
+
+fn latlonalt_str(nmea: &Nmea, prefs: &Prefs) -> Option<String> {
+    let latlon = prefs.coord_format.latlon(nmea.latitude?, nmea.longitude?);
+    // GLL has no altitude
+    match nmea.altitude {
+        Some(altitude) => Some(format!(
+            "{} / {} MSL",
+            latlon,
+            prefs.units.altitude(altitude)
+        )),
+        None => Some(latlon),
+    }
+}
+
+fn speed_str(
+    nmea: &Nmea,
+    smoothed: Option<f32>,
+    variation: Option<Variation>,
+    prefs: &Prefs,
+) -> Option<String> {
+    let mut speed = prefs.units.speed(nmea.speed_over_ground?);
+    if let Some(smoothed) = smoothed {
+        speed = format!("{} (avg {})", speed, prefs.units.speed(smoothed));
+    }
+    match (nmea.true_course, variation) {
+        (Some(course), Some(variation)) => Some(format!(
+            "{} / {:.1}°T {:.1}°M",
+            speed,
+            course,
+            variation.magnetic(course as f64)
+        )),
+        (Some(course), None) => Some(format!("{} / {:.1}°", speed, course)),
+        (None, _) => Some(speed),
+    }
+}
+
+/// " outliers: 3", or nothing while no fix was dropped
+fn outliers_str(rejected: u64) -> String {
+    if rejected == 0 {
+        String::new()
+    } else {
+        format!(" outliers: {}", rejected)
+    }
+}
+
+fn dop_str(nmea: &Nmea) -> Option<String> {
+    Some(format!(
+        "{:.2} / {:.2} / {:.2}",
+        nmea.hdop?, nmea.vdop?, nmea.pdop?
+    ))
+}
+
+/// Horizontal error above which a fix is no longer good, and beyond which it is poor
+const ERR_GOOD: f32 = 1.0;
+const ERR_POOR: f32 = 5.0;
+
+fn errors_str(errors: &ErrorStats) -> Option<String> {
+    let mut s = format!(
+        "{:.2} / {:.2} / {:.2} m",
+        errors.latitude?, errors.longitude?, errors.altitude?
+    );
+    if let (Some(major), Some(minor), Some(orientation)) =
+        (errors.semi_major, errors.semi_minor, errors.orientation)
+    {
+        s += &format!(
+            " (ellipse {:.2} x {:.2} m @ {:.0}°)",
+            major, minor, orientation
+        );
+    }
+    Some(s)
+}
+
+fn errors_style(errors: &ErrorStats, theme: &Theme) -> Style {
+    match (errors.latitude, errors.longitude) {
+        (Some(lat), Some(lon)) if lat.max(lon) <= ERR_GOOD => theme.good(),
+        (Some(lat), Some(lon)) if lat.max(lon) <= ERR_POOR => theme.warn(),
+        (Some(_), Some(_)) => theme.bad(),
+        _ => Style::default(),
+    }
+}
+
+fn corrections_str(nmea: &Nmea, corrections: Option<&Corrections>) -> String {
+    let corrections = match corrections {
+        Some(corrections) => corrections,
+        None => return "no corrections".to_owned(),
+    };
+    let quality = match nmea.fix_type {
+        Some(FixType::Rtk) => "RTK fixed",
+        Some(FixType::FloatRtk) => "RTK float",
+        Some(FixType::DGps) => "DGPS",
+        _ => "no differential fix",
+    };
+    format!(
+        "{} / age {} / station {}",
+        quality,
+        option_str(corrections.age.map(|age| format!("{:.1}s", age))),
+        option_str(corrections.station.clone()),
+    )
+}
+
+/// Corrections coming in from the NTRIP caster
+fn ntrip_str(status: &ntrip::Status, now: DateTime<Local>) -> (String, bool) {
+    if !status.connected {
+        let state = match &status.error {
+            Some(e) => format!("{} (retrying)", e),
+            None => "connecting".to_owned(),
+        };
+        return (format!("{}: {}", status.caster, state), false);
+    }
+    let age = status.last_data.map(|t| now - t);
+    let fresh = age.is_some_and(|age| age.num_seconds() <= STALE_NTRIP_SECS);
+    let text = format!(
+        "{:.0} B/s / last data {} / {} frames",
+        status.rate(now),
+        option_str(age.map(|age| format!("{} ago", duration_str(age)))),
+        status.frames,
+    );
+    (text, fresh)
+}
+
+fn option_str(s: Option<String>) -> String {
+    match s {
+        Some(s) => s,
+        None => "<not available>".to_owned(),
+    }
+}
+
+/// Bounds of `values` padded by 10%, never narrower than `min_span` so a
+/// single point (or a receiver sitting still) ends up centered
+fn track_bounds(values: impl Iterator<Item = f64> + Clone, min_span: f64) -> [f64; 2] {
+    let min = values.clone().fold(f64::INFINITY, f64::min);
+    let max = values.fold(f64::NEG_INFINITY, f64::max);
+    let center = (min + max) / 2.0;
+    let half = ((max - min) * 1.1).max(min_span) / 2.0;
+    [center - half, center + half]
+}
+
+fn draw_track_map<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    positions: &VecDeque<Position>,
+    theme: &Theme,
+) {
+    let block = theme.block(format!("Track (last {})", positions.len()));
+
+    let last = match positions.back() {
+        Some(last) => (last.longitude, last.latitude),
+        None => {
+            f.render_widget(block, area);
+            return;
+        }
+    };
+
+    let coords: Vec<(f64, f64)> = positions
+        .iter()
+        .map(|p| (p.longitude, p.latitude))
+        .collect();
+    // ~1m at the equator
+    let min_span = 0.00001;
+    let x_bounds = track_bounds(coords.iter().map(|c| c.0), min_span);
+    let y_bounds = track_bounds(coords.iter().map(|c| c.1), min_span);
+
+    let canvas = Canvas::default()
+        .block(block)
+        .marker(symbols::Marker::Braille)
+        .x_bounds(x_bounds)
+        .y_bounds(y_bounds)
+        .paint(|ctx| {
+            ctx.draw(&Points {
+                coords: &coords,
+                color: theme.dim_color(),
+            });
+            ctx.layer();
+            ctx.draw(&Points {
+                coords: &[last],
+                color: theme.warn_color(),
+            });
+        });
+    f.render_widget(canvas, area);
+}
+
+fn draw_rates<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let block = theme.block(format!(
+        "Sentences/s (last {}s)",
+        SentenceRates::WINDOW_SECS
+    ));
+    let body_rect = block.inner(area);
+
+    let rates = app.rates.rates(app.now);
+    let max = rates.iter().map(|(_, rate)| *rate).fold(0.0, f64::max);
+    // marker, name and rate take 15 columns, the bar gets the rest
+    let bar_width = body_rect.width.saturating_sub(15) as f64;
+
+    let mut msgs: Vec<Spans> = Vec::new();
+    // the fix rate, and whether epochs arrive as evenly as they are sent
+    if let Some(stats) = app.epochs.stats() {
+        msgs.push(Spans::from(format!(
+            " epoch {:.1} Hz: {}",
+            stats.rate_hz,
+            stats.last.describe()
+        )));
+        let style = if stats.max_ms - stats.min_ms > stats.avg_ms / 2 {
+            theme.warn()
+        } else {
+            Style::default()
+        };
+        msgs.push(Spans::from(Span::styled(
+            format!(
+                " interval {}/{}/{} ms min/avg/max (last {}s)",
+                stats.min_ms,
+                stats.avg_ms,
+                stats.max_ms,
+                epoch::WINDOW_SECS
+            ),
+            style,
+        )));
+    }
+
+    // times to first fix, newest first, under the one being timed
+    if let Some(ttff) = &app.ttff {
+        if let Some((start_type, since)) = ttff.waiting() {
+            let start = start_type.map_or_else(String::new, |s| format!(", {} start", s));
+            msgs.push(Spans::from(Span::styled(
+                format!(
+                    " TTFF waiting for a fix, {}s{}",
+                    (app.now - since).num_seconds(),
+                    start
+                ),
+                theme.warn(),
+            )));
+        }
+        for measurement in &ttff.history {
+            msgs.push(Spans::from(format!(
+                " TTFF {} {}",
+                measurement.started.format("%H:%M:%S"),
+                measurement.describe()
+            )));
+        }
+    }
+
+    msgs.extend(rates.iter().map(|(name, rate)| {
+        let len = if max > 0.0 {
+            (rate / max * bar_width).round() as usize
+        } else {
+            0
+        };
+        let selected = app.selected_kind.as_ref() == Some(name);
+        let marker = if selected { ">" } else { " " };
+        let bar_style = if app.filter.is_ignored(name) {
+            theme.dim()
+        } else {
+            theme.good()
+        };
+        Spans::from(vec![
+            Span::raw(format!("{}{:<5} {:>6.1} ", marker, name, rate)),
+            Span::styled("█".repeat(len.max(1)), bar_style),
+        ])
+    }));
+
+    // sentences held back from the parser since launch or the last reset
+    for (kind, count) in app.filter.counts() {
+        let state = if app.filter.is_ignored(kind) {
+            "ignored"
+        } else {
+            "filtered by talker"
+        };
+        msgs.push(Spans::from(Span::styled(
+            format!(" {:<5} {:>6} {}", kind, count, state),
+            theme.dim(),
+        )));
+    }
+
+    // rejected lines since launch or the last reset, to tell what the
+    // receiver could stop sending
+    for (kind, count, reason) in app.rejected.breakdown() {
+        msgs.push(Spans::from(Span::styled(
+            format!(" {:<5} {:>6} {}", kind, count, reason),
+            theme.bad(),
+        )));
+    }
+
+    let paragraph = Paragraph::new(msgs);
+
+    f.render_widget(block, area);
+    f.render_widget(paragraph, body_rect);
+}
+
+/// Width of a column of the line gap histogram
+const GAP_BUCKET: Duration = Duration::from_millis(4);
+
+/// Gaps between lines as they were read, one histogram column per bucket,
+/// under their percentiles
+fn draw_latency<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let gaps = &app.gaps;
+    let block = theme.block(format!("Line gaps (last {})", gaps.len()));
+    let body_rect = block.inner(area);
+    f.render_widget(block, area);
+
+    let ms = |gap: Option<Duration>| {
+        gap.map_or_else(|| "-".to_owned(), |gap| format!("{} ms", gap.as_millis()))
+    };
+    let summary = format!(
+        " p50 {}, p95 {}, max {}",
+        ms(gaps.percentile(50.0)),
+        ms(gaps.percentile(95.0)),
+        ms(gaps.max())
+    );
+    if body_rect.height < 3 {
+        f.render_widget(Paragraph::new(summary), body_rect);
+        return;
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(body_rect);
+
+    let buckets = body_rect.width as usize;
+    let histogram = gaps.histogram(GAP_BUCKET, buckets);
+    // the last column takes the longer gaps as well
+    let right = format!(
+        "{} ms+",
+        (GAP_BUCKET * (buckets as u32).saturating_sub(1)).as_millis()
+    );
+    let axis = format!("0{:>width$}", right, width = buckets.saturating_sub(1));
+
+    f.render_widget(Paragraph::new(summary), chunks[0]);
+    f.render_widget(
+        Sparkline::default().data(&histogram).style(theme.good()),
+        chunks[1],
+    );
+    f.render_widget(Paragraph::new(Span::styled(axis, theme.dim())), chunks[2]);
+}
+
+/// "1.25km 045°" from the current position, when there is one
+fn distance_str(app: &App, waypoint: &Waypoint) -> Option<String> {
+    let (distance, bearing) = app.distance_to(waypoint)?;
+    Some(format!(
+        "{} {:03.0}°",
+        app.prefs.units.distance(distance),
+        bearing
+    ))
+}
+
+fn draw_waypoints<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let prefs = &app.prefs;
+    let waypoints = &app.waypoints;
+    let block = match app.target_waypoint() {
+        Some(target) => theme.block(format!(
+            "Waypoints ({}) - to {} {}",
+            waypoints.len(),
+            target.name,
+            option_str(distance_str(app, target))
+        )),
+        None => theme.block(format!("Waypoints ({})", waypoints.len())),
+    };
+
+    let msgs: Vec<Spans> = waypoints
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, wp)| {
+            let (marker, style) = if app.target == Some(i) {
+                (">", theme.good())
+            } else {
+                (" ", Style::default())
+            };
+            let distance = distance_str(app, wp).unwrap_or_default();
+            Spans::from(vec![
+                Span::styled(
+                    format!("{:>3} {} ", wp.seq, wp.local.format("%H:%M:%S")),
+                    theme.timestamp(),
+                ),
+                Span::styled(
+                    format!(
+                        "{}{} {} {}",
+                        marker,
+                        wp.name,
+                        prefs.coord_format.latlon(wp.latitude, wp.longitude),
+                        distance
+                    ),
+                    style,
+                ),
+            ])
+        })
+        .collect();
+
+    let body_rect = block.inner(area);
+    let paragraph = Paragraph::new(msgs).wrap(Wrap { trim: false });
+
+    f.render_widget(block, area);
+    f.render_widget(paragraph, body_rect);
+}
+
+fn draw_status<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let nmea = &app.nmea;
+    let theme = &app.theme;
+    let block = match (&app.antenna, &app.toast) {
+        (Some(antenna), _) => theme.alert_block(format!("Status - {}", antenna.to_uppercase())),
+        (_, Some((t, msg))) if (app.now - *t).num_seconds() < TOAST_SECS => {
+            theme.block(format!("Status - {}", msg))
+        }
+        _ => theme.block("Status"),
+    };
+
+    let mut msgs = Vec::new();
+
+    // shifted by up to hundreds of meters from what maps take them to be
+    if let Some(datum) = app.datum.as_ref().filter(|d| !d.is_wgs84()) {
+        msgs.push(Spans::from(Span::styled(
+            format!(
+                "DATUM: {} — positions are not WGS84\n",
+                datum.name().to_uppercase()
+            ),
+            theme.warn(),
+        )));
+    }
+    let date_warning = match app.date_warning {
+        Some(DateWarning::Behind) => " (week rollover?)",
+        Some(DateWarning::Ahead) => " (in the future?)",
+        None => "",
+    };
+    msgs.push(Spans::from(vec![
+        Span::raw(format!("datetime   : {}", option_str(datetime_str(nmea)))),
+        Span::styled(date_warning, theme.bad()),
+        Span::raw("\n"),
+    ]));
+    let clock_style = if app.clock_skewed() {
+        theme.bad()
+    } else {
+        Style::default()
+    };
+    msgs.push(Spans::from(vec![
+        Span::raw("clock skew : "),
+        Span::styled(clock_offset_str(app.clock_offset), clock_style),
+        Span::raw("\n"),
+    ]));
+    let fix_age = app.last_fix.map(|t| (app.now - t).num_seconds());
+    let (position_style, age_style) = match fix_age {
+        Some(age) if age <= STALE_FIX_SECS => (Style::default(), theme.good()),
+        _ => (theme.dim(), theme.bad()),
+    };
+    // dead reckoned positions drift, however good they look
+    let dead_reckoning = app.nav_status.is_some_and(NavStatus::dead_reckoning);
+    msgs.push(Spans::from(vec![
+        Span::raw("latlonalt  : "),
+        Span::styled(option_str(latlonalt_str(nmea, &app.prefs)), position_style),
+        Span::styled(if dead_reckoning { " DR" } else { "" }, theme.warn()),
+        Span::raw(" (age: "),
+        Span::styled(
+            option_str(fix_age.map(|age| format!("{}s", age))),
+            age_style,
+        ),
+        Span::raw(")"),
+        Span::styled(outliers_str(app.outliers.rejected), theme.warn()),
+        Span::raw("\n"),
+    ]));
+    // receivers which leave the separation out only get the MSL altitude,
+    // u-blox PUBX,00 only the height above the ellipsoid
+    let separation = app.geoid_separation;
+    if let Some(height) =
+        export::ellipsoidal_height(nmea.altitude, separation).or(app.ellipsoidal_height)
+    {
+        let units = app.prefs.units;
+        msgs.push(Spans::from(vec![
+            Span::raw("ellipsoid  : "),
+            Span::styled(units.altitude(height), position_style),
+            Span::raw(match separation {
+                Some(separation) => {
+                    format!(" (geoid separation {})\n", units.altitude(separation))
+                }
+                None => "\n".to_owned(),
+            }),
+        ]));
+    }
+    if let Some(status) = app.nav_status {
+        let style = if !status.valid() {
+            theme.bad()
+        } else if status.dead_reckoning() {
+            theme.warn()
+        } else {
+            Style::default()
+        };
+        msgs.push(Spans::from(vec![
+            Span::raw("nav status : "),
+            Span::styled(status.describe(), style),
+            Span::raw("\n"),
+        ]));
+    }
+    if let Some(datum) = &app.datum {
+        let style = if datum.is_wgs84() {
+            Style::default()
+        } else {
+            theme.warn()
+        };
+        msgs.push(Spans::from(vec![
+            Span::raw("datum      : "),
+            Span::styled(datum::datum_str(datum), style),
+            Span::raw("\n"),
+        ]));
+    }
+    msgs.push(Spans::from(format!(
+        "speed      : {}\n",
+        option_str(speed_str(
+            nmea,
+            app.speed_filter.value,
+            app.variation,
+            &app.prefs
+        )),
+    )));
+    if let Some(variation) = app.variation {
+        msgs.push(Spans::from(format!(
+            "variation  : {}\n",
+            magnetic::variation_str(variation)
+        )));
+    }
+    let accuracy = nmea.hdop.map(|hdop| {
+        let meters = estimated_accuracy_m(hdop, app.options.uere);
+        format!(" (est. ±{})", app.prefs.units.distance(meters as f64))
+    });
+    msgs.push(Spans::from(vec![
+        Span::raw(format!("dop (h/v/p): {}", option_str(dop_str(nmea)))),
+        Span::styled(accuracy.unwrap_or_default(), theme.dim()),
+        Span::raw("\n"),
+    ]));
+    // UBX tells the accuracy the receiver estimates instead of HDOP times UERE
+    let mut spans = vec![Span::raw("ubx acc h/v: ")];
+    match &app.nav_pvt {
+        Some((t, pvt)) => {
+            let style = if (app.now - *t).num_seconds() <= STALE_FIX_SECS {
+                Style::default()
+            } else {
+                theme.dim()
+            };
+            let carrier_style = match pvt.carr_soln {
+                2 => theme.good(),
+                1 => theme.warn(),
+                _ => style,
+            };
+            let dops = app
+                .nav_dop
+                .map(|dop| format!(", dop {:.2}/{:.2}", dop.h_dop, dop.v_dop));
+            spans.push(Span::styled(
+                format!(
+                    "±{:.3}m / ±{:.3}m, {} fix, ",
+                    pvt.h_acc,
+                    pvt.v_acc,
+                    pvt.fix_type_name()
+                ),
+                style,
+            ));
+            spans.push(Span::styled(
+                format!("carrier {}", pvt.carr_soln_name()),
+                carrier_style,
+            ));
+            spans.push(Span::styled(dops.unwrap_or_default(), style));
+        }
+        None => spans.push(Span::styled(option_str(None), theme.dim())),
+    }
+    spans.push(Span::raw("\n"));
+    msgs.push(Spans::from(spans));
+    let (errors, errors_style) = match &app.errors {
+        Some(errors) => (errors_str(errors), errors_style(errors, theme)),
+        None => (None, Style::default()),
+    };
+    msgs.push(Spans::from(vec![
+        Span::raw("err (lat/lon/alt): "),
+        Span::styled(option_str(errors), errors_style),
+        Span::raw("\n"),
+    ]));
+    let corrections_style = match nmea.fix_type {
+        Some(FixType::Rtk) => theme.good(),
+        Some(FixType::FloatRtk) => theme.warn(),
+        Some(FixType::DGps) => Style::default(),
+        _ => theme.dim(),
+    };
+    msgs.push(Spans::from(vec![
+        Span::raw("corrections: "),
+        Span::styled(
+            corrections_str(nmea, app.corrections.as_ref()),
+            corrections_style,
+        ),
+        Span::raw("\n"),
+    ]));
+    if let Some(ntrip) = &app.ntrip {
+        let (text, fresh) = ntrip_str(&ntrip.lock().unwrap(), app.now);
+        msgs.push(Spans::from(vec![
+            Span::raw("ntrip      : "),
+            Span::styled(text, if fresh { theme.good() } else { theme.bad() }),
+            Span::raw("\n"),
+        ]));
+    }
+    msgs.push(Spans::from(format!(
+        "average    : {}\n",
+        option_str(app.averager.as_ref().and_then(|avg| avg.summary())),
+    )));
+    if !app.geofence.is_empty() {
+        let mut spans = vec![Span::raw("zones      :")];
+        for (name, inside, time) in app.geofence.summary(app.now) {
+            let style = if inside { theme.good() } else { theme.dim() };
+            spans.push(Span::styled(
+                format!(" {} {}", name, duration_str(time)),
+                style,
+            ));
+        }
+        spans.push(Span::raw("\n"));
+        msgs.push(Spans::from(spans));
+    }
+    let unparsed_style = if app.sentences_failed > 0 {
+        theme.warn()
+    } else {
+        Style::default()
+    };
+    let mut spans = vec![
+        Span::raw("unparsed   : "),
+        Span::styled(app.sentences_failed.to_string(), unparsed_style),
+    ];
+    if app.bytes_skipped > 0 {
+        spans.push(Span::styled(
+            format!(" ({} bytes dropped to sync)", app.bytes_skipped),
+            theme.dim(),
+        ));
+    }
+    spans.push(Span::raw("\n"));
+    msgs.push(Spans::from(spans));
+    // only lines from a reader thread are timed
+    if let Some(lag) = app.queue_lag {
+        let lag_style = if lag >= SLOW_UI_LAG {
+            theme.warn()
+        } else {
+            Style::default()
+        };
+        let mut spans = vec![
+            Span::raw("ui lag     : "),
+            Span::styled(format!("{} ms", lag.as_millis()), lag_style),
+        ];
+        if app.lines_dropped > 0 {
+            spans.push(Span::styled(
+                format!(", dropped {} lines (UI too slow)", app.lines_dropped),
+                theme.warn(),
+            ));
+        }
+        spans.push(Span::raw("\n"));
+        msgs.push(Spans::from(spans));
+    }
+
+    let body_rect = block.inner(area);
+    let paragraph = Paragraph::new(msgs).wrap(Wrap { trim: false });
+
+    f.render_widget(block, area);
+    f.render_widget(paragraph, body_rect);
+}
+
+/// Signal strength above which tracking is solid, and below which it is marginal
+const SNR_GOOD: f32 = 35.0;
+const SNR_WEAK: f32 = 25.0;
+
+fn snr_style(snr: Option<f32>, theme: &Theme) -> Style {
+    match snr {
+        Some(snr) if snr >= SNR_GOOD => theme.good(),
+        Some(snr) if snr >= SNR_WEAK => theme.warn(),
+        Some(_) => theme.bad(),
+        None => theme.dim(),
+    }
+}
+
+/// Columns of the satellite table
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SatColumn {
+    Prn,
+    /// constellation name
+    Gnss,
+    /// talker instead of the constellation name, for narrow panes
+    Talker,
+    Elev,
+    Azim,
+    Snr,
+    Used,
+}
+
+impl SatColumn {
+    fn title(self) -> &'static str {
+        match self {
+            SatColumn::Prn => "PRN",
+            SatColumn::Gnss | SatColumn::Talker => "GNSS",
+            SatColumn::Elev => "Elev",
+            SatColumn::Azim => "Azim",
+            SatColumn::Snr => "SNR",
+            SatColumn::Used => "Used",
+        }
+    }
+
+    fn width(self) -> u16 {
+        match self {
+            SatColumn::Prn | SatColumn::Snr => 3,
+            SatColumn::Gnss => 7,
+            SatColumn::Talker | SatColumn::Elev | SatColumn::Azim | SatColumn::Used => 4,
+        }
+    }
+}
+
+const SAT_COLUMN_SPACING: u16 = 2;
+
+/// The columns which fit a pane `width` wide: the azimuth goes first, then
+/// constellation names give way to talkers
+fn sat_columns(width: u16) -> Vec<SatColumn> {
+    let layouts = [
+        vec![
+            SatColumn::Prn,
+            SatColumn::Gnss,
+            SatColumn::Elev,
+            SatColumn::Azim,
+            SatColumn::Snr,
+            SatColumn::Used,
+        ],
+        vec![
+            SatColumn::Prn,
+            SatColumn::Gnss,
+            SatColumn::Elev,
+            SatColumn::Snr,
+            SatColumn::Used,
+        ],
+        vec![
+            SatColumn::Prn,
+            SatColumn::Talker,
+            SatColumn::Elev,
+            SatColumn::Snr,
+            SatColumn::Used,
+        ],
+    ];
+    let fits = |columns: &&Vec<SatColumn>| {
+        let spacing = SAT_COLUMN_SPACING * (columns.len() as u16 - 1);
+        columns.iter().map(|c| c.width()).sum::<u16>() + spacing <= width
+    };
+    let narrowest = &layouts[layouts.len() - 1];
+    layouts.iter().find(fits).unwrap_or(narrowest).clone()
+}
+
+/// Satellites in view, strongest signal first, then those just lost. Those
+/// just acquired stand out, and the title counts changes over the last
+/// minute.
+fn draw_sat_table<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    sky: &Sky,
+    now: DateTime<Local>,
+    theme: &Theme,
+) {
+    let churn = match sky.churn() {
+        (0, 0) => String::new(),
+        (acquired, lost) => format!(", +{}/-{} a minute", acquired, lost),
+    };
+    let block = theme.block(format!(
+        "Satellites (fixed={}, total={}{})",
+        sky.used_count(),
+        sky.len(),
+        churn,
+    ));
+
+    let mut sats: Vec<&Satellite> = sky.satellites().collect();
+    sats.sort_by(|a, b| {
+        let snr = |sat: &Satellite| sat.snr.unwrap_or(-1.0);
+        a.lost
+            .is_some()
+            .cmp(&b.lost.is_some())
+            .then_with(|| snr(b).partial_cmp(&snr(a)).unwrap_or(Ordering::Equal))
+    });
+
+    let columns = sat_columns(block.inner(area).width);
+    // numbers are right-aligned under their titles
+    let number = |v: Option<f32>, column: SatColumn| match v {
+        Some(v) => format!("{:>width$.0}", v, width = column.width() as usize),
+        None => format!("{:>width$}", "-", width = column.width() as usize),
+    };
+    let cell = |column: SatColumn, sat: &Satellite| match column {
+        SatColumn::Prn => Cell::from(format!("{:>3}", sat.prn)),
+        SatColumn::Gnss => Cell::from(constellation(&sat.talker)),
+        SatColumn::Talker => Cell::from(sat.talker.clone()),
+        SatColumn::Elev => Cell::from(number(sat.elevation, column)),
+        SatColumn::Azim => Cell::from(number(sat.azimuth, column)),
+        SatColumn::Snr => Cell::from(number(sat.snr, column)).style(snr_style(sat.snr, theme)),
+        SatColumn::Used if sat.used => Cell::from("yes").style(theme.good()),
+        SatColumn::Used => Cell::from(""),
+    };
+    let rows = sats.into_iter().map(|sat| {
+        if sat.lost.is_some() {
+            // greyed out whole, kept until it expires
+            let cells = columns.iter().map(|&column| match column {
+                SatColumn::Snr => Cell::from(number(sat.snr, column)),
+                SatColumn::Used => Cell::from(""),
+                _ => cell(column, sat),
+            });
+            return Row::new(cells).style(theme.dim().add_modifier(Modifier::CROSSED_OUT));
+        }
+        let style = if sat.just_acquired(now) {
+            theme.good()
+        } else {
+            Style::default()
+        };
+        Row::new(columns.iter().map(|&column| cell(column, sat))).style(style)
+    });
+    let header = Row::new(columns.iter().map(|c| c.title())).style(theme.dim());
+
+    let widths: Vec<Constraint> = columns
+        .iter()
+        .map(|c| Constraint::Length(c.width()))
+        .collect();
+    let table = Table::new(rows)
+        .header(header)
+        .block(block)
+        .widths(&widths)
+        .column_spacing(SAT_COLUMN_SPACING);
+    f.render_widget(table, area);
+}
+
+/// Satellites used and DOPs per constellation, from the latest GSA of each
+fn draw_gsa_table<B: Backend>(f: &mut Frame<B>, area: Rect, sky: &Sky, theme: &Theme) {
+    let reports = sky.gsa_reports();
+    let in_fix = reports.iter().filter(|r| !r.prns.is_empty()).count();
+    let block = theme.block(format!("Constellations in fix ({})", in_fix));
+
+    let dop = |v: Option<f32>| match v {
+        Some(v) => format!("{:>5.2}", v),
+        None => format!("{:>5}", "-"),
+    };
+    let rows = reports.iter().map(|report| {
+        let style = if report.prns.is_empty() {
+            theme.dim()
+        } else {
+            Style::default()
+        };
+        Row::new(vec![
+            Cell::from(constellation(&report.talker)),
+            Cell::from(format!("{:>4}", report.prns.len())),
+            Cell::from(dop(report.pdop)),
+            Cell::from(dop(report.hdop)),
+            Cell::from(dop(report.vdop)),
+        ])
+        .style(style)
+    });
+    let header = Row::new(vec!["System", "Used", " PDOP", " HDOP", " VDOP"]).style(theme.dim());
+
+    let widths = [
+        Constraint::Length(7),
+        Constraint::Length(4),
+        Constraint::Length(5),
+        Constraint::Length(5),
+        Constraint::Length(5),
+    ];
+    let table = Table::new(rows)
+        .header(header)
+        .block(block)
+        .widths(&widths)
+        .column_spacing(2);
+    f.render_widget(table, area);
+}
+
+fn draw_text<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let block = theme.block(format!("Receiver text ({})", app.texts.len()));
+
+    let msgs: Vec<Spans> = app
+        .texts
+        .iter()
+        .map(|msg| {
+            let style = match msg.level {
+                TextLevel::Error => theme.bad(),
+                TextLevel::Warning => theme.warn(),
+                TextLevel::Notice => Style::default(),
+                TextLevel::User => theme.dim(),
+            };
+            Spans::from(vec![
+                Span::styled(
+                    format!("{} ", msg.local.format("%H:%M:%S")),
+                    theme.timestamp(),
+                ),
+                Span::styled(msg.text.clone(), style),
+            ])
+        })
+        .collect();
+
+    let body_rect = block.inner(area);
+    let paragraph = Paragraph::new(msgs)
+        .wrap(Wrap { trim: false })
+        .scroll((app.text_scroll.min(u16::MAX as usize) as u16, 0));
+
+    f.render_widget(block, area);
+    f.render_widget(paragraph, body_rect);
+}
+
+/// Control characters would upset the terminal
+fn printable(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_control() { '.' } else { c })
+        .collect()
+}
+
+fn hex_str(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    hex.join(" ")
+}
+
+/// One receiver's column of the Compare panel
+fn receiver_lines(receiver: &App, prefs: &Prefs) -> Vec<Spans<'static>> {
+    let nmea = &receiver.nmea;
+    let fix_age = receiver.last_fix.map(|t| (receiver.now - t).num_seconds());
+    vec![
+        Spans::from(format!("source: {}", receiver.source)),
+        Spans::from(format!(
+            "fix   : {}",
+            option_str(nmea.fix_type.map(|t| format!("{:?}", t)))
+        )),
+        Spans::from(format!(
+            "used  : {} of {} in view",
+            receiver.sky.used_count(),
+            receiver.sky.len()
+        )),
+        Spans::from(format!("dop   : {}", option_str(dop_str(nmea)))),
+        Spans::from(format!(
+            "pos   : {} (age: {})",
+            option_str(latlonalt_str(nmea, prefs)),
+            option_str(fix_age.map(|age| format!("{}s", age)))
+        )),
+    ]
+}
+
+/// Both receivers side by side, with the distance between their fixes
+fn draw_compare<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let peer = match &app.peer {
+        Some(peer) => peer,
+        None => return,
+    };
+    let theme = &app.theme;
+    let baseline = match app.baseline() {
+        Some((distance, bearing)) => {
+            format!("{} {:03.0}°", app.prefs.units.distance(distance), bearing)
+        }
+        None => "—".to_owned(),
+    };
+    let block = theme.block(format!("Compare - baseline {}", baseline));
+    let body_rect = block.inner(area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Ratio(1, 2); 2])
+        .split(body_rect);
+
+    f.render_widget(block, area);
+    for (receiver, &column) in [app, &**peer].iter().zip(&columns) {
+        let paragraph = Paragraph::new(receiver_lines(receiver, &app.prefs));
+        f.render_widget(paragraph, column);
+    }
+}
+
+/// Distance, bearing, cross-track error and ETA of the active route leg
+fn draw_navigation<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let route = match &app.route {
+        Some(route) => route,
+        None => return,
+    };
+    let theme = &app.theme;
+    let units = app.prefs.units;
+    let block = theme.block(format!(
+        "Navigation ({}/{})",
+        (route.active + 1).min(route.points.len()),
+        route.points.len()
+    ));
+
+    let nav = match (app.nmea.latitude, app.nmea.longitude) {
+        (Some(lat), Some(lon)) => route.navigation(lat, lon, app.nmea.speed_over_ground),
+        _ => None,
+    };
+    let msgs = match (nav, route.finished()) {
+        (_, true) => vec![Spans::from(Span::styled("route finished", theme.good()))],
+        (None, false) => vec![Spans::from(format!(
+            "to {}: no position",
+            route.target().map_or("", |point| point.name.as_str())
+        ))],
+        (Some(nav), false) => {
+            let xte = nav.xte.map(|xte| {
+                let side = if xte < 0.0 { "left" } else { "right" };
+                format!("{} {}", units.distance(xte.abs()), side)
+            });
+            let eta = nav.eta.map(|eta| {
+                let arrival = app.now + eta;
+                format!("{} ({})", duration_str(eta), arrival.format("%H:%M"))
+            });
+            vec![
+                Spans::from(format!("to      : {}", nav.to)),
+                Spans::from(format!(
+                    "distance: {} {:03.0}°",
+                    units.distance(nav.distance),
+                    nav.bearing
+                )),
+                Spans::from(format!("xte     : {}", option_str(xte))),
+                Spans::from(format!("eta     : {}", option_str(eta))),
+            ]
+        }
+    };
+
+    let body_rect = block.inner(area);
+    f.render_widget(block, area);
+    f.render_widget(Paragraph::new(msgs), body_rect);
+}
+
+/// AIS targets nearest first, with the closest approach if both keep course
+/// and speed
+fn draw_ais<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let units = app.prefs.units;
+    let block = theme.block(format!("AIS ({})", app.ais.len()));
+
+    let nmea = &app.nmea;
+    let here = match (nmea.latitude, nmea.longitude) {
+        (Some(lat), Some(lon)) => Some((lat, lon)),
+        _ => None,
+    };
+    let own = here.map(|(lat, lon)| Motion {
+        lat,
+        lon,
+        sog: nmea.speed_over_ground.unwrap_or(0.0),
+        cog: nmea.true_course.unwrap_or(0.0),
+    });
+    let motion = |target: &Target| {
+        Some(Motion {
+            lat: target.lat?,
+            lon: target.lon?,
+            sog: target.sog.unwrap_or(0.0),
+            cog: target.cog.unwrap_or(0.0),
+        })
+    };
+    let number = |v: Option<f32>| match v {
+        Some(v) => format!("{:>5.1}", v),
+        None => format!("{:>5}", "-"),
+    };
+
+    let rows = app.ais.by_distance(here).into_iter().map(|target| {
+        let position = here.and_then(|here| target.distance_bearing(here));
+        let cpa = match (own, motion(target)) {
+            (Some(own), Some(target)) => ais::cpa(own, target),
+            _ => None,
+        };
+        let (cpa, tcpa) = match cpa {
+            Some(cpa) if cpa.time >= chrono::Duration::zero() => {
+                (units.distance(cpa.distance), duration_str(cpa.time))
+            }
+            Some(_) => ("-".to_owned(), "past".to_owned()),
+            None => ("-".to_owned(), "-".to_owned()),
+        };
+        let age = (app.now - target.last_seen).max(chrono::Duration::zero());
+        let style = if age.num_seconds() > STALE_AIS_SECS {
+            theme.dim()
+        } else {
+            Style::default()
+        };
+        Row::new(vec![
+            Cell::from(target.mmsi.to_string()),
+            Cell::from(target.name.clone().unwrap_or_default()),
+            Cell::from(position.map_or_else(|| "-".to_owned(), |(d, _)| units.distance(d))),
+            Cell::from(position.map_or_else(|| "-".to_owned(), |(_, b)| format!("{:03.0}°", b))),
+            Cell::from(number(target.sog)),
+            Cell::from(number(target.cog)),
+            Cell::from(cpa),
+            Cell::from(tcpa),
+            Cell::from(duration_str(age)),
+        ])
+        .style(style)
+    });
+    let header = Row::new(vec![
+        "MMSI", "Name", "Dist", "Brg", "  SOG", "  COG", "CPA", "TCPA", "Age",
+    ])
+    .style(theme.dim());
+
+    let widths = [
+        Constraint::Length(9),
+        Constraint::Length(20),
+        Constraint::Length(9),
+        Constraint::Length(4),
+        Constraint::Length(5),
+        Constraint::Length(5),
+        Constraint::Length(9),
+        Constraint::Length(8),
+        Constraint::Length(8),
+    ];
+    let table = Table::new(rows)
+        .header(header)
+        .block(block)
+        .widths(&widths)
+        .column_spacing(1);
+    f.render_widget(table, area);
+}
+
+/// `label: value`, dimmed with its age once the instrument has gone quiet
+fn reading_line<T>(
+    label: &str,
+    reading: Option<&Reading<T>>,
+    app: &App,
+    format: impl Fn(&T) -> String,
+) -> Spans<'static> {
+    match reading {
+        Some(reading) if reading.is_stale(app.now) => Spans::from(Span::styled(
+            format!(
+                "{:<11}: {} ({} ago)",
+                label,
+                format(&reading.value),
+                duration_str(app.now - reading.local)
+            ),
+            app.theme.dim(),
+        )),
+        Some(reading) => Spans::from(format!("{:<11}: {}", label, format(&reading.value))),
+        None => Spans::from(format!("{:<11}: {}", label, option_str(None))),
+    }
+}
+
+/// Instruments other than the receiver, each with its own age
+fn draw_vessel<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let units = app.prefs.units;
+    let vessel = &app.vessel;
+    let block = theme.block("Vessel");
+
+    let wind = |wind: &Wind| format!("{:03.0}° {}", wind.angle, units.speed(wind.knots as f32));
+    let heading = |heading: &f64| format!("{:05.1}°", heading);
+    let msgs = vec![
+        reading_line("depth", vessel.depth.as_ref(), app, |depth| {
+            let below = match (depth.below_surface(), depth.below_keel()) {
+                (Some(d), _) => format!(" ({} below surface)", units.altitude(d as f32)),
+                (_, Some(d)) => format!(" ({} below keel)", units.altitude(d as f32)),
+                _ => String::new(),
+            };
+            format!("{}{}", units.altitude(depth.meters as f32), below)
+        }),
+        reading_line("wind app", vessel.apparent_wind.as_ref(), app, wind),
+        reading_line("wind true", vessel.true_wind.as_ref(), app, wind),
+        reading_line("heading T", vessel.heading_true.as_ref(), app, heading),
+        reading_line("heading M", vessel.heading_magnetic.as_ref(), app, heading),
+        reading_line("turn rate", vessel.rate_of_turn.as_ref(), app, |rate| {
+            let side = if *rate < 0.0 { "port" } else { "starboard" };
+            format!("{:.1}°/min {}", rate.abs(), side)
+        }),
+        reading_line("water temp", vessel.water_temp.as_ref(), app, |temp| {
+            format!("{:.1}°C", temp)
+        }),
+    ];
+
+    let body_rect = block.inner(area);
+    f.render_widget(block, area);
+    f.render_widget(Paragraph::new(msgs), body_rect);
+}
+
+fn draw_rejected<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let block = theme.block(format!("Messages (rejected: {})", app.sentences_failed));
+
+    let msgs: Vec<Spans> = app
+        .rejected
+        .lines
+        .iter()
+        .map(|rejection| {
+            Spans::from(vec![
+                Span::styled(
+                    rejection.local.to_rfc3339_opts(SecondsFormat::Secs, true),
+                    theme.timestamp(),
+                ),
+                Span::styled(
+                    format!(" {}: {}\n", rejection.reason, printable(&rejection.line)),
+                    theme.bad(),
+                ),
+            ])
+        })
+        .collect();
+
+    let body_rect = block.inner(area);
+    let paragraph = Paragraph::new(msgs).wrap(Wrap { trim: false });
+
+    f.render_widget(block, area);
+    f.render_widget(paragraph, body_rect);
+}
+
+fn draw_messages<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    if app.show_rejected {
+        draw_rejected(f, area, app);
+        return;
+    }
+    let theme = &app.theme;
+    let mut title = if app.hex_view {
+        "Messages (hex)".to_owned()
+    } else {
+        "Messages".to_owned()
+    };
+    if let Some(query) = &app.search {
+        let matches = app.search_matches();
+        let current = app
+            .messages
+            .selected
+            .and_then(|selected| matches.iter().position(|&i| i == selected));
+        title += &match current {
+            Some(i) => format!(" - /{} {} of {}", query, i + 1, matches.len()),
+            None => format!(" - /{} {} matches", query, matches.len()),
+        };
+        title += ", n/N for more, Esc to cancel";
+    } else if app.messages.selected.is_some() {
+        title += " - Enter to inspect, Esc to unselect";
+    }
+    let block = theme.block(title);
+
+    let body_rect = block.inner(area);
+    let lines = message_lines(app);
+    let (scroll, _) = message_rows(app, &lines, body_rect);
+    let lines: Vec<Spans> = lines.into_iter().map(|(_, spans)| spans).collect();
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0));
+
+    f.render_widget(block, area);
+    f.render_widget(paragraph, body_rect);
+}
+
+/// Lines of the Messages pane, each with the index of its message
+fn message_lines(app: &App) -> Vec<(usize, Spans<'static>)> {
+    let theme = &app.theme;
+    let mut lines = Vec::new();
+    for (i, msg) in app.messages.lines.iter().enumerate() {
+        let style = match msg.kind {
+            MessageKind::Sentence => Style::default(),
+            MessageKind::Filtered => theme.dim(),
+            MessageKind::Sent => theme.good(),
+            MessageKind::Rejected if !app.hex_view => continue,
+            MessageKind::Rejected | MessageKind::Alert => theme.bad(),
+            MessageKind::Notice => theme.warn(),
+        };
+        let mut spans = vec![Span::styled(
+            msg.local.to_rfc3339_opts(SecondsFormat::Secs, true),
+            theme.timestamp(),
+        )];
+        // tell the receivers apart when comparing two
+        match (&app.peer, msg.peer) {
+            (Some(_), false) => spans.push(Span::styled(" [1]", theme.good())),
+            (Some(_), true) => spans.push(Span::styled(" [2]", theme.warn())),
+            (None, _) => {}
+        }
+        match msg.kind {
+            MessageKind::Filtered => spans.push(Span::styled(" filtered", theme.warn())),
+            MessageKind::Sent => spans.push(Span::styled(" sent", theme.good())),
+            _ => {}
+        }
+        let style = if app.messages.selected == Some(i) {
+            style.add_modifier(Modifier::REVERSED)
+        } else {
+            style
+        };
+        spans.push(Span::styled(format!(" {}\n", printable(&msg.text)), style));
+        lines.push((i, Spans::from(spans)));
+        if app.hex_view
+            && matches!(
+                msg.kind,
+                MessageKind::Sentence | MessageKind::Rejected | MessageKind::Filtered
+            )
+        {
+            lines.push((
+                i,
+                Spans::from(Span::styled(
+                    format!("  {}\n", hex_str(msg.bytes())),
+                    theme.dim(),
+                )),
+            ));
+        }
+    }
+    lines
+}
+
+/// Rows the Messages pane is scrolled by, and the first row of each line
+/// wrapped into `body`, followed by the row after the last
+fn message_rows(app: &App, lines: &[(usize, Spans)], body: Rect) -> (u16, Vec<usize>) {
+    let width = body.width.max(1) as usize;
+    let mut starts = Vec::with_capacity(lines.len() + 1);
+    let mut row = 0;
+    for (_, spans) in lines {
+        starts.push(row);
+        row += spans.width().div_ceil(width).max(1);
+    }
+    starts.push(row);
+
+    // a line which isn't shown stands for the next one which is
+    let row_of = |i: usize| {
+        lines
+            .iter()
+            .position(|&(j, _)| j >= i)
+            .map(|line| starts[line])
+    };
+    let scroll = match (app.messages.top, app.messages.selected) {
+        (Some(top), _) => row_of(top).unwrap_or(0),
+        // the selected line is kept in the middle
+        (None, Some(selected)) => {
+            row_of(selected).map_or(0, |row| row.saturating_sub(body.height as usize / 2))
+        }
+        (None, None) => 0,
+    };
+    (scroll.min(u16::MAX as usize) as u16, starts)
+}
+
+/// The message shown on a row of the screen, in the Messages pane at `area`
+fn message_at(app: &App, area: Rect, row: u16) -> Option<usize> {
+    let body = app.theme.block("").inner(area);
+    if app.show_rejected || row < body.y || row >= body.bottom() {
+        return None;
+    }
+    let lines = message_lines(app);
+    let (scroll, starts) = message_rows(app, &lines, body);
+    let row = (row - body.y) as usize + scroll as usize;
+    let line = starts[..lines.len()]
+        .iter()
+        .rposition(|&start| start <= row)?;
+    (row < starts[line + 1]).then_some(lines[line].0)
+}
+
+/// Overlay with the fields of the selected message, raw and as read
+fn draw_inspector<B: Backend>(f: &mut Frame<B>, inspection: &Inspection, theme: &Theme) {
+    let width = inspection
+        .fields
+        .iter()
+        .map(|field| field.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let raw_width = inspection
+        .fields
+        .iter()
+        .map(|field| field.raw.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines: Vec<Spans> = inspection
+        .fields
+        .iter()
+        .map(|field| {
+            Spans::from(vec![
+                Span::styled(
+                    format!("{:<width$}  ", field.name, width = width),
+                    theme.dim(),
+                ),
+                Span::raw(format!("{:<width$}  ", field.raw, width = raw_width)),
+                Span::styled(field.value.clone().unwrap_or_default(), theme.good()),
+            ])
+        })
+        .collect();
+    let checksum_style = if inspection.checksum.is_valid() {
+        theme.good()
+    } else {
+        theme.bad()
+    };
+    lines.push(Spans::from(Span::styled(
+        inspection.checksum.describe(),
+        checksum_style,
+    )));
+
+    let size = f.size();
+    let width = size.width.min(72);
+    let height = size.height.min(lines.len() as u16 + 2);
+    let area = Rect::new(
+        size.x + (size.width - width) / 2,
+        size.y + (size.height - height) / 2,
+        width,
+        height,
+    );
+
+    let kind = if inspection.known {
+        inspection.kind.clone()
+    } else {
+        format!("{} (fields unknown)", inspection.kind)
+    };
+    let title = format!("{} from {} (any key to close)", kind, inspection.talker);
+    let block = theme.block(title).borders(Borders::ALL);
+    let paragraph = Paragraph::new(lines).block(block);
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// Single-line input box drawn over the middle of the screen
+fn draw_prompt<B: Backend>(f: &mut Frame<B>, title: &str, input: &str, theme: &Theme) {
+    let size = f.size();
+    if size.height < 3 {
+        return;
+    }
+    let width = size.width.min(60);
+    let area = Rect::new(
+        size.x + (size.width - width) / 2,
+        size.y + (size.height - 3) / 2,
+        width,
+        3,
+    );
+
+    let block = theme.block(title).borders(Borders::ALL);
+    let paragraph = Paragraph::new(format!("{}_", input)).block(block);
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// Overlay listing the effective key bindings
+fn draw_help<B: Backend>(f: &mut Frame<B>, keymap: &KeyMap, theme: &Theme) {
+    let lines: Vec<Spans> = Action::ALL
+        .iter()
+        .map(|&action| {
+            let keys: Vec<String> = keymap.keys(action).into_iter().map(key_name).collect();
+            let keys = if keys.is_empty() {
+                "(unbound)".to_owned()
+            } else {
+                keys.join(", ")
+            };
+            Spans::from(vec![
+                Span::styled(format!("{:>12}", keys), theme.good()),
+                Span::raw(format!("  {}", action.description())),
+            ])
+        })
+        .collect();
+
+    let size = f.size();
+    let width = size.width.min(50);
+    let height = size.height.min(lines.len() as u16 + 2);
+    let area = Rect::new(
+        size.x + (size.width - width) / 2,
+        size.y + (size.height - height) / 2,
+        width,
+        height,
+    );
+
+    let block = theme.block("Keys (any key to close)").borders(Borders::ALL);
+    let paragraph = Paragraph::new(lines).block(block);
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// Overlay listing the canned PMTK commands
+fn draw_pmtk_menu<B: Backend>(f: &mut Frame<B>, selected: usize, theme: &Theme) {
+    let lines: Vec<Spans> = PRESETS
+        .iter()
+        .enumerate()
+        .map(|(i, preset)| {
+            let (marker, style) = if i == selected {
+                (">", theme.good())
+            } else {
+                (" ", Style::default())
+            };
+            Spans::from(vec![
+                Span::styled(format!("{} {}", marker, preset.label), style),
+                Span::styled(format!("  {}", preset.body), theme.dim()),
+            ])
+        })
+        .collect();
+
+    let size = f.size();
+    let width = size.width.min(72);
+    let height = size.height.min(lines.len() as u16 + 2);
+    let area = Rect::new(
+        size.x + (size.width - width) / 2,
+        size.y + (size.height - height) / 2,
+        width,
+        height,
+    );
+
+    let block = theme
+        .block("PMTK commands (Enter to send, Esc to close)")
+        .borders(Borders::ALL);
+    let paragraph = Paragraph::new(lines).block(block);
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// A line across the bottom of the screen which can't be missed
+fn draw_banner<B: Backend>(f: &mut Frame<B>, text: &str, theme: &Theme) {
+    let size = f.size();
+    let area = Rect::new(size.x, size.bottom() - 1, size.width, 1);
+    let paragraph =
+        Paragraph::new(text.to_owned()).style(theme.bad().add_modifier(Modifier::REVERSED));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// Where each panel goes on a screen of `size`, none when it is too small.
+/// A short screen shows the focused panel alone, or else the first.
+fn panel_areas(size: Rect, app: &App) -> Vec<(Panel, Rect)> {
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        return Vec::new();
+    }
+    if app.zen {
+        return Vec::new();
+    }
+    let rows = app.panel_rows();
+    if size.height < COMPACT_HEIGHT {
+        let mut panels = rows.iter().flatten();
+        let shown = match app.focus {
+            Some(focus) if panels.clone().any(|&p| p == focus) => Some(focus),
+            _ => panels.next().copied(),
+        };
+        return shown.map(|panel| (panel, size)).into_iter().collect();
+    }
+    let row_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(PanelLayout::row_constraints(&rows, size.height))
+        .split(size);
+
+    let mut areas = Vec::new();
+    for (row, row_chunk) in rows.iter().zip(row_chunks) {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, row.len() as u32); row.len()])
+            .split(row_chunk);
+        areas.extend(row.iter().copied().zip(chunks));
+    }
+    areas
+}
+
+/// The panel under a cell of a screen of `size`, with the message there
+/// when it is the Messages pane
+pub fn panel_at(size: Rect, app: &App, column: u16, row: u16) -> Option<(Panel, Option<usize>)> {
+    let (panel, area) = panel_areas(size, app).into_iter().find(|(_, area)| {
+        (area.x..area.right()).contains(&column) && (area.y..area.bottom()).contains(&row)
+    })?;
+    let message = match panel {
+        Panel::Messages => message_at(app, area, row),
+        _ => None,
+    };
+    Some((panel, message))
+}
+
+/// Speed and course in digits as big as the screen allows and the fix below
+/// them, to be read from a distance
+fn draw_zen<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let (nmea, theme) = (&app.nmea, &app.theme);
+    let fresh = app
+        .last_fix
+        .is_some_and(|t| (app.now - t).num_seconds() <= STALE_FIX_SECS);
+    let digits_style = if fresh { Style::default() } else { theme.dim() };
+
+    let (speed, unit) = app
+        .prefs
+        .units
+        .speed_in(nmea.speed_over_ground.unwrap_or(0.0));
+    let speed = match nmea.speed_over_ground {
+        Some(_) => format!("{:.1}", speed),
+        None => "-.-".to_owned(),
+    };
+    let course = match nmea.true_course {
+        Some(course) => format!("{:03}", course.round() as u32 % 360),
+        None => "---".to_owned(),
+    };
+    let fix = if has_fix(nmea) {
+        format!(
+            "{} fix ({}), {} used, HDOP {}",
+            if app.fix_3d { "3D" } else { "2D" },
+            option_str(nmea.fix_type.map(|t| format!("{:?}", t))),
+            option_str(nmea.num_of_fix_satellites.map(|n| n.to_string())),
+            option_str(nmea.hdop.map(|hdop| hdop.to_string())),
+        )
+    } else {
+        "no fix".to_owned()
+    };
+    let fix_style = if fresh && has_fix(nmea) {
+        theme.good()
+    } else {
+        theme.bad()
+    };
+
+    // a label above each number, a blank line between them and the fix
+    let max_height = area.height.saturating_sub(4) / 2;
+    let height = bigdigits::fit_height(&speed, area.width, max_height)
+        .and_then(|h| bigdigits::fit_height(&course, area.width, h));
+    let number = |label: String, text: &str| {
+        let mut lines = vec![Spans::from(Span::styled(label, theme.focused()))];
+        match height {
+            Some(height) => lines.extend(
+                bigdigits::render(text, height)
+                    .into_iter()
+                    .map(|row| Spans::from(Span::styled(row, digits_style))),
+            ),
+            // too small for big digits
+            None => lines.push(Spans::from(Span::styled(text.to_owned(), digits_style))),
+        }
+        lines
+    };
+    let mut lines = number(format!("speed ({})", unit), &speed);
+    lines.push(Spans::default());
+    lines.extend(number("course (°T)".to_owned(), &course));
+    lines.push(Spans::from(vec![
+        Span::styled(fix, fix_style),
+        Span::styled(
+            format!("  ({} to leave)", app.key_hint(Action::Zen)),
+            theme.dim(),
+        ),
+    ]));
+
+    let top = area.height.saturating_sub(lines.len() as u16) / 2;
+    let mut padded = vec![Spans::default(); top as usize];
+    padded.extend(lines);
+    let paragraph = Paragraph::new(padded).alignment(Alignment::Center);
+    f.render_widget(paragraph, area);
+}
+
+/// Restyles the cells of its area, keeping what was drawn there
+struct Restyle(Style);
+
+impl Widget for Restyle {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        buf.set_style(area, self.0);
+    }
+}
+
+/// Renders the whole screen
+pub fn draw<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let size = f.size();
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        let msg = format!(
+            "terminal too small ({}x{}, need {}x{})",
+            size.width, size.height, MIN_WIDTH, MIN_HEIGHT
+        );
+        let paragraph = Paragraph::new(msg).wrap(Wrap { trim: true });
+        f.render_widget(paragraph, size);
+        return;
+    }
+
+    let theme = &app.theme;
+    if app.zen {
+        draw_zen(f, size, app);
+    }
+    for (panel, chunk) in panel_areas(size, app) {
+        match panel {
+            Panel::Status => draw_status(f, chunk, app),
+            Panel::Sky => draw_sat_table(f, chunk, &app.sky, app.now, theme),
+            Panel::Gsa => draw_gsa_table(f, chunk, &app.sky, theme),
+            Panel::Track => draw_track_map(f, chunk, &app.positions, theme),
+            Panel::Waypoints => draw_waypoints(f, chunk, app),
+            Panel::Rates => draw_rates(f, chunk, app),
+            Panel::Latency => draw_latency(f, chunk, app),
+            Panel::Compare => draw_compare(f, chunk, app),
+            Panel::Navigation => draw_navigation(f, chunk, app),
+            Panel::Ais => draw_ais(f, chunk, app),
+            Panel::Vessel => draw_vessel(f, chunk, app),
+            Panel::Text => draw_text(f, chunk, app),
+            Panel::Messages => draw_messages(f, chunk, app),
+        }
+        if app.focus == Some(panel) {
+            let title = Rect {
+                height: chunk.height.min(1),
+                ..chunk
+            };
+            f.render_widget(Restyle(theme.focused()), title);
+        }
+    }
+
+    if let Some(notice) = app.end_notice() {
+        draw_banner(f, &notice, theme);
+    }
+
+    if app.show_help {
+        draw_help(f, &app.keymap, theme);
+    }
+
+    if let Some(inspection) = &app.inspector {
+        draw_inspector(f, inspection, theme);
+    }
+
+    if let Some(selected) = app.pmtk_menu {
+        draw_pmtk_menu(f, selected, theme);
+    }
+
+    if let Some(command) = &app.command {
+        draw_prompt(
+            f,
+            "Command (Enter to send, Esc to cancel, Up/Down for history)",
+            command,
+            theme,
+        );
+    }
+
+    if let Some(input) = &app.search_input {
+        draw_prompt(
+            f,
+            "Search messages, re: for a regex (Enter to search, Esc to cancel)",
+            input,
+            theme,
+        );
+    }
+
+    if app.confirm_reset {
+        draw_prompt(
+            f,
+            "Reset all receiver state and counters? (y to reset, any other key to cancel)",
+            "",
+            theme,
+        );
+    }
+
+    if let Some(waypoint) = &app.naming {
+        draw_prompt(
+            f,
+            "Waypoint name (Enter to save, Esc to cancel)",
+            &waypoint.name,
+            theme,
+        );
+    }
+}
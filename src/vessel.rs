@@ -0,0 +1,247 @@
+//! Instruments on a boat's NMEA bus: depth, wind, water temperature, heading
+//! and rate of turn
+
+use chrono::{DateTime, Local};
+
+use crate::sentence::Sentence;
+
+const FEET_TO_M: f64 = 0.3048;
+const FATHOMS_TO_M: f64 = 1.8288;
+const KMH_TO_KNOTS: f64 = 1.0 / 1.852;
+const MPS_TO_KNOTS: f64 = 3600.0 / 1852.0;
+
+/// A value and when it arrived. Instruments come and go on the bus
+/// independently, so each value goes stale on its own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading<T> {
+    pub value: T,
+    pub local: DateTime<Local>,
+}
+
+impl<T> Reading<T> {
+    /// Seconds without an update after which a value is shown as stale
+    pub const STALE_SECS: i64 = 10;
+
+    pub fn new(value: T, local: DateTime<Local>) -> Reading<T> {
+        Reading { value, local }
+    }
+
+    pub fn is_stale(&self, now: DateTime<Local>) -> bool {
+        (now - self.local).num_seconds() >= Self::STALE_SECS
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Depth {
+    /// meters below the transducer
+    pub meters: f64,
+    /// from DPT: meters from the transducer to the waterline when positive,
+    /// or to the keel when negative
+    pub offset: Option<f64>,
+}
+
+impl Depth {
+    pub fn below_surface(&self) -> Option<f64> {
+        self.offset.filter(|&o| o > 0.0).map(|o| self.meters + o)
+    }
+
+    pub fn below_keel(&self) -> Option<f64> {
+        self.offset.filter(|&o| o < 0.0).map(|o| self.meters + o)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wind {
+    /// degrees from the bow, clockwise
+    pub angle: f64,
+    pub knots: f64,
+}
+
+/// The latest value of each instrument
+#[derive(Debug, Clone, Default)]
+pub struct Vessel {
+    pub depth: Option<Reading<Depth>>,
+    pub apparent_wind: Option<Reading<Wind>>,
+    pub true_wind: Option<Reading<Wind>>,
+    /// degrees Celsius
+    pub water_temp: Option<Reading<f64>>,
+    /// degrees
+    pub heading_true: Option<Reading<f64>>,
+    /// degrees
+    pub heading_magnetic: Option<Reading<f64>>,
+    /// degrees per minute, negative to port
+    pub rate_of_turn: Option<Reading<f64>>,
+}
+
+/// Meters from DBT, which gives the depth in feet, meters and fathoms, not
+/// always all three
+fn dbt_meters(sentence: &Sentence) -> Option<f64> {
+    let field = |i: usize, unit: &str| {
+        sentence
+            .parse_field::<f64>(i)
+            .filter(|_| sentence.field(i + 1) == Some(unit))
+    };
+    field(2, "M")
+        .or_else(|| field(0, "f").map(|ft| ft * FEET_TO_M))
+        .or_else(|| field(4, "F").map(|fathoms| fathoms * FATHOMS_TO_M))
+}
+
+fn mwv_wind(sentence: &Sentence) -> Option<(Wind, bool)> {
+    if sentence.field(4) != Some("A") {
+        return None;
+    }
+    let speed: f64 = sentence.parse_field(2)?;
+    let knots = match sentence.field(3)? {
+        "N" => speed,
+        "K" => speed * KMH_TO_KNOTS,
+        "M" => speed * MPS_TO_KNOTS,
+        _ => return None,
+    };
+    let wind = Wind {
+        angle: sentence.parse_field(0)?,
+        knots,
+    };
+    match sentence.field(1)? {
+        "R" => Some((wind, false)),
+        "T" => Some((wind, true)),
+        _ => None,
+    }
+}
+
+fn mtw_celsius(sentence: &Sentence) -> Option<f64> {
+    let temp: f64 = sentence.parse_field(0)?;
+    match sentence.field(1) {
+        Some("C") | None => Some(temp),
+        Some("F") => Some((temp - 32.0) / 1.8),
+        Some(_) => None,
+    }
+}
+
+impl Vessel {
+    pub fn is_empty(&self) -> bool {
+        self.depth.is_none()
+            && self.apparent_wind.is_none()
+            && self.true_wind.is_none()
+            && self.water_temp.is_none()
+            && self.heading_true.is_none()
+            && self.heading_magnetic.is_none()
+            && self.rate_of_turn.is_none()
+    }
+
+    /// Takes whatever `sentence` says about the vessel; other sentences are
+    /// ignored
+    pub fn handle(&mut self, local: DateTime<Local>, sentence: &Sentence) {
+        match sentence.kind {
+            "DPT" => {
+                if let Some(meters) = sentence.parse_field(0) {
+                    let offset = sentence.parse_field(1);
+                    self.depth = Some(Reading::new(Depth { meters, offset }, local));
+                }
+            }
+            "DBT" => {
+                if let Some(meters) = dbt_meters(sentence) {
+                    let offset = None;
+                    self.depth = Some(Reading::new(Depth { meters, offset }, local));
+                }
+            }
+            "MWV" => match mwv_wind(sentence) {
+                Some((wind, true)) => self.true_wind = Some(Reading::new(wind, local)),
+                Some((wind, false)) => self.apparent_wind = Some(Reading::new(wind, local)),
+                None => {}
+            },
+            "MTW" => {
+                if let Some(temp) = mtw_celsius(sentence) {
+                    self.water_temp = Some(Reading::new(temp, local));
+                }
+            }
+            "HDT" => {
+                if let Some(heading) = sentence.parse_field(0) {
+                    self.heading_true = Some(Reading::new(heading, local));
+                }
+            }
+            "HDM" => {
+                if let Some(heading) = sentence.parse_field(0) {
+                    self.heading_magnetic = Some(Reading::new(heading, local));
+                }
+            }
+            "ROT" => {
+                if let (Some(rate), Some("A")) = (sentence.parse_field(0), sentence.field(1)) {
+                    self.rate_of_turn = Some(Reading::new(rate, local));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// From a multiplexer: a depth sounder giving feet only, a wind
+    /// instrument, a compass and a rate gyro, between GPS sentences
+    const MUX_LOG: &str = "\
+$GPGGA,092750.000,5321.6802,N,00630.3372,W,1,8,1.03,61.7,M,55.2,M,,*76
+$SDDBT,42.0,f,,M,,F*30
+$WIMWV,045.0,R,12.4,N,A*15
+$WIMWV,052.5,T,15.0,M,A*10
+$YXMTW,18.5,C*1E
+$HEHDT,274.1,T*2F
+$HCHDM,270.3,M*2F
+!AIVDM,1,1,,B,15M67FC000G?ufbE`FepT@3n00Sa,0*5C
+$TIROT,-12.5,A*20
+$WIMWV,100.0,R,20.0,K,V*02
+";
+
+    fn feed(vessel: &mut Vessel, local: DateTime<Local>, log: &str) {
+        for line in log.lines() {
+            vessel.handle(local, &Sentence::parse(line).expect("valid sentence"));
+        }
+    }
+
+    #[test]
+    fn multiplexer_log_fills_each_instrument() {
+        let mut vessel = Vessel::default();
+        assert!(vessel.is_empty());
+        let now = Local::now();
+        feed(&mut vessel, now, MUX_LOG);
+
+        let depth = vessel.depth.unwrap().value;
+        assert!((depth.meters - 12.8016).abs() < 1e-9);
+        assert_eq!(depth.below_surface(), None);
+
+        // the void reading at the end leaves the apparent wind alone
+        let apparent = vessel.apparent_wind.unwrap().value;
+        assert_eq!((apparent.angle, apparent.knots), (45.0, 12.4));
+        let true_wind = vessel.true_wind.unwrap().value;
+        assert!((true_wind.knots - 29.158).abs() < 0.001);
+
+        assert_eq!(vessel.water_temp.unwrap().value, 18.5);
+        assert_eq!(vessel.heading_true.unwrap().value, 274.1);
+        assert_eq!(vessel.heading_magnetic.unwrap().value, 270.3);
+        assert_eq!(vessel.rate_of_turn.unwrap().value, -12.5);
+    }
+
+    #[test]
+    fn dpt_offsets_and_staleness() {
+        let mut vessel = Vessel::default();
+        let start = Local::now();
+        feed(&mut vessel, start, "$IIDPT,4.1,0.5,*6C");
+        let depth = vessel.depth.unwrap().value;
+        assert!((depth.below_surface().unwrap() - 4.6).abs() < 1e-9);
+        assert_eq!(depth.below_keel(), None);
+
+        let later = start + chrono::Duration::seconds(15);
+        feed(&mut vessel, later, "$IIDPT,3.9,-1.2,100*79");
+        let depth = vessel.depth.unwrap().value;
+        assert!((depth.below_keel().unwrap() - 2.7).abs() < 1e-9);
+
+        // meters win over the other units when a sounder gives all three
+        feed(&mut vessel, later, "$SDDBT,,f,12.5,M,6.8,F*10");
+        assert_eq!(vessel.depth.unwrap().value.meters, 12.5);
+
+        feed(&mut vessel, start, "$YXMTW,18.5,C*1E");
+        assert!(vessel.water_temp.unwrap().is_stale(later));
+        assert!(!vessel.depth.unwrap().is_stale(later));
+    }
+}
@@ -0,0 +1,169 @@
+//! Property tests of the path from received bytes to the state: whatever
+//! arrives, `App` mustn't panic or grow without bound, and the sentences in
+//! between garbage still get through. Mutations start from example.txt, a
+//! real capture. Inputs which once panicked are kept as plain tests below.
+
+use chrono::{DateTime, Duration, Local, TimeZone};
+use nmeacli::{app::RejectLog, sentence::checksum, ui, App, Key};
+use proptest::{collection::vec, prelude::*};
+use tui::{backend::TestBackend, Terminal};
+
+const EXAMPLE: &str = include_str!("../example.txt");
+
+/// A GGA with a fix at 37.565510 N
+const GGA: &str = "$GPGGA,060437.00,3733.93059,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,*6F";
+
+fn start() -> DateTime<Local> {
+    Local.timestamp(1_591_509_873, 0)
+}
+
+/// Feeds `lines` 100 ms apart after `local`, returning when the last came
+fn feed<T: AsRef<[u8]>>(app: &mut App, mut local: DateTime<Local>, lines: &[T]) -> DateTime<Local> {
+    for line in lines {
+        local = local + Duration::milliseconds(100);
+        app.handle_raw_at(local, line.as_ref());
+    }
+    local
+}
+
+/// Selects the newest line, inspects it and renders the screen, which all
+/// take the lines apart again
+fn inspect_and_draw(app: &mut App) {
+    app.handle_key(Key::Down);
+    app.handle_key(Key::Char('\n'));
+    let mut terminal = Terminal::new(TestBackend::new(80, 40)).unwrap();
+    terminal.draw(|f| ui::draw(f, app)).unwrap();
+    app.handle_key(Key::Esc);
+    app.handle_key(Key::Esc);
+}
+
+fn assert_bounded(app: &App) {
+    assert!(app.messages.lines.len() <= app.options.messages);
+    assert!(app.rejected.lines.len() <= RejectLog::CAPACITY);
+    assert!(app.positions.len() <= app.options.track);
+}
+
+/// `line` with a `*hh` that matches again, so a mutation gets past the
+/// checksum and into the parsers
+fn fix_checksum(line: &str) -> String {
+    match line.get(1..).and_then(|body| body.rsplit_once('*')) {
+        Some((body, _)) if line.starts_with(|c| c == '$' || c == '!') => {
+            format!("{}{}*{:02X}", &line[..1], body, checksum(body))
+        }
+        _ => line.to_owned(),
+    }
+}
+
+/// A line of example.txt with some bytes replaced, inserted or cut out
+fn mutated_sentence() -> impl Strategy<Value = Vec<u8>> {
+    let lines: Vec<&'static str> = EXAMPLE.lines().collect();
+    (
+        proptest::sample::select(lines),
+        vec((any::<prop::sample::Index>(), 0..3u8, any::<u8>()), 1..6),
+        any::<bool>(),
+    )
+        .prop_map(|(line, edits, keep_checksum)| {
+            let mut bytes = line.as_bytes().to_vec();
+            for (at, edit, byte) in edits {
+                let at = at.index(bytes.len() + 1);
+                match edit {
+                    0 if at < bytes.len() => bytes[at] = byte,
+                    1 => bytes.insert(at, byte),
+                    _ => bytes.truncate(at),
+                }
+            }
+            if keep_checksum {
+                bytes
+            } else {
+                fix_checksum(&String::from_utf8_lossy(&bytes)).into_bytes()
+            }
+        })
+}
+
+/// Lines of anything but the start of a sentence or of a UBX packet
+fn garbage() -> impl Strategy<Value = Vec<u8>> {
+    let byte = any::<u8>().prop_filter("no start", |b| !b"$!\xb5".contains(b));
+    vec(byte, 0..120)
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_bytes_never_panic(lines in vec(vec(any::<u8>(), 0..300), 0..40)) {
+        let mut app = App::with_defaults();
+        feed(&mut app, start(), &lines);
+        inspect_and_draw(&mut app);
+        assert_bounded(&app);
+    }
+
+    #[test]
+    fn mutated_sentences_never_panic(lines in vec(mutated_sentence(), 1..40)) {
+        let mut app = App::with_defaults();
+        feed(&mut app, start(), &lines);
+        inspect_and_draw(&mut app);
+        assert_bounded(&app);
+    }
+
+    #[test]
+    fn sentences_between_garbage_get_through(
+        before in vec(garbage(), 0..10),
+        after in vec(garbage(), 0..10),
+    ) {
+        let mut app = App::with_defaults();
+        let local = feed(&mut app, start(), &before);
+        feed(&mut app, local, &[GGA]);
+        feed(&mut app, local + Duration::seconds(1), &after);
+        let latitude = app.nmea.latitude.unwrap();
+        prop_assert!((latitude - 37.565510).abs() < 1e-6);
+        prop_assert_eq!(app.sentences_failed as usize, before.len() + after.len());
+    }
+}
+
+#[test]
+fn buffers_stay_bounded() {
+    let mut app = App::with_defaults();
+    let example: Vec<&str> = EXAMPLE.lines().collect();
+    let mut local = start();
+    for i in 0..2_000 {
+        local = local + Duration::milliseconds(100);
+        app.handle_line_at(local, example[i % example.len()]);
+        app.handle_raw_at(local, format!("garbage {}", i).as_bytes());
+        app.handle_line_at(local, "$GPTXT,01,01,02,ANTSTATUS=OK*3B");
+    }
+    assert_bounded(&app);
+    assert_eq!(app.messages.lines.len(), app.options.messages);
+    assert_eq!(app.rejected.lines.len(), RejectLog::CAPACITY);
+    assert!(app.texts.len() <= 100);
+}
+
+#[test]
+fn once_panicking_lines() {
+    let long = format!("$GPGGA,{}*00", "1,".repeat(5_000));
+    let lines: Vec<Vec<u8>> = vec![
+        b"$".to_vec(),
+        b"!".to_vec(),
+        b"*".to_vec(),
+        b"$*".to_vec(),
+        b"$*00".to_vec(),
+        b"$P*50".to_vec(),
+        b"$GP*17".to_vec(),
+        b"$GPGGA\0,060437.00,3733.93059,N*00".to_vec(),
+        b"\0\0\0\0".to_vec(),
+        b"$GPGGA,060437.00,\0\0\0,N,12654.85088,E,1,04,1.22,46.3,M,18.3,M,,*6F".to_vec(),
+        // a talker cut in the middle of a multibyte character
+        "$Gé,1*00".as_bytes().to_vec(),
+        fix_checksum("$Gé,1*00").into_bytes(),
+        b"$GP\xff\xfeGGA,1*00".to_vec(),
+        long.clone().into_bytes(),
+        fix_checksum(&long).into_bytes(),
+        vec![b'$'; 10_240],
+        // the UBX sync chars, then nothing
+        b"\xb5\x62".to_vec(),
+        b"\xb5\x62\x01\x07\xff\xff".to_vec(),
+    ];
+    for line in &lines {
+        let mut app = App::with_defaults();
+        feed(&mut app, start(), &[line]);
+        inspect_and_draw(&mut app);
+        assert_bounded(&app);
+    }
+}
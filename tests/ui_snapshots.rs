@@ -0,0 +1,205 @@
+//! Snapshot tests of the rendered screen. The screen is rendered into a
+//! `TestBackend` and compared as text with the golden files in
+//! `tests/snapshots`. A missing golden file fails the test like a differing
+//! one; `UPDATE_SNAPSHOTS=1` writes them all from the current rendering, to
+//! be reviewed and committed.
+
+use std::{fs, path::PathBuf, sync::Once};
+
+use chrono::{DateTime, Duration, Local, TimeZone};
+use nmeacli::{ui, App, Key};
+use tui::{backend::TestBackend, Terminal};
+
+const EXAMPLE: &str = include_str!("../example.txt");
+
+/// Timestamps are rendered in local time, pin it so the golden files don't
+/// depend on the machine running the tests
+fn setup() {
+    static TZ: Once = Once::new();
+    TZ.call_once(|| std::env::set_var("TZ", "UTC"));
+}
+
+/// 2020-06-07 06:04:33 UTC, when the first sentence of example.txt was sent
+fn start() -> DateTime<Local> {
+    Local.timestamp(1_591_509_873, 0)
+}
+
+fn app() -> App {
+    let mut app = App::with_defaults();
+    app.prefs.panels = "status,sky,messages".parse().unwrap();
+    app.tick_at(start());
+    app
+}
+
+/// Feeds example.txt one epoch per second, as the receiver sent it, and
+/// returns the arrival time of the last line
+fn feed_example(app: &mut App) -> DateTime<Local> {
+    let mut local = start();
+    let mut first = true;
+    for line in EXAMPLE.lines() {
+        if line.starts_with("$GPRMC") && !first {
+            local = local + Duration::seconds(1);
+        }
+        first = false;
+        app.handle_line_at(local, line);
+    }
+    local
+}
+
+fn render(app: &App) -> String {
+    render_at(app, 80, 40)
+}
+
+fn render_at(app: &App, width: u16, height: u16) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|f| ui::draw(f, app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let mut dump = String::new();
+    for y in 0..buffer.area.height {
+        let line: String = (0..buffer.area.width)
+            .map(|x| buffer.get(x, y).symbol.as_str())
+            .collect();
+        dump.push_str(line.trim_end());
+        dump.push('\n');
+    }
+    dump
+}
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "snapshots"]
+        .iter()
+        .collect::<PathBuf>()
+        .join(format!("{}.txt", name));
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some_and(|v| v == "1") {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    match fs::read_to_string(&path) {
+        Ok(expected) => assert_eq!(
+            expected,
+            actual,
+            "{} differs, rerun with UPDATE_SNAPSHOTS=1 if the change is intended",
+            path.display()
+        ),
+        Err(e) => panic!(
+            "no golden file {} ({}), create it with UPDATE_SNAPSHOTS=1 and commit it",
+            path.display(),
+            e
+        ),
+    }
+}
+
+#[test]
+fn no_data_yet() {
+    setup();
+    let app = app();
+    assert_snapshot("no_data", &render(&app));
+}
+
+#[test]
+fn full_fix() {
+    setup();
+    let mut app = app();
+    let last = feed_example(&mut app);
+    app.tick_at(last);
+    assert_snapshot("full_fix", &render(&app));
+}
+
+#[test]
+fn stale_data() {
+    setup();
+    let mut app = app();
+    let last = feed_example(&mut app);
+    app.tick_at(last + Duration::seconds(30));
+    assert_snapshot("stale", &render(&app));
+}
+
+#[test]
+fn small_terminals() {
+    setup();
+    let mut app = app();
+    let last = feed_example(&mut app);
+    app.tick_at(last);
+
+    // every panel keeps a share of the rows
+    let screen = render_at(&app, 80, 24);
+    assert!(screen.contains("Status") && screen.contains("Messages"));
+    assert_snapshot("small_80x24", &screen);
+
+    // one panel, the first until another is focused
+    let screen = render_at(&app, 60, 15);
+    assert!(screen.starts_with("Status") && !screen.contains("Messages"));
+    assert_snapshot("small_60x15", &screen);
+    for _ in 0..3 {
+        app.handle_key(Key::Char('\t'));
+    }
+    let screen = render_at(&app, 60, 15);
+    assert!(screen.starts_with("Messages") && !screen.contains("Status"));
+
+    let screen = render_at(&app, 20, 10);
+    assert!(screen.starts_with("terminal too small"));
+    assert_snapshot("small_20x10", &screen);
+}
+
+#[test]
+fn satellite_columns_fit_the_pane() {
+    setup();
+    let mut app = app();
+    let last = feed_example(&mut app);
+    app.tick_at(last);
+    // the title, the header, then the strongest satellite
+    let sky_rows = |app: &App, width: u16, pane: usize| -> Vec<String> {
+        render_at(app, width, 24)
+            .lines()
+            .skip(1)
+            .take(2)
+            .map(|line| line.chars().take(pane).collect())
+            .collect()
+    };
+
+    app.prefs.panels = "sky".parse().unwrap();
+    let rows = sky_rows(&app, 80, 80);
+    assert!(rows[0].starts_with("PRN  GNSS     Elev  Azim  SNR  Used"));
+    assert!(rows[1].contains("GPS"));
+
+    app.prefs.panels = "sky+gsa".parse().unwrap();
+    let rows = sky_rows(&app, 60, 30);
+    assert!(rows[0].starts_with("PRN  GNSS     Elev  SNR  Used"));
+    assert!(rows[1].contains("GPS"));
+
+    app.prefs.panels = "sky+gsa+rates".parse().unwrap();
+    let rows = sky_rows(&app, 80, 26);
+    assert!(rows[0].starts_with("PRN  GNSS  Elev  SNR  Used"));
+    assert!(rows[1].contains("GP ") && !rows[1].contains("GPS"));
+    assert_snapshot("sky_narrow", &render_at(&app, 80, 24));
+}
+
+#[test]
+fn panels_hide_and_zen_leaves_them_as_they_were() {
+    setup();
+    let mut app = app();
+    let last = feed_example(&mut app);
+    app.tick_at(last);
+
+    // focus the sky, hide it and bring it back
+    app.handle_key(Key::Char('\t'));
+    app.handle_key(Key::Char('\t'));
+    app.handle_key(Key::Char('h'));
+    let hidden = render_at(&app, 80, 40);
+    assert!(hidden.contains("Status") && !hidden.contains("Satellites"));
+    app.handle_key(Key::Char('H'));
+    assert!(render_at(&app, 80, 40).contains("Satellites"));
+    app.handle_key(Key::Char('h'));
+
+    app.handle_key(Key::Char('z'));
+    let zen = render_at(&app, 80, 40);
+    assert!(zen.contains("speed (km/h)") && zen.contains("course (°T)"));
+    assert!(zen.contains('█') && !zen.contains("Status"));
+    assert_snapshot("zen", &zen);
+
+    app.handle_key(Key::Char('z'));
+    assert_eq!(render_at(&app, 80, 40), hidden);
+}